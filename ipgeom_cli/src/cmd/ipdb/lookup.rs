@@ -1,6 +1,6 @@
 use anyhow::Result;
 use clap::Args;
-use maxminddb::Reader;
+use maxminddb::{geoip2, Reader};
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 use std::path::PathBuf;
@@ -14,12 +14,25 @@ pub struct Lookup {
     pub db: PathBuf,
 }
 
-pub fn handle(args: Lookup) -> Result<()> {
-    #[derive(Debug, Deserialize, Serialize)]
-    struct Record {
+/// Records of the GeoIP2-Country schema and of the minimal schema written by
+/// `ipgeom store build-geoipdb --format minimal`.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Record<'a> {
+    Country {
+        #[serde(borrow, flatten)]
+        record: Box<geoip2::Country<'a>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<&'a str>,
+    },
+    Minimal {
         country: String,
-    }
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+}
 
+pub fn handle(args: Lookup) -> Result<()> {
     let reader = Reader::open_readfile(args.db)?;
     if let Some(record) = reader.lookup::<Record>(args.ip)? {
         println!("{}", serde_json::to_string_pretty(&record)?);
@@ -28,3 +41,85 @@ pub fn handle(args: Lookup) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ipgeom_rir::{GeoipFormat, GeoipOptions, RirKind, RirProvider, Store};
+
+    use super::*;
+
+    /// Registry whose data is only imported from disk.
+    #[derive(Debug)]
+    struct LocalRir;
+
+    impl RirProvider for LocalRir {
+        fn rpsl_db_urls(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    fn build_dbs() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut rirs: HashMap<RirKind, Box<dyn RirProvider>> = HashMap::new();
+        rirs.insert(RirKind::Ripe, Box::new(LocalRir));
+        let store = Store::with_rirs(dir.path().join("store"), rirs).unwrap();
+        let dump = dir.path().join("ripe.db");
+        std::fs::write(
+            &dump,
+            "inetnum: 192.0.2.0 - 192.0.2.255\ncountry: FR\nsource: TST\n\n\
+             inetnum: 198.51.100.0 - 198.51.100.255\ncountry: EU\nsource: TST\n",
+        )
+        .unwrap();
+        store.import_file(RirKind::Ripe, &dump).unwrap();
+
+        let country = dir.path().join("country.mmdb");
+        store
+            .write_geoip_db(&country, GeoipOptions::default())
+            .unwrap();
+        let minimal = dir.path().join("minimal.mmdb");
+        let options = GeoipOptions {
+            format: GeoipFormat::Minimal,
+            ..Default::default()
+        };
+        store.write_geoip_db(&minimal, options).unwrap();
+        (dir, country, minimal)
+    }
+
+    #[test]
+    fn decode_records() {
+        let (_dir, country, minimal) = build_dbs();
+
+        let reader = Reader::open_readfile(&country).unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let Some(Record::Country { record, source }) = reader.lookup(ip).unwrap() else {
+            panic!("expected a country record");
+        };
+        let country = record.country.unwrap();
+        assert_eq!(country.iso_code, Some("FR"));
+        assert_eq!(country.geoname_id, Some(3017382));
+        assert_eq!(record.registered_country.unwrap().geoname_id, Some(3017382));
+        assert_eq!(record.continent.unwrap().code, Some("EU"));
+        assert_eq!(source, Some("rpsl"));
+
+        // The EU pseudo-code has a continent, but no country data.
+        let ip: IpAddr = "198.51.100.1".parse().unwrap();
+        let Some(Record::Country { record, .. }) = reader.lookup(ip).unwrap() else {
+            panic!("expected a country record");
+        };
+        let continent = record.continent.unwrap();
+        assert_eq!(
+            (continent.code, continent.geoname_id),
+            (Some("EU"), Some(6255148))
+        );
+        assert_eq!(record.country.unwrap().geoname_id, None);
+
+        let reader = Reader::open_readfile(&minimal).unwrap();
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let Some(Record::Minimal { country, source }) = reader.lookup(ip).unwrap() else {
+            panic!("expected a minimal record");
+        };
+        assert_eq!((country.as_str(), source.as_deref()), ("FR", Some("rpsl")));
+    }
+}
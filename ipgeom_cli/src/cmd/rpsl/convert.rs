@@ -0,0 +1,465 @@
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use ipgeom_rpsl::{Object, RpslObject};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use time::format_description::well_known::Rfc3339;
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Format {
+    /// One JSON object per line
+    Ndjson,
+    /// One CSV file per object type
+    Csv,
+    /// A SQLite database with one table per object type
+    Sqlite,
+}
+
+#[derive(Args)]
+pub struct Convert {
+    /// Path to the RPSL file
+    pub path: PathBuf,
+    /// Output format
+    #[arg(long, short, value_enum)]
+    pub format: Format,
+    /// Output file for NDJSON (default: stdout) and SQLite, output directory
+    /// for CSV
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// Convert objects to their typed representation. Objects that can not
+    /// be converted are skipped.
+    #[arg(long)]
+    pub typed: bool,
+}
+
+pub fn handle(args: Convert) -> Result<()> {
+    match args.format {
+        Format::Ndjson => write_ndjson(&args),
+        Format::Csv => {
+            let dir = args
+                .output
+                .as_deref()
+                .context("--output directory is required for CSV")?;
+            write_csv(&args.path, args.typed, dir)
+        }
+        Format::Sqlite => {
+            let path = args
+                .output
+                .as_deref()
+                .context("--output file is required for SQLite")?;
+            write_sqlite(&args.path, args.typed, path)
+        }
+    }
+}
+
+/// An object converted to a flat list of named values.
+///
+/// Raw objects have one array of strings per attribute, typed objects the
+/// fields of their serde representation.
+struct Row {
+    obj_type: String,
+    fields: Vec<(String, Value)>,
+}
+
+impl Row {
+    fn raw(obj: Object) -> Self {
+        let obj_type = obj.obj_type().as_key().to_string();
+        let mut fields: Vec<(String, Value)> = Vec::new();
+        for attr in obj.into_ordered_attributes() {
+            let value = Value::String(attr.value().to_string());
+            match fields.iter_mut().find(|(k, _)| k == attr.key()) {
+                Some((_, Value::Array(values))) => values.push(value),
+                _ => fields.push((attr.key().to_string(), Value::Array(vec![value]))),
+            }
+        }
+        Self { obj_type, fields }
+    }
+
+    fn typed(obj: RpslObject) -> Result<Self> {
+        let obj_type = obj.type_name().to_string();
+        if let RpslObject::Other(obj) = obj {
+            return Ok(Self::raw(obj));
+        }
+        // IP ranges serialize as prefix tries and dates as tuples, use the
+        // RPSL notation and RFC 3339 instead.
+        let mut replaced = Vec::new();
+        if let RpslObject::Inetnum(_)
+        | RpslObject::Inet6num(_)
+        | RpslObject::Route(_)
+        | RpslObject::Route6(_) = &obj
+        {
+            if let Some(range) = Object::from(obj.clone()).get_first(&obj_type) {
+                replaced.push((obj_type.clone(), Value::String(range.to_string())));
+            }
+        }
+        if let Some(common) = obj.common() {
+            for (key, date) in [
+                ("created", common.created),
+                ("last_modified", common.last_modified),
+                ("changed", common.changed),
+            ] {
+                if let Some(date) = date {
+                    replaced.push((key.to_string(), Value::String(date.format(&Rfc3339)?)));
+                }
+            }
+        }
+        // Variants are serialized as `{"Variant": {...fields}}`.
+        let mut fields: Vec<(String, Value)> = match serde_json::to_value(&obj)? {
+            Value::Object(map) => match map.into_iter().next() {
+                Some((_, Value::Object(fields))) => fields.into_iter().collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+        for (key, value) in &mut fields {
+            if let Some((_, new)) = replaced.iter().find(|(k, _)| k == key) {
+                *value = new.clone();
+            }
+        }
+        Ok(Self { obj_type, fields })
+    }
+
+    fn into_json(self) -> Value {
+        let mut map = serde_json::Map::new();
+        map.insert("type".to_string(), Value::String(self.obj_type));
+        map.extend(self.fields);
+        Value::Object(map)
+    }
+}
+
+/// Stream the objects of `path` as rows.
+///
+/// Malformed objects and objects that can not be converted are reported if
+/// `warn` is set.
+fn read_rows(
+    path: &Path,
+    typed: bool,
+    warn: bool,
+    mut f: impl FnMut(Row) -> Result<()>,
+) -> Result<()> {
+    let objects = if warn {
+        super::read_objects(path)?
+    } else {
+        super::read_objects_with(path, |_| {})?
+    };
+    for res in objects {
+        let obj = res?;
+        let row = if typed {
+            let obj_type = obj.obj_type().as_key().to_string();
+            match RpslObject::try_from(obj) {
+                Ok(obj) => Row::typed(obj)?,
+                Err(err) => {
+                    if warn {
+                        eprintln!("warning: skipping {} object: {:#}", obj_type, err);
+                    }
+                    continue;
+                }
+            }
+        } else {
+            Row::raw(obj)
+        };
+        f(row)?;
+    }
+    Ok(())
+}
+
+fn write_ndjson(args: &Convert) -> Result<()> {
+    let out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(out);
+    read_rows(&args.path, args.typed, true, |row| {
+        serde_json::to_writer(&mut out, &row.into_json())?;
+        out.write_all(b"\n")?;
+        Ok(())
+    })?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Collect the columns of each object type, in order of first appearance.
+///
+/// Tabular output needs the columns up front, so the input is read twice
+/// instead of buffering all objects.
+fn collect_columns(path: &Path, typed: bool) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut columns = BTreeMap::<String, Vec<String>>::new();
+    read_rows(path, typed, false, |row| {
+        let cols = columns.entry(row.obj_type).or_default();
+        for (key, _) in row.fields {
+            if !cols.contains(&key) {
+                cols.push(key);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(columns)
+}
+
+/// Text of a single table cell. Lists are joined with newlines.
+fn cell(value: Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s),
+        Value::Array(values) if values.is_empty() => None,
+        Value::Array(values) => Some(
+            values
+                .into_iter()
+                .filter_map(cell)
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        Value::Object(map) if map.is_empty() => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// The cells of `row` in the order of `columns`.
+fn cells(row: Row, columns: &[String]) -> Vec<Option<String>> {
+    let mut cells = vec![None; columns.len()];
+    for (key, value) in row.fields {
+        if let Some(i) = columns.iter().position(|c| *c == key) {
+            cells[i] = cell(value);
+        }
+    }
+    cells
+}
+
+/// Name usable as file or table name.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Sanitized names that are unique regardless of case.
+///
+/// Names like `mnt-by` and `mnt_by` sanitize to the same text, so later ones
+/// get a numeric suffix instead of overwriting the first.
+fn unique_names<'a>(names: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut used = HashSet::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let base = sanitize(name);
+            let mut unique = base.clone();
+            let mut n = 1;
+            while !used.insert(unique.to_ascii_lowercase()) {
+                n += 1;
+                unique = format!("{}_{}", base, n);
+            }
+            if unique != base {
+                eprintln!("warning: '{}' is written as '{}'", name, unique);
+            }
+            unique
+        })
+        .collect()
+}
+
+fn write_csv(path: &Path, typed: bool, dir: &Path) -> Result<()> {
+    let columns = collect_columns(path, typed)?;
+    std::fs::create_dir_all(dir)?;
+
+    let mut writers = BTreeMap::new();
+    for ((obj_type, cols), name) in columns.iter().zip(unique_names(columns.keys())) {
+        let file_path = dir.join(format!("{}.csv", name));
+        let mut out = BufWriter::new(File::create(&file_path)?);
+        write_csv_record(&mut out, cols.iter().map(|c| Some(c.as_str())))?;
+        writers.insert(obj_type.clone(), (out, file_path));
+    }
+
+    read_rows(path, typed, true, |row| {
+        let cols = &columns[&row.obj_type];
+        let (out, _) = writers
+            .get_mut(&row.obj_type)
+            .expect("writer exists for every type");
+        let cells = cells(row, cols);
+        write_csv_record(out, cells.iter().map(|c| c.as_deref()))?;
+        Ok(())
+    })?;
+
+    for (obj_type, (mut out, file_path)) in writers {
+        out.flush()?;
+        eprintln!("{}: {}", obj_type, file_path.display());
+    }
+    Ok(())
+}
+
+fn write_csv_record<'a, W: Write>(
+    out: &mut W,
+    cells: impl Iterator<Item = Option<&'a str>>,
+) -> Result<()> {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        let cell = cell.unwrap_or("");
+        if cell.contains(['"', ',', '\n', '\r']) {
+            write!(out, "\"{}\"", cell.replace('"', "\"\""))?;
+        } else {
+            out.write_all(cell.as_bytes())?;
+        }
+    }
+    out.write_all(b"\r\n")?;
+    Ok(())
+}
+
+/// Quote a name returned by [`unique_names`].
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name)
+}
+
+fn write_sqlite(path: &Path, typed: bool, output: &Path) -> Result<()> {
+    if output.exists() {
+        anyhow::bail!("{} already exists", output.display());
+    }
+    let columns = collect_columns(path, typed)?;
+
+    let mut conn = rusqlite::Connection::open(output)?;
+    let tx = conn.transaction()?;
+    let mut inserts = BTreeMap::new();
+    for ((obj_type, cols), table) in columns.iter().zip(unique_names(columns.keys())) {
+        let table = quote_ident(&table);
+        let names: Vec<_> = unique_names(cols).iter().map(|c| quote_ident(c)).collect();
+        let col_defs: Vec<_> = names.iter().map(|c| format!("{} TEXT", c)).collect();
+        tx.execute(
+            &format!("CREATE TABLE {} ({})", table, col_defs.join(", ")),
+            [],
+        )?;
+        let params: Vec<_> = (1..=cols.len()).map(|i| format!("?{}", i)).collect();
+        inserts.insert(
+            obj_type.clone(),
+            format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                names.join(", "),
+                params.join(", ")
+            ),
+        );
+    }
+
+    let mut count = 0usize;
+    read_rows(path, typed, true, |row| {
+        let cols = &columns[&row.obj_type];
+        let mut stmt = tx.prepare_cached(&inserts[&row.obj_type])?;
+        let cells = cells(row, cols);
+        stmt.execute(rusqlite::params_from_iter(cells))?;
+        count += 1;
+        Ok(())
+    })?;
+    tx.commit()?;
+    eprintln!("wrote {} objects to {}", count, output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipgeom_rpsl::parse_objects;
+    use serde_json::json;
+
+    const DATA: &str = "\
+inetnum: 192.0.2.0 - 192.0.2.255
+netname: TEST-NET
+mnt-by: MNT-A
+mnt-by: MNT-B
+remarks: first
+source: TEST
+";
+
+    fn object() -> Object {
+        parse_objects(DATA).unwrap().remove(0)
+    }
+
+    #[test]
+    fn raw_row() {
+        let row = Row::raw(object());
+        assert_eq!(row.obj_type, "inetnum");
+        assert_eq!(
+            row.into_json(),
+            json!({
+                "type": "inetnum",
+                "inetnum": ["192.0.2.0 - 192.0.2.255"],
+                "netname": ["TEST-NET"],
+                "mnt-by": ["MNT-A", "MNT-B"],
+                "remarks": ["first"],
+                "source": ["TEST"],
+            })
+        );
+    }
+
+    #[test]
+    fn typed_row() {
+        let mut obj = object();
+        obj.add("created".into(), "2020-02-03T04:05:06Z".into());
+        obj.add("last-modified".into(), "20210101".into());
+        let typed = RpslObject::try_from(obj).unwrap();
+        // Dates are a single cell in tables.
+        let row = Row::typed(typed.clone()).unwrap();
+        assert_eq!(
+            cells(row, &["created".to_string()]),
+            [Some("2020-02-03T04:05:06Z".to_string())]
+        );
+        let json = Row::typed(typed).unwrap().into_json();
+        assert_eq!(json["created"], "2020-02-03T04:05:06Z");
+        assert_eq!(json["last_modified"], "2021-01-01T00:00:00Z");
+        assert_eq!(json["changed"], Value::Null);
+        assert_eq!(json["type"], "inetnum");
+        assert_eq!(json["inetnum"], "192.0.2.0 - 192.0.2.255");
+        assert_eq!(json["netname"], "TEST-NET");
+        assert_eq!(json["mnt_by"], json!(["MNT-A", "MNT-B"]));
+        assert_eq!(json["source"], "TEST");
+        assert!(json.get("mnt-by").is_none());
+    }
+
+    #[test]
+    fn read_gzipped_latin1_and_malformed_input() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.rpsl.gz");
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        enc.write_all(b"person: M\xfcller\nnic-hdl: MU1\n\nbroken line\n\nmntner: TEST-MNT\n")
+            .unwrap();
+        std::fs::write(&input, enc.finish().unwrap()).unwrap();
+
+        let mut rows = Vec::new();
+        read_rows(&input, false, false, |row| {
+            rows.push(row.into_json());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["person"], json!(["Müller"]));
+        assert_eq!(rows[1]["type"], "mntner");
+    }
+
+    #[test]
+    fn unique_sanitized_names() {
+        let names = ["mnt-by", "mnt_by", "MNT_BY", "as-set", "mnt_by_2"].map(String::from);
+        assert_eq!(
+            unique_names(&names),
+            ["mnt_by", "mnt_by_2", "MNT_BY_3", "as_set", "mnt_by_2_2"]
+        );
+    }
+
+    #[test]
+    fn sqlite_keeps_colliding_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.rpsl");
+        std::fs::write(&input, "mntner: TEST-MNT\nmnt-by: A\nmnt_by: B\n").unwrap();
+        let output = dir.path().join("out.db");
+        write_sqlite(&input, false, &output).unwrap();
+
+        let conn = rusqlite::Connection::open(&output).unwrap();
+        let row: (String, String) = conn
+            .query_row("SELECT mnt_by, mnt_by_2 FROM mntner", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(row, ("A".to_string(), "B".to_string()));
+    }
+}
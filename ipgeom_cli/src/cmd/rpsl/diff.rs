@@ -0,0 +1,137 @@
+use anyhow::Result;
+use clap::Args;
+use ipgeom_rir::{diff_rpsl, open_dump_file, DiffOptions, RirKind, RpslChange, Store};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cmd::store::StoreArgs;
+
+#[derive(Args)]
+pub struct Diff {
+    /// Old RPSL file, optionally gzip-compressed, or a stored snapshot as
+    /// `<rir>@<stamp>`, e.g. `ripe@20240102T030405Z` or `ripe@latest`
+    pub old: String,
+    /// New RPSL file or stored snapshot, see `old`
+    pub new: String,
+    /// Print one JSON object per change instead of a readable diff
+    #[arg(long)]
+    pub json: bool,
+    /// Attributes to ignore when comparing objects
+    #[arg(long, value_name = "ATTR", value_delimiter = ',')]
+    pub ignore: Vec<String>,
+    /// Number of objects sorted in memory at a time
+    #[arg(long, default_value_t = DiffOptions::default().chunk_size)]
+    pub chunk_size: usize,
+}
+
+pub fn handle(data_dir: PathBuf, store_args: &StoreArgs, args: Diff) -> Result<()> {
+    let mut store = None;
+    let mut open = |input: &str| -> Result<Box<dyn Read + Send>> {
+        let Some((rir, stamp)) = snapshot_ref(input) else {
+            return open_dump_file(Path::new(input));
+        };
+        let store: &Store = match &mut store {
+            Some(store) => store,
+            None => store.insert(store_args.open(data_dir.clone())?),
+        };
+        let snapshot = store.find_snapshot(rir, stamp)?;
+        open_dump_file(&store.snapshot_path(rir, &snapshot))
+    };
+    let old = open(&args.old)?;
+    let new = open(&args.new)?;
+    let options = DiffOptions {
+        chunk_size: args.chunk_size,
+        ignore_attributes: args.ignore.iter().map(|a| a.to_ascii_lowercase()).collect(),
+    };
+
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    let stats = diff_rpsl(old, new, &options, |change| {
+        if args.json {
+            serde_json::to_writer(&mut out, &change_json(&change))?;
+            writeln!(out)?;
+        } else {
+            write_change(&mut out, &change)?;
+        }
+        Ok(())
+    })?;
+    out.flush()?;
+
+    eprintln!(
+        "{} added, {} removed, {} modified, {} unchanged",
+        stats.added, stats.removed, stats.modified, stats.unchanged
+    );
+    if stats.duplicates > 0 {
+        eprintln!(
+            "warning: {} duplicate objects, only the last occurrence was compared",
+            stats.duplicates
+        );
+    }
+    Ok(())
+}
+
+/// Split a snapshot reference `<rir>@<stamp>`.
+///
+/// Existing files take precedence, so files with an `@` in their name can
+/// still be compared.
+fn snapshot_ref(input: &str) -> Option<(RirKind, &str)> {
+    if Path::new(input).exists() {
+        return None;
+    }
+    let (rir, stamp) = input.split_once('@')?;
+    Some((rir.parse().ok()?, stamp))
+}
+
+/// The type and the value of the type attribute of an object.
+fn header(change: &RpslChange) -> (&str, &str) {
+    let obj = change.object();
+    let key = obj
+        .ordered_attributes()
+        .first()
+        .map(|a| a.value())
+        .unwrap_or_default();
+    (obj.obj_type().as_key(), key)
+}
+
+fn write_change<W: Write>(out: &mut W, change: &RpslChange) -> Result<()> {
+    let (obj_type, key) = header(change);
+    match change {
+        RpslChange::Added(_) => writeln!(out, "+ {} {}", obj_type, key)?,
+        RpslChange::Removed(_) => writeln!(out, "- {} {}", obj_type, key)?,
+        RpslChange::Modified { changes, .. } => {
+            writeln!(out, "~ {} {}", obj_type, key)?;
+            for change in changes {
+                for value in &change.old {
+                    writeln!(out, "    - {}: {}", change.attribute, value)?;
+                }
+                for value in &change.new {
+                    writeln!(out, "    + {}: {}", change.attribute, value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn change_json(change: &RpslChange) -> serde_json::Value {
+    let (obj_type, key) = header(change);
+    match change {
+        RpslChange::Added(obj) => serde_json::json!({
+            "change": "added",
+            "type": obj_type,
+            "key": key,
+            "object": obj.to_rpsl(),
+        }),
+        RpslChange::Removed(obj) => serde_json::json!({
+            "change": "removed",
+            "type": obj_type,
+            "key": key,
+            "object": obj.to_rpsl(),
+        }),
+        RpslChange::Modified { changes, .. } => serde_json::json!({
+            "change": "modified",
+            "type": obj_type,
+            "key": key,
+            "attributes": changes,
+        }),
+    }
+}
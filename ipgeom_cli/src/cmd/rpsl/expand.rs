@@ -0,0 +1,107 @@
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use ipgeom_rpsl::expand::{ExpandOptions, IrrIndex, PrefixListFormat};
+use ipgeom_rpsl::RpslObject;
+use std::path::{Path, PathBuf};
+
+use crate::cmd::store::StoreArgs;
+
+#[derive(Copy, Clone, ValueEnum)]
+pub enum Format {
+    Cisco,
+    Juniper,
+    Bird,
+    Json,
+}
+
+impl From<Format> for PrefixListFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Cisco => PrefixListFormat::Cisco,
+            Format::Juniper => PrefixListFormat::Juniper,
+            Format::Bird => PrefixListFormat::Bird,
+            Format::Json => PrefixListFormat::Json,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct Expand {
+    /// AS number, as-set or route-set to expand
+    pub name: String,
+    /// RPSL files to load objects from instead of the local store
+    #[arg(long, value_name = "PATH")]
+    pub file: Vec<PathBuf>,
+    /// Output format of the prefix list
+    #[arg(long, short, value_enum, default_value = "cisco")]
+    pub format: Format,
+    /// Generate an IPv6 prefix list instead of an IPv4 one
+    #[arg(short = '6', long)]
+    pub ipv6: bool,
+    /// Aggregate adjacent and overlapping prefixes
+    #[arg(short = 'A', long)]
+    pub aggregate: bool,
+    /// Name of the generated prefix list
+    #[arg(long, short = 'l', default_value = "NN")]
+    pub list_name: String,
+    /// Print the AS numbers of the set instead of a prefix list
+    #[arg(long)]
+    pub asns: bool,
+    /// Maximum nesting depth of sets
+    #[arg(long, default_value_t = ExpandOptions::default().max_depth)]
+    pub max_depth: usize,
+}
+
+pub fn handle(data_dir: PathBuf, store_args: &StoreArgs, args: Expand) -> Result<()> {
+    let mut index = IrrIndex::new();
+    if args.file.is_empty() {
+        let store = store_args.open(data_dir)?;
+        for res in store.all_objects_iter()? {
+            match res {
+                Ok(obj) => index.insert(obj),
+                Err(err) => eprintln!("warning: skipping RPSL object: {}", err),
+            }
+        }
+    } else {
+        for path in &args.file {
+            load_file(&mut index, path)?;
+        }
+    }
+
+    let options = ExpandOptions {
+        max_depth: args.max_depth,
+    };
+    let expansion = index.expand(&args.name, &options);
+    for warning in &expansion.warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    if args.asns {
+        for asn in &expansion.asns {
+            println!("AS{}", asn);
+        }
+        return Ok(());
+    }
+
+    let mut prefixes = if args.ipv6 {
+        expansion.prefixes.ipv6()
+    } else {
+        expansion.prefixes.ipv4()
+    };
+    if args.aggregate {
+        prefixes = prefixes.aggregate();
+    }
+    let format = PrefixListFormat::from(args.format);
+    print!("{}", format.render(&args.list_name, &prefixes));
+    Ok(())
+}
+
+fn load_file(index: &mut IrrIndex, path: &Path) -> Result<()> {
+    for res in super::read_objects(path)? {
+        match RpslObject::try_from(res?) {
+            Ok(obj) => index.insert(obj),
+            Err(err) => eprintln!("warning: {}: skipping RPSL object: {}", path.display(), err),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use anyhow::{bail, Result};
+use clap::Args;
+use ipgeom_rpsl::validate::{validate, Finding, FindingKind, Severity};
+use ipgeom_rpsl::MalformedObject;
+use std::collections::BTreeMap;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+#[derive(Args)]
+pub struct Lint {
+    /// Path to the RPSL file
+    pub path: PathBuf,
+    /// Print one JSON object per finding
+    #[arg(long)]
+    pub json: bool,
+    /// Only report errors, not warnings
+    #[arg(long)]
+    pub errors_only: bool,
+    /// Print the number of findings per kind instead of the findings
+    #[arg(long)]
+    pub summary: bool,
+}
+
+pub fn handle(args: Lint) -> Result<()> {
+    let (malformed_tx, malformed_rx) = mpsc::channel();
+    let objects_iter = super::read_objects_with(&args.path, move |m| {
+        let _ = malformed_tx.send(m);
+    })?;
+    let min_severity = if args.errors_only {
+        Severity::Error
+    } else {
+        Severity::Warning
+    };
+
+    let mut report = Report {
+        summary: args.summary,
+        json: args.json,
+        out: BufWriter::new(std::io::stdout().lock()),
+        objects: 0,
+        invalid: 0,
+        errors: 0,
+        counts: BTreeMap::new(),
+    };
+
+    for res in objects_iter {
+        let obj = res?;
+        for malformed in malformed_rx.try_iter() {
+            report.malformed(malformed)?;
+        }
+        let findings: Vec<_> = validate(&obj)
+            .into_iter()
+            .filter(|f| f.severity >= min_severity)
+            .collect();
+
+        let obj_type = obj.obj_type().as_key();
+        let key = obj
+            .ordered_attributes()
+            .first()
+            .map(|a| a.value())
+            .unwrap_or_default();
+        report.object(obj_type, key, findings)?;
+    }
+    for malformed in malformed_rx.try_iter() {
+        report.malformed(malformed)?;
+    }
+
+    report.finish()
+}
+
+/// Output and counters of a lint run.
+struct Report<W: Write> {
+    summary: bool,
+    json: bool,
+    out: W,
+    objects: usize,
+    invalid: usize,
+    errors: usize,
+    counts: BTreeMap<(Severity, String), usize>,
+}
+
+impl<W: Write> Report<W> {
+    /// Record an object and its findings.
+    fn object(&mut self, obj_type: &str, key: &str, findings: Vec<Finding>) -> Result<()> {
+        self.objects += 1;
+        if findings.is_empty() {
+            return Ok(());
+        }
+        self.invalid += 1;
+
+        for finding in findings {
+            if finding.severity == Severity::Error {
+                self.errors += 1;
+            }
+            if self.summary {
+                *self
+                    .counts
+                    .entry((finding.severity, kind_name(finding.kind)))
+                    .or_default() += 1;
+            } else if self.json {
+                serde_json::to_writer(
+                    &mut self.out,
+                    &serde_json::json!({
+                        "type": obj_type,
+                        "key": key,
+                        "finding": finding,
+                    }),
+                )?;
+                writeln!(self.out)?;
+            } else {
+                writeln!(self.out, "{} {}: {}", obj_type, key, finding)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record an object the parser skipped as a single error finding.
+    ///
+    /// The object is identified by its first attribute that has a name.
+    fn malformed(&mut self, malformed: MalformedObject) -> Result<()> {
+        let (obj_type, key) = malformed
+            .raw
+            .lines()
+            .find_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .unwrap_or_default();
+        let finding = Finding {
+            severity: Severity::Error,
+            kind: FindingKind::MalformedObject,
+            attribute: None,
+            line: Some(malformed.line),
+            message: format!(
+                "could not parse '{}' ({:?}), skipped object at lines {}-{}",
+                malformed.content, malformed.kind, malformed.start_line, malformed.end_line
+            ),
+        };
+        self.object(obj_type, key, vec![finding])
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if self.summary {
+            for ((severity, kind), count) in &self.counts {
+                writeln!(self.out, "{}\t{}\t{}", severity, kind, count)?;
+            }
+        }
+        self.out.flush()?;
+
+        eprintln!(
+            "{} objects checked, {} with findings",
+            self.objects, self.invalid
+        );
+        if self.errors > 0 {
+            bail!("{} errors found", self.errors);
+        }
+        Ok(())
+    }
+}
+
+fn kind_name(kind: FindingKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_objects_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.rpsl");
+        std::fs::write(
+            &input,
+            "mntner: TEST-MNT\n\nperson Test\nnic-hdl: TE1-TEST\n",
+        )
+        .unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let objects: Vec<_> = super::super::read_objects_with(&input, move |m| {
+            let _ = tx.send(m);
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert_eq!(objects.len(), 1);
+
+        let mut report = Report {
+            summary: false,
+            json: true,
+            out: Vec::new(),
+            objects: 0,
+            invalid: 0,
+            errors: 0,
+            counts: BTreeMap::new(),
+        };
+        for malformed in rx.try_iter() {
+            report.malformed(malformed).unwrap();
+        }
+        assert_eq!(report.objects, 1);
+        assert_eq!(report.invalid, 1);
+        assert_eq!(report.errors, 1);
+
+        let line: serde_json::Value = serde_json::from_slice(&report.out).unwrap();
+        assert_eq!(line["type"], "nic-hdl");
+        assert_eq!(line["key"], "TE1-TEST");
+        assert_eq!(line["finding"]["kind"], "malformed-object");
+        assert_eq!(line["finding"]["line"], 3);
+        assert!(report.finish().is_err());
+    }
+}
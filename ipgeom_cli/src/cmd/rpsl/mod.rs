@@ -1,16 +1,64 @@
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
 use clap::Subcommand;
+use ipgeom_rir::open_dump_file;
+use ipgeom_rpsl::{parse_objects_read_iter, Decoding, MalformedObject, ObjectsReadIter};
+
+use crate::cmd::store::StoreArgs;
 
+pub mod convert;
+pub mod diff;
+pub mod expand;
+pub mod lint;
 pub mod print;
 
 #[derive(Subcommand)]
 pub enum RpslCmd {
     /// Print RPSL objects from a file
     Print(print::Print),
+    /// Convert an RPSL file to NDJSON, CSV or SQLite
+    Convert(convert::Convert),
+    /// Show the differences between two RPSL files or stored snapshots
+    Diff(diff::Diff),
+    /// Expand an as-set or route-set into a prefix list
+    Expand(expand::Expand),
+    /// Check RPSL objects against the object templates
+    Lint(lint::Lint),
 }
 
-pub fn handle(cmd: RpslCmd) -> Result<()> {
+pub fn handle(data_dir: PathBuf, store_args: &StoreArgs, cmd: RpslCmd) -> Result<()> {
     match cmd {
         RpslCmd::Print(args) => print::handle(args),
+        RpslCmd::Convert(args) => convert::handle(args),
+        RpslCmd::Diff(args) => diff::handle(data_dir, store_args, args),
+        RpslCmd::Expand(args) => expand::handle(data_dir, store_args, args),
+        RpslCmd::Lint(args) => lint::handle(args),
     }
 }
+
+/// Objects of an RPSL file.
+type ObjectsIter = ObjectsReadIter<BufReader<Box<dyn Read + Send>>>;
+
+/// Read the objects of an RPSL file, plain or gzip-compressed.
+///
+/// Latin-1 encoded text is accepted, and malformed objects are skipped with a
+/// warning.
+fn read_objects(path: &Path) -> Result<ObjectsIter> {
+    let display = path.display().to_string();
+    read_objects_with(path, move |malformed| {
+        eprintln!("warning: {}: skipping {}", display, malformed)
+    })
+}
+
+/// Like [`read_objects`], but pass malformed objects to `on_malformed`
+/// instead of warning about them.
+fn read_objects_with<F>(path: &Path, on_malformed: F) -> Result<ObjectsIter>
+where
+    F: FnMut(MalformedObject) + Send + 'static,
+{
+    Ok(parse_objects_read_iter(open_dump_file(path)?)
+        .with_decoding(Decoding::Utf8OrLatin1)
+        .with_recovery(on_malformed))
+}
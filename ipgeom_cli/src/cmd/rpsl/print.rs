@@ -1,8 +1,8 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Args;
-use ipgeom_rpsl::{parse_objects_read_iter, RpslObject};
-use std::fs::File;
-use std::io::BufReader;
+use ipgeom_rpsl::filter::Filter;
+use ipgeom_rpsl::{Object, RpslObject};
+use std::collections::BTreeMap;
 use std::net::IpAddr;
 use std::path::PathBuf;
 
@@ -10,42 +10,104 @@ use std::path::PathBuf;
 pub struct Print {
     /// Path to the RPSL file
     pub path: PathBuf,
+    /// Filter expression, e.g. `type=route origin=AS3333` or
+    /// `inetnum within 10.0.0.0/8`
+    pub filter: Vec<String>,
     /// Only print inetnum/inet6num objects that contain one of these IPs
     #[arg(long, value_name = "IP")]
     pub ip: Vec<IpAddr>,
+    /// Only print objects of these types
+    #[arg(long = "type", short, value_name = "TYPE", value_delimiter = ',')]
+    pub types: Vec<String>,
+    /// Only print these attributes in addition to the type attribute
+    #[arg(long, short, value_name = "ATTR", value_delimiter = ',')]
+    pub attributes: Vec<String>,
+    /// Print the number of matching objects per type instead of the objects
+    #[arg(long)]
+    pub count: bool,
 }
 
 pub fn handle(args: Print) -> Result<()> {
-    let file = File::open(args.path)?;
-    let reader = BufReader::new(file);
+    let filter_text = args.filter.join(" ");
+    let filter =
+        Filter::parse(&filter_text).with_context(|| format!("invalid filter '{}'", filter_text))?;
+    let types: Vec<_> = args.types.iter().map(|t| t.to_ascii_lowercase()).collect();
+    let attributes: Vec<_> = args
+        .attributes
+        .iter()
+        .map(|a| a.to_ascii_lowercase().replace('_', "-"))
+        .collect();
 
-    for res in parse_objects_read_iter(reader) {
+    let mut counts = BTreeMap::<String, usize>::new();
+
+    for res in super::read_objects(&args.path)? {
         let obj = res?;
-        if args.ip.is_empty() {
-            println!("{}", obj.to_rpsl());
+        if !types.is_empty() && !types.iter().any(|t| t == obj.obj_type().as_key()) {
+            continue;
+        }
+        // The typed object is only needed for IP lookups and normalized
+        // ranges; objects that fail to convert are matched as written.
+        let typed = if !args.ip.is_empty() || filter.needs_typed() {
+            RpslObject::try_from(obj.clone()).ok()
+        } else {
+            None
+        };
+        let matches = match &typed {
+            Some(typed) => filter.matches_typed(&obj, typed),
+            None => filter.matches(&obj),
+        };
+        if !matches {
+            continue;
+        }
+        if !args.ip.is_empty() && !typed.as_ref().is_some_and(|t| contains_ip(t, &args.ip)) {
             continue;
         }
 
-        match RpslObject::try_from(obj.clone()).unwrap_or(RpslObject::Other(obj.clone())) {
-            RpslObject::Inetnum(inet) => {
-                if args.ip.iter().any(|ip| match ip {
-                    IpAddr::V4(addr) => inet.inetnum.contains(addr),
-                    _ => false,
-                }) {
-                    println!("{}", obj.to_rpsl());
-                }
-            }
-            RpslObject::Inet6num(inet) => {
-                if args.ip.iter().any(|ip| match ip {
-                    IpAddr::V6(addr) => inet.inet6num.contains(addr),
-                    _ => false,
-                }) {
-                    println!("{}", obj.to_rpsl());
-                }
-            }
-            _ => {}
+        if args.count {
+            *counts
+                .entry(obj.obj_type().as_key().to_string())
+                .or_default() += 1;
+        } else if attributes.is_empty() {
+            println!("{}", obj.to_rpsl());
+        } else {
+            println!("{}", project(obj, &attributes).to_rpsl());
         }
     }
 
+    if args.count {
+        for (obj_type, count) in &counts {
+            println!("{}\t{}", obj_type, count);
+        }
+        println!("total\t{}", counts.values().sum::<usize>());
+    }
+
     Ok(())
 }
+
+/// Whether `obj` is an inetnum/inet6num containing one of `ips`.
+fn contains_ip(obj: &RpslObject, ips: &[IpAddr]) -> bool {
+    match obj {
+        RpslObject::Inetnum(inet) => ips.iter().any(|ip| match ip {
+            IpAddr::V4(addr) => inet.inetnum.contains(addr),
+            _ => false,
+        }),
+        RpslObject::Inet6num(inet) => ips.iter().any(|ip| match ip {
+            IpAddr::V6(addr) => inet.inet6num.contains(addr),
+            _ => false,
+        }),
+        _ => false,
+    }
+}
+
+/// Keep only the type attribute and `attributes` of `obj`.
+fn project(obj: Object, attributes: &[String]) -> Object {
+    let obj_type = obj.obj_type().clone();
+    let attrs = obj
+        .into_ordered_attributes()
+        .into_iter()
+        .enumerate()
+        .filter(|(i, a)| *i == 0 || attributes.iter().any(|k| k == a.key()))
+        .map(|(_, a)| a)
+        .collect();
+    Object::from_ordered_attributes(obj_type, attrs)
+}
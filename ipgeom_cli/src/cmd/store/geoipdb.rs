@@ -0,0 +1,87 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use ipgeom_rir::{
+    ConflictKind, GeoSourceKind, GeoipCandidate, GeoipFormat, GeoipOptions, RirKind, Store,
+    TieBreak,
+};
+
+#[derive(Args)]
+pub struct BuildGeoipdb {
+    /// Path of the GeoIP database file to create
+    pub path: PathBuf,
+    /// Data whose country wins where both sources cover a network:
+    /// `rpsl` or `delegated`
+    #[arg(long, value_name = "SOURCE", default_value = "rpsl")]
+    pub primary_source: GeoSourceKind,
+    /// Criterion compared first between candidates for the same network:
+    /// `source` or `registry`
+    #[arg(long, value_name = "CRITERION", default_value = "source")]
+    pub tie_break: TieBreak,
+    /// Registries in order of preference for the same network, e.g.
+    /// `ripe,arin`
+    #[arg(long, value_name = "RIRS", value_delimiter = ',')]
+    pub rir_priority: Vec<RirKind>,
+    /// Write the networks with conflicting countries to this file as
+    /// tab-separated values
+    #[arg(long, value_name = "PATH")]
+    pub conflicts: Option<PathBuf>,
+    /// Record schema: `country` for GeoIP2-Country records understood by
+    /// standard readers, or `minimal` for just the country code
+    #[arg(long, value_name = "FORMAT", default_value = "country")]
+    pub format: GeoipFormat,
+}
+
+pub fn handle(store: &Store, args: BuildGeoipdb) -> Result<()> {
+    let report = store.write_geoip_db(
+        &args.path,
+        GeoipOptions {
+            primary_source: args.primary_source,
+            tie_break: args.tie_break,
+            rir_priority: args.rir_priority,
+            format: args.format,
+        },
+    )?;
+    eprintln!(
+        "wrote {} networks, {} inherited from a parent network, {} from an organisation, {} without country",
+        report.networks,
+        report.inherited_from_parent,
+        report.inherited_from_org,
+        report.missing_country
+    );
+    eprintln!("{} conflicting countries", report.conflicts.len());
+
+    if let Some(path) = args.conflicts {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(
+            out,
+            "kind\tnetwork\tcountry\tsource\trir\tother_network\tother_country\tother_source\tother_rir"
+        )?;
+        for conflict in &report.conflicts {
+            let kind = match conflict.kind {
+                ConflictKind::SameNetwork => "same-network",
+                ConflictKind::Overlap => "overlap",
+            };
+            writeln!(
+                out,
+                "{kind}\t{}\t{}",
+                columns(&conflict.winner),
+                columns(&conflict.other)
+            )?;
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+fn columns(candidate: &GeoipCandidate) -> String {
+    format!(
+        "{}\t{}\t{}\t{}",
+        candidate.network,
+        candidate.country,
+        candidate.source,
+        candidate.rir.name()
+    )
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use ipgeom_rir::{RetentionPolicy, RirKind, Store};
+
+#[derive(Args)]
+pub struct Import {
+    /// Registry the dump belongs to
+    #[arg(long)]
+    pub rir: RirKind,
+    /// Dump files, plain or gzip-compressed, or directories of split
+    /// per-type dump files
+    #[arg(required_unless_present = "delegated")]
+    pub paths: Vec<PathBuf>,
+    /// Delegated-extended statistics file, plain or gzip-compressed
+    #[arg(long, value_name = "FILE")]
+    pub delegated: Option<PathBuf>,
+    /// Number of RPSL snapshots to keep per registry
+    #[arg(long, value_name = "N", default_value_t = RetentionPolicy::default().keep_snapshots)]
+    pub keep_snapshots: usize,
+}
+
+pub fn handle(store: Store, args: Import) -> Result<()> {
+    let store = store.with_retention(RetentionPolicy {
+        keep_snapshots: args.keep_snapshots,
+        ..Default::default()
+    });
+    if !args.paths.is_empty() {
+        match store.import_files(args.rir, &args.paths)? {
+            Some(snapshot) => eprintln!(
+                "imported {} objects from {} as snapshot {}",
+                snapshot.objects,
+                args.rir.name(),
+                snapshot.file
+            ),
+            None => eprintln!("{} dump is unchanged", args.rir.name()),
+        }
+    }
+    if let Some(path) = &args.delegated {
+        store.import_delegated_stats(args.rir, path)?;
+        eprintln!("imported delegated statistics for {}", args.rir.name());
+    }
+    Ok(())
+}
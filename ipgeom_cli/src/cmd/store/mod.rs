@@ -1,31 +1,147 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use anyhow::Result;
-use clap::Subcommand;
-use ipgeom_rir::Store;
+use clap::{Args, Subcommand};
+use ipgeom_rir::{FailurePolicy, RirKind, Store, StoreConfig};
 
+pub mod geoipdb;
+pub mod import;
+pub mod progress;
 pub mod sqlite;
 pub mod update;
 
+/// Options selecting the registries of the local store.
+#[derive(Args)]
+pub struct StoreArgs {
+    /// Registries to download and read, e.g. `ripe,afrinic` [default: all]
+    #[arg(
+        long,
+        global = true,
+        env = "IPGEOM_REGISTRIES",
+        value_name = "RIR",
+        value_delimiter = ','
+    )]
+    pub registries: Vec<RirKind>,
+    /// API key for ARIN bulk WHOIS access, required for ARIN network data
+    #[arg(
+        long,
+        global = true,
+        env = "IPGEOM_ARIN_API_KEY",
+        hide_env_values = true
+    )]
+    pub arin_api_key: Option<String>,
+    /// Download the files of a registry from a mirror, e.g.
+    /// `ripe=https://mirror.example.org/ripe`
+    #[arg(
+        long = "mirror",
+        global = true,
+        env = "IPGEOM_MIRRORS",
+        value_name = "RIR=URL",
+        value_delimiter = ',',
+        value_parser = parse_mirror
+    )]
+    pub mirrors: Vec<(RirKind, String)>,
+    /// What to do when updating a registry fails: `continue` or `abort`,
+    /// for all registries or for one, e.g. `ripe=abort`
+    #[arg(
+        long,
+        global = true,
+        value_name = "[RIR=]POLICY",
+        value_delimiter = ',',
+        value_parser = parse_failure_policy
+    )]
+    pub on_failure: Vec<(Option<RirKind>, FailurePolicy)>,
+}
+
+fn parse_failure_policy(value: &str) -> Result<(Option<RirKind>, FailurePolicy)> {
+    match value.split_once('=') {
+        Some((rir, policy)) => {
+            let rir = rir.parse().map_err(anyhow::Error::msg)?;
+            let policy = policy.parse().map_err(anyhow::Error::msg)?;
+            Ok((Some(rir), policy))
+        }
+        None => Ok((None, value.parse().map_err(anyhow::Error::msg)?)),
+    }
+}
+
+fn parse_mirror(value: &str) -> Result<(RirKind, String)> {
+    let Some((rir, url)) = value.split_once('=') else {
+        anyhow::bail!("expected RIR=URL");
+    };
+    let rir = rir.parse().map_err(anyhow::Error::msg)?;
+    Ok((rir, url.to_string()))
+}
+
+impl StoreArgs {
+    /// Open the store in `data_dir`.
+    pub fn open(&self, data_dir: PathBuf) -> Result<Store> {
+        let mut config = StoreConfig {
+            arin_api_key: self.arin_api_key.clone(),
+            mirrors: self.mirrors.iter().cloned().collect::<HashMap<_, _>>(),
+            ..Default::default()
+        };
+        if !self.registries.is_empty() {
+            config.registries = self.registries.clone();
+        }
+        for (rir, policy) in &self.on_failure {
+            match rir {
+                Some(rir) => {
+                    config.rir_failure_policies.insert(*rir, *policy);
+                }
+                None => config.failure_policy = *policy,
+            }
+        }
+        let bars = progress::ProgressBars::new();
+        Ok(Store::with_config(data_dir, config)?.with_progress(move |e| bars.handle(e)))
+    }
+}
+
 #[derive(Subcommand)]
 pub enum StoreCmd {
     /// Download database dumps from all RIRs
     Update(update::Update),
+    /// Import local dump files as if they had been downloaded
+    Import(import::Import),
     /// Build a MaxMind GeoIP database from stored RIR data
-    BuildGeoipdb {
-        /// Path of the GeoIP database file to create
-        path: PathBuf,
-    },
+    BuildGeoipdb(geoipdb::BuildGeoipdb),
     /// Populate a SQLite database from stored RIR data
     BuildSqlitedb(sqlite::SqliteDbCmd),
+    /// List the stored RPSL snapshots of each registry
+    Snapshots,
 }
 
-pub fn handle(data_dir: PathBuf, cmd: StoreCmd) -> Result<()> {
-    let store = Store::new(data_dir)?;
+pub fn handle(data_dir: PathBuf, store_args: &StoreArgs, cmd: StoreCmd) -> Result<()> {
+    let store = store_args.open(data_dir)?;
     match cmd {
-        StoreCmd::Update(args) => update::handle(&store, args)?,
-        StoreCmd::BuildGeoipdb { path } => store.write_geoip_db(path)?,
+        StoreCmd::Update(args) => update::handle(store, args)?,
+        StoreCmd::Import(args) => import::handle(store, args)?,
+        StoreCmd::BuildGeoipdb(args) => geoipdb::handle(&store, args)?,
         StoreCmd::BuildSqlitedb(args) => sqlite::handle(&store, args)?,
+        StoreCmd::Snapshots => {
+            for rir in RirKind::ALL {
+                let latest = store.latest_snapshot(rir)?.map(|s| s.file);
+                for snapshot in store.snapshots(rir)? {
+                    let marker = if Some(&snapshot.file) == latest.as_ref() {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!(
+                        "{marker} {}\t{}\t{}\t{} objects\t{} bytes\tserial {}\tsha256 {}",
+                        rir.name(),
+                        snapshot.file,
+                        snapshot.fetched_at,
+                        snapshot.objects,
+                        snapshot.size,
+                        snapshot
+                            .serial
+                            .map_or_else(|| "-".to_string(), |s| s.to_string()),
+                        snapshot.sha256
+                    );
+                }
+            }
+        }
     }
     Ok(())
 }
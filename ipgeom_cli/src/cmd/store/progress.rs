@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use ipgeom_rir::{Progress, ProgressEvent, ProgressStage};
+
+const BAR_WIDTH: usize = 20;
+
+/// Renders store progress events on stderr.
+///
+/// On a terminal the running stages are shown as progress bars on a single
+/// status line. Completed stages and failures are always printed.
+pub struct ProgressBars {
+    interactive: bool,
+    running: Mutex<BTreeMap<(&'static str, String), Progress>>,
+}
+
+impl ProgressBars {
+    pub fn new() -> Self {
+        Self {
+            interactive: std::io::stderr().is_terminal(),
+            running: Mutex::default(),
+        }
+    }
+
+    pub fn handle(&self, event: &ProgressEvent) {
+        let mut running = self.running.lock().unwrap();
+        let mut out = std::io::stderr().lock();
+        if self.interactive {
+            // Clear the status line.
+            let _ = write!(out, "\r\x1b[2K");
+        }
+        match event {
+            ProgressEvent::Progress(progress) => {
+                running.insert(key(progress), progress.clone());
+            }
+            ProgressEvent::Finished(progress) => {
+                running.remove(&key(progress));
+                let _ = writeln!(
+                    out,
+                    "{} {}: {} in {}",
+                    progress.rir.name(),
+                    progress.stage,
+                    amount(progress.stage, progress.done),
+                    duration(progress.elapsed)
+                );
+            }
+            ProgressEvent::Failed { rir, error } => {
+                running.retain(|(name, _), _| *name != rir.name());
+                let _ = writeln!(out, "{} failed: {error}", rir.name());
+            }
+        }
+        if self.interactive && !running.is_empty() {
+            let line: Vec<_> = running.values().map(bar).collect();
+            let _ = write!(out, "{}", line.join(" | "));
+        }
+        let _ = out.flush();
+    }
+}
+
+fn key(progress: &Progress) -> (&'static str, String) {
+    (progress.rir.name(), progress.stage.to_string())
+}
+
+fn bar(progress: &Progress) -> String {
+    let mut text = format!("{} {}", progress.rir.name(), progress.stage);
+    if let Some(fraction) = progress.fraction() {
+        let filled = (fraction * BAR_WIDTH as f64) as usize;
+        text.push_str(&format!(
+            " [{}{}] {:>3.0}%",
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            fraction * 100.0
+        ));
+    } else {
+        text.push_str(&format!(" {}", amount(progress.stage, progress.done)));
+    }
+    text.push_str(&format!(
+        " {}/s",
+        amount(progress.stage, progress.rate() as u64)
+    ));
+    if let Some(eta) = progress.eta() {
+        text.push_str(&format!(" ETA {}", duration(eta)));
+    }
+    text
+}
+
+fn amount(stage: ProgressStage, value: u64) -> String {
+    match stage {
+        ProgressStage::Download => {
+            let mut value = value as f64;
+            let mut unit = "B";
+            for next in ["KiB", "MiB", "GiB"] {
+                if value < 1024.0 {
+                    break;
+                }
+                value /= 1024.0;
+                unit = next;
+            }
+            format!("{value:.1} {unit}")
+        }
+        ProgressStage::Parse | ProgressStage::Persist => format!("{value} objects"),
+    }
+}
+
+fn duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{:.1}s", d.as_secs_f64())
+    }
+}
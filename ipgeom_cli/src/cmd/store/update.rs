@@ -1,11 +1,19 @@
 use anyhow::Result;
 use clap::Args;
-use ipgeom_rir::Store;
+use ipgeom_rir::{RetentionPolicy, Store};
 
 #[derive(Args)]
-pub struct Update {}
+pub struct Update {
+    /// Number of RPSL snapshots to keep per registry
+    #[arg(long, value_name = "N", default_value_t = RetentionPolicy::default().keep_snapshots)]
+    pub keep_snapshots: usize,
+}
 
-pub fn handle(store: &Store, _args: Update) -> Result<()> {
+pub fn handle(store: Store, args: Update) -> Result<()> {
+    let store = store.with_retention(RetentionPolicy {
+        keep_snapshots: args.keep_snapshots,
+        ..Default::default()
+    });
     store.update()?;
     Ok(())
 }
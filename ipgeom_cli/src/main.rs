@@ -12,6 +12,9 @@ struct Cli {
     #[arg(long, short, env = "IPGEOM_DATA_DIR", default_value = "data")]
     data_dir: PathBuf,
 
+    #[command(flatten)]
+    store: cmd::store::StoreArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -61,9 +64,15 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Store(cmd) => cmd::store::handle(cli.data_dir, cmd)?,
+        // The store uses a blocking HTTP client, which must not be created
+        // or dropped on a runtime thread.
+        Commands::Store(cmd) => {
+            tokio::task::block_in_place(|| cmd::store::handle(cli.data_dir, &cli.store, cmd))?
+        }
         Commands::Ipdb(cmd) => cmd::ipdb::handle(cmd)?,
-        Commands::Rpsl(cmd) => cmd::rpsl::handle(cmd)?,
+        Commands::Rpsl(cmd) => {
+            tokio::task::block_in_place(|| cmd::rpsl::handle(cli.data_dir, &cli.store, cmd))?
+        }
         Commands::Domain(cmd) => cmd::domain::handle(cmd).await?,
         Commands::Server(cmd) => cmd::server::handle(cmd).await?,
         Commands::Dns(cmd) => cmd::dns::handle(cmd).await?,
@@ -0,0 +1,368 @@
+//! Parent/child tree of `inetnum` and `inet6num` objects.
+
+use std::net::IpAddr;
+
+use ipgeom_rpsl::{InetStatus, RpslObject};
+
+use crate::db::object_key;
+
+/// Identifier of a node in an [`AllocationTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// An address block in an [`AllocationTree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocationNode {
+    pub first: IpAddr,
+    pub last: IpAddr,
+    /// Object type, `inetnum` or `inet6num`.
+    pub obj_type: &'static str,
+    /// Primary key of the object, as used by [`Database::get_object`](crate::Database::get_object).
+    pub obj_key: String,
+    pub netname: Option<String>,
+    pub status: Option<InetStatus>,
+    pub source: Option<String>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl AllocationNode {
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (addr, self.first, self.last) {
+            (IpAddr::V4(_), IpAddr::V4(_), IpAddr::V4(_))
+            | (IpAddr::V6(_), IpAddr::V6(_), IpAddr::V6(_)) => {
+                self.first <= addr && addr <= self.last
+            }
+            _ => false,
+        }
+    }
+
+    fn is_v4(&self) -> bool {
+        self.first.is_ipv4()
+    }
+
+    fn bounds(&self) -> (u128, u128) {
+        (addr_to_u128(self.first), addr_to_u128(self.last))
+    }
+}
+
+/// Containment hierarchy of address blocks.
+///
+/// Every block is a child of the smallest block that fully contains it.
+/// This answers questions like "which LIR allocation does this assignment
+/// belong to" ([`AllocationTree::allocation_of`]) and "which assignments
+/// were made from this block" ([`AllocationTree::sub_assignments`]).
+#[derive(Debug, Clone, Default)]
+pub struct AllocationTree {
+    nodes: Vec<AllocationNode>,
+    roots_v4: Vec<NodeId>,
+    roots_v6: Vec<NodeId>,
+}
+
+impl AllocationTree {
+    /// Build a tree from the `inetnum` and `inet6num` objects in `objects`.
+    ///
+    /// Other object types are ignored.
+    pub fn from_objects<I>(objects: I) -> Self
+    where
+        I: IntoIterator<Item = RpslObject>,
+    {
+        let mut nodes = Vec::new();
+        for obj in objects {
+            let node = match &obj {
+                RpslObject::Inetnum(inet) => {
+                    let first = inet.inetnum.iter().map(|n| n.network()).min();
+                    let last = inet.inetnum.iter().map(|n| n.broadcast()).max();
+                    let (Some(first), Some(last)) = (first, last) else {
+                        continue;
+                    };
+                    AllocationNode {
+                        first: first.into(),
+                        last: last.into(),
+                        obj_type: "inetnum",
+                        obj_key: object_key(&obj),
+                        netname: inet.netname.clone(),
+                        status: inet.status.clone(),
+                        source: inet.common.source.clone(),
+                        parent: None,
+                        children: Vec::new(),
+                    }
+                }
+                RpslObject::Inet6num(inet) => {
+                    let first = inet.inet6num.iter().map(|n| n.network()).min();
+                    let last = inet.inet6num.iter().map(|n| n.broadcast()).max();
+                    let (Some(first), Some(last)) = (first, last) else {
+                        continue;
+                    };
+                    AllocationNode {
+                        first: first.into(),
+                        last: last.into(),
+                        obj_type: "inet6num",
+                        obj_key: object_key(&obj),
+                        netname: inet.netname.clone(),
+                        status: inet.status.clone(),
+                        source: inet.common.source.clone(),
+                        parent: None,
+                        children: Vec::new(),
+                    }
+                }
+                _ => continue,
+            };
+            nodes.push(node);
+        }
+        Self::build(nodes)
+    }
+
+    fn build(mut nodes: Vec<AllocationNode>) -> Self {
+        // Sort by family, then start ascending and end descending, so every
+        // block comes after all blocks that contain it.
+        nodes.sort_by(|a, b| {
+            let (a_first, a_last) = a.bounds();
+            let (b_first, b_last) = b.bounds();
+            b.is_v4()
+                .cmp(&a.is_v4())
+                .then(a_first.cmp(&b_first))
+                .then(b_last.cmp(&a_last))
+        });
+
+        let mut tree = AllocationTree {
+            nodes,
+            roots_v4: Vec::new(),
+            roots_v6: Vec::new(),
+        };
+
+        let mut stack: Vec<NodeId> = Vec::new();
+        for idx in 0..tree.nodes.len() {
+            let id = NodeId(idx);
+            let (_, last) = tree.nodes[idx].bounds();
+            let is_v4 = tree.nodes[idx].is_v4();
+
+            while let Some(&top) = stack.last() {
+                let top_node = &tree.nodes[top.0];
+                let (_, top_last) = top_node.bounds();
+                if top_node.is_v4() == is_v4 && top_last >= last {
+                    break;
+                }
+                stack.pop();
+            }
+
+            match stack.last() {
+                Some(&parent) => {
+                    tree.nodes[idx].parent = Some(parent);
+                    tree.nodes[parent.0].children.push(id);
+                }
+                None if is_v4 => tree.roots_v4.push(id),
+                None => tree.roots_v6.push(id),
+            }
+            stack.push(id);
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: NodeId) -> &AllocationNode {
+        &self.nodes[id.0]
+    }
+
+    /// Top level blocks, IPv4 first.
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.roots_v4.iter().chain(self.roots_v6.iter()).copied()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    /// Direct children, ordered by start address.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
+    }
+
+    /// Ancestors of a block, from its parent up to the root.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), |id| self.parent(*id))
+    }
+
+    /// All blocks below a block, depth first.
+    pub fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        let mut out = Vec::new();
+        let mut pending: Vec<NodeId> = self.children(id).iter().rev().copied().collect();
+        while let Some(next) = pending.pop() {
+            out.push(next);
+            pending.extend(self.children(next).iter().rev().copied());
+        }
+        out
+    }
+
+    /// Find the block with exactly the given bounds.
+    pub fn find(&self, first: IpAddr, last: IpAddr) -> Option<NodeId> {
+        let mut candidate = self.lookup(first)?;
+        loop {
+            let node = self.node(candidate);
+            if node.first == first && node.last == last {
+                return Some(candidate);
+            }
+            candidate = self.parent(candidate)?;
+        }
+    }
+
+    /// The most specific block containing `addr`.
+    pub fn lookup(&self, addr: IpAddr) -> Option<NodeId> {
+        let mut level: &[NodeId] = match addr {
+            IpAddr::V4(_) => &self.roots_v4,
+            IpAddr::V6(_) => &self.roots_v6,
+        };
+        let mut found = None;
+        loop {
+            // Siblings are sorted by start address. The containing block is
+            // usually the last one starting at or before `addr`, but siblings
+            // may partially overlap, so keep looking further back.
+            let end = level.partition_point(|id| self.node(*id).first <= addr);
+            let Some(next) = level[..end]
+                .iter()
+                .rev()
+                .find(|id| self.node(**id).contains(addr))
+            else {
+                return found;
+            };
+            found = Some(*next);
+            level = self.children(*next);
+        }
+    }
+
+    /// The closest enclosing allocation of a block, e.g. the LIR allocation an
+    /// assignment was made from.
+    pub fn allocation_of(&self, id: NodeId) -> Option<NodeId> {
+        self.ancestors(id).find(|a| {
+            self.node(*a)
+                .status
+                .as_ref()
+                .is_some_and(InetStatus::is_allocation)
+        })
+    }
+
+    /// All assignments made from a block, including those made from its
+    /// sub-allocations.
+    pub fn sub_assignments(&self, id: NodeId) -> Vec<NodeId> {
+        self.descendants(id)
+            .into_iter()
+            .filter(|d| {
+                self.node(*d)
+                    .status
+                    .as_ref()
+                    .is_some_and(InetStatus::is_assignment)
+            })
+            .collect()
+    }
+}
+
+fn addr_to_u128(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(v4) => u32::from(v4) as u128,
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipgeom_rpsl::parse_objects;
+
+    fn tree(text: &str) -> AllocationTree {
+        AllocationTree::from_objects(
+            parse_objects(text)
+                .unwrap()
+                .into_iter()
+                .map(|o| RpslObject::try_from(o).unwrap()),
+        )
+    }
+
+    const DATA: &str = "\
+inetnum: 10.0.0.0 - 10.255.255.255
+netname: LIR-ALLOC
+status: ALLOCATED PA
+
+inetnum: 10.1.0.0 - 10.1.255.255
+netname: SUB-ALLOC
+status: SUB-ALLOCATED PA
+
+inetnum: 10.1.2.0 - 10.1.2.255
+netname: CUSTOMER-A
+status: ASSIGNED PA
+
+inetnum: 10.2.0.0 - 10.2.0.255
+netname: CUSTOMER-B
+status: ASSIGNED PA
+
+inetnum: 192.0.2.0 - 192.0.2.255
+netname: LEGACY-NET
+status: LEGACY
+
+inet6num: 2001:db8::/32
+netname: V6-ALLOC
+status: ALLOCATED-BY-RIR
+
+inet6num: 2001:db8:1::/48
+netname: V6-ASSIGN
+status: ASSIGNED
+";
+
+    #[test]
+    fn builds_hierarchy() {
+        let tree = tree(DATA);
+        assert_eq!(tree.len(), 7);
+        let roots: Vec<_> = tree
+            .roots()
+            .map(|id| tree.node(id).netname.clone().unwrap())
+            .collect();
+        assert_eq!(roots, ["LIR-ALLOC", "LEGACY-NET", "V6-ALLOC"]);
+
+        let customer = tree.lookup("10.1.2.3".parse().unwrap()).unwrap();
+        assert_eq!(tree.node(customer).netname.as_deref(), Some("CUSTOMER-A"));
+        let chain: Vec<_> = tree
+            .ancestors(customer)
+            .map(|id| tree.node(id).netname.clone().unwrap())
+            .collect();
+        assert_eq!(chain, ["SUB-ALLOC", "LIR-ALLOC"]);
+
+        let v6 = tree.lookup("2001:db8:1::1".parse().unwrap()).unwrap();
+        assert_eq!(tree.node(v6).netname.as_deref(), Some("V6-ASSIGN"));
+        assert!(tree.lookup("172.16.0.1".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn allocation_and_sub_assignments() {
+        let tree = tree(DATA);
+        let customer = tree.lookup("10.2.0.1".parse().unwrap()).unwrap();
+        let alloc = tree.allocation_of(customer).unwrap();
+        assert_eq!(tree.node(alloc).netname.as_deref(), Some("LIR-ALLOC"));
+
+        let customer = tree.lookup("10.1.2.1".parse().unwrap()).unwrap();
+        let alloc = tree.allocation_of(customer).unwrap();
+        assert_eq!(tree.node(alloc).netname.as_deref(), Some("SUB-ALLOC"));
+
+        let lir = tree
+            .find(
+                "10.0.0.0".parse().unwrap(),
+                "10.255.255.255".parse().unwrap(),
+            )
+            .unwrap();
+        let assignments: Vec<_> = tree
+            .sub_assignments(lir)
+            .into_iter()
+            .map(|id| tree.node(id).netname.clone().unwrap())
+            .collect();
+        assert_eq!(assignments, ["CUSTOMER-A", "CUSTOMER-B"]);
+        assert_eq!(tree.descendants(lir).len(), 3);
+
+        let legacy = tree.lookup("192.0.2.1".parse().unwrap()).unwrap();
+        assert!(tree.allocation_of(legacy).is_none());
+    }
+}
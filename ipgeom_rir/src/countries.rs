@@ -0,0 +1,412 @@
+//! ISO 3166-1 country names and continents.
+//!
+//! Continents follow the assignment used by GeoNames and GeoIP2 databases,
+//! e.g. Russia is in Europe and Türkiye in Asia.
+
+/// A continent as used in GeoIP2 databases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Continent {
+    Africa,
+    Antarctica,
+    Asia,
+    Europe,
+    NorthAmerica,
+    Oceania,
+    SouthAmerica,
+}
+
+impl Continent {
+    /// Two-letter continent code, e.g. `EU`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Africa => "AF",
+            Self::Antarctica => "AN",
+            Self::Asia => "AS",
+            Self::Europe => "EU",
+            Self::NorthAmerica => "NA",
+            Self::Oceania => "OC",
+            Self::SouthAmerica => "SA",
+        }
+    }
+
+    /// English name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Africa => "Africa",
+            Self::Antarctica => "Antarctica",
+            Self::Asia => "Asia",
+            Self::Europe => "Europe",
+            Self::NorthAmerica => "North America",
+            Self::Oceania => "Oceania",
+            Self::SouthAmerica => "South America",
+        }
+    }
+
+    /// Continent of the pseudo-codes the registries use instead of a
+    /// country, e.g. `EU` in RIPE data and `AP` in APNIC data.
+    ///
+    /// GeoIP2 databases assign these to their continent.
+    pub fn from_region_code(code: &str) -> Option<Self> {
+        match code.to_ascii_uppercase().as_str() {
+            "EU" => Some(Self::Europe),
+            "AP" => Some(Self::Asia),
+            _ => None,
+        }
+    }
+
+    /// GeoNames ID of the continent.
+    pub fn geoname_id(self) -> u32 {
+        match self {
+            Self::Africa => 6255146,
+            Self::Asia => 6255147,
+            Self::Europe => 6255148,
+            Self::NorthAmerica => 6255149,
+            Self::SouthAmerica => 6255150,
+            Self::Oceania => 6255151,
+            Self::Antarctica => 6255152,
+        }
+    }
+}
+
+/// A country of the ISO 3166-1 table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Country {
+    /// ISO 3166-1 alpha-2 code, e.g. `DE`.
+    pub iso_code: &'static str,
+    /// English short name.
+    pub name: &'static str,
+    pub continent: Continent,
+    /// GeoNames ID, used by GeoIP2 databases to join with GeoNames data.
+    pub geoname_id: u32,
+}
+
+impl Country {
+    /// Look up a country by its alpha-2 code, ignoring case.
+    pub fn from_iso_code(code: &str) -> Option<&'static Country> {
+        let code = code.to_ascii_uppercase();
+        COUNTRIES
+            .binary_search_by(|c| c.iso_code.cmp(&code))
+            .ok()
+            .map(|i| &COUNTRIES[i])
+    }
+
+    /// Whether the country is a member state of the European Union.
+    pub fn is_in_european_union(&self) -> bool {
+        EU_MEMBERS.contains(&self.iso_code)
+    }
+}
+
+const EU_MEMBERS: &[&str] = &[
+    "AT", "BE", "BG", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU", "IE", "IT",
+    "LT", "LU", "LV", "MT", "NL", "PL", "PT", "RO", "SE", "SI", "SK",
+];
+
+const fn c(
+    iso_code: &'static str,
+    name: &'static str,
+    continent: Continent,
+    geoname_id: u32,
+) -> Country {
+    Country {
+        iso_code,
+        name,
+        continent,
+        geoname_id,
+    }
+}
+
+use Continent::{
+    Africa as AF, Antarctica as AN, Asia as AS, Europe as EU, NorthAmerica as NA, Oceania as OC,
+    SouthAmerica as SA,
+};
+
+/// All countries, sorted by code.
+static COUNTRIES: &[Country] = &[
+    c("AD", "Andorra", EU, 3041565),
+    c("AE", "United Arab Emirates", AS, 290557),
+    c("AF", "Afghanistan", AS, 1149361),
+    c("AG", "Antigua and Barbuda", NA, 3576396),
+    c("AI", "Anguilla", NA, 3573511),
+    c("AL", "Albania", EU, 783754),
+    c("AM", "Armenia", AS, 174982),
+    c("AO", "Angola", AF, 3351879),
+    c("AQ", "Antarctica", AN, 6697173),
+    c("AR", "Argentina", SA, 3865483),
+    c("AS", "American Samoa", OC, 5880801),
+    c("AT", "Austria", EU, 2782113),
+    c("AU", "Australia", OC, 2077456),
+    c("AW", "Aruba", NA, 3577279),
+    c("AX", "Åland Islands", EU, 661882),
+    c("AZ", "Azerbaijan", AS, 587116),
+    c("BA", "Bosnia and Herzegovina", EU, 3277605),
+    c("BB", "Barbados", NA, 3374084),
+    c("BD", "Bangladesh", AS, 1210997),
+    c("BE", "Belgium", EU, 2802361),
+    c("BF", "Burkina Faso", AF, 2361809),
+    c("BG", "Bulgaria", EU, 732800),
+    c("BH", "Bahrain", AS, 290291),
+    c("BI", "Burundi", AF, 433561),
+    c("BJ", "Benin", AF, 2395170),
+    c("BL", "Saint Barthélemy", NA, 3578476),
+    c("BM", "Bermuda", NA, 3573345),
+    c("BN", "Brunei", AS, 1820814),
+    c("BO", "Bolivia", SA, 3923057),
+    c("BQ", "Bonaire, Sint Eustatius, and Saba", NA, 7626844),
+    c("BR", "Brazil", SA, 3469034),
+    c("BS", "Bahamas", NA, 3572887),
+    c("BT", "Bhutan", AS, 1252634),
+    c("BV", "Bouvet Island", AN, 3371123),
+    c("BW", "Botswana", AF, 933860),
+    c("BY", "Belarus", EU, 630336),
+    c("BZ", "Belize", NA, 3582678),
+    c("CA", "Canada", NA, 6251999),
+    c("CC", "Cocos (Keeling) Islands", AS, 1547376),
+    c("CD", "DR Congo", AF, 203312),
+    c("CF", "Central African Republic", AF, 239880),
+    c("CG", "Congo Republic", AF, 2260494),
+    c("CH", "Switzerland", EU, 2658434),
+    c("CI", "Ivory Coast", AF, 2287781),
+    c("CK", "Cook Islands", OC, 1899402),
+    c("CL", "Chile", SA, 3895114),
+    c("CM", "Cameroon", AF, 2233387),
+    c("CN", "China", AS, 1814991),
+    c("CO", "Colombia", SA, 3686110),
+    c("CR", "Costa Rica", NA, 3624060),
+    c("CU", "Cuba", NA, 3562981),
+    c("CV", "Cabo Verde", AF, 3374766),
+    c("CW", "Curaçao", NA, 7626836),
+    c("CX", "Christmas Island", OC, 2078138),
+    c("CY", "Cyprus", EU, 146669),
+    c("CZ", "Czechia", EU, 3077311),
+    c("DE", "Germany", EU, 2921044),
+    c("DJ", "Djibouti", AF, 223816),
+    c("DK", "Denmark", EU, 2623032),
+    c("DM", "Dominica", NA, 3575830),
+    c("DO", "Dominican Republic", NA, 3508796),
+    c("DZ", "Algeria", AF, 2589581),
+    c("EC", "Ecuador", SA, 3658394),
+    c("EE", "Estonia", EU, 453733),
+    c("EG", "Egypt", AF, 357994),
+    c("EH", "Western Sahara", AF, 2461445),
+    c("ER", "Eritrea", AF, 338010),
+    c("ES", "Spain", EU, 2510769),
+    c("ET", "Ethiopia", AF, 337996),
+    c("FI", "Finland", EU, 660013),
+    c("FJ", "Fiji", OC, 2205218),
+    c("FK", "Falkland Islands", SA, 3474414),
+    c("FM", "Micronesia", OC, 2081918),
+    c("FO", "Faroe Islands", EU, 2622320),
+    c("FR", "France", EU, 3017382),
+    c("GA", "Gabon", AF, 2400553),
+    c("GB", "United Kingdom", EU, 2635167),
+    c("GD", "Grenada", NA, 3580239),
+    c("GE", "Georgia", AS, 614540),
+    c("GF", "French Guiana", SA, 3381670),
+    c("GG", "Guernsey", EU, 3042362),
+    c("GH", "Ghana", AF, 2300660),
+    c("GI", "Gibraltar", EU, 2411586),
+    c("GL", "Greenland", NA, 3425505),
+    c("GM", "Gambia", AF, 2413451),
+    c("GN", "Guinea", AF, 2420477),
+    c("GP", "Guadeloupe", NA, 3579143),
+    c("GQ", "Equatorial Guinea", AF, 2309096),
+    c("GR", "Greece", EU, 390903),
+    c(
+        "GS",
+        "South Georgia and the South Sandwich Islands",
+        AN,
+        3474415,
+    ),
+    c("GT", "Guatemala", NA, 3595528),
+    c("GU", "Guam", OC, 4043988),
+    c("GW", "Guinea-Bissau", AF, 2372248),
+    c("GY", "Guyana", SA, 3378535),
+    c("HK", "Hong Kong", AS, 1819730),
+    c("HM", "Heard Island and McDonald Islands", AN, 1547314),
+    c("HN", "Honduras", NA, 3608932),
+    c("HR", "Croatia", EU, 3202326),
+    c("HT", "Haiti", NA, 3723988),
+    c("HU", "Hungary", EU, 719819),
+    c("ID", "Indonesia", AS, 1643084),
+    c("IE", "Ireland", EU, 2963597),
+    c("IL", "Israel", AS, 294640),
+    c("IM", "Isle of Man", EU, 3042225),
+    c("IN", "India", AS, 1269750),
+    c("IO", "British Indian Ocean Territory", AS, 1282588),
+    c("IQ", "Iraq", AS, 99237),
+    c("IR", "Iran", AS, 130758),
+    c("IS", "Iceland", EU, 2629691),
+    c("IT", "Italy", EU, 3175395),
+    c("JE", "Jersey", EU, 3042142),
+    c("JM", "Jamaica", NA, 3489940),
+    c("JO", "Jordan", AS, 248816),
+    c("JP", "Japan", AS, 1861060),
+    c("KE", "Kenya", AF, 192950),
+    c("KG", "Kyrgyzstan", AS, 1527747),
+    c("KH", "Cambodia", AS, 1831722),
+    c("KI", "Kiribati", OC, 4030945),
+    c("KM", "Comoros", AF, 921929),
+    c("KN", "Saint Kitts and Nevis", NA, 3575174),
+    c("KP", "North Korea", AS, 1873107),
+    c("KR", "South Korea", AS, 1835841),
+    c("KW", "Kuwait", AS, 285570),
+    c("KY", "Cayman Islands", NA, 3580718),
+    c("KZ", "Kazakhstan", AS, 1522867),
+    c("LA", "Laos", AS, 1655842),
+    c("LB", "Lebanon", AS, 272103),
+    c("LC", "Saint Lucia", NA, 3576468),
+    c("LI", "Liechtenstein", EU, 3042058),
+    c("LK", "Sri Lanka", AS, 1227603),
+    c("LR", "Liberia", AF, 2275384),
+    c("LS", "Lesotho", AF, 932692),
+    c("LT", "Lithuania", EU, 597427),
+    c("LU", "Luxembourg", EU, 2960313),
+    c("LV", "Latvia", EU, 458258),
+    c("LY", "Libya", AF, 2215636),
+    c("MA", "Morocco", AF, 2542007),
+    c("MC", "Monaco", EU, 2993457),
+    c("MD", "Moldova", EU, 617790),
+    c("ME", "Montenegro", EU, 3194884),
+    c("MF", "Saint Martin", NA, 3578421),
+    c("MG", "Madagascar", AF, 1062947),
+    c("MH", "Marshall Islands", OC, 2080185),
+    c("MK", "North Macedonia", EU, 718075),
+    c("ML", "Mali", AF, 2453866),
+    c("MM", "Myanmar", AS, 1327865),
+    c("MN", "Mongolia", AS, 2029969),
+    c("MO", "Macao", AS, 1821275),
+    c("MP", "Northern Mariana Islands", OC, 4041468),
+    c("MQ", "Martinique", NA, 3570311),
+    c("MR", "Mauritania", AF, 2378080),
+    c("MS", "Montserrat", NA, 3578097),
+    c("MT", "Malta", EU, 2562770),
+    c("MU", "Mauritius", AF, 934292),
+    c("MV", "Maldives", AS, 1282028),
+    c("MW", "Malawi", AF, 927384),
+    c("MX", "Mexico", NA, 3996063),
+    c("MY", "Malaysia", AS, 1733045),
+    c("MZ", "Mozambique", AF, 1036973),
+    c("NA", "Namibia", AF, 3355338),
+    c("NC", "New Caledonia", OC, 2139685),
+    c("NE", "Niger", AF, 2440476),
+    c("NF", "Norfolk Island", OC, 2155115),
+    c("NG", "Nigeria", AF, 2328926),
+    c("NI", "Nicaragua", NA, 3617476),
+    c("NL", "Netherlands", EU, 2750405),
+    c("NO", "Norway", EU, 3144096),
+    c("NP", "Nepal", AS, 1282988),
+    c("NR", "Nauru", OC, 2110425),
+    c("NU", "Niue", OC, 4036232),
+    c("NZ", "New Zealand", OC, 2186224),
+    c("OM", "Oman", AS, 286963),
+    c("PA", "Panama", NA, 3703430),
+    c("PE", "Peru", SA, 3932488),
+    c("PF", "French Polynesia", OC, 4030656),
+    c("PG", "Papua New Guinea", OC, 2088628),
+    c("PH", "Philippines", AS, 1694008),
+    c("PK", "Pakistan", AS, 1168579),
+    c("PL", "Poland", EU, 798544),
+    c("PM", "Saint Pierre and Miquelon", NA, 3424932),
+    c("PN", "Pitcairn Islands", OC, 4030699),
+    c("PR", "Puerto Rico", NA, 4566966),
+    c("PS", "Palestine", AS, 6254930),
+    c("PT", "Portugal", EU, 2264397),
+    c("PW", "Palau", OC, 1559582),
+    c("PY", "Paraguay", SA, 3437598),
+    c("QA", "Qatar", AS, 289688),
+    c("RE", "Réunion", AF, 935317),
+    c("RO", "Romania", EU, 798549),
+    c("RS", "Serbia", EU, 6290252),
+    c("RU", "Russia", EU, 2017370),
+    c("RW", "Rwanda", AF, 49518),
+    c("SA", "Saudi Arabia", AS, 102358),
+    c("SB", "Solomon Islands", OC, 2103350),
+    c("SC", "Seychelles", AF, 241170),
+    c("SD", "Sudan", AF, 366755),
+    c("SE", "Sweden", EU, 2661886),
+    c("SG", "Singapore", AS, 1880251),
+    c("SH", "Saint Helena", AF, 3370751),
+    c("SI", "Slovenia", EU, 3190538),
+    c("SJ", "Svalbard and Jan Mayen", EU, 607072),
+    c("SK", "Slovakia", EU, 3057568),
+    c("SL", "Sierra Leone", AF, 2403846),
+    c("SM", "San Marino", EU, 3168068),
+    c("SN", "Senegal", AF, 2245662),
+    c("SO", "Somalia", AF, 51537),
+    c("SR", "Suriname", SA, 3382998),
+    c("SS", "South Sudan", AF, 7909807),
+    c("ST", "São Tomé and Príncipe", AF, 2410758),
+    c("SV", "El Salvador", NA, 3585968),
+    c("SX", "Sint Maarten", NA, 7609695),
+    c("SY", "Syria", AS, 163843),
+    c("SZ", "Eswatini", AF, 934841),
+    c("TC", "Turks and Caicos Islands", NA, 3576916),
+    c("TD", "Chad", AF, 2434508),
+    c("TF", "French Southern Territories", AN, 1546748),
+    c("TG", "Togo", AF, 2363686),
+    c("TH", "Thailand", AS, 1605651),
+    c("TJ", "Tajikistan", AS, 1220409),
+    c("TK", "Tokelau", OC, 4031074),
+    c("TL", "Timor-Leste", OC, 1966436),
+    c("TM", "Turkmenistan", AS, 1218197),
+    c("TN", "Tunisia", AF, 2464461),
+    c("TO", "Tonga", OC, 4032283),
+    c("TR", "Türkiye", AS, 298795),
+    c("TT", "Trinidad and Tobago", NA, 3573591),
+    c("TV", "Tuvalu", OC, 2110297),
+    c("TW", "Taiwan", AS, 1668284),
+    c("TZ", "Tanzania", AF, 149590),
+    c("UA", "Ukraine", EU, 690791),
+    c("UG", "Uganda", AF, 226074),
+    c("UM", "United States Minor Outlying Islands", OC, 5854968),
+    c("US", "United States", NA, 6252001),
+    c("UY", "Uruguay", SA, 3439705),
+    c("UZ", "Uzbekistan", AS, 1512440),
+    c("VA", "Vatican City", EU, 3164670),
+    c("VC", "Saint Vincent and the Grenadines", NA, 3577815),
+    c("VE", "Venezuela", SA, 3625428),
+    c("VG", "British Virgin Islands", NA, 3577718),
+    c("VI", "United States Virgin Islands", NA, 4796775),
+    c("VN", "Vietnam", AS, 1562822),
+    c("VU", "Vanuatu", OC, 2134431),
+    c("WF", "Wallis and Futuna", OC, 4034749),
+    c("WS", "Samoa", OC, 4034894),
+    // User-assigned code used for Kosovo by the registries.
+    c("XK", "Kosovo", EU, 831053),
+    c("YE", "Yemen", AS, 69543),
+    c("YT", "Mayotte", AF, 1024031),
+    c("ZA", "South Africa", AF, 953987),
+    c("ZM", "Zambia", AF, 895949),
+    c("ZW", "Zimbabwe", AF, 878675),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn country_table() {
+        assert!(COUNTRIES.windows(2).all(|w| w[0].iso_code < w[1].iso_code));
+        assert_eq!(COUNTRIES.len(), 250);
+
+        let de = Country::from_iso_code("de").unwrap();
+        assert_eq!((de.name, de.continent.code()), ("Germany", "EU"));
+        assert!(de.is_in_european_union());
+        let us = Country::from_iso_code("US").unwrap();
+        assert_eq!(us.continent.name(), "North America");
+        assert!(!us.is_in_european_union());
+        // Placeholder codes of the registries are not countries.
+        assert_eq!(Country::from_iso_code("ZZ"), None);
+        assert_eq!(Country::from_iso_code("EU"), None);
+        assert_eq!(Continent::from_region_code("eu"), Some(Continent::Europe));
+        assert_eq!(Continent::from_region_code("AP"), Some(Continent::Asia));
+        assert_eq!(Continent::from_region_code("ZZ"), None);
+
+        assert_eq!(de.geoname_id, 2921044);
+        assert_eq!(us.geoname_id, 6252001);
+        let mut ids: Vec<_> = COUNTRIES.iter().map(|c| c.geoname_id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), COUNTRIES.len());
+    }
+}
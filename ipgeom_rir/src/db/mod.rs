@@ -2,6 +2,75 @@ use std::net::{Ipv4Addr, Ipv6Addr};
 
 use ipgeom_rpsl::RpslObject;
 
+use crate::delegated::Delegation;
+use crate::types::Rir;
+
+/// Kind of data a country mapping is derived from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GeoSourceKind {
+    /// `inetnum` and `inet6num` objects of the RPSL databases.
+    #[default]
+    Rpsl,
+    /// Delegated-extended statistics files.
+    Delegated,
+}
+
+impl GeoSourceKind {
+    /// The other source.
+    pub fn other(self) -> Self {
+        match self {
+            Self::Rpsl => Self::Delegated,
+            Self::Delegated => Self::Rpsl,
+        }
+    }
+}
+
+impl std::fmt::Display for GeoSourceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rpsl => write!(f, "rpsl"),
+            Self::Delegated => write!(f, "delegated"),
+        }
+    }
+}
+
+impl std::str::FromStr for GeoSourceKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rpsl" => Ok(Self::Rpsl),
+            "delegated" => Ok(Self::Delegated),
+            _ => Err("Invalid geo source, expected `rpsl` or `delegated`"),
+        }
+    }
+}
+
+/// The record that decided the country of an address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeoSource {
+    /// An `inetnum` or `inet6num` object.
+    Rpsl { obj_type: String, obj_key: String },
+    /// A delegated-extended statistics record.
+    Delegated { registry: Rir, status: String },
+}
+
+impl GeoSource {
+    pub fn kind(&self) -> GeoSourceKind {
+        match self {
+            Self::Rpsl { .. } => GeoSourceKind::Rpsl,
+            Self::Delegated { .. } => GeoSourceKind::Delegated,
+        }
+    }
+}
+
+/// Result of a geolocation lookup together with its provenance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeoMatch {
+    pub country: String,
+    pub source: GeoSource,
+}
+
 /// Trait describing database backends that can store RPSL information and
 /// provide IP geolocation lookups.
 pub trait Database: Send + Sync {
@@ -19,14 +88,108 @@ pub trait Database: Send + Sync {
     // NOTE: batch inserts with multiple objects are important for performance
     fn upsert_rpsl_objects(&self, objs: &[RpslObject]) -> Result<(), anyhow::Error>;
 
-    /// Fetch the raw JSON for an RPSL object by its type and key.
-    fn get_object(&self, obj_type: &str, obj_key: &str) -> Result<Option<String>, anyhow::Error>;
+    /// Delete an RPSL object and its geoip mappings.
+    ///
+    /// Returns `false` if no such object was stored.
+    fn delete_rpsl_object(&self, obj_type: &str, obj_key: &str) -> Result<bool, anyhow::Error>;
+
+    /// Delete all RPSL objects of a source and their geoip mappings.
+    ///
+    /// Returns the number of deleted objects.
+    fn delete_rpsl_objects_by_source(&self, source: &str) -> Result<usize, anyhow::Error>;
+
+    /// The last NRTM serial applied for a source, if any.
+    fn nrtm_serial(&self, source: &str) -> Result<Option<u64>, anyhow::Error>;
+
+    /// Record the last NRTM serial applied for a source.
+    fn set_nrtm_serial(&self, source: &str, serial: u64) -> Result<(), anyhow::Error>;
+
+    /// The NRTMv4 session ID and last applied version for a source, if any.
+    fn nrtm_v4_state(&self, source: &str) -> Result<Option<(String, u64)>, anyhow::Error>;
+
+    /// Record the NRTMv4 session ID and last applied version for a source.
+    fn set_nrtm_v4_state(
+        &self,
+        source: &str,
+        session_id: &str,
+        version: u64,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Replace all RPSL objects of a source with an NRTMv4 snapshot and record
+    /// the snapshot's session ID and version.
+    ///
+    /// This happens atomically: if `objects` yields an error, the previous
+    /// objects and NRTMv4 state are kept.
+    ///
+    /// Returns the number of deleted objects.
+    fn load_nrtm_v4_snapshot(
+        &self,
+        source: &str,
+        session_id: &str,
+        version: u64,
+        objects: &mut dyn Iterator<Item = Result<RpslObject, anyhow::Error>>,
+    ) -> Result<usize, anyhow::Error>;
+
+    /// Fetch an RPSL object by its type and key.
+    fn get_object(
+        &self,
+        obj_type: &str,
+        obj_key: &str,
+    ) -> Result<Option<RpslObject>, anyhow::Error>;
+
+    /// Replace all delegated-extended statistics records of a registry.
+    ///
+    /// Returns the number of stored records.
+    fn replace_delegations(
+        &self,
+        registry: Rir,
+        delegations: &[Delegation],
+    ) -> Result<usize, anyhow::Error>;
+
+    /// Find the delegation record covering an AS number.
+    fn lookup_asn_delegation(&self, asn: u32) -> Result<Option<Delegation>, anyhow::Error>;
+
+    /// Perform a lookup for an IPv4 address and report which record decided
+    /// the country.
+    ///
+    /// The most specific match of either source wins. For matches of the
+    /// same network the `primary` source wins, like in generated GeoIP
+    /// databases.
+    fn lookup_ipv4_match(
+        &self,
+        addr: Ipv4Addr,
+        primary: GeoSourceKind,
+    ) -> Result<Option<GeoMatch>, anyhow::Error>;
+
+    /// Perform a lookup for an IPv6 address and report which record decided
+    /// the country.
+    ///
+    /// See [`Database::lookup_ipv4_match`].
+    fn lookup_ipv6_match(
+        &self,
+        addr: Ipv6Addr,
+        primary: GeoSourceKind,
+    ) -> Result<Option<GeoMatch>, anyhow::Error>;
 
     /// Perform a lookup for an IPv4 address. Returns the country code if found.
-    fn lookup_ipv4(&self, addr: Ipv4Addr) -> Result<Option<String>, anyhow::Error>;
+    ///
+    /// RPSL data takes precedence over delegated statistics for the same
+    /// network.
+    fn lookup_ipv4(&self, addr: Ipv4Addr) -> Result<Option<String>, anyhow::Error> {
+        Ok(self
+            .lookup_ipv4_match(addr, GeoSourceKind::Rpsl)?
+            .map(|m| m.country))
+    }
 
     /// Perform a lookup for an IPv6 address. Returns the country code if found.
-    fn lookup_ipv6(&self, addr: Ipv6Addr) -> Result<Option<String>, anyhow::Error>;
+    ///
+    /// RPSL data takes precedence over delegated statistics for the same
+    /// network.
+    fn lookup_ipv6(&self, addr: Ipv6Addr) -> Result<Option<String>, anyhow::Error> {
+        Ok(self
+            .lookup_ipv6_match(addr, GeoSourceKind::Rpsl)?
+            .map(|m| m.country))
+    }
 
     /// Perform a lookup for an IPv4 address and return all matching country codes.
     fn lookup_ipv4_all(&self, addr: Ipv4Addr) -> Result<Vec<String>, anyhow::Error>;
@@ -73,11 +236,20 @@ pub fn object_key(obj: &RpslObject) -> String {
         RpslObject::Mntner(m) => m.mntner.clone(),
         RpslObject::Route(r) => range_to_string(&r.route),
         RpslObject::Route6(r) => range_to_string(&r.route6),
+        RpslObject::AsSet(s) => s.as_set.clone(),
+        RpslObject::RouteSet(s) => s.route_set.clone(),
+        RpslObject::RtrSet(s) => s.rtr_set.clone(),
+        RpslObject::Domain(d) => d.domain.clone(),
+        RpslObject::InetRtr(r) => r.inet_rtr.clone(),
+        RpslObject::Irt(i) => i.irt.clone(),
+        RpslObject::KeyCert(k) => k.key_cert.clone(),
+        RpslObject::AsBlock(b) => b.as_block.clone(),
+        RpslObject::PeeringSet(s) => s.peering_set.clone(),
+        RpslObject::FilterSet(s) => s.filter_set.clone(),
         RpslObject::Other(o) => o
-            .attributes()
-            .iter()
-            .next()
-            .map(|(k, v)| format!("{k}:{}", v.first().cloned().unwrap_or_default()))
+            .ordered_attributes()
+            .first()
+            .map(|a| format!("{}:{}", a.key(), a.value()))
             .unwrap_or_else(|| "other".to_string()),
     }
 }
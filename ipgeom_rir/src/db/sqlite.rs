@@ -1,10 +1,14 @@
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-use super::{object_key, Database};
-use ipgeom_rpsl::{ObjectType, RpslObject};
-use rusqlite::{params, OptionalExtension};
+use super::{object_key, Database, GeoMatch, GeoSource, GeoSourceKind};
+use crate::delegated::{DelegatedResource, Delegation};
+use crate::types::Rir;
+use ipgeom_rpsl::RpslObject;
+use ipnet::{IpNet, Ipv4Subnets, Ipv6Subnets};
+use rusqlite::types::Value;
+use rusqlite::{params, OptionalExtension, ToSql};
 
 /// Simple SQLite implementation of [`Database`].
 #[derive(Debug, Clone)]
@@ -81,27 +85,9 @@ impl SqliteDb {
         tx: &rusqlite::Transaction<'_>,
         obj: &RpslObject,
     ) -> Result<(), anyhow::Error> {
-        let (obj_type, source, json) = match obj {
-            RpslObject::Inetnum(i) => ("inetnum", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::Inet6num(i) => ("inet6num", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::AutNum(i) => ("aut-num", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::Person(i) => ("person", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::Role(i) => ("role", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::Organisation(i) => {
-                ("organisation", i.source.clone(), serde_json::to_string(i)?)
-            }
-            RpslObject::Mntner(i) => ("mntner", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::Route(i) => ("route", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::Route6(i) => ("route6", i.source.clone(), serde_json::to_string(i)?),
-            RpslObject::Other(o) => (
-                match o.obj_type() {
-                    ObjectType::Other(name) => name.as_str(),
-                    _ => "other",
-                },
-                None,
-                serde_json::to_string(o)?,
-            ),
-        };
+        let obj_type = obj.type_name();
+        let source = obj.source();
+        let json = obj.to_json()?;
         let key = object_key(obj);
         tx
             .prepare_cached(
@@ -154,6 +140,229 @@ impl SqliteDb {
         }
         Ok(())
     }
+
+    fn delete_rpsl_objects_by_source_tx(
+        &self,
+        tx: &rusqlite::Transaction<'_>,
+        source: &str,
+    ) -> Result<usize, anyhow::Error> {
+        for table in ["ipv4_geo", "ipv6_geo"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {table} WHERE obj_id IN \
+                     (SELECT id FROM rpsl WHERE source = ?1 COLLATE NOCASE)"
+                ),
+                [source],
+            )?;
+        }
+        Ok(tx.execute(
+            "DELETE FROM rpsl WHERE source = ?1 COLLATE NOCASE",
+            [source],
+        )?)
+    }
+
+    fn set_nrtm_v4_state_conn(
+        conn: &rusqlite::Connection,
+        source: &str,
+        session_id: &str,
+        version: u64,
+    ) -> Result<(), anyhow::Error> {
+        conn.execute(
+            "INSERT INTO nrtm_v4_state (source, session_id, version) VALUES (?1, ?2, ?3)
+             ON CONFLICT(source) DO UPDATE SET session_id=excluded.session_id, version=excluded.version",
+            params![source, session_id, version as i64],
+        )?;
+        Ok(())
+    }
+
+    fn insert_delegation_tx(
+        &self,
+        tx: &rusqlite::Transaction<'_>,
+        delegation: &Delegation,
+    ) -> Result<(), anyhow::Error> {
+        let res = &delegation.resource;
+        tx.prepare_cached(
+            "INSERT INTO delegations \
+             (registry, country, resource_type, start, value, date, status, opaque_id) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?
+        .execute(params![
+            delegation.registry.name(),
+            delegation.country,
+            res.kind(),
+            res.start(),
+            res.value(),
+            delegation.date,
+            delegation.status,
+            delegation.opaque_id,
+        ])?;
+        let id = tx.last_insert_rowid();
+
+        match res {
+            DelegatedResource::Asn { first, count } => {
+                tx.prepare_cached(
+                    "INSERT INTO asn_delegated (first, last, delegation_id) VALUES (?1, ?2, ?3)",
+                )?
+                .execute(params![first, first + (count - 1), id])?;
+            }
+            DelegatedResource::Ipv4 { .. } => {
+                if let (Some(country), Some((start, end))) =
+                    (delegation.delegated_country(), res.ipv4_range())
+                {
+                    tx.prepare_cached(
+                        "INSERT INTO ipv4_delegated (start, end, country, delegation_id) \
+                         VALUES (?1, ?2, ?3, ?4)",
+                    )?
+                    .execute(params![
+                        u32::from(start),
+                        u32::from(end),
+                        country,
+                        id
+                    ])?;
+                }
+            }
+            DelegatedResource::Ipv6(net) => {
+                if let Some(country) = delegation.delegated_country() {
+                    let sb = net.network().octets();
+                    let eb = net.broadcast().octets();
+                    tx.prepare_cached(
+                        "INSERT INTO ipv6_delegated (start, end, country, delegation_id) \
+                         VALUES (?1, ?2, ?3, ?4)",
+                    )?
+                    .execute(params![
+                        sb.as_slice(),
+                        eb.as_slice(),
+                        country,
+                        id
+                    ])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the most specific match for an address in the RPSL or delegated
+    /// geo tables of the given IP version (`ipv4` or `ipv6`).
+    ///
+    /// The match is returned together with the network of its range that
+    /// contains `addr`.
+    fn lookup_geo_source(
+        &self,
+        version: &str,
+        param: &dyn ToSql,
+        addr: IpAddr,
+        kind: GeoSourceKind,
+    ) -> Result<Option<(GeoMatch, IpNet)>, anyhow::Error> {
+        let conn = self.conn.lock().unwrap();
+        let res = match kind {
+            GeoSourceKind::Rpsl => {
+                let row: Option<(GeoMatch, Value, Value)> = conn
+                    .query_row(
+                        &format!(
+                            "SELECT country, obj_type, obj_key, start, end FROM {version}_geo \
+                             JOIN rpsl ON {version}_geo.obj_id = rpsl.id \
+                             WHERE start <= ?1 AND end >= ?1 ORDER BY start DESC, end ASC LIMIT 1"
+                        ),
+                        [param],
+                        |r| {
+                            let m = GeoMatch {
+                                country: r.get(0)?,
+                                source: GeoSource::Rpsl {
+                                    obj_type: r.get(1)?,
+                                    obj_key: r.get(2)?,
+                                },
+                            };
+                            Ok((m, r.get(3)?, r.get(4)?))
+                        },
+                    )
+                    .optional()?;
+                match row {
+                    Some((m, start, end)) => Some((m, range_network(start, end, addr)?)),
+                    None => None,
+                }
+            }
+            GeoSourceKind::Delegated => {
+                let row: Option<(String, String, String, Value, Value)> = conn
+                    .query_row(
+                        &format!(
+                            "SELECT g.country, d.registry, d.status, g.start, g.end \
+                             FROM {version}_delegated g \
+                             JOIN delegations d ON g.delegation_id = d.id \
+                             WHERE g.start <= ?1 AND g.end >= ?1 \
+                             ORDER BY g.start DESC, g.end ASC LIMIT 1"
+                        ),
+                        [param],
+                        |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+                    )
+                    .optional()?;
+                match row {
+                    Some((country, registry, status, start, end)) => Some((
+                        GeoMatch {
+                            country,
+                            source: GeoSource::Delegated {
+                                registry: parse_registry(&registry)?,
+                                status,
+                            },
+                        },
+                        range_network(start, end, addr)?,
+                    )),
+                    None => None,
+                }
+            }
+        };
+        Ok(res)
+    }
+
+    /// Find the most specific match of both sources. The `primary` source
+    /// wins for the same network.
+    ///
+    /// This is the rule generated GeoIP databases follow, so both return the
+    /// same country for an address.
+    fn lookup_geo_match(
+        &self,
+        version: &str,
+        param: &dyn ToSql,
+        addr: IpAddr,
+        primary: GeoSourceKind,
+    ) -> Result<Option<GeoMatch>, anyhow::Error> {
+        let primary_match = self.lookup_geo_source(version, param, addr, primary)?;
+        let other_match = self.lookup_geo_source(version, param, addr, primary.other())?;
+        let best = match (primary_match, other_match) {
+            (Some(p), Some(o)) if o.1.prefix_len() > p.1.prefix_len() => Some(o),
+            (Some(p), _) => Some(p),
+            (None, o) => o,
+        };
+        Ok(best.map(|(m, _)| m))
+    }
+}
+
+/// The network of the range from `start` to `end` that contains `addr`.
+///
+/// Ranges that are not a single network are split into networks, like in
+/// generated GeoIP databases.
+fn range_network(start: Value, end: Value, addr: IpAddr) -> Result<IpNet, anyhow::Error> {
+    let net = match (start, end, addr) {
+        (Value::Integer(start), Value::Integer(end), IpAddr::V4(addr)) => {
+            let (start, end) = (Ipv4Addr::from(start as u32), Ipv4Addr::from(end as u32));
+            Ipv4Subnets::new(start, end, 0)
+                .find(|net| net.contains(&addr))
+                .map(IpNet::V4)
+        }
+        (Value::Blob(start), Value::Blob(end), IpAddr::V6(addr)) => {
+            let start = Ipv6Addr::from(<[u8; 16]>::try_from(start.as_slice())?);
+            let end = Ipv6Addr::from(<[u8; 16]>::try_from(end.as_slice())?);
+            Ipv6Subnets::new(start, end, 0)
+                .find(|net| net.contains(&addr))
+                .map(IpNet::V6)
+        }
+        _ => None,
+    };
+    net.ok_or_else(|| anyhow::anyhow!("invalid range in database for {addr}"))
+}
+
+fn parse_registry(name: &str) -> Result<Rir, anyhow::Error> {
+    name.parse()
+        .map_err(|_| anyhow::anyhow!("invalid registry in database: {name}"))
 }
 
 impl Database for SqliteDb {
@@ -192,6 +401,75 @@ impl Database for SqliteDb {
             drop(conn);
             self.set_version(1)?;
         }
+        if ver < 2 {
+            let conn = self.conn.lock().unwrap();
+            conn.execute_batch(
+                r#"
+                CREATE TABLE nrtm_serials (
+                    source TEXT PRIMARY KEY,
+                    serial INTEGER NOT NULL
+                );
+                "#,
+            )?;
+            drop(conn);
+            self.set_version(2)?;
+        }
+        if ver < 3 {
+            let conn = self.conn.lock().unwrap();
+            conn.execute_batch(
+                r#"
+                CREATE TABLE nrtm_v4_state (
+                    source TEXT PRIMARY KEY,
+                    session_id TEXT NOT NULL,
+                    version INTEGER NOT NULL
+                );
+                CREATE INDEX rpsl_source_idx ON rpsl(source COLLATE NOCASE);
+                "#,
+            )?;
+            drop(conn);
+            self.set_version(3)?;
+        }
+        if ver < 4 {
+            let conn = self.conn.lock().unwrap();
+            conn.execute_batch(
+                r#"
+                CREATE TABLE delegations (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    registry TEXT NOT NULL,
+                    country TEXT,
+                    resource_type TEXT NOT NULL,
+                    start TEXT NOT NULL,
+                    value INTEGER NOT NULL,
+                    date TEXT,
+                    status TEXT NOT NULL,
+                    opaque_id TEXT
+                );
+                CREATE INDEX delegations_registry_idx ON delegations(registry);
+                CREATE TABLE asn_delegated (
+                    first INTEGER NOT NULL,
+                    last INTEGER NOT NULL,
+                    delegation_id INTEGER NOT NULL REFERENCES delegations(id) ON DELETE CASCADE
+                );
+                CREATE INDEX asn_delegated_idx ON asn_delegated(first, last);
+                CREATE TABLE ipv4_delegated (
+                    start INTEGER NOT NULL,
+                    end INTEGER NOT NULL,
+                    country TEXT NOT NULL,
+                    delegation_id INTEGER NOT NULL REFERENCES delegations(id) ON DELETE CASCADE
+                );
+                CREATE INDEX ipv4_delegated_idx ON ipv4_delegated(start, end);
+                CREATE TABLE ipv6_delegated (
+                    start BLOB NOT NULL,
+                    end BLOB NOT NULL,
+                    country TEXT NOT NULL,
+                    delegation_id INTEGER NOT NULL REFERENCES delegations(id) ON DELETE CASCADE
+                );
+                CREATE INDEX ipv6_delegated_idx ON ipv6_delegated(start, end);
+                "#,
+            )?;
+            drop(conn);
+            self.set_version(4)?;
+        }
         Ok(())
     }
 
@@ -216,17 +494,13 @@ impl Database for SqliteDb {
         Ok(())
     }
 
-    fn lookup_ipv4(&self, addr: Ipv4Addr) -> Result<Option<String>, anyhow::Error> {
+    fn lookup_ipv4_match(
+        &self,
+        addr: Ipv4Addr,
+        primary: GeoSourceKind,
+    ) -> Result<Option<GeoMatch>, anyhow::Error> {
         let num: u32 = addr.into();
-        let conn = self.conn.lock().unwrap();
-        let res = conn
-            .query_row(
-                "SELECT country FROM ipv4_geo WHERE start <= ?1 AND end >= ?1 LIMIT 1",
-                [num as i64],
-                |r| r.get(0),
-            )
-            .optional()?;
-        Ok(res)
+        self.lookup_geo_match("ipv4", &(num as i64), addr.into(), primary)
     }
 
     fn lookup_ipv4_with_obj(
@@ -239,7 +513,7 @@ impl Database for SqliteDb {
             .query_row(
                 "SELECT country, obj_type, obj_key FROM ipv4_geo \
                  JOIN rpsl ON ipv4_geo.obj_id = rpsl.id \
-                 WHERE start <= ?1 AND end >= ?1 LIMIT 1",
+                 WHERE start <= ?1 AND end >= ?1 ORDER BY start DESC, end ASC LIMIT 1",
                 [num as i64],
                 |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
             )
@@ -247,17 +521,13 @@ impl Database for SqliteDb {
         Ok(res)
     }
 
-    fn lookup_ipv6(&self, addr: Ipv6Addr) -> Result<Option<String>, anyhow::Error> {
+    fn lookup_ipv6_match(
+        &self,
+        addr: Ipv6Addr,
+        primary: GeoSourceKind,
+    ) -> Result<Option<GeoMatch>, anyhow::Error> {
         let bytes = addr.octets();
-        let conn = self.conn.lock().unwrap();
-        let res = conn
-            .query_row(
-                "SELECT country FROM ipv6_geo WHERE start <= ?1 AND end >= ?1 ORDER BY (end-start) ASC LIMIT 1",
-                [bytes.as_slice()],
-                |r| r.get(0),
-            )
-            .optional()?;
-        Ok(res)
+        self.lookup_geo_match("ipv6", &bytes.as_slice(), addr.into(), primary)
     }
 
     fn lookup_ipv6_with_obj(
@@ -270,7 +540,7 @@ impl Database for SqliteDb {
             .query_row(
                 "SELECT country, obj_type, obj_key FROM ipv6_geo \
                  JOIN rpsl ON ipv6_geo.obj_id = rpsl.id \
-                 WHERE start <= ?1 AND end >= ?1 ORDER BY (end-start) ASC LIMIT 1",
+                 WHERE start <= ?1 AND end >= ?1 ORDER BY start DESC, end ASC LIMIT 1",
                 [bytes.as_slice()],
                 |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
             )
@@ -294,7 +564,7 @@ impl Database for SqliteDb {
         let bytes = addr.octets();
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT country FROM ipv6_geo WHERE start <= ?1 AND end >= ?1 ORDER BY (end-start) ASC",
+            "SELECT country FROM ipv6_geo WHERE start <= ?1 AND end >= ?1 ORDER BY start DESC, end ASC",
         )?;
         let rows = stmt
             .query_map([bytes.as_slice()], |r| r.get(0))?
@@ -302,16 +572,189 @@ impl Database for SqliteDb {
         Ok(rows)
     }
 
-    fn get_object(&self, obj_type: &str, obj_key: &str) -> Result<Option<String>, anyhow::Error> {
+    fn delete_rpsl_object(&self, obj_type: &str, obj_key: &str) -> Result<bool, anyhow::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let obj_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM rpsl WHERE obj_type=?1 AND obj_key=?2",
+                params![obj_type, obj_key],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let Some(obj_id) = obj_id else {
+            return Ok(false);
+        };
+        tx.execute("DELETE FROM ipv4_geo WHERE obj_id=?1", [obj_id])?;
+        tx.execute("DELETE FROM ipv6_geo WHERE obj_id=?1", [obj_id])?;
+        tx.execute("DELETE FROM rpsl WHERE id=?1", [obj_id])?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn delete_rpsl_objects_by_source(&self, source: &str) -> Result<usize, anyhow::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let count = self.delete_rpsl_objects_by_source_tx(&tx, source)?;
+        tx.commit()?;
+        Ok(count)
+    }
+
+    fn nrtm_serial(&self, source: &str) -> Result<Option<u64>, anyhow::Error> {
         let conn = self.conn.lock().unwrap();
-        let res = conn
+        let serial: Option<i64> = conn
+            .query_row(
+                "SELECT serial FROM nrtm_serials WHERE source = ?1",
+                [source],
+                |r| r.get(0),
+            )
+            .optional()?;
+        Ok(serial.map(|s| s as u64))
+    }
+
+    fn set_nrtm_serial(&self, source: &str, serial: u64) -> Result<(), anyhow::Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO nrtm_serials (source, serial) VALUES (?1, ?2)
+             ON CONFLICT(source) DO UPDATE SET serial=excluded.serial",
+            params![source, serial as i64],
+        )?;
+        Ok(())
+    }
+
+    fn nrtm_v4_state(&self, source: &str) -> Result<Option<(String, u64)>, anyhow::Error> {
+        let conn = self.conn.lock().unwrap();
+        let state: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT session_id, version FROM nrtm_v4_state WHERE source = ?1",
+                [source],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+        Ok(state.map(|(session, version)| (session, version as u64)))
+    }
+
+    fn set_nrtm_v4_state(
+        &self,
+        source: &str,
+        session_id: &str,
+        version: u64,
+    ) -> Result<(), anyhow::Error> {
+        let conn = self.conn.lock().unwrap();
+        Self::set_nrtm_v4_state_conn(&conn, source, session_id, version)
+    }
+
+    fn load_nrtm_v4_snapshot(
+        &self,
+        source: &str,
+        session_id: &str,
+        version: u64,
+        objects: &mut dyn Iterator<Item = Result<RpslObject, anyhow::Error>>,
+    ) -> Result<usize, anyhow::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        // Dropping the transaction on error rolls back the delete.
+        let tx = conn.transaction()?;
+        let deleted = self.delete_rpsl_objects_by_source_tx(&tx, source)?;
+        for obj in objects {
+            self.upsert_rpsl_object_tx(&tx, &obj?)?;
+        }
+        Self::set_nrtm_v4_state_conn(&tx, source, session_id, version)?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    fn get_object(
+        &self,
+        obj_type: &str,
+        obj_key: &str,
+    ) -> Result<Option<RpslObject>, anyhow::Error> {
+        let conn = self.conn.lock().unwrap();
+        let json: Option<String> = conn
             .query_row(
                 "SELECT json FROM rpsl WHERE obj_type = ?1 AND obj_key = ?2",
                 params![obj_type, obj_key],
                 |r| r.get(0),
             )
             .optional()?;
-        Ok(res)
+        match json {
+            Some(json) => Ok(Some(RpslObject::from_json(obj_type, &json)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn replace_delegations(
+        &self,
+        registry: Rir,
+        delegations: &[Delegation],
+    ) -> Result<usize, anyhow::Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for table in ["asn_delegated", "ipv4_delegated", "ipv6_delegated"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {table} WHERE delegation_id IN \
+                     (SELECT id FROM delegations WHERE registry = ?1)"
+                ),
+                [registry.name()],
+            )?;
+        }
+        tx.execute(
+            "DELETE FROM delegations WHERE registry = ?1",
+            [registry.name()],
+        )?;
+        for delegation in delegations {
+            self.insert_delegation_tx(&tx, delegation)?;
+        }
+        tx.commit()?;
+        Ok(delegations.len())
+    }
+
+    fn lookup_asn_delegation(&self, asn: u32) -> Result<Option<Delegation>, anyhow::Error> {
+        let conn = self.conn.lock().unwrap();
+        type Row = (
+            String,
+            Option<String>,
+            String,
+            String,
+            String,
+            Option<String>,
+            String,
+            Option<String>,
+        );
+        let row: Option<Row> = conn
+            .query_row(
+                "SELECT d.registry, d.country, d.resource_type, d.start, d.value, d.date, \
+                 d.status, d.opaque_id FROM asn_delegated a \
+                 JOIN delegations d ON a.delegation_id = d.id \
+                 WHERE a.first <= ?1 AND a.last >= ?1 ORDER BY a.first DESC LIMIT 1",
+                [asn],
+                |r| {
+                    Ok((
+                        r.get(0)?,
+                        r.get(1)?,
+                        r.get(2)?,
+                        r.get(3)?,
+                        r.get::<_, i64>(4)?.to_string(),
+                        r.get(5)?,
+                        r.get(6)?,
+                        r.get(7)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((registry, country, kind, start, value, date, status, opaque_id)) = row else {
+            return Ok(None);
+        };
+        let resource = DelegatedResource::parse(&kind, &start, &value)
+            .ok_or_else(|| anyhow::anyhow!("invalid delegation in database: {kind} {start}"))?;
+        Ok(Some(Delegation {
+            registry: parse_registry(&registry)?,
+            country,
+            resource,
+            date,
+            status,
+            opaque_id,
+        }))
     }
 }
 
@@ -344,4 +787,67 @@ mod tests {
         let res = db.lookup_ipv4_all("192.0.2.1".parse().unwrap()).unwrap();
         assert_eq!(res, vec!["AA".to_string(), "BB".to_string()]);
     }
+
+    #[test]
+    fn lookup_with_obj_most_specific() {
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+
+        let objects = parse_objects(
+            "inetnum: 192.0.2.0 - 192.0.2.255\ncountry: BB\nsource: TEST\n\n\
+             inetnum: 192.0.2.128 - 192.0.2.255\ncountry: AA\nsource: TEST\n\n\
+             inet6num: 2001:db8::/32\ncountry: BB\nsource: TEST\n\n\
+             inet6num: 2001:db8:1::/48\ncountry: AA\nsource: TEST\n",
+        )
+        .unwrap();
+        for obj in objects {
+            db.upsert_rpsl_object(&RpslObject::try_from(obj).unwrap())
+                .unwrap();
+        }
+
+        let res = db
+            .lookup_ipv4_with_obj("192.0.2.200".parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            res,
+            Some((
+                "AA".to_string(),
+                "inetnum".to_string(),
+                "192.0.2.128/25".to_string()
+            ))
+        );
+
+        let res = db
+            .lookup_ipv6_with_obj("2001:db8:1::1".parse().unwrap())
+            .unwrap();
+        assert_eq!(
+            res,
+            Some((
+                "AA".to_string(),
+                "inet6num".to_string(),
+                "2001:db8:1::/48".to_string()
+            ))
+        );
+        let res = db
+            .lookup_ipv6_all("2001:db8:1::1".parse().unwrap())
+            .unwrap();
+        assert_eq!(res, vec!["AA".to_string(), "BB".to_string()]);
+    }
+
+    #[test]
+    fn stores_set_objects_by_primary_key() {
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+
+        let obj = RpslObject::try_from(
+            parse_objects("as-set: AS-EXAMPLE\nmembers: AS1, AS2\nsource: TEST\n")
+                .unwrap()
+                .remove(0),
+        )
+        .unwrap();
+        db.upsert_rpsl_object(&obj).unwrap();
+
+        let stored = db.get_object("as-set", "AS-EXAMPLE").unwrap().unwrap();
+        assert_eq!(stored, obj);
+    }
 }
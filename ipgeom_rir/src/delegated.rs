@@ -0,0 +1,322 @@
+//! Parser for RIR delegated-extended statistics files.
+//!
+//! Every RIR publishes a `delegated-<rir>-extended-latest` file listing the
+//! AS numbers and address blocks it manages, one per line:
+//!
+//! ```text
+//! registry|cc|type|start|value|date|status|opaque-id
+//! ripencc|NL|ipv4|193.0.0.0|2048|19930901|allocated|2b5ce4c9-...
+//! ```
+//!
+//! The country codes in these files are the most complete per-registry
+//! mapping, which makes them a good complement to the RPSL data.
+
+use std::io::BufRead;
+use std::net::Ipv4Addr;
+
+use ipnet::{IpNet, Ipv4Subnets, Ipv6Net};
+
+use crate::registry::normalize_date;
+use crate::types::Rir;
+
+/// A resource listed in a delegated-extended statistics file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelegatedResource {
+    /// `count` consecutive AS numbers starting at `first`.
+    Asn { first: u32, count: u32 },
+    /// `count` consecutive IPv4 addresses starting at `start`.
+    ///
+    /// The count is not necessarily a power of two.
+    Ipv4 { start: Ipv4Addr, count: u32 },
+    /// An IPv6 prefix.
+    Ipv6(Ipv6Net),
+}
+
+impl DelegatedResource {
+    /// Parse the `type`, `start` and `value` fields of a record.
+    pub fn parse(kind: &str, start: &str, value: &str) -> Option<Self> {
+        match kind {
+            "asn" => {
+                let first: u32 = start.parse().ok()?;
+                let count: u32 = value.parse().ok()?;
+                first.checked_add(count.checked_sub(1)?)?;
+                Some(Self::Asn { first, count })
+            }
+            "ipv4" => {
+                let start: Ipv4Addr = start.parse().ok()?;
+                let count: u32 = value.parse().ok()?;
+                u32::from(start).checked_add(count.checked_sub(1)?)?;
+                Some(Self::Ipv4 { start, count })
+            }
+            "ipv6" => {
+                let net = Ipv6Net::new(start.parse().ok()?, value.parse().ok()?).ok()?;
+                Some(Self::Ipv6(net.trunc()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The `type` field of the record.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Asn { .. } => "asn",
+            Self::Ipv4 { .. } => "ipv4",
+            Self::Ipv6(_) => "ipv6",
+        }
+    }
+
+    /// The `start` field of the record.
+    pub fn start(&self) -> String {
+        match self {
+            Self::Asn { first, .. } => first.to_string(),
+            Self::Ipv4 { start, .. } => start.to_string(),
+            Self::Ipv6(net) => net.network().to_string(),
+        }
+    }
+
+    /// The `value` field of the record: a count for AS numbers and IPv4,
+    /// the prefix length for IPv6.
+    pub fn value(&self) -> u32 {
+        match self {
+            Self::Asn { count, .. } => *count,
+            Self::Ipv4 { count, .. } => *count,
+            Self::Ipv6(net) => net.prefix_len().into(),
+        }
+    }
+
+    /// Decompose an address range into networks.
+    ///
+    /// Returns an empty list for AS numbers.
+    pub fn networks(&self) -> Vec<IpNet> {
+        match self {
+            Self::Asn { .. } => Vec::new(),
+            Self::Ipv4 { start, count } => {
+                let end = Ipv4Addr::from(u32::from(*start) + (count - 1));
+                Ipv4Subnets::new(*start, end, 0).map(IpNet::V4).collect()
+            }
+            Self::Ipv6(net) => vec![IpNet::V6(*net)],
+        }
+    }
+
+    /// First and last address of an IPv4 range.
+    pub fn ipv4_range(&self) -> Option<(Ipv4Addr, Ipv4Addr)> {
+        match self {
+            Self::Ipv4 { start, count } => {
+                Some((*start, Ipv4Addr::from(u32::from(*start) + (count - 1))))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A single record of a delegated-extended statistics file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delegation {
+    pub registry: Rir,
+    /// ISO 3166 country code.
+    ///
+    /// `None` for resources without a country, which are published with an
+    /// empty code or the `ZZ` placeholder.
+    pub country: Option<String>,
+    pub resource: DelegatedResource,
+    /// Date of the delegation as an RFC 3339 timestamp.
+    pub date: Option<String>,
+    /// `allocated`, `assigned`, `available` or `reserved`.
+    pub status: String,
+    /// Registry-specific identifier of the resource holder.
+    pub opaque_id: Option<String>,
+}
+
+impl Delegation {
+    /// Whether the resource was handed out to a holder.
+    pub fn is_delegated(&self) -> bool {
+        matches!(self.status.as_str(), "allocated" | "assigned")
+    }
+
+    /// Country code of a delegated resource, if any.
+    pub fn delegated_country(&self) -> Option<&str> {
+        self.country.as_deref().filter(|_| self.is_delegated())
+    }
+
+    /// Parse a record line.
+    ///
+    /// Returns `None` for malformed records.
+    fn parse_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        if fields.len() < 7 {
+            return None;
+        }
+        let registry = fields[0].parse().ok()?;
+        let resource = DelegatedResource::parse(fields[2], fields[3], fields[4])?;
+        let country = Some(fields[1].to_uppercase()).filter(|cc| !cc.is_empty() && cc != "ZZ");
+        Some(Self {
+            registry,
+            country,
+            resource,
+            date: normalize_date(fields[5]),
+            status: fields[6].to_lowercase(),
+            opaque_id: fields
+                .get(7)
+                .filter(|id| !id.is_empty())
+                .map(|id| id.to_string()),
+        })
+    }
+}
+
+/// Parse a delegated-extended statistics file.
+///
+/// The version header, summary lines and comments are skipped. Malformed
+/// records are logged and skipped as well, so the iterator only fails on
+/// IO errors.
+pub fn parse_delegated<R: BufRead>(reader: R) -> DelegatedIter<R> {
+    DelegatedIter {
+        reader,
+        line: Vec::new(),
+        line_number: 0,
+    }
+}
+
+/// Iterator over the records of a delegated-extended statistics file.
+///
+/// Created by [`parse_delegated`].
+pub struct DelegatedIter<R> {
+    reader: R,
+    line: Vec<u8>,
+    line_number: usize,
+}
+
+impl<R: BufRead> Iterator for DelegatedIter<R> {
+    type Item = Result<Delegation, anyhow::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+            self.line_number += 1;
+
+            let line = String::from_utf8_lossy(&self.line);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('|');
+            let first = fields.next().unwrap_or_default();
+            // The version header starts with the format version, e.g. `2.3|`.
+            if first.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+                continue;
+            }
+            if fields.nth(4) == Some("summary") {
+                continue;
+            }
+
+            match Delegation::parse_line(line) {
+                Some(delegation) => return Some(Ok(delegation)),
+                None => {
+                    tracing::warn!(line = self.line_number, record = %line, "skipping malformed delegation record");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipnet::Ipv4Net;
+
+    fn ipv4_resource(net: Ipv4Net) -> DelegatedResource {
+        DelegatedResource::Ipv4 {
+            start: net.network(),
+            count: 1u32 << (32 - net.prefix_len()),
+        }
+    }
+
+    const SAMPLE: &str = "\
+2.3|ripencc|1700000000|3|19830705|20231114|+0100
+# comment
+ripencc|*|asn|*|1|summary
+ripencc|*|ipv4|*|1|summary
+ripencc|*|ipv6|*|1|summary
+ripencc|NL|asn|3333|1|19930901|allocated|opaque-1
+ripencc|nl|ipv4|193.0.0.0|1536|19930901|allocated|opaque-1
+ripencc||ipv4|198.51.100.0|256||available
+ripencc|ZZ|ipv6|2001:db8::|32||reserved
+ripencc|DE|ipv6|2001:db8:1::|48|20100101|assigned|opaque-2
+ripencc|DE|ipv4|not-an-address|256|20100101|assigned|opaque-2
+";
+
+    #[test]
+    fn parse_records() {
+        let records: Vec<_> = parse_delegated(SAMPLE.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 5);
+
+        assert_eq!(
+            records[0],
+            Delegation {
+                registry: Rir::Ripe,
+                country: Some("NL".into()),
+                resource: DelegatedResource::Asn {
+                    first: 3333,
+                    count: 1
+                },
+                date: Some("1993-09-01T00:00:00Z".into()),
+                status: "allocated".into(),
+                opaque_id: Some("opaque-1".into()),
+            }
+        );
+
+        let v4 = &records[1];
+        assert_eq!(v4.delegated_country(), Some("NL"));
+        assert_eq!(
+            v4.resource.networks(),
+            vec![
+                "193.0.0.0/22".parse::<IpNet>().unwrap(),
+                "193.0.4.0/23".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            v4.resource.ipv4_range(),
+            Some(("193.0.0.0".parse().unwrap(), "193.0.5.255".parse().unwrap()))
+        );
+
+        assert_eq!(records[2].country, None);
+        assert_eq!(records[2].date, None);
+        assert!(!records[2].is_delegated());
+        assert_eq!(records[2].opaque_id, None);
+        assert_eq!(records[3].country, None);
+
+        assert_eq!(
+            records[4].resource,
+            DelegatedResource::Ipv6("2001:db8:1::/48".parse().unwrap())
+        );
+        assert_eq!(records[4].resource.value(), 48);
+    }
+
+    #[test]
+    fn resource_round_trip() {
+        let resources = [
+            DelegatedResource::Asn {
+                first: 64496,
+                count: 16,
+            },
+            ipv4_resource("192.0.2.0/24".parse().unwrap()),
+            DelegatedResource::Ipv6("2001:db8::/32".parse().unwrap()),
+        ];
+        for res in resources {
+            let parsed =
+                DelegatedResource::parse(res.kind(), &res.start(), &res.value().to_string());
+            assert_eq!(parsed, Some(res));
+        }
+        assert_eq!(
+            DelegatedResource::parse("ipv4", "255.255.255.0", "512"),
+            None
+        );
+        assert_eq!(DelegatedResource::parse("asn", "1", "0"), None);
+    }
+}
@@ -0,0 +1,510 @@
+//! Differences between two RPSL dumps.
+//!
+//! Both dumps are sorted by object key with an external merge sort, so
+//! memory use is bounded by [`DiffOptions::chunk_size`] rather than the
+//! size of the dumps.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use ipgeom_rpsl::{
+    parse_objects_bufread_iter, Attribute, Decoding, Object, ObjectType, RpslObject,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::db::object_key;
+
+/// Options for [`diff_rpsl`].
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// Number of objects sorted in memory at a time.
+    pub chunk_size: usize,
+    /// Attributes that are ignored when comparing objects, e.g.
+    /// `last-modified`.
+    pub ignore_attributes: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 100_000,
+            ignore_attributes: Vec::new(),
+        }
+    }
+}
+
+/// A difference between two dumps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpslChange {
+    Added(Object),
+    Removed(Object),
+    Modified {
+        old: Object,
+        new: Object,
+        changes: Vec<AttributeChange>,
+    },
+}
+
+impl RpslChange {
+    /// The object as it is in the new dump, or the removed object.
+    pub fn object(&self) -> &Object {
+        match self {
+            RpslChange::Added(obj) | RpslChange::Removed(obj) => obj,
+            RpslChange::Modified { new, .. } => new,
+        }
+    }
+}
+
+/// The values of an attribute before and after a modification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AttributeChange {
+    pub attribute: String,
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+}
+
+/// Counters describing a diff.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub unchanged: usize,
+    /// Objects that appeared more than once in a dump. Only the last
+    /// occurrence is compared.
+    pub duplicates: usize,
+}
+
+/// Compare two RPSL dumps.
+///
+/// Objects are matched by type and key (see `object_key`, compared case
+/// insensitively). `on_change` is called for each difference in key order.
+pub fn diff_rpsl<A, B, F>(
+    old: A,
+    new: B,
+    options: &DiffOptions,
+    mut on_change: F,
+) -> Result<DiffStats, anyhow::Error>
+where
+    A: Read,
+    B: Read,
+    F: FnMut(RpslChange) -> Result<(), anyhow::Error>,
+{
+    let tmp = tempfile::tempdir().context("could not create temporary directory")?;
+    let old = sort_objects(old, &tmp.path().join("old"), options.chunk_size)?;
+    let new = sort_objects(new, &tmp.path().join("new"), options.chunk_size)?;
+    let mut stats = DiffStats::default();
+    let mut old = Dedup::new(old);
+    let mut new = Dedup::new(new);
+
+    let mut a = old.next().transpose()?;
+    let mut b = new.next().transpose()?;
+    loop {
+        let ord = match (&a, &b) {
+            (None, None) => break,
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (Some(a), Some(b)) => a.sort_key().cmp(&b.sort_key()),
+        };
+        match ord {
+            std::cmp::Ordering::Less => {
+                let rec = a.take().expect("checked above");
+                stats.removed += 1;
+                on_change(RpslChange::Removed(rec.into_object()))?;
+                a = old.next().transpose()?;
+            }
+            std::cmp::Ordering::Greater => {
+                let rec = b.take().expect("checked above");
+                stats.added += 1;
+                on_change(RpslChange::Added(rec.into_object()))?;
+                b = new.next().transpose()?;
+            }
+            std::cmp::Ordering::Equal => {
+                let old_obj = a.take().expect("checked above").into_object();
+                let new_obj = b.take().expect("checked above").into_object();
+                let changes = attribute_changes(&old_obj, &new_obj, &options.ignore_attributes);
+                if changes.is_empty() {
+                    stats.unchanged += 1;
+                } else {
+                    stats.modified += 1;
+                    on_change(RpslChange::Modified {
+                        old: old_obj,
+                        new: new_obj,
+                        changes,
+                    })?;
+                }
+                a = old.next().transpose()?;
+                b = new.next().transpose()?;
+            }
+        }
+    }
+    stats.duplicates = old.duplicates + new.duplicates;
+    Ok(stats)
+}
+
+/// Compare the attributes of two objects. Attributes are compared as lists
+/// of values, so reordering values of the same attribute is a change but
+/// moving attributes with different keys is not.
+fn attribute_changes(old: &Object, new: &Object, ignore: &[String]) -> Vec<AttributeChange> {
+    let mut keys: Vec<&str> = Vec::new();
+    for attr in old
+        .ordered_attributes()
+        .iter()
+        .chain(new.ordered_attributes())
+    {
+        if !keys.contains(&attr.key()) && !ignore.iter().any(|i| i == attr.key()) {
+            keys.push(attr.key());
+        }
+    }
+    keys.into_iter()
+        .filter_map(|key| {
+            let values = |obj: &Object| -> Vec<String> {
+                obj.ordered_attributes()
+                    .iter()
+                    .filter(|a| a.key() == key)
+                    .map(|a| a.value().trim().to_string())
+                    .collect()
+            };
+            let (old, new) = (values(old), values(new));
+            (old != new).then(|| AttributeChange {
+                attribute: key.to_string(),
+                old,
+                new,
+            })
+        })
+        .collect()
+}
+
+/// An object as written to the temporary sort files.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    /// Object type.
+    t: String,
+    /// Uppercased object key.
+    k: String,
+    /// Position in the dump, used to keep the last of duplicate objects.
+    n: u64,
+    /// Attributes.
+    a: Vec<(String, String)>,
+}
+
+impl Record {
+    fn new(obj: Object, n: u64) -> Self {
+        let t = obj.obj_type().as_key().to_string();
+        let key = match RpslObject::try_from(obj.clone()) {
+            Ok(typed) => object_key(&typed),
+            // Fall back to the value of the type attribute.
+            Err(_) => obj
+                .ordered_attributes()
+                .first()
+                .map(|a| a.value().trim().to_string())
+                .unwrap_or_default(),
+        };
+        let a = obj
+            .into_ordered_attributes()
+            .into_iter()
+            .map(|a| (a.key().to_string(), a.value().to_string()))
+            .collect();
+        Self {
+            t,
+            k: key.to_ascii_uppercase(),
+            n,
+            a,
+        }
+    }
+
+    fn sort_key(&self) -> (&str, &str) {
+        (&self.t, &self.k)
+    }
+
+    fn into_object(self) -> Object {
+        let attrs = self
+            .a
+            .into_iter()
+            .map(|(k, v)| Attribute::new(k, v))
+            .collect();
+        Object::from_ordered_attributes(ObjectType::from_key(&self.t), attrs)
+    }
+}
+
+/// Write sorted chunks of the objects read from `reader` to files in `dir`
+/// and return an iterator merging them.
+fn sort_objects<R: Read>(
+    reader: R,
+    dir: &Path,
+    chunk_size: usize,
+) -> Result<MergeIter, anyhow::Error> {
+    std::fs::create_dir_all(dir)?;
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let iter = parse_objects_bufread_iter(BufReader::with_capacity(64 * 1024, reader))
+        .with_decoding(Decoding::Utf8OrLatin1)
+        .with_recovery(|malformed| {
+            tracing::warn!(
+                start_line = malformed.start_line,
+                end_line = malformed.end_line,
+                reason = ?malformed.kind,
+                "skipping malformed RPSL object"
+            );
+        });
+    for (n, res) in iter.enumerate() {
+        chunk.push(Record::new(res?, n as u64));
+        if chunk.len() >= chunk_size.max(1) {
+            chunks.push(write_chunk(&mut chunk, dir, chunks.len())?);
+        }
+    }
+    if !chunk.is_empty() {
+        chunks.push(write_chunk(&mut chunk, dir, chunks.len())?);
+    }
+    MergeIter::new(chunks)
+}
+
+fn write_chunk(
+    chunk: &mut Vec<Record>,
+    dir: &Path,
+    index: usize,
+) -> Result<std::path::PathBuf, anyhow::Error> {
+    chunk.sort_by(|a, b| (a.sort_key(), a.n).cmp(&(b.sort_key(), b.n)));
+    let path = dir.join(format!("chunk-{}.jsonl", index));
+    let mut out = BufWriter::new(File::create(&path)?);
+    for rec in chunk.drain(..) {
+        serde_json::to_writer(&mut out, &rec)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(path)
+}
+
+/// K-way merge of sorted chunk files.
+struct MergeIter {
+    readers: Vec<BufReader<File>>,
+    heads: Vec<Option<Record>>,
+    /// Chunk indexes ordered by their current record.
+    heap: BinaryHeap<Reverse<(String, String, u64, usize)>>,
+}
+
+impl MergeIter {
+    fn new(paths: Vec<std::path::PathBuf>) -> Result<Self, anyhow::Error> {
+        let mut iter = Self {
+            readers: Vec::new(),
+            heads: Vec::new(),
+            heap: BinaryHeap::new(),
+        };
+        for path in paths {
+            iter.readers.push(BufReader::new(File::open(path)?));
+            iter.heads.push(None);
+            iter.advance(iter.readers.len() - 1)?;
+        }
+        Ok(iter)
+    }
+
+    /// Read the next record of chunk `i`.
+    fn advance(&mut self, i: usize) -> Result<(), anyhow::Error> {
+        let mut line = String::new();
+        if self.readers[i].read_line(&mut line)? == 0 {
+            self.heads[i] = None;
+            return Ok(());
+        }
+        let rec: Record = serde_json::from_str(&line)?;
+        self.heap
+            .push(Reverse((rec.t.clone(), rec.k.clone(), rec.n, i)));
+        self.heads[i] = Some(rec);
+        Ok(())
+    }
+}
+
+impl Iterator for MergeIter {
+    type Item = Result<Record, anyhow::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, _, _, i)) = self.heap.pop()?;
+        let rec = self.heads[i].take().expect("heap entries have a record");
+        if let Err(err) = self.advance(i) {
+            return Some(Err(err));
+        }
+        Some(Ok(rec))
+    }
+}
+
+/// Keeps the last of consecutive records with the same key.
+struct Dedup {
+    inner: std::iter::Peekable<MergeIter>,
+    duplicates: usize,
+}
+
+impl Dedup {
+    fn new(inner: MergeIter) -> Self {
+        Self {
+            inner: inner.peekable(),
+            duplicates: 0,
+        }
+    }
+}
+
+impl Iterator for Dedup {
+    type Item = Result<Record, anyhow::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut rec = match self.inner.next()? {
+            Ok(rec) => rec,
+            Err(err) => return Some(Err(err)),
+        };
+        while let Some(Ok(next)) = self.inner.peek() {
+            if next.sort_key() != rec.sort_key() {
+                break;
+            }
+            self.duplicates += 1;
+            rec = match self.inner.next()? {
+                Ok(rec) => rec,
+                Err(err) => return Some(Err(err)),
+            };
+        }
+        Some(Ok(rec))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "\
+inetnum: 192.0.2.0 - 192.0.2.255
+netname: NET-A
+country: DE
+last-modified: 2024-01-01T00:00:00Z
+
+route: 198.51.100.0/24
+origin: AS65000
+
+person: John Doe
+nic-hdl: JD1-TEST
+
+aut-num: AS65000
+as-name: OLD-NAME
+
+mntner: TEST-MNT
+source: TEST
+";
+
+    const NEW: &str = "\
+aut-num: as65000
+as-name: NEW-NAME
+remarks: added
+
+inetnum: 192.0.2.0/24
+netname: NET-A
+country: DE
+last-modified: 2024-02-01T00:00:00Z
+
+person: John Doe
+nic-hdl: JD1-TEST
+
+route6: 2001:db8::/32
+origin: AS65000
+
+person: John Doe
+nic-hdl: JD1-TEST
+remarks: duplicate
+
+mntner: TEST-MNT
+source: TEST
+";
+
+    fn run(options: &DiffOptions) -> (Vec<RpslChange>, DiffStats) {
+        let mut changes = Vec::new();
+        let stats = diff_rpsl(OLD.as_bytes(), NEW.as_bytes(), options, |c| {
+            changes.push(c);
+            Ok(())
+        })
+        .unwrap();
+        (changes, stats)
+    }
+
+    #[test]
+    fn diff_dumps() {
+        // A chunk size of 2 forces a multi-way merge.
+        let options = DiffOptions {
+            chunk_size: 2,
+            ignore_attributes: vec!["last-modified".into()],
+        };
+        let (changes, stats) = run(&options);
+        assert_eq!(
+            stats,
+            DiffStats {
+                added: 1,
+                removed: 1,
+                modified: 3,
+                unchanged: 1,
+                duplicates: 1,
+            }
+        );
+
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| {
+                let kind = match c {
+                    RpslChange::Added(_) => "+",
+                    RpslChange::Removed(_) => "-",
+                    RpslChange::Modified { .. } => "~",
+                };
+                format!("{} {}", kind, c.object().obj_type().as_key())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            ["~ aut-num", "~ inetnum", "~ person", "- route", "+ route6"]
+        );
+
+        let RpslChange::Modified { changes, .. } = &changes[0] else {
+            panic!("expected modification");
+        };
+        assert_eq!(
+            changes,
+            &[
+                AttributeChange {
+                    attribute: "aut-num".into(),
+                    old: vec!["AS65000".into()],
+                    new: vec!["as65000".into()],
+                },
+                AttributeChange {
+                    attribute: "as-name".into(),
+                    old: vec!["OLD-NAME".into()],
+                    new: vec!["NEW-NAME".into()],
+                },
+                AttributeChange {
+                    attribute: "remarks".into(),
+                    old: vec![],
+                    new: vec!["added".into()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn ignored_attributes() {
+        // The inetnum is matched across notations, but its type attribute
+        // differs.
+        let changed = |options: &DiffOptions| -> Vec<String> {
+            let (changes, _) = run(options);
+            match &changes[1] {
+                RpslChange::Modified { changes, .. } => {
+                    changes.iter().map(|a| a.attribute.clone()).collect()
+                }
+                other => panic!("expected modification, got {:?}", other),
+            }
+        };
+        assert_eq!(
+            changed(&DiffOptions::default()),
+            ["inetnum", "last-modified"]
+        );
+        let options = DiffOptions {
+            ignore_attributes: vec!["last-modified".into()],
+            ..Default::default()
+        };
+        assert_eq!(changed(&options), ["inetnum"]);
+    }
+}
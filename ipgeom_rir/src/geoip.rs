@@ -0,0 +1,393 @@
+//! Overlap resolution for generated GeoIP databases.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use ipnet::IpNet;
+
+use crate::db::GeoSourceKind;
+use crate::types::Rir;
+
+/// A network and the country a source assigns to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeoipCandidate {
+    pub network: IpNet,
+    pub country: String,
+    pub source: GeoSourceKind,
+    pub rir: Rir,
+}
+
+/// Which criterion decides first between candidates for the same network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreak {
+    /// Prefer the primary source, then the registry priority.
+    #[default]
+    Source,
+    /// Prefer the registry priority, then the primary source.
+    Registry,
+}
+
+impl std::fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Source => write!(f, "source"),
+            Self::Registry => write!(f, "registry"),
+        }
+    }
+}
+
+impl std::str::FromStr for TieBreak {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "source" => Ok(Self::Source),
+            "registry" => Ok(Self::Registry),
+            _ => Err("Invalid tie-break, expected `source` or `registry`"),
+        }
+    }
+}
+
+/// How two candidates with different countries overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both candidates are for the same network.
+    SameNetwork,
+    /// The winner is a more specific network inside `other`.
+    Overlap,
+}
+
+/// Candidates with different countries for overlapping networks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeoipConflict {
+    pub kind: ConflictKind,
+    /// The candidate stored in the database.
+    pub winner: GeoipCandidate,
+    /// The candidate whose country lost for the winner's network.
+    pub other: GeoipCandidate,
+}
+
+/// Summary of a generated GeoIP database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeoipReport {
+    /// Networks written to the database.
+    pub networks: usize,
+    /// Networks without a country that inherited the one of their nearest
+    /// parent network.
+    pub inherited_from_parent: usize,
+    /// Networks without a country that inherited the one of their
+    /// organisation.
+    pub inherited_from_org: usize,
+    /// Networks skipped because no country could be determined.
+    pub missing_country: usize,
+    pub conflicts: Vec<GeoipConflict>,
+}
+
+/// An RPSL network whose country may still have to be inherited.
+#[derive(Debug)]
+struct PendingNetwork {
+    network: IpNet,
+    country: Option<String>,
+    org: Option<String>,
+    rir: Rir,
+}
+
+/// Collects candidates and resolves overlaps between them.
+///
+/// For every network the best candidate according to the tie-break wins.
+/// The winners are ordered so that more specific networks are inserted
+/// after the networks covering them, so the most specific network decides
+/// the country of an address regardless of the order of the input.
+#[derive(Debug)]
+pub(crate) struct GeoipResolver {
+    primary_source: GeoSourceKind,
+    tie_break: TieBreak,
+    rir_priority: Vec<Rir>,
+    candidates: Vec<GeoipCandidate>,
+    pending: Vec<PendingNetwork>,
+    org_countries: HashMap<String, String>,
+}
+
+impl GeoipResolver {
+    pub(crate) fn new(
+        primary_source: GeoSourceKind,
+        tie_break: TieBreak,
+        rir_priority: Vec<Rir>,
+    ) -> Self {
+        Self {
+            primary_source,
+            tie_break,
+            rir_priority,
+            candidates: Vec::new(),
+            pending: Vec::new(),
+            org_countries: HashMap::new(),
+        }
+    }
+
+    /// Add a network from an `inetnum` or `inet6num` object.
+    ///
+    /// Networks without a country inherit the one of the nearest covering
+    /// RPSL network, or else the one of `org`.
+    pub(crate) fn add_rpsl(
+        &mut self,
+        network: IpNet,
+        country: Option<String>,
+        org: Option<String>,
+        rir: Rir,
+    ) {
+        self.pending.push(PendingNetwork {
+            network: network.trunc(),
+            country: country.map(|c| c.to_uppercase()),
+            org,
+            rir,
+        });
+    }
+
+    /// Add a network from the delegated statistics.
+    pub(crate) fn add_delegated(&mut self, network: IpNet, country: &str, rir: Rir) {
+        self.candidates.push(GeoipCandidate {
+            network: network.trunc(),
+            country: country.to_uppercase(),
+            source: GeoSourceKind::Delegated,
+            rir,
+        });
+    }
+
+    /// Record the country of an organisation.
+    pub(crate) fn add_org(&mut self, org: String, country: &str) {
+        self.org_countries.insert(org, country.to_uppercase());
+    }
+
+    fn rank(&self, candidate: &GeoipCandidate) -> (usize, usize) {
+        let source = usize::from(candidate.source != self.primary_source);
+        let rir = match self.rir_priority.iter().position(|r| *r == candidate.rir) {
+            Some(pos) => pos,
+            None => {
+                self.rir_priority.len()
+                    + Rir::ALL
+                        .iter()
+                        .position(|r| *r == candidate.rir)
+                        .unwrap_or_default()
+            }
+        };
+        match self.tie_break {
+            TieBreak::Source => (source, rir),
+            TieBreak::Registry => (rir, source),
+        }
+    }
+
+    fn compare(&self, a: &GeoipCandidate, b: &GeoipCandidate) -> Ordering {
+        network_key(&a.network)
+            .cmp(&network_key(&b.network))
+            .then_with(|| self.rank(a).cmp(&self.rank(b)))
+            .then_with(|| a.country.cmp(&b.country))
+    }
+
+    /// Resolve the collected candidates.
+    ///
+    /// Returns the winning candidate of every network, ordered from the
+    /// least to the most specific network.
+    pub(crate) fn resolve(mut self) -> (Vec<GeoipCandidate>, GeoipReport) {
+        let mut report = GeoipReport::default();
+        self.inherit_countries(&mut report);
+
+        let mut candidates = std::mem::take(&mut self.candidates);
+        candidates.sort_by(|a, b| self.compare(a, b));
+
+        // The first candidate of every network wins.
+        let mut winners: Vec<GeoipCandidate> = Vec::new();
+        for candidate in candidates {
+            match winners.last() {
+                Some(winner) if winner.network == candidate.network => {
+                    if winner.country != candidate.country {
+                        report.conflicts.push(GeoipConflict {
+                            kind: ConflictKind::SameNetwork,
+                            winner: winner.clone(),
+                            other: candidate,
+                        });
+                    }
+                }
+                _ => winners.push(candidate),
+            }
+        }
+
+        // Winners are sorted by address and then prefix length, so covering
+        // networks come before the networks they contain.
+        let mut parents: Vec<&GeoipCandidate> = Vec::new();
+        for winner in &winners {
+            while parents
+                .last()
+                .is_some_and(|p| !p.network.contains(&winner.network))
+            {
+                parents.pop();
+            }
+            if let Some(parent) = parents.last() {
+                if parent.country != winner.country {
+                    report.conflicts.push(GeoipConflict {
+                        kind: ConflictKind::Overlap,
+                        winner: winner.clone(),
+                        other: (*parent).clone(),
+                    });
+                }
+            }
+            parents.push(winner);
+        }
+
+        winners.sort_by_key(|w| (tree_prefix_len(&w.network), network_key(&w.network)));
+        report.networks = winners.len();
+        (winners, report)
+    }
+
+    /// Turn the pending RPSL networks into candidates, inheriting missing
+    /// countries.
+    fn inherit_countries(&mut self, report: &mut GeoipReport) {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.sort_by_key(|p| network_key(&p.network));
+
+        let mut parents: Vec<(IpNet, Option<String>)> = Vec::new();
+        for network in pending {
+            while parents
+                .last()
+                .is_some_and(|(p, _)| !p.contains(&network.network))
+            {
+                parents.pop();
+            }
+            let country = match network.country {
+                Some(country) => Some(country),
+                None => {
+                    let parent = parents.iter().rev().find_map(|(_, c)| c.clone());
+                    let org = || {
+                        network
+                            .org
+                            .as_ref()
+                            .and_then(|org| self.org_countries.get(org).cloned())
+                    };
+                    if parent.is_some() {
+                        report.inherited_from_parent += 1;
+                        parent
+                    } else if let Some(country) = org() {
+                        report.inherited_from_org += 1;
+                        Some(country)
+                    } else {
+                        tracing::debug!(network = %network.network, "network without country");
+                        report.missing_country += 1;
+                        None
+                    }
+                }
+            };
+            parents.push((network.network, country.clone()));
+            if let Some(country) = country {
+                self.candidates.push(GeoipCandidate {
+                    network: network.network,
+                    country,
+                    source: GeoSourceKind::Rpsl,
+                    rir: network.rir,
+                });
+            }
+        }
+    }
+}
+
+/// Sort key placing covering networks before the networks they contain.
+fn network_key(network: &IpNet) -> (std::net::IpAddr, u8) {
+    (network.network(), network.prefix_len())
+}
+
+/// Prefix length of a network in the IPv6 tree of the database.
+fn tree_prefix_len(network: &IpNet) -> u8 {
+    match network {
+        IpNet::V4(net) => net.prefix_len() + 96,
+        IpNet::V6(net) => net.prefix_len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(s: &str) -> IpNet {
+        s.parse().unwrap()
+    }
+
+    fn countries(winners: &[GeoipCandidate]) -> Vec<(String, &str)> {
+        winners
+            .iter()
+            .map(|w| (w.network.to_string(), w.country.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn most_specific_and_tie_break() {
+        let mut resolver =
+            GeoipResolver::new(GeoSourceKind::Rpsl, TieBreak::Source, vec![Rir::Ripe]);
+        // The assignment is added before its covering allocation.
+        resolver.add_rpsl(net("192.0.2.128/25"), Some("de".into()), None, Rir::Ripe);
+        resolver.add_rpsl(net("192.0.2.0/24"), Some("NL".into()), None, Rir::Ripe);
+        resolver.add_delegated(net("192.0.2.0/24"), "BE", Rir::Ripe);
+        resolver.add_rpsl(net("198.51.100.0/24"), Some("US".into()), None, Rir::Arin);
+        resolver.add_rpsl(net("198.51.100.0/24"), Some("GB".into()), None, Rir::Ripe);
+
+        let (winners, report) = resolver.resolve();
+        assert_eq!(
+            countries(&winners),
+            vec![
+                ("192.0.2.0/24".to_string(), "NL"),
+                ("198.51.100.0/24".to_string(), "GB"),
+                ("192.0.2.128/25".to_string(), "DE"),
+            ]
+        );
+        assert_eq!(report.networks, 3);
+        let conflicts: Vec<_> = report
+            .conflicts
+            .iter()
+            .map(|c| (c.kind, c.winner.country.as_str(), c.other.country.as_str()))
+            .collect();
+        assert_eq!(
+            conflicts,
+            vec![
+                (ConflictKind::SameNetwork, "NL", "BE"),
+                (ConflictKind::SameNetwork, "GB", "US"),
+                (ConflictKind::Overlap, "DE", "NL"),
+            ]
+        );
+
+        let mut resolver =
+            GeoipResolver::new(GeoSourceKind::Rpsl, TieBreak::Registry, vec![Rir::Arin]);
+        resolver.add_rpsl(net("198.51.100.0/24"), Some("GB".into()), None, Rir::Ripe);
+        resolver.add_delegated(net("198.51.100.0/24"), "US", Rir::Arin);
+        let (winners, _) = resolver.resolve();
+        assert_eq!(winners[0].country, "US");
+    }
+
+    #[test]
+    fn inherit_missing_countries() {
+        let mut resolver = GeoipResolver::new(GeoSourceKind::Rpsl, TieBreak::Source, vec![]);
+        resolver.add_rpsl(net("2001:db8:1::/48"), None, None, Rir::Ripe);
+        resolver.add_rpsl(net("2001:db8::/32"), Some("NL".into()), None, Rir::Ripe);
+        resolver.add_rpsl(net("2001:db8:1:1::/64"), None, None, Rir::Ripe);
+        resolver.add_rpsl(
+            net("203.0.113.0/24"),
+            None,
+            Some("ORG-X".into()),
+            Rir::Apnic,
+        );
+        resolver.add_rpsl(net("100.64.0.0/10"), None, Some("ORG-Y".into()), Rir::Arin);
+        resolver.add_org("ORG-X".into(), "au");
+
+        let (winners, report) = resolver.resolve();
+        assert_eq!(
+            countries(&winners),
+            vec![
+                ("2001:db8::/32".to_string(), "NL"),
+                ("2001:db8:1::/48".to_string(), "NL"),
+                ("2001:db8:1:1::/64".to_string(), "NL"),
+                // At depth 96 + 24 in the tree.
+                ("203.0.113.0/24".to_string(), "AU"),
+            ]
+        );
+        assert_eq!(report.inherited_from_parent, 2);
+        assert_eq!(report.inherited_from_org, 1);
+        assert_eq!(report.missing_country, 1);
+        assert!(report.conflicts.is_empty());
+    }
+}
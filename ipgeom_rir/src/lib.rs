@@ -1,12 +1,30 @@
+mod allocation;
+mod countries;
 mod db;
+mod delegated;
+mod diff;
+mod geoip;
+mod nrtm;
+mod progress;
 mod store;
 mod types;
 
 use std::io::Read;
 
 pub use {
-    self::db::{sqlite::SqliteDb, Database},
-    self::store::Store,
+    self::allocation::{AllocationNode, AllocationTree, NodeId},
+    self::countries::{Continent, Country},
+    self::db::{sqlite::SqliteDb, Database, GeoMatch, GeoSource, GeoSourceKind},
+    self::delegated::{parse_delegated, DelegatedIter, DelegatedResource, Delegation},
+    self::diff::{diff_rpsl, AttributeChange, DiffOptions, DiffStats, RpslChange},
+    self::geoip::{ConflictKind, GeoipCandidate, GeoipConflict, GeoipReport, TieBreak},
+    self::nrtm::{apply_nrtm_operations, NrtmClient, NrtmStats, NrtmV4Client},
+    self::progress::{Progress, ProgressEvent, ProgressStage},
+    self::registry::download::Downloader,
+    self::store::{
+        open_dump_file, FailurePolicy, GeoipFormat, GeoipOptions, PersistFilter, RetentionPolicy,
+        SnapshotMeta, Store, StoreConfig,
+    },
     self::types::Rir as RirKind,
 };
 
@@ -15,41 +33,93 @@ mod registry;
 type Client = reqwest::blocking::Client;
 
 pub trait RirProvider: std::fmt::Debug + Send + Sync {
-    /// Build the request used to download the latest dump of the RPSL database
-    /// from the RIR.
-    fn build_rpsl_db_request(&self, client: &Client) -> reqwest::blocking::RequestBuilder;
+    /// URLs of the files the RIR publishes its RPSL database as.
+    ///
+    /// Most registries publish a single dump, others one file per object
+    /// type.
+    fn rpsl_db_urls(&self) -> Vec<String>;
 
     /// Download the latest dump of the RPSL database from the RIR.
     ///
-    /// The default implementation sends the request built by
-    /// [`build_rpsl_db_request`] and, if the response body appears to be
-    /// gzip-compressed based on the `Content-Type` header, transparently
-    /// decompresses it.
-    fn download_rpsl_db(&self, client: &Client) -> Result<DbData, anyhow::Error> {
-        let req = self
-            .build_rpsl_db_request(client)
-            .header(reqwest::header::ACCEPT_ENCODING, "gzip")
-            .build()?;
-
-        let uri_is_gzip = req.url().path().ends_with(".gz");
-
-        let res = client.execute(req)?.error_for_status()?;
-
-        let reader: Box<dyn Read + Send + Sync> = if uri_is_gzip {
-            Box::new(flate2::read::MultiGzDecoder::new(res))
-        } else {
-            Box::new(res)
-        };
+    /// The default implementation fetches the files listed by
+    /// [`rpsl_db_urls`] through the [`Downloader`] cache, one after the
+    /// other, and concatenates them. Files with a `.gz` extension are
+    /// transparently decompressed.
+    ///
+    /// Registries that do not publish RPSL override this to convert the
+    /// available data into RPSL objects.
+    fn download_rpsl_db(&self, dl: &Downloader) -> Result<DbData, anyhow::Error> {
+        let mut reader = registry::ConcatReader::new();
+        for url in self.rpsl_db_urls() {
+            let dl = dl.clone();
+            let checksum_url = self.rpsl_db_checksum_url(&url);
+            reader.push(move || dl.fetch_verified(dl.client().get(url), checksum_url.as_deref()));
+        }
+        Ok(DbData {
+            gzip: false,
+            reader: Box::new(reader),
+        })
+    }
 
+    /// Convert dump files stored on disk, as published by the RIR, into
+    /// RPSL.
+    ///
+    /// Used when importing local files instead of downloading them. The
+    /// default implementation expects RPSL and concatenates the files;
+    /// registries with their own dump format convert it like their
+    /// downloads.
+    fn import_rpsl_db(&self, files: Vec<Box<dyn Read + Send>>) -> Result<DbData, anyhow::Error> {
+        let mut reader = registry::ConcatReader::new();
+        for file in files {
+            reader.push(move || Ok(file));
+        }
         Ok(DbData {
             gzip: false,
-            reader,
+            reader: Box::new(reader),
         })
     }
+
+    /// URL of the checksum file published for the RPSL dump file `url`, if
+    /// any.
+    ///
+    /// Used by the default [`download_rpsl_db`] to verify each downloaded
+    /// file.
+    fn rpsl_db_checksum_url(&self, _url: &str) -> Option<String> {
+        None
+    }
+
+    /// Minimum number of objects in a complete RPSL dump.
+    ///
+    /// Downloads with fewer objects are rejected as truncated.
+    fn min_rpsl_objects(&self) -> usize {
+        1
+    }
+
+    /// URL of the delegated-extended statistics file published by the RIR.
+    fn delegated_stats_url(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Download the latest delegated-extended statistics file.
+    ///
+    /// The file is verified against the `.md5` checksum all registries
+    /// publish next to it. Returns `None` if the registry does not publish
+    /// statistics.
+    fn download_delegated_stats(
+        &self,
+        dl: &Downloader,
+    ) -> Result<Option<Box<dyn Read + Send>>, anyhow::Error> {
+        let Some(url) = self.delegated_stats_url() else {
+            return Ok(None);
+        };
+        let checksum_url = format!("{url}.md5");
+        let reader = dl.fetch_verified(dl.client().get(url), Some(&checksum_url))?;
+        Ok(Some(reader))
+    }
 }
 
 pub struct DbData {
     /// If the data is gzip-compressed.
     pub gzip: bool,
-    pub reader: Box<dyn Read + Send + Sync>,
+    pub reader: Box<dyn Read + Send>,
 }
@@ -0,0 +1,99 @@
+//! Incremental updates from NRTM mirror servers.
+
+mod v3;
+mod v4;
+
+pub use self::{
+    v3::{apply_nrtm_operations, NrtmClient},
+    v4::NrtmV4Client,
+};
+
+use ipgeom_rpsl::{Object, RpslObject};
+
+use crate::db::Database;
+
+/// Counters describing the changes applied by an NRTM update.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NrtmStats {
+    pub added: usize,
+    pub deleted: usize,
+    /// Changes whose object could not be converted.
+    pub skipped: usize,
+    /// Serial (NRTMv3) or version (NRTMv4) of the last applied change.
+    pub serial: Option<u64>,
+}
+
+/// Applies changes to a database, writing consecutive additions in batches.
+struct ChangeApplier<'a, D: Database + ?Sized> {
+    db: &'a D,
+    source: &'a str,
+    added: Vec<RpslObject>,
+    stats: NrtmStats,
+}
+
+impl<'a, D: Database + ?Sized> ChangeApplier<'a, D> {
+    const BATCH_SIZE: usize = 1000;
+
+    fn new(db: &'a D, source: &'a str) -> Self {
+        Self {
+            db,
+            source,
+            added: Vec::new(),
+            stats: NrtmStats::default(),
+        }
+    }
+
+    /// Convert an object, logging conversion problems.
+    ///
+    /// `serial` is only used for logging.
+    fn convert(&mut self, obj: Object, serial: u64) -> Option<RpslObject> {
+        match RpslObject::from_object(obj) {
+            Ok((obj, warnings)) => {
+                for w in warnings {
+                    tracing::warn!(
+                        source = self.source,
+                        serial,
+                        attribute = %w.attribute,
+                        value = %w.value,
+                        "{}",
+                        w.message
+                    );
+                }
+                Some(obj)
+            }
+            Err(err) => {
+                tracing::warn!(source = self.source, serial, error = %err, "skipping NRTM object");
+                self.stats.skipped += 1;
+                None
+            }
+        }
+    }
+
+    fn add(&mut self, obj: RpslObject) -> Result<(), anyhow::Error> {
+        self.added.push(obj);
+        self.stats.added += 1;
+        if self.added.len() >= Self::BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, obj_type: &str, obj_key: &str) -> Result<(), anyhow::Error> {
+        // Pending additions may include the deleted object.
+        self.flush()?;
+        self.db.delete_rpsl_object(obj_type, obj_key)?;
+        self.stats.deleted += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), anyhow::Error> {
+        self.db.upsert_rpsl_objects(&self.added)?;
+        self.added.clear();
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<NrtmStats, anyhow::Error> {
+        self.flush()?;
+        Ok(self.stats)
+    }
+}
@@ -0,0 +1,263 @@
+use std::io::{BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use anyhow::Context;
+use ipgeom_rpsl::nrtm::{v3_query, NrtmOperation, NrtmOperationKind, NrtmV3Reader};
+
+use super::{ChangeApplier, NrtmStats};
+use crate::db::{object_key, Database};
+
+/// Client for an NRTMv3 mirror server.
+///
+/// The server is queried with `-g SOURCE:3:FIRST-LAST` over a plain TCP
+/// connection, as supported by the RIPE database and IRRd.
+#[derive(Debug, Clone)]
+pub struct NrtmClient {
+    addr: String,
+    source: String,
+    timeout: Duration,
+}
+
+impl NrtmClient {
+    /// Create a client for `source` on the server at `addr` (`host:port`).
+    pub fn new<A: Into<String>, S: Into<String>>(addr: A, source: S) -> Self {
+        Self {
+            addr: addr.into(),
+            source: source.into(),
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// Set the connect, read and write timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Fetch the operations from serial `first` up to `last`, or up to the
+    /// most recent serial if `last` is `None`.
+    pub fn fetch(
+        &self,
+        first: u64,
+        last: Option<u64>,
+    ) -> Result<Vec<NrtmOperation>, anyhow::Error> {
+        let addr = std::net::ToSocketAddrs::to_socket_addrs(&self.addr)
+            .with_context(|| format!("could not resolve NRTM server '{}'", self.addr))?
+            .next()
+            .with_context(|| format!("could not resolve NRTM server '{}'", self.addr))?;
+        let mut stream = TcpStream::connect_timeout(&addr, self.timeout)
+            .with_context(|| format!("could not connect to NRTM server '{}'", self.addr))?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let query = v3_query(&self.source, first, last);
+        tracing::debug!(server = %self.addr, query = query.trim_end(), "sending NRTM query");
+        stream.write_all(query.as_bytes())?;
+        stream.flush()?;
+
+        let mut reader = NrtmV3Reader::new(BufReader::new(stream));
+        let ops = reader
+            .by_ref()
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("NRTM query for {} failed", self.source))?;
+        if let Some(header) = reader.header() {
+            if !header.source.eq_ignore_ascii_case(&self.source) {
+                anyhow::bail!(
+                    "NRTM server returned source {} instead of {}",
+                    header.source,
+                    self.source
+                );
+            }
+        }
+        Ok(ops)
+    }
+
+    /// Apply all changes after the serial recorded for the source in `db`.
+    ///
+    /// The serial of a source must be set with [`Database::set_nrtm_serial`]
+    /// after importing the full dump it belongs to.
+    pub fn update<D: Database + ?Sized>(&self, db: &D) -> Result<NrtmStats, anyhow::Error> {
+        let current = db.nrtm_serial(&self.source)?.with_context(|| {
+            format!(
+                "no NRTM serial recorded for {}, import a full dump first",
+                self.source
+            )
+        })?;
+        let ops = self.fetch(current + 1, None)?;
+        let stats = apply_nrtm_operations(db, &self.source, ops)?;
+        tracing::info!(
+            source = %self.source,
+            added = stats.added,
+            deleted = stats.deleted,
+            skipped = stats.skipped,
+            serial = stats.serial.unwrap_or(current),
+            "applied NRTM update"
+        );
+        Ok(stats)
+    }
+}
+
+/// Apply NRTM operations to a database and record the last serial for
+/// `source`.
+///
+/// The serial is only recorded once all operations were applied. Applying
+/// the same operations again is harmless, so an interrupted update can be
+/// retried from the previous serial.
+pub fn apply_nrtm_operations<D, I>(db: &D, source: &str, ops: I) -> Result<NrtmStats, anyhow::Error>
+where
+    D: Database + ?Sized,
+    I: IntoIterator<Item = NrtmOperation>,
+{
+    let mut applier = ChangeApplier::new(db, source);
+    let mut serial = None;
+    for op in ops {
+        serial = Some(op.serial);
+        let Some(obj) = applier.convert(op.object, op.serial) else {
+            continue;
+        };
+        match op.kind {
+            NrtmOperationKind::Add => applier.add(obj)?,
+            NrtmOperationKind::Del => applier.delete(obj.type_name(), &object_key(&obj))?,
+        }
+    }
+    let mut stats = applier.finish()?;
+    stats.serial = serial;
+
+    if let Some(serial) = serial {
+        db.set_nrtm_serial(source, serial)?;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, Read};
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::SqliteDb;
+    use ipgeom_rpsl::RpslObject;
+
+    /// Recorded response to `-g TEST:3:11-LAST`.
+    const STREAM: &str = "\
+% NRTM test server
+
+%START Version: 3 TEST 11-13
+
+ADD 11
+
+inetnum: 192.0.2.0 - 192.0.2.255
+netname: NEW-NET
+country: DE
+source: TEST
+
+DEL 12
+
+inetnum: 198.51.100.0 - 198.51.100.255
+netname: OLD-NET
+country: FR
+source: TEST
+
+ADD 13
+
+aut-num: AS64500
+as-name: EXAMPLE
+source: TEST
+
+%END TEST
+";
+
+    /// Serve `response` to a single client and return the received query.
+    fn serve_once(response: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut query = String::new();
+            reader.read_line(&mut query).unwrap();
+            let mut stream = reader.into_inner();
+            stream.write_all(response.as_bytes()).unwrap();
+            stream.shutdown(std::net::Shutdown::Write).unwrap();
+            // Wait for the client to close the connection.
+            let _ = stream.read(&mut [0u8; 1]);
+            query
+        });
+        (addr, handle)
+    }
+
+    fn inetnum(text: &str) -> RpslObject {
+        RpslObject::try_from(ipgeom_rpsl::parse_objects(text).unwrap().remove(0)).unwrap()
+    }
+
+    #[test]
+    fn update_applies_stream() {
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+        db.upsert_rpsl_object(&inetnum(
+            "inetnum: 198.51.100.0 - 198.51.100.255\nnetname: OLD-NET\ncountry: FR\nsource: TEST\n",
+        ))
+        .unwrap();
+        db.set_nrtm_serial("TEST", 10).unwrap();
+        assert_eq!(
+            db.lookup_ipv4("198.51.100.1".parse().unwrap()).unwrap(),
+            Some("FR".into())
+        );
+
+        let (addr, server) = serve_once(STREAM);
+        let stats = NrtmClient::new(addr, "TEST").update(&db).unwrap();
+        assert_eq!(server.join().unwrap(), "-g TEST:3:11-LAST\n");
+        assert_eq!(
+            stats,
+            NrtmStats {
+                added: 2,
+                deleted: 1,
+                skipped: 0,
+                serial: Some(13),
+            }
+        );
+
+        assert_eq!(db.nrtm_serial("TEST").unwrap(), Some(13));
+        assert_eq!(
+            db.lookup_ipv4("192.0.2.1".parse().unwrap()).unwrap(),
+            Some("DE".into())
+        );
+        assert_eq!(
+            db.lookup_ipv4("198.51.100.1".parse().unwrap()).unwrap(),
+            None
+        );
+        assert!(db
+            .get_object("inetnum", "198.51.100.0/24")
+            .unwrap()
+            .is_none());
+        assert!(db.get_object("aut-num", "AS64500").unwrap().is_some());
+    }
+
+    #[test]
+    fn update_requires_serial() {
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+        let err = NrtmClient::new("127.0.0.1:1", "TEST")
+            .update(&db)
+            .unwrap_err();
+        assert!(err.to_string().contains("no NRTM serial"));
+    }
+
+    #[test]
+    fn server_error_keeps_serial() {
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+        db.set_nrtm_serial("TEST", 10).unwrap();
+
+        let (addr, server) = serve_once("%ERROR:401: invalid range: Not within 20-30\n");
+        let err = NrtmClient::new(addr, "TEST").update(&db).unwrap_err();
+        server.join().unwrap();
+        assert!(format!("{:#}", err).contains("invalid range"));
+        assert_eq!(db.nrtm_serial("TEST").unwrap(), Some(10));
+    }
+}
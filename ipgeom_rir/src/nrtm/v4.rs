@@ -0,0 +1,658 @@
+use std::io::{BufReader, Read};
+
+use anyhow::Context;
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use ipgeom_rpsl::{
+    nrtm::{NrtmV4Change, NrtmV4FileReader, NrtmV4FileRef, NrtmV4FileType, NrtmV4Notification},
+    Object, ObjectType, RpslObject,
+};
+use sha2::{Digest, Sha256};
+
+use super::{ChangeApplier, NrtmStats};
+use crate::db::{object_key, Database};
+use crate::Client;
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, followed by the 32 key
+/// bytes.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Client for an NRTMv4 server.
+///
+/// The update notification file is fetched over HTTPS and its Ed25519
+/// signature is checked against the configured public key. Snapshot and
+/// delta files are verified against the SHA-256 hashes listed in the
+/// notification file before they are applied.
+#[derive(Debug, Clone)]
+pub struct NrtmV4Client {
+    notification_url: reqwest::Url,
+    source: String,
+    public_key: Vec<u8>,
+    client: Client,
+}
+
+impl NrtmV4Client {
+    /// Create a client for `source`.
+    ///
+    /// `public_key` is the Ed25519 key published by the server operator,
+    /// either PEM encoded or as base64 of the raw 32 byte key.
+    pub fn new<S: Into<String>>(
+        notification_url: &str,
+        source: S,
+        public_key: &str,
+    ) -> Result<Self, anyhow::Error> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent("ipgeomancer")
+            .build()?;
+        Ok(Self {
+            notification_url: notification_url
+                .parse()
+                .with_context(|| format!("invalid notification URL '{}'", notification_url))?,
+            source: source.into(),
+            public_key: parse_public_key(public_key)?,
+            client,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Fetch the update notification file and verify its signature.
+    pub fn fetch_notification(&self) -> Result<NrtmV4Notification, anyhow::Error> {
+        let jws = self
+            .client
+            .get(self.notification_url.clone())
+            .send()?
+            .error_for_status()?
+            .text()?;
+        let payload = verify_jws(&jws, &self.public_key)
+            .with_context(|| format!("invalid notification file for {}", self.source))?;
+        let notification = NrtmV4Notification::from_json(&payload)?;
+        if !notification.source.eq_ignore_ascii_case(&self.source) {
+            anyhow::bail!(
+                "notification file is for source {} instead of {}",
+                notification.source,
+                self.source
+            );
+        }
+        if let Some(key) = &notification.next_signing_key {
+            tracing::info!(source = %self.source, key, "NRTMv4 server announced a new signing key");
+        }
+        Ok(notification)
+    }
+
+    /// Bring the objects of the source in `db` up to date.
+    ///
+    /// The snapshot is loaded if nothing was loaded for the source before,
+    /// the server started a new session, or the available deltas do not
+    /// continue from the stored version. Deltas are then applied in order,
+    /// and the session ID and version are recorded after each file.
+    pub fn update<D: Database + ?Sized>(&self, db: &D) -> Result<NrtmStats, anyhow::Error> {
+        let notification = self.fetch_notification()?;
+        let session = notification.session_id.as_str();
+
+        let state = db.nrtm_v4_state(&self.source)?;
+        let reload_reason = match &state {
+            None => Some("no previous state"),
+            Some((stored, _)) if stored != session => Some("new session"),
+            Some((_, version)) if *version > notification.version => {
+                Some("stored version is newer than the server")
+            }
+            Some((_, version))
+                if *version < notification.version
+                    && !notification.deltas.iter().any(|d| d.version == version + 1) =>
+            {
+                Some("deltas do not continue from the stored version")
+            }
+            Some(_) => None,
+        };
+
+        let mut stats = NrtmStats::default();
+        let mut version = match (reload_reason, state) {
+            (None, Some((_, version))) => version,
+            (reason, _) => {
+                tracing::info!(
+                    source = %self.source,
+                    version = notification.snapshot.version,
+                    reason,
+                    "loading NRTMv4 snapshot"
+                );
+                let snapshot = self.load_snapshot(db, &notification)?;
+                stats.added += snapshot.added;
+                stats.skipped += snapshot.skipped;
+                notification.snapshot.version
+            }
+        };
+
+        let mut deltas: Vec<_> = notification
+            .deltas
+            .iter()
+            .filter(|d| d.version > version)
+            .collect();
+        deltas.sort_by_key(|d| d.version);
+        for delta in deltas {
+            if delta.version != version + 1 {
+                anyhow::bail!(
+                    "missing NRTMv4 delta for {} version {}",
+                    self.source,
+                    version + 1
+                );
+            }
+            let applied = self.apply_delta(db, session, delta)?;
+            stats.added += applied.added;
+            stats.deleted += applied.deleted;
+            stats.skipped += applied.skipped;
+            version = delta.version;
+        }
+        stats.serial = Some(version);
+
+        tracing::info!(
+            source = %self.source,
+            session,
+            version,
+            added = stats.added,
+            deleted = stats.deleted,
+            skipped = stats.skipped,
+            "applied NRTMv4 update"
+        );
+        Ok(stats)
+    }
+
+    /// Replace all objects of the source with the snapshot.
+    fn load_snapshot<D: Database + ?Sized>(
+        &self,
+        db: &D,
+        notification: &NrtmV4Notification,
+    ) -> Result<NrtmStats, anyhow::Error> {
+        let reader = self.open_file(
+            &notification.snapshot,
+            NrtmV4FileType::Snapshot,
+            &notification.session_id,
+        )?;
+        let version = notification.snapshot.version;
+        let mut applier = ChangeApplier::new(db, &self.source);
+        let mut objects = reader.filter_map(|change| match change {
+            Ok(NrtmV4Change::AddModify(obj)) => {
+                let obj = applier.convert(obj, version)?;
+                applier.stats.added += 1;
+                Some(Ok(obj))
+            }
+            Ok(NrtmV4Change::Delete { .. }) => Some(Err(anyhow::anyhow!(
+                "snapshot for {} contains a delete",
+                self.source
+            ))),
+            Err(err) => Some(Err(err.into())),
+        });
+        let deleted = db.load_nrtm_v4_snapshot(
+            &self.source,
+            &notification.session_id,
+            version,
+            &mut objects,
+        )?;
+        tracing::debug!(source = %self.source, deleted, "replaced objects with snapshot");
+        applier.finish()
+    }
+
+    fn apply_delta<D: Database + ?Sized>(
+        &self,
+        db: &D,
+        session_id: &str,
+        delta: &NrtmV4FileRef,
+    ) -> Result<NrtmStats, anyhow::Error> {
+        let reader = self.open_file(delta, NrtmV4FileType::Delta, session_id)?;
+        let mut applier = ChangeApplier::new(db, &self.source);
+        for change in reader {
+            match change? {
+                NrtmV4Change::AddModify(obj) => {
+                    if let Some(obj) = applier.convert(obj, delta.version) {
+                        applier.add(obj)?;
+                    }
+                }
+                NrtmV4Change::Delete {
+                    object_class,
+                    primary_key,
+                } => {
+                    let (obj_type, obj_key) = key_from_primary_key(&object_class, &primary_key)
+                        .with_context(|| {
+                            format!("invalid primary key '{}' in delete", primary_key)
+                        })?;
+                    applier.delete(&obj_type, &obj_key)?;
+                }
+            }
+        }
+        let stats = applier.finish()?;
+        db.set_nrtm_v4_state(&self.source, session_id, delta.version)?;
+        Ok(stats)
+    }
+
+    /// Download a snapshot or delta file, verify its hash and read its
+    /// header.
+    ///
+    /// Files are small enough to be kept in memory, which allows checking
+    /// the hash before any change is applied.
+    fn open_file(
+        &self,
+        file: &NrtmV4FileRef,
+        file_type: NrtmV4FileType,
+        session_id: &str,
+    ) -> Result<NrtmV4FileReader<BufReader<Box<dyn Read>>>, anyhow::Error> {
+        let url = self
+            .notification_url
+            .join(&file.url)
+            .with_context(|| format!("invalid file URL '{}'", file.url))?;
+        tracing::debug!(%url, version = file.version, "downloading NRTMv4 file");
+        let data = self
+            .client
+            .get(url.clone())
+            .send()?
+            .error_for_status()?
+            .bytes()?
+            .to_vec();
+
+        let hash = sha256_hex(&data);
+        if !hash.eq_ignore_ascii_case(&file.hash) {
+            anyhow::bail!(
+                "hash mismatch for {}: expected {}, got {}",
+                url,
+                file.hash,
+                hash
+            );
+        }
+
+        let reader: Box<dyn Read> = if data.starts_with(&[0x1f, 0x8b]) {
+            Box::new(flate2::read::GzDecoder::new(std::io::Cursor::new(data)))
+        } else {
+            Box::new(std::io::Cursor::new(data))
+        };
+        let reader = NrtmV4FileReader::new(BufReader::new(reader))
+            .with_context(|| format!("invalid NRTMv4 file {}", url))?;
+
+        let header = reader.header();
+        if header.file_type != file_type
+            || header.session_id != session_id
+            || header.version != file.version
+            || !header.source.eq_ignore_ascii_case(&self.source)
+        {
+            anyhow::bail!(
+                "NRTMv4 file {} does not match the notification file: \
+                 expected {:?} {} version {} of session {}, got {:?} {} version {} of session {}",
+                url,
+                file_type,
+                self.source,
+                file.version,
+                session_id,
+                header.file_type,
+                header.source,
+                header.version,
+                header.session_id
+            );
+        }
+        Ok(reader)
+    }
+}
+
+/// Decode an Ed25519 public key given as PEM or base64.
+fn parse_public_key(key: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let b64: String = key
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.starts_with("-----"))
+        .collect();
+    let der = STANDARD
+        .decode(b64)
+        .context("public key is not valid base64")?;
+    match der.len() {
+        32 => Ok(der),
+        44 if der.starts_with(&ED25519_SPKI_PREFIX) => Ok(der[12..].to_vec()),
+        _ => anyhow::bail!("not an Ed25519 public key"),
+    }
+}
+
+/// Verify a JWS in compact serialization signed with EdDSA and return the
+/// payload.
+fn verify_jws(jws: &str, public_key: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let jws = jws.trim();
+    let mut parts = jws.split('.');
+    let (Some(header), Some(payload), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        anyhow::bail!("not a JWS in compact serialization");
+    };
+
+    let header: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header)?).context("invalid JWS header")?;
+    let alg = header.get("alg").and_then(|a| a.as_str());
+    if alg != Some("EdDSA") {
+        anyhow::bail!("unsupported JWS algorithm {:?}", alg);
+    }
+
+    // The signature covers the encoded header and payload.
+    let signed = &jws[..jws.len() - signature.len() - 1];
+    let signature = URL_SAFE_NO_PAD.decode(signature)?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(signed.as_bytes(), &signature)
+        .map_err(|_| anyhow::anyhow!("signature verification failed"))?;
+
+    Ok(URL_SAFE_NO_PAD.decode(payload)?)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Map the object class and primary key of an NRTMv4 delete to the type and
+/// key used in the database.
+fn key_from_primary_key(
+    object_class: &str,
+    primary_key: &str,
+) -> Result<(String, String), anyhow::Error> {
+    let obj_type = ObjectType::from_key(&object_class.to_lowercase());
+    let mut obj = Object::new(obj_type.clone());
+    match obj_type {
+        // Persons and roles are stored by their NIC handle.
+        ObjectType::Person | ObjectType::Role => {
+            obj.add(obj_type.as_key().to_string(), primary_key.to_string());
+            obj.add("nic-hdl".to_string(), primary_key.to_string());
+        }
+        // The primary key of a route is the prefix followed by the origin.
+        ObjectType::Route | ObjectType::Route6 => {
+            let split = primary_key
+                .to_ascii_uppercase()
+                .rfind("AS")
+                .unwrap_or(primary_key.len());
+            obj.add(
+                obj_type.as_key().to_string(),
+                primary_key[..split].to_string(),
+            );
+        }
+        _ => obj.add(obj_type.as_key().to_string(), primary_key.to_string()),
+    }
+    let obj = RpslObject::try_from(obj)?;
+    Ok((obj.type_name().to_string(), object_key(&obj)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{BufRead, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    use flate2::{write::GzEncoder, Compression};
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use super::*;
+    use crate::SqliteDb;
+
+    const SESSION: &str = "ca128382-78d9-41d1-8927-1ecef15275be";
+
+    type Files = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+    /// Minimal HTTP server answering GET requests from `files`.
+    fn serve(files: Files) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                let body = files.lock().unwrap().get(path).cloned();
+                let (status, body) = match body {
+                    Some(body) => ("200 OK", body),
+                    None => ("404 Not Found", Vec::new()),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn key_pair() -> Ed25519KeyPair {
+        Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap()
+    }
+
+    fn json_seq(records: &[serde_json::Value]) -> Vec<u8> {
+        records
+            .iter()
+            .flat_map(|r| format!("\x1e{}\n", r).into_bytes())
+            .collect()
+    }
+
+    fn header(file_type: &str, version: u64) -> serde_json::Value {
+        serde_json::json!({
+            "nrtm_version": 4,
+            "type": file_type,
+            "source": "TEST",
+            "session_id": SESSION,
+            "version": version,
+        })
+    }
+
+    /// Publish a snapshot file and return its reference.
+    fn publish(files: &Files, path: &str, data: Vec<u8>, version: u64) -> serde_json::Value {
+        let hash = sha256_hex(&data);
+        files.lock().unwrap().insert(format!("/{}", path), data);
+        serde_json::json!({"version": version, "url": path, "hash": hash})
+    }
+
+    /// Sign and publish a notification file.
+    fn publish_notification(
+        files: &Files,
+        version: u64,
+        snapshot: serde_json::Value,
+        deltas: Vec<serde_json::Value>,
+    ) {
+        let payload = serde_json::json!({
+            "nrtm_version": 4,
+            "timestamp": "2024-01-01T00:00:00Z",
+            "type": "notification",
+            "source": "TEST",
+            "session_id": SESSION,
+            "version": version,
+            "snapshot": snapshot,
+            "deltas": deltas,
+        });
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"EdDSA"}"#),
+            URL_SAFE_NO_PAD.encode(payload.to_string())
+        );
+        let signature = key_pair().sign(signing_input.as_bytes());
+        let jws = format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.as_ref())
+        );
+        files
+            .lock()
+            .unwrap()
+            .insert("/update-notification-file.jose".into(), jws.into_bytes());
+    }
+
+    fn snapshot() -> Vec<u8> {
+        let data = json_seq(&[
+            header("snapshot", 1),
+            serde_json::json!({"object": "inetnum: 192.0.2.0 - 192.0.2.255\ncountry: DE\nsource: TEST\n"}),
+            serde_json::json!({"object": "person: John Doe\nnic-hdl: JD1-TEST\nsource: TEST\n"}),
+        ]);
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&data).unwrap();
+        gz.finish().unwrap()
+    }
+
+    fn client(base: &str) -> NrtmV4Client {
+        let public_key = STANDARD.encode(key_pair().public_key().as_ref());
+        NrtmV4Client::new(
+            &format!("{}/update-notification-file.jose", base),
+            "TEST",
+            &public_key,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn snapshot_and_deltas() {
+        let files = Files::default();
+        let base = serve(files.clone());
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+
+        let snap = publish(&files, "snapshot.1.json.gz", snapshot(), 1);
+        let delta2 = publish(
+            &files,
+            "delta.2.json",
+            json_seq(&[
+                header("delta", 2),
+                serde_json::json!({"action": "delete", "object_class": "person", "primary_key": "JD1-TEST"}),
+                serde_json::json!({"action": "add_modify", "object": "route: 198.51.100.0/24\norigin: AS64500\nsource: TEST\n"}),
+            ]),
+            2,
+        );
+        publish_notification(&files, 2, snap.clone(), vec![delta2.clone()]);
+
+        let stats = client(&base).update(&db).unwrap();
+        assert_eq!(
+            stats,
+            NrtmStats {
+                added: 3,
+                deleted: 1,
+                skipped: 0,
+                serial: Some(2),
+            }
+        );
+        assert_eq!(db.nrtm_v4_state("TEST").unwrap(), Some((SESSION.into(), 2)));
+        assert_eq!(
+            db.lookup_ipv4("192.0.2.1".parse().unwrap()).unwrap(),
+            Some("DE".into())
+        );
+        assert!(db.get_object("person", "JD1-TEST").unwrap().is_none());
+        assert!(db.get_object("route", "198.51.100.0/24").unwrap().is_some());
+
+        // The next update only applies the new delta.
+        let delta3 = publish(
+            &files,
+            "delta.3.json",
+            json_seq(&[
+                header("delta", 3),
+                serde_json::json!({"action": "delete", "object_class": "route", "primary_key": "198.51.100.0/24AS64500"}),
+                serde_json::json!({"action": "delete", "object_class": "inetnum", "primary_key": "192.0.2.0 - 192.0.2.255"}),
+            ]),
+            3,
+        );
+        publish_notification(&files, 3, snap, vec![delta2, delta3]);
+        let stats = client(&base).update(&db).unwrap();
+        assert_eq!((stats.added, stats.deleted), (0, 2));
+        assert_eq!(db.nrtm_v4_state("TEST").unwrap(), Some((SESSION.into(), 3)));
+        assert!(db.get_object("route", "198.51.100.0/24").unwrap().is_none());
+        assert_eq!(db.lookup_ipv4("192.0.2.1".parse().unwrap()).unwrap(), None);
+    }
+
+    #[test]
+    fn failed_snapshot_keeps_previous_state() {
+        let files = Files::default();
+        let base = serve(files.clone());
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+
+        let snap = publish(&files, "snapshot.1.json.gz", snapshot(), 1);
+        publish_notification(&files, 1, snap, vec![]);
+        client(&base).update(&db).unwrap();
+
+        // No delta continues from version 1, so the snapshot is loaded. It
+        // fails on its last record, after the route was read.
+        let snap = publish(
+            &files,
+            "snapshot.5.json",
+            json_seq(&[
+                header("snapshot", 5),
+                serde_json::json!({"object": "route: 198.51.100.0/24\norigin: AS64500\nsource: TEST\n"}),
+                serde_json::json!({"action": "delete", "object_class": "person", "primary_key": "JD1-TEST"}),
+            ]),
+            5,
+        );
+        publish_notification(&files, 5, snap, vec![]);
+        let err = client(&base).update(&db).unwrap_err();
+        assert!(format!("{err:#}").contains("record 3"), "{err:#}");
+
+        assert_eq!(db.nrtm_v4_state("TEST").unwrap(), Some((SESSION.into(), 1)));
+        assert_eq!(
+            db.lookup_ipv4("192.0.2.1".parse().unwrap()).unwrap(),
+            Some("DE".into())
+        );
+        assert!(db.get_object("person", "JD1-TEST").unwrap().is_some());
+        assert!(db.get_object("route", "198.51.100.0/24").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_bad_signature_and_hash() {
+        let files = Files::default();
+        let base = serve(files.clone());
+        let db = SqliteDb::memory().unwrap();
+        db.migrate().unwrap();
+
+        let mut snap = publish(&files, "snapshot.1.json.gz", snapshot(), 1);
+        snap["hash"] = "00".repeat(32).into();
+        publish_notification(&files, 1, snap, vec![]);
+        let err = client(&base).update(&db).unwrap_err();
+        assert!(err.to_string().contains("hash mismatch"), "{err:#}");
+        assert_eq!(db.nrtm_v4_state("TEST").unwrap(), None);
+
+        let other_key = STANDARD.encode(
+            Ed25519KeyPair::from_seed_unchecked(&[8; 32])
+                .unwrap()
+                .public_key()
+                .as_ref(),
+        );
+        let err = NrtmV4Client::new(
+            &format!("{}/update-notification-file.jose", base),
+            "TEST",
+            &other_key,
+        )
+        .unwrap()
+        .update(&db)
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("signature verification failed"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn public_key_formats() {
+        let raw = key_pair().public_key().as_ref().to_vec();
+        assert_eq!(parse_public_key(&STANDARD.encode(&raw)).unwrap(), raw);
+
+        let spki = [ED25519_SPKI_PREFIX.as_slice(), &raw].concat();
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            STANDARD.encode(spki)
+        );
+        assert_eq!(parse_public_key(&pem).unwrap(), raw);
+        assert!(parse_public_key("AAAA").is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::types::Rir;
+
+/// Minimum time between two progress events of the same operation.
+const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Stage of the work on a registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProgressStage {
+    /// Downloading files, counted in bytes.
+    Download,
+    /// Parsing stored RPSL objects.
+    Parse,
+    /// Writing RPSL objects into a database.
+    Persist,
+}
+
+impl std::fmt::Display for ProgressStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Download => write!(f, "download"),
+            Self::Parse => write!(f, "parse"),
+            Self::Persist => write!(f, "persist"),
+        }
+    }
+}
+
+/// Progress of one stage of the work on a registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub rir: Rir,
+    pub stage: ProgressStage,
+    /// Bytes downloaded or objects processed so far.
+    pub done: u64,
+    /// Expected final value of `done`, if known.
+    pub total: Option<u64>,
+    /// Time since the stage started.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Average rate per second since the stage started.
+    pub fn rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.done as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated time until the stage is complete, at the average rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.done);
+        let rate = self.rate();
+        (rate > 0.0).then(|| Duration::from_secs_f64(remaining as f64 / rate))
+    }
+
+    /// Completed fraction between 0 and 1, if the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total? {
+            0 => Some(1.0),
+            total => Some((self.done as f64 / total as f64).min(1.0)),
+        }
+    }
+}
+
+/// Event reported while updating or persisting a [`Store`](crate::Store).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// A stage made progress. Events are rate limited.
+    Progress(Progress),
+    /// A stage completed.
+    Finished(Progress),
+    /// Updating a registry failed.
+    Failed { rir: Rir, error: String },
+}
+
+type Callback = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// Delivers progress events and tracks cancellation of a run.
+#[derive(Clone, Default)]
+pub(crate) struct Reporter {
+    callback: Option<Callback>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for Reporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Reporter")
+            .field("callback", &self.callback.as_ref().map(|_| "<callback>"))
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl Reporter {
+    pub(crate) fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&ProgressEvent) + Send + Sync + 'static,
+    {
+        Self {
+            callback: Some(Arc::new(callback)),
+            cancelled: Arc::default(),
+        }
+    }
+
+    /// A reporter for a new run, sharing the callback but not the
+    /// cancellation state.
+    pub(crate) fn run(&self) -> Self {
+        Self {
+            callback: self.callback.clone(),
+            cancelled: Arc::default(),
+        }
+    }
+
+    fn emit(&self, event: ProgressEvent) {
+        if let Some(callback) = &self.callback {
+            callback(&event);
+        }
+    }
+
+    /// Start tracking a stage.
+    pub(crate) fn track(&self, rir: Rir, stage: ProgressStage, total: Option<u64>) -> Tracker {
+        let now = Instant::now();
+        Tracker {
+            reporter: self.clone(),
+            rir,
+            stage,
+            done: 0,
+            total,
+            start: now,
+            last_report: now,
+        }
+    }
+
+    pub(crate) fn failed(&self, rir: Rir, error: &anyhow::Error) {
+        self.emit(ProgressEvent::Failed {
+            rir,
+            error: format!("{error:#}"),
+        });
+    }
+
+    /// Ask all work of the run to stop.
+    pub(crate) fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail if the run was cancelled.
+    pub(crate) fn check(&self) -> std::io::Result<()> {
+        if self.is_cancelled() {
+            Err(std::io::Error::other("update aborted"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Progress of a single stage.
+#[derive(Debug)]
+pub(crate) struct Tracker {
+    reporter: Reporter,
+    rir: Rir,
+    stage: ProgressStage,
+    done: u64,
+    total: Option<u64>,
+    start: Instant,
+    last_report: Instant,
+}
+
+impl Tracker {
+    fn progress(&self) -> Progress {
+        Progress {
+            rir: self.rir,
+            stage: self.stage,
+            done: self.done,
+            total: self.total,
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    /// Continue counting from `done`, e.g. for a resumed download.
+    pub(crate) fn set_done(&mut self, done: u64) {
+        self.done = done;
+    }
+
+    /// Record `n` more units of work.
+    ///
+    /// Fails if the run was cancelled.
+    pub(crate) fn advance(&mut self, n: u64) -> std::io::Result<()> {
+        self.reporter.check()?;
+        self.done += n;
+        if self.last_report.elapsed() >= REPORT_INTERVAL {
+            self.last_report = Instant::now();
+            self.reporter.emit(ProgressEvent::Progress(self.progress()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) {
+        self.reporter.emit(ProgressEvent::Finished(self.progress()));
+    }
+}
+
+/// Reader that reports the bytes read from `inner`.
+pub(crate) struct TrackingReader<R> {
+    inner: R,
+    tracker: Option<Tracker>,
+}
+
+impl<R: Read> TrackingReader<R> {
+    pub(crate) fn new(inner: R, tracker: Tracker) -> Self {
+        Self {
+            inner,
+            tracker: Some(tracker),
+        }
+    }
+}
+
+impl<R: Read> Read for TrackingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            if let Some(tracker) = &mut self.tracker {
+                tracker.advance(n as u64)?;
+            }
+        } else if !buf.is_empty() {
+            if let Some(tracker) = self.tracker.take() {
+                tracker.finish();
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn progress_estimates() {
+        let progress = Progress {
+            rir: Rir::Ripe,
+            stage: ProgressStage::Download,
+            done: 50,
+            total: Some(200),
+            elapsed: Duration::from_secs(10),
+        };
+        assert_eq!(progress.rate(), 5.0);
+        assert_eq!(progress.eta(), Some(Duration::from_secs(30)));
+        assert_eq!(progress.fraction(), Some(0.25));
+        let unknown = Progress {
+            total: None,
+            ..progress
+        };
+        assert_eq!(unknown.eta(), None);
+        assert_eq!(unknown.fraction(), None);
+    }
+
+    #[test]
+    fn tracking_reader() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let reporter = {
+            let events = events.clone();
+            Reporter::new(move |e| events.lock().unwrap().push(e.clone()))
+        };
+        let tracker = reporter.track(Rir::Apnic, ProgressStage::Download, Some(3));
+        let mut out = Vec::new();
+        TrackingReader::new(&b"abc"[..], tracker)
+            .read_to_end(&mut out)
+            .unwrap();
+        let events = events.lock().unwrap();
+        let Some(ProgressEvent::Finished(progress)) = events.last() else {
+            panic!("missing finished event: {events:?}");
+        };
+        assert_eq!((progress.done, progress.total), (3, Some(3)));
+
+        let run = reporter.run();
+        run.cancel();
+        assert!(!reporter.is_cancelled());
+        let tracker = run.track(Rir::Apnic, ProgressStage::Download, None);
+        let err = TrackingReader::new(&b"abc"[..], tracker)
+            .read_to_end(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "update aborted");
+    }
+}
@@ -1,15 +1,29 @@
-use crate::{Client, RirProvider};
+use crate::RirProvider;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Afrinic {}
 
 impl Afrinic {
     const RPSL_DOWNLOAD_URL: &'static str = "https://ftp.afrinic.net/pub/dbase/afrinic.db.gz";
+    const DELEGATED_STATS_URL: &'static str =
+        "https://ftp.afrinic.net/pub/stats/afrinic/delegated-afrinic-extended-latest";
 }
 
 impl RirProvider for Afrinic {
-    fn build_rpsl_db_request(&self, client: &Client) -> reqwest::blocking::RequestBuilder {
-        client.get(Self::RPSL_DOWNLOAD_URL)
+    fn rpsl_db_urls(&self) -> Vec<String> {
+        vec![Self::RPSL_DOWNLOAD_URL.to_string()]
+    }
+
+    fn rpsl_db_checksum_url(&self, url: &str) -> Option<String> {
+        Some(format!("{url}.md5"))
+    }
+
+    fn delegated_stats_url(&self) -> Option<&'static str> {
+        Some(Self::DELEGATED_STATS_URL)
+    }
+
+    fn min_rpsl_objects(&self) -> usize {
+        10_000
     }
 }
 
@@ -1,17 +1,75 @@
-use crate::{Client, RirProvider};
+use crate::RirProvider;
 
+/// APNIC publishes its database as one file per object type.
 #[derive(Debug, Clone, Copy)]
 pub struct Apnic {}
 
 impl Apnic {
-    const RPSL_DOWNLOAD_URL: &'static str = "https://ftp.apnic.net/apnic/dbase/data/apnic.db.gz";
+    const RPSL_DOWNLOAD_BASE_URL: &'static str = "https://ftp.apnic.net/apnic/whois";
+    const DELEGATED_STATS_URL: &'static str =
+        "https://ftp.apnic.net/stats/apnic/delegated-apnic-extended-latest";
+
+    /// Object types published as `apnic.db.<type>.gz`.
+    ///
+    /// `person` objects are not included in the public dumps, `role` objects
+    /// are published without contact details.
+    const OBJECT_TYPES: &'static [&'static str] = &[
+        "as-block",
+        "as-set",
+        "aut-num",
+        "domain",
+        "filter-set",
+        "inet-rtr",
+        "inet6num",
+        "inetnum",
+        "irt",
+        "key-cert",
+        "mntner",
+        "organisation",
+        "peering-set",
+        "role",
+        "route-set",
+        "route",
+        "route6",
+        "rtr-set",
+    ];
+
+    fn url(obj_type: &str) -> String {
+        format!("{}/apnic.db.{}.gz", Self::RPSL_DOWNLOAD_BASE_URL, obj_type)
+    }
 }
 
 impl RirProvider for Apnic {
-    fn build_rpsl_db_request(&self, client: &Client) -> reqwest::blocking::RequestBuilder {
-        client.get(Self::RPSL_DOWNLOAD_URL)
+    fn rpsl_db_urls(&self) -> Vec<String> {
+        Self::OBJECT_TYPES.iter().map(|t| Self::url(t)).collect()
+    }
+
+    fn rpsl_db_checksum_url(&self, url: &str) -> Option<String> {
+        Some(format!("{url}.md5"))
+    }
+
+    fn delegated_stats_url(&self) -> Option<&'static str> {
+        Some(Self::DELEGATED_STATS_URL)
+    }
+
+    fn min_rpsl_objects(&self) -> usize {
+        100_000
     }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_file_urls() {
+        assert_eq!(
+            Apnic::url("inet6num"),
+            "https://ftp.apnic.net/apnic/whois/apnic.db.inet6num.gz"
+        );
+        let urls = Apnic {}.rpsl_db_urls();
+        assert_eq!(urls.len(), Apnic::OBJECT_TYPES.len());
+        assert!(urls.contains(&Apnic::url("inetnum")));
+        assert!(urls.contains(&Apnic::url("role")));
+    }
+}
@@ -1,17 +1,415 @@
-use crate::{Client, RirProvider};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 
-#[derive(Debug, Clone, Copy)]
-pub struct Arin {}
+use anyhow::Context;
+use ipgeom_rpsl::{parse_objects_read_iter, Decoding, Object, ObjectType};
+use ipnet::IpNet;
+
+use crate::{Client, DbData, Downloader, RirProvider};
+
+use super::{normalize_date, ConcatReader, ObjectsReader};
+
+/// ARIN only publishes its IRR data as an RPSL dump.
+///
+/// Networks, organisations and AS numbers are only available from the bulk
+/// WHOIS service, which requires an API key with bulk access. If a key is
+/// configured, the bulk WHOIS dump is downloaded and its records are
+/// converted into RPSL objects.
+#[derive(Clone)]
+pub struct Arin {
+    api_key: Option<String>,
+}
+
+impl std::fmt::Debug for Arin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Arin")
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
 
 impl Arin {
     const RPSL_DOWNLOAD_URL: &'static str = "https://ftp.arin.net/pub/rr/arin.db.gz";
+    const DELEGATED_STATS_URL: &'static str =
+        "https://ftp.arin.net/pub/stats/arin/delegated-arin-extended-latest";
+    const BULK_WHOIS_URL: &'static str =
+        "https://accountws.arin.net/public/secure/downloads/bulkwhois";
+    /// Name of the text dump in the bulk WHOIS archive.
+    const BULK_WHOIS_FILE: &'static str = "arin_db.txt";
+
+    pub fn new(api_key: Option<String>) -> Self {
+        Self { api_key }
+    }
+
+    /// Download the bulk WHOIS archive and convert its records.
+    fn bulk_whois(client: &Client, api_key: &str) -> Result<Box<dyn Read + Send>, anyhow::Error> {
+        let mut res = client
+            .get(Self::BULK_WHOIS_URL)
+            .query(&[("apikey", api_key)])
+            .send()?
+            .error_for_status()
+            .context("could not download ARIN bulk WHOIS data")?;
+        let mut archive = tempfile::tempfile()?;
+        std::io::copy(&mut res, &mut archive)?;
+        let dump = extract_zip_entry(archive, Self::BULK_WHOIS_FILE)?;
+        convert_dumps(vec![dump])
+    }
 }
 
 impl RirProvider for Arin {
-    fn build_rpsl_db_request(&self, client: &Client) -> reqwest::blocking::RequestBuilder {
-        client.get(Self::RPSL_DOWNLOAD_URL)
+    fn rpsl_db_urls(&self) -> Vec<String> {
+        let mut urls = Vec::new();
+        if self.api_key.is_some() {
+            urls.push(Self::BULK_WHOIS_URL.to_string());
+        }
+        urls.push(Self::RPSL_DOWNLOAD_URL.to_string());
+        urls
+    }
+
+    fn download_rpsl_db(&self, dl: &Downloader) -> Result<DbData, anyhow::Error> {
+        let mut reader = ConcatReader::new();
+        match &self.api_key {
+            Some(api_key) => {
+                let (client, api_key) = (dl.client().clone(), api_key.clone());
+                reader.push(move || Arin::bulk_whois(&client, &api_key));
+            }
+            None => tracing::warn!(
+                "no ARIN API key configured, only downloading the ARIN IRR data without networks"
+            ),
+        }
+        let dl = dl.clone();
+        reader.push(move || dl.fetch(dl.client().get(Arin::RPSL_DOWNLOAD_URL)));
+        Ok(DbData {
+            gzip: false,
+            reader: Box::new(reader),
+        })
+    }
+
+    /// Import bulk WHOIS archives, their extracted text dump and the IRR
+    /// dump, in any combination.
+    fn import_rpsl_db(&self, files: Vec<Box<dyn Read + Send>>) -> Result<DbData, anyhow::Error> {
+        let mut dumps = Vec::new();
+        for mut file in files {
+            let mut dump = tempfile::tempfile()?;
+            std::io::copy(&mut file, &mut dump)?;
+            dump.seek(SeekFrom::Start(0))?;
+            let mut magic = [0; 4];
+            let is_zip = dump.read(&mut magic)? == magic.len() && magic == *b"PK\x03\x04";
+            if is_zip {
+                dump = extract_zip_entry(dump, Self::BULK_WHOIS_FILE)?;
+            }
+            dumps.push(dump);
+        }
+        Ok(DbData {
+            gzip: false,
+            reader: convert_dumps(dumps)?,
+        })
+    }
+
+    fn delegated_stats_url(&self) -> Option<&'static str> {
+        Some(Self::DELEGATED_STATS_URL)
+    }
+
+    fn min_rpsl_objects(&self) -> usize {
+        1_000
+    }
+}
+
+/// Convert text dumps into RPSL.
+///
+/// Records of the bulk WHOIS dump are converted, objects of the IRR dump are
+/// kept as they are.
+fn convert_dumps(dumps: Vec<File>) -> Result<Box<dyn Read + Send>, anyhow::Error> {
+    // Networks reference organisations by ID, so the countries of all
+    // organisations are collected in a first pass.
+    let mut countries = HashMap::new();
+    for dump in &dumps {
+        for res in bulk_records(dump)? {
+            let obj = res?;
+            if obj.obj_type().as_key() == "orgid" {
+                if let (Some(id), Some(country)) =
+                    (obj.get_first("orgid"), obj.get_first("country"))
+                {
+                    countries.insert(id.to_string(), country.to_uppercase());
+                }
+            }
+        }
+    }
+    tracing::debug!(
+        organisations = countries.len(),
+        "read ARIN bulk WHOIS organisations"
+    );
+
+    let records = dumps
+        .iter()
+        .map(bulk_records)
+        .collect::<Result<Vec<_>, _>>()?;
+    let objects = records
+        .into_iter()
+        .flatten()
+        .map(move |res| Ok(normalize(res?, &countries)))
+        .flat_map(|res: Result<Vec<Object>, anyhow::Error>| match res {
+            Ok(objs) => objs.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(e) => vec![Err(e)],
+        });
+    Ok(Box::new(ObjectsReader::new(objects)))
+}
+
+/// Iterate over the records of the text dump of the bulk WHOIS archive.
+fn bulk_records(
+    dump: &File,
+) -> Result<impl Iterator<Item = Result<Object, anyhow::Error>>, anyhow::Error> {
+    let mut file = dump.try_clone()?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(parse_objects_read_iter(file)
+        .with_decoding(Decoding::Utf8OrLatin1)
+        .with_recovery(|malformed| {
+            tracing::warn!(
+                rir = "arin",
+                line = malformed.line,
+                "skipping malformed record"
+            );
+        })
+        .map(|res| Ok(res?)))
+}
+
+/// Convert a bulk WHOIS record into RPSL objects.
+///
+/// Keys of the records are `CamelCase` in the dump and lowercase after
+/// parsing. Points of contact and unknown records are dropped. Objects with
+/// an RPSL type are returned unchanged.
+fn normalize(obj: Object, countries: &HashMap<String, String>) -> Vec<Object> {
+    if !matches!(obj.obj_type(), ObjectType::Other(_)) {
+        return vec![obj];
     }
+    let org = obj.get_first("orgid");
+    let country = org.and_then(|id| countries.get(id));
+    let mut objects = match obj.obj_type().as_key() {
+        "nethandle" => networks(&obj),
+        "ashandle" => aut_nums(&obj),
+        "orgid" => {
+            let mut out = Object::new(ObjectType::Organisation);
+            out.add("organisation".into(), org.unwrap_or_default().to_string());
+            if let Some(name) = obj.get_first("orgname") {
+                out.add("org-name".into(), name.to_string());
+            }
+            vec![out]
+        }
+        _ => return Vec::new(),
+    };
+
+    for out in &mut objects {
+        if out.obj_type() != &ObjectType::Organisation {
+            if let Some(org) = org {
+                out.add("org".into(), org.to_string());
+            }
+        }
+        if let Some(country) = country {
+            out.add("country".into(), country.clone());
+        }
+        if let Some(created) = obj.get_first("regdate").and_then(normalize_date) {
+            out.add("created".into(), created);
+        }
+        if let Some(updated) = obj.get_first("updated").and_then(normalize_date) {
+            out.add("last-modified".into(), updated);
+        }
+        out.add("source".into(), "ARIN".into());
+    }
+    objects
+}
+
+/// `NetRange` for IPv4 networks, one object per `CIDR` for IPv6.
+fn networks(obj: &Object) -> Vec<Object> {
+    let name = obj.get_first("netname").unwrap_or("ARIN-NET");
+    let range = obj.get_first("netrange").unwrap_or_default();
+    let mut ranges = Vec::new();
+    if range.contains('.') {
+        ranges.push(("inetnum", range.to_string()));
+    } else {
+        for cidr in obj.get_first("cidr").unwrap_or_default().split(',') {
+            if let Ok(net) = cidr.trim().parse::<IpNet>() {
+                ranges.push(("inet6num", net.trunc().to_string()));
+            }
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(key, value)| {
+            let mut out = Object::new(ObjectType::from_key(key));
+            out.add(key.into(), value);
+            out.add("netname".into(), name.to_string());
+            out
+        })
+        .collect()
+}
+
+/// `ASNumber` is either a single number or a range.
+fn aut_nums(obj: &Object) -> Vec<Object> {
+    let number = obj.get_first("asnumber").unwrap_or_default();
+    let mut out = match number.split_once('-') {
+        Some((first, last)) => {
+            let mut out = Object::new(ObjectType::AsBlock);
+            out.add(
+                "as-block".into(),
+                format!("AS{} - AS{}", first.trim(), last.trim()),
+            );
+            out
+        }
+        None if !number.is_empty() => {
+            let mut out = Object::new(ObjectType::AutNum);
+            out.add("aut-num".into(), format!("AS{}", number.trim()));
+            out
+        }
+        None => return Vec::new(),
+    };
+    if let Some(name) = obj.get_first("asname") {
+        out.add("as-name".into(), name.to_string());
+    }
+    vec![out]
+}
+
+/// Extract the file `name` from a zip archive into a temporary file.
+///
+/// The dump is read twice, so it is decompressed only once up front.
+fn extract_zip_entry<R: Read + Seek>(archive: R, name: &str) -> Result<File, anyhow::Error> {
+    let mut archive = zip::ZipArchive::new(archive).context("invalid zip archive")?;
+    let index = (0..archive.len())
+        .find(|&i| {
+            archive
+                .name_for_index(i)
+                .is_some_and(|entry| entry.rsplit('/').next() == Some(name))
+        })
+        .with_context(|| format!("file {} not found in zip archive", name))?;
+    let mut entry = archive.by_index(index)?;
+    let mut file = tempfile::tempfile()?;
+    std::io::copy(&mut entry, &mut file)?;
+    Ok(file)
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use ipgeom_rpsl::parse_objects;
+    use std::io::Write;
+
+    /// Build a zip archive. Files marked as large use zip64 headers.
+    fn zip(files: &[(&str, &str, bool)]) -> std::io::Cursor<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, content, large) in files {
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .large_file(*large);
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn read_zip_entry() {
+        let archive = zip(&[
+            ("README", "ignore me", false),
+            ("arin_db/arin_db.txt", "NetHandle: NET-1\n", true),
+        ]);
+        let mut text = String::new();
+        let mut file = extract_zip_entry(archive.clone(), "arin_db.txt").unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "NetHandle: NET-1\n");
+
+        let records: Vec<_> = bulk_records(&file).unwrap().collect();
+        assert_eq!(records.len(), 1);
+        assert!(extract_zip_entry(archive, "missing.txt").is_err());
+    }
+
+    #[test]
+    fn import_bulk_whois_and_irr_dumps() {
+        let archive = zip(&[(
+            "arin_db.txt",
+            "NetHandle: NET-192-0-2-0-1\nOrgID: EXAMPLE\nNetRange: 192.0.2.0 - 192.0.2.255\n\n\
+             OrgID: EXAMPLE\nOrgName: Example Corp\nCountry: us\n",
+            false,
+        )]);
+        let irr = "route: 192.0.2.0/24\norigin: AS64496\nsource: ARIN\n";
+        let files: Vec<Box<dyn Read + Send>> = vec![
+            Box::new(std::io::Cursor::new(archive.into_inner())),
+            Box::new(std::io::Cursor::new(irr.as_bytes().to_vec())),
+        ];
+        let mut text = String::new();
+        Arin::new(None)
+            .import_rpsl_db(files)
+            .unwrap()
+            .reader
+            .read_to_string(&mut text)
+            .unwrap();
+
+        let objs = parse_objects(&text).unwrap();
+        let types: Vec<_> = objs.iter().map(|o| o.obj_type().as_key()).collect();
+        assert_eq!(types, ["inetnum", "organisation", "route"]);
+        // The organisation is read after the network, its country is still
+        // applied.
+        assert_eq!(objs[0].get_first("country"), Some("US"));
+        assert_eq!(objs[2].get_first("origin"), Some("AS64496"));
+    }
+
+    #[test]
+    fn normalize_bulk_whois() {
+        let text = "\
+OrgID:          EXAMPLE
+OrgName:        Example Corp
+Country:        us
+
+NetHandle:      NET-192-0-2-0-1
+OrgID:          EXAMPLE
+NetName:        EXAMPLE-NET
+NetRange:       192.0.2.0 - 192.0.2.255
+CIDR:           192.0.2.0/24
+RegDate:        2001-02-03
+Updated:        2020-01-01
+
+NetHandle:      NET6-2001-DB8-1
+OrgID:          EXAMPLE
+NetName:        EXAMPLE-V6
+NetRange:       2001:db8:: - 2001:db9:ffff:ffff:ffff:ffff:ffff:ffff
+CIDR:           2001:db8::/32, 2001:db9::/32
+
+ASHandle:       AS64496
+OrgID:          EXAMPLE
+ASName:         EXAMPLE-AS
+ASNumber:       64496
+
+POCHandle:      JD1-ARIN
+Name:           Doe, John
+";
+        let records = parse_objects(text).unwrap();
+        let countries = [("EXAMPLE".to_string(), "US".to_string())].into();
+        let objs: Vec<_> = records
+            .into_iter()
+            .flat_map(|obj| normalize(obj, &countries))
+            .collect();
+
+        let types: Vec<_> = objs.iter().map(|o| o.obj_type().as_key()).collect();
+        assert_eq!(
+            types,
+            ["organisation", "inetnum", "inet6num", "inet6num", "aut-num"]
+        );
+        assert_eq!(
+            objs[1].to_rpsl(),
+            "inetnum: 192.0.2.0 - 192.0.2.255\n\
+             netname: EXAMPLE-NET\n\
+             org: EXAMPLE\n\
+             country: US\n\
+             created: 2001-02-03T00:00:00Z\n\
+             last-modified: 2020-01-01T00:00:00Z\n\
+             source: ARIN\n"
+        );
+        assert_eq!(objs[3].get_first("inet6num"), Some("2001:db9::/32"));
+        assert_eq!(objs[4].get_first("as-name"), Some("EXAMPLE-AS"));
+
+        for obj in objs {
+            ipgeom_rpsl::RpslObject::try_from(obj).unwrap();
+        }
+    }
+}
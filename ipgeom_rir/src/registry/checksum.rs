@@ -0,0 +1,171 @@
+use std::io::Read;
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::Client;
+
+/// Checksum published next to a download, e.g. in a `.md5` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Checksum {
+    Md5([u8; 16]),
+    Sha256([u8; 32]),
+}
+
+impl Checksum {
+    /// Extract the checksum from the content of a checksum file.
+    ///
+    /// Both the `md5sum` format (`<hex>  <file>`) and the BSD format
+    /// (`MD5 (<file>) = <hex>`) are supported. The algorithm is derived from
+    /// the length of the digest.
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        text.split(|c: char| c.is_whitespace() || c == '=')
+            .find_map(|token| match token.len() {
+                32 => decode_hex(token).map(Self::Md5),
+                64 => decode_hex(token).map(Self::Sha256),
+                _ => None,
+            })
+    }
+
+    fn hasher(&self) -> Hasher {
+        match self {
+            Self::Md5(_) => Hasher::Md5(Md5::new()),
+            Self::Sha256(_) => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    fn digest(&self) -> &[u8] {
+        match self {
+            Self::Md5(d) => d,
+            Self::Sha256(d) => d,
+        }
+    }
+}
+
+fn decode_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(out)
+}
+
+/// Download and parse a checksum file.
+///
+/// Returns `None` if the registry does not publish the file.
+pub(crate) fn fetch_checksum(
+    client: &Client,
+    url: &str,
+) -> Result<Option<Checksum>, anyhow::Error> {
+    let res = client.get(url).send()?;
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        tracing::warn!(url, "checksum file not found, download is not verified");
+        return Ok(None);
+    }
+    let text = res.error_for_status()?.text()?;
+    match Checksum::parse(&text) {
+        Some(checksum) => Ok(Some(checksum)),
+        None => anyhow::bail!("no checksum found in {url}"),
+    }
+}
+
+enum Hasher {
+    Md5(Md5),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(h) => h.update(data),
+            Self::Sha256(h) => h.update(data),
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        match self {
+            Self::Md5(h) => h.finalize().to_vec(),
+            Self::Sha256(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+/// Reader that verifies the checksum of the data read from `inner`.
+///
+/// Reaching the end of the input with a different digest is reported as an
+/// [`std::io::ErrorKind::InvalidData`] error.
+pub(crate) struct VerifyingReader<R> {
+    inner: R,
+    expected: Checksum,
+    hasher: Option<Hasher>,
+    name: String,
+}
+
+impl<R: Read> VerifyingReader<R> {
+    pub(crate) fn new(inner: R, expected: Checksum, name: impl Into<String>) -> Self {
+        Self {
+            inner,
+            hasher: Some(expected.hasher()),
+            expected,
+            name: name.into(),
+        }
+    }
+}
+
+impl<R: Read> Read for VerifyingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            if let Some(hasher) = &mut self.hasher {
+                hasher.update(&buf[..n]);
+            }
+        } else if !buf.is_empty() {
+            if let Some(hasher) = self.hasher.take() {
+                if hasher.finish() != self.expected.digest() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("checksum mismatch for {}", self.name),
+                    ));
+                }
+                tracing::debug!(name = %self.name, "checksum verified");
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_checksums() {
+        let md5 = Checksum::parse(
+            "MD5 (delegated-ripencc-extended-latest) = 900150983cd24fb0d6963f7d28e17f72\n",
+        )
+        .unwrap();
+        let mut out = String::new();
+        VerifyingReader::new(&b"abc"[..], md5.clone(), "abc")
+            .read_to_string(&mut out)
+            .unwrap();
+        assert_eq!(out, "abc");
+        let err = VerifyingReader::new(&b"abd"[..], md5, "abd")
+            .read_to_string(&mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let sha = Checksum::parse(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  abc.txt",
+        )
+        .unwrap();
+        assert!(matches!(sha, Checksum::Sha256(_)));
+        VerifyingReader::new(&b"abc"[..], sha, "abc")
+            .read_to_end(&mut Vec::new())
+            .unwrap();
+
+        assert_eq!(Checksum::parse("not a checksum"), None);
+    }
+}
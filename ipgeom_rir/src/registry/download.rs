@@ -0,0 +1,519 @@
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use reqwest::header::{
+    HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use super::checksum::{self, VerifyingReader};
+use crate::progress::{ProgressStage, Reporter, TrackingReader};
+use crate::{types, Client};
+
+/// Downloads files published by a registry through a local cache.
+///
+/// Every file is stored in the cache directory together with its `ETag` and
+/// `Last-Modified` headers. Later downloads of the same URL are conditional
+/// requests, so unchanged files are not transferred again, and interrupted
+/// downloads are resumed with a range request.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: Client,
+    cache_dir: PathBuf,
+    mirror: Option<String>,
+    progress: Option<(Reporter, types::Rir)>,
+    fetched: Arc<Mutex<Vec<FetchedFile>>>,
+}
+
+/// Validators of a cached download.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheMeta {
+    fn has_validator(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// A file obtained by a [`Downloader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FetchedFile {
+    pub(crate) url: String,
+    pub(crate) size: u64,
+    /// The cached file was still current.
+    pub(crate) not_modified: bool,
+    /// The download continued a previously interrupted one.
+    pub(crate) resumed: bool,
+}
+
+impl Downloader {
+    /// Create a downloader caching files in `cache_dir`.
+    pub fn new(client: Client, cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            client,
+            cache_dir: cache_dir.into(),
+            mirror: None,
+            progress: None,
+            fetched: Arc::default(),
+        }
+    }
+
+    /// Download files from a mirror instead of the registry.
+    ///
+    /// Files are requested by their file name below `base_url`, e.g.
+    /// `https://ftp.ripe.net/ripe/dbase/ripe.db.gz` is fetched from
+    /// `<base_url>/ripe.db.gz`. Checksum files are fetched from the mirror as
+    /// well.
+    pub fn with_mirror(mut self, base_url: impl Into<String>) -> Self {
+        self.mirror = Some(base_url.into());
+        self
+    }
+
+    /// Report the progress of downloads as downloads of `rir`.
+    pub(crate) fn with_reporter(mut self, reporter: Reporter, rir: types::Rir) -> Self {
+        self.progress = Some((reporter, rir));
+        self
+    }
+
+    /// The HTTP client, for requests that should bypass the cache.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Download a file and return its content.
+    ///
+    /// Files with a `.gz` extension are transparently decompressed.
+    pub fn fetch(
+        &self,
+        req: reqwest::blocking::RequestBuilder,
+    ) -> Result<Box<dyn Read + Send>, anyhow::Error> {
+        self.fetch_verified(req, None)
+    }
+
+    /// Like [`Downloader::fetch`], but verify the file against the checksum
+    /// published at `checksum_url`.
+    ///
+    /// The checksum covers the file as published, before decompression. A
+    /// cached file that does not match is removed, so it is downloaded again
+    /// on the next attempt.
+    pub fn fetch_verified(
+        &self,
+        req: reqwest::blocking::RequestBuilder,
+        checksum_url: Option<&str>,
+    ) -> Result<Box<dyn Read + Send>, anyhow::Error> {
+        let checksum = match checksum_url {
+            Some(url) => {
+                let url = self.resolve(&reqwest::Url::parse(url)?)?;
+                checksum::fetch_checksum(&self.client, url.as_str())?
+            }
+            None => None,
+        };
+        let mut req = req.build()?;
+        *req.url_mut() = self.resolve(req.url())?;
+        let url = req.url().to_string();
+        let is_gzip = req.url().path().ends_with(".gz");
+        let path = self.cache_dir.join(cache_name(req.url()));
+
+        let fetched = self.download(req, &path)?;
+
+        if let Some(checksum) = checksum {
+            let file = File::open(&path)?;
+            if let Err(err) = std::io::copy(
+                &mut VerifyingReader::new(file, checksum, &url),
+                &mut std::io::sink(),
+            ) {
+                let _ = std::fs::remove_file(&path);
+                let _ = std::fs::remove_file(meta_path(&path));
+                return Err(err.into());
+            }
+        }
+        tracing::info!(
+            url,
+            size = fetched.size,
+            not_modified = fetched.not_modified,
+            resumed = fetched.resumed,
+            "fetched file"
+        );
+        self.fetched.lock().unwrap().push(fetched);
+
+        let file = File::open(&path)?;
+        if is_gzip {
+            Ok(Box::new(flate2::read::MultiGzDecoder::new(file)))
+        } else {
+            Ok(Box::new(file))
+        }
+    }
+
+    /// The URL `url` is downloaded from.
+    fn resolve(&self, url: &reqwest::Url) -> Result<reqwest::Url, anyhow::Error> {
+        let Some(mirror) = &self.mirror else {
+            return Ok(url.clone());
+        };
+        let name = url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .unwrap_or_default();
+        let mirrored = format!("{}/{name}", mirror.trim_end_matches('/'));
+        reqwest::Url::parse(&mirrored).with_context(|| format!("invalid mirror URL {mirrored}"))
+    }
+
+    /// Files obtained so far, in the order they were requested.
+    pub(crate) fn fetched(&self) -> Vec<FetchedFile> {
+        self.fetched.lock().unwrap().clone()
+    }
+
+    /// Bring the cached copy at `path` up to date.
+    fn download(
+        &self,
+        req: reqwest::blocking::Request,
+        path: &Path,
+    ) -> Result<FetchedFile, anyhow::Error> {
+        std::fs::create_dir_all(&self.cache_dir)?;
+        let url = req.url().to_string();
+        let part_path = part_path(path);
+        let meta_path = meta_path(path);
+        let meta = read_meta(&meta_path).filter(|m| m.url == url && m.has_validator());
+
+        let part_len = std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+        let mut resume_from = 0;
+        let mut conditional = false;
+        let mut attempt = req
+            .try_clone()
+            .context("download requests must not have a streaming body")?;
+        if let Some(meta) = &meta {
+            let validator = meta.etag.as_ref().or(meta.last_modified.as_ref());
+            let headers = attempt.headers_mut();
+            if part_len > 0 {
+                if let Some(validator) = validator {
+                    headers.insert(RANGE, HeaderValue::from_str(&format!("bytes={part_len}-"))?);
+                    headers.insert(IF_RANGE, HeaderValue::from_str(validator)?);
+                    resume_from = part_len;
+                }
+            } else if path.exists() {
+                if let Some(etag) = &meta.etag {
+                    headers.insert(IF_NONE_MATCH, HeaderValue::from_str(etag)?);
+                }
+                if let Some(modified) = &meta.last_modified {
+                    headers.insert(IF_MODIFIED_SINCE, HeaderValue::from_str(modified)?);
+                }
+                conditional = true;
+            }
+        }
+
+        tracing::debug!(url, resume_from, conditional, "downloading");
+        let mut res = self.client.execute(attempt)?;
+
+        if conditional && res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(FetchedFile {
+                url,
+                size: std::fs::metadata(path)?.len(),
+                not_modified: true,
+                resumed: false,
+            });
+        }
+        if resume_from > 0 && res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file does not belong to the current version.
+            std::fs::remove_file(&part_path)?;
+            res = self.client.execute(req)?;
+            resume_from = 0;
+        }
+        let res = res.error_for_status()?;
+        let resumed = resume_from > 0 && res.status() == StatusCode::PARTIAL_CONTENT;
+        let offset = if resumed { resume_from } else { 0 };
+
+        // Validators are stored first, so an interrupted download can be
+        // resumed later.
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let new_meta = CacheMeta {
+            url: url.clone(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        std::fs::write(&meta_path, serde_json::to_vec(&new_meta)?)?;
+
+        let mut part = if resumed {
+            OpenOptions::new().append(true).open(&part_path)?
+        } else {
+            File::create(&part_path)?
+        };
+        let mut body: Box<dyn Read> = match &self.progress {
+            Some((reporter, rir)) => {
+                let total = res.content_length().map(|len| len + offset);
+                let mut tracker = reporter.track(*rir, ProgressStage::Download, total);
+                tracker.set_done(offset);
+                Box::new(TrackingReader::new(res, tracker))
+            }
+            None => Box::new(res),
+        };
+        std::io::copy(&mut body, &mut part)
+            .with_context(|| format!("download of {url} was interrupted"))?;
+        part.sync_all()?;
+        std::fs::rename(&part_path, path)?;
+
+        Ok(FetchedFile {
+            url,
+            size: std::fs::metadata(path)?.len(),
+            not_modified: false,
+            resumed,
+        })
+    }
+}
+
+/// Name of the cached copy of `url`.
+fn cache_name(url: &reqwest::Url) -> String {
+    let name = url
+        .path_segments()
+        .and_then(|mut s| s.next_back())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("download");
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+fn meta_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".meta.json");
+    path.with_file_name(name)
+}
+
+fn read_meta(path: &Path) -> Option<CacheMeta> {
+    let data = std::fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    type Files = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+    /// Minimal HTTP server supporting `If-None-Match` and `Range` requests.
+    ///
+    /// A file is sent with its ETag, which is the length of its content. The
+    /// first `truncate` responses are cut off after half of the body.
+    fn serve(files: Files, truncate: Arc<AtomicUsize>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((k, v)) = line.split_once(':') {
+                        headers.insert(k.trim().to_lowercase(), v.trim().to_string());
+                    }
+                }
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                let Some(body) = files.lock().unwrap().get(path).cloned() else {
+                    write!(
+                        stream,
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n"
+                    )
+                    .unwrap();
+                    continue;
+                };
+                let etag = format!("\"{}\"", body.len());
+                if headers.get("if-none-match") == Some(&etag) {
+                    write!(stream, "HTTP/1.1 304 Not Modified\r\nETag: {etag}\r\n\r\n").unwrap();
+                    continue;
+                }
+                let start = headers
+                    .get("range")
+                    .filter(|_| headers.get("if-range") == Some(&etag))
+                    .and_then(|r| r.strip_prefix("bytes="))
+                    .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok());
+                let (status, content) = match start {
+                    Some(start) => ("206 Partial Content", &body[start..]),
+                    None => ("200 OK", &body[..]),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nETag: {etag}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    content.len()
+                )
+                .unwrap();
+                if truncate.load(Ordering::SeqCst) > 0 {
+                    truncate.fetch_sub(1, Ordering::SeqCst);
+                    stream.write_all(&content[..content.len() / 2]).unwrap();
+                } else {
+                    stream.write_all(content).unwrap();
+                }
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn read_all(mut reader: Box<dyn Read + Send>) -> String {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn conditional_and_resumed_downloads() {
+        let files = Files::default();
+        let truncate = Arc::new(AtomicUsize::new(1));
+        let base = serve(files.clone(), truncate.clone());
+        let data = "inetnum: 192.0.2.0/24\n".repeat(100);
+        files
+            .lock()
+            .unwrap()
+            .insert("/dump.txt".into(), data.clone().into_bytes());
+
+        let dir = tempfile::tempdir().unwrap();
+        let dl = Downloader::new(Client::new(), dir.path());
+        let url = format!("{base}/dump.txt");
+
+        // The first attempt is interrupted, the second one resumes it.
+        assert!(dl.fetch(dl.client().get(&url)).is_err());
+        assert!(dir.path().join("dump.txt.part").exists());
+        assert_eq!(read_all(dl.fetch(dl.client().get(&url)).unwrap()), data);
+        // Not modified, served from the cache.
+        assert_eq!(read_all(dl.fetch(dl.client().get(&url)).unwrap()), data);
+
+        let fetched = dl.fetched();
+        assert_eq!(fetched.len(), 2);
+        assert!(fetched[0].resumed && !fetched[0].not_modified);
+        assert!(fetched[1].not_modified);
+        assert_eq!(fetched[1].size, data.len() as u64);
+
+        // A changed file is downloaded again.
+        let changed = format!("{data}route: 192.0.2.0/24\n");
+        files
+            .lock()
+            .unwrap()
+            .insert("/dump.txt".into(), changed.clone().into_bytes());
+        assert_eq!(read_all(dl.fetch(dl.client().get(&url)).unwrap()), changed);
+        assert!(!dl.fetched()[2].not_modified);
+    }
+
+    #[test]
+    fn checksum_mismatch_discards_cache() {
+        let files = Files::default();
+        let base = serve(files.clone(), Arc::default());
+        files
+            .lock()
+            .unwrap()
+            .insert("/stats".into(), b"abd".to_vec());
+        files.lock().unwrap().insert(
+            "/stats.md5".into(),
+            b"900150983cd24fb0d6963f7d28e17f72  stats\n".to_vec(),
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let dl = Downloader::new(Client::new(), dir.path());
+        let url = format!("{base}/stats");
+        let checksum_url = format!("{url}.md5");
+        assert!(dl
+            .fetch_verified(dl.client().get(&url), Some(&checksum_url))
+            .is_err());
+        assert!(!dir.path().join("stats").exists());
+
+        files
+            .lock()
+            .unwrap()
+            .insert("/stats".into(), b"abc".to_vec());
+        let reader = dl
+            .fetch_verified(dl.client().get(&url), Some(&checksum_url))
+            .unwrap();
+        assert_eq!(read_all(reader), "abc");
+    }
+
+    #[test]
+    fn mirror() {
+        let files = Files::default();
+        let base = serve(files.clone(), Arc::default());
+        files
+            .lock()
+            .unwrap()
+            .insert("/mirror/stats".into(), b"abc".to_vec());
+        files.lock().unwrap().insert(
+            "/mirror/stats.md5".into(),
+            b"900150983cd24fb0d6963f7d28e17f72  stats\n".to_vec(),
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let dl = Downloader::new(Client::new(), dir.path()).with_mirror(format!("{base}/mirror/"));
+        let url = "https://registry.invalid/pub/stats";
+        let reader = dl
+            .fetch_verified(dl.client().get(url), Some(&format!("{url}.md5")))
+            .unwrap();
+        assert_eq!(read_all(reader), "abc");
+        assert_eq!(dl.fetched()[0].url, format!("{base}/mirror/stats"));
+    }
+
+    #[test]
+    fn corrupted_rpsl_dump_is_rejected() {
+        use md5::{Digest, Md5};
+
+        use crate::registry::ripe::Ripe;
+        use crate::RirProvider;
+
+        let mut dump = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        dump.write_all(b"inetnum: 192.0.2.0 - 192.0.2.255\nsource: RIPE\n")
+            .unwrap();
+        let dump = dump.finish().unwrap();
+        let mut corrupted = dump.clone();
+        corrupted[dump.len() / 2] ^= 0xff;
+
+        let files = Files::default();
+        let base = serve(files.clone(), Arc::default());
+        files.lock().unwrap().insert(
+            "/ripe.db.gz.md5".into(),
+            format!("{:x}  ripe.db.gz\n", Md5::digest(&dump)).into_bytes(),
+        );
+        files
+            .lock()
+            .unwrap()
+            .insert("/ripe.db.gz".into(), corrupted);
+
+        let dir = tempfile::tempdir().unwrap();
+        let dl = Downloader::new(Client::new(), dir.path()).with_mirror(&base);
+        let mut data = Ripe {}.download_rpsl_db(&dl).unwrap();
+        let err = data.reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{err}");
+
+        files.lock().unwrap().insert("/ripe.db.gz".into(), dump);
+        let data = Ripe {}.download_rpsl_db(&dl).unwrap();
+        assert!(read_all(data.reader).starts_with("inetnum: 192.0.2.0"));
+    }
+}
@@ -1,17 +1,219 @@
-use crate::{Client, RirProvider};
+use std::io::Read;
 
+use ipgeom_rpsl::{parse_objects_read_iter, Decoding, Object, ObjectType};
+use ipnet::IpNet;
+
+use crate::{DbData, Downloader, RirProvider};
+
+use super::{network_attribute, normalize_date, sanitize_name, ConcatReader, ObjectsReader};
+
+/// LACNIC publishes its database in its own whois format.
+///
+/// Networks use abbreviated prefixes (`200.3.16/20`) and the holder is
+/// described by `owner`/`ownerid` attributes. Objects are converted into
+/// regular `inetnum`, `inet6num` and `aut-num` objects while downloading.
 #[derive(Debug, Clone, Copy)]
 pub struct Lacnic {}
 
 impl Lacnic {
-    const RPSL_DOWNLOAD_URL: &'static str = "https://ftp.lacnic.net/pub/dbase/lacnic.db.gz";
+    const RPSL_DOWNLOAD_URL: &'static str = "https://ftp.lacnic.net/lacnic/dbase/lacnic.db.gz";
+    const DELEGATED_STATS_URL: &'static str =
+        "https://ftp.lacnic.net/pub/stats/lacnic/delegated-lacnic-extended-latest";
 }
 
 impl RirProvider for Lacnic {
-    fn build_rpsl_db_request(&self, client: &Client) -> reqwest::blocking::RequestBuilder {
-        client.get(Self::RPSL_DOWNLOAD_URL)
+    fn rpsl_db_urls(&self) -> Vec<String> {
+        vec![Self::RPSL_DOWNLOAD_URL.to_string()]
+    }
+
+    fn download_rpsl_db(&self, dl: &Downloader) -> Result<DbData, anyhow::Error> {
+        let url = Self::RPSL_DOWNLOAD_URL;
+        let checksum_url = self.rpsl_db_checksum_url(url);
+        let body = dl.fetch_verified(dl.client().get(url), checksum_url.as_deref())?;
+        self.import_rpsl_db(vec![body])
+    }
+
+    fn import_rpsl_db(&self, files: Vec<Box<dyn Read + Send>>) -> Result<DbData, anyhow::Error> {
+        let mut reader = ConcatReader::new();
+        for file in files {
+            reader.push(move || Ok(file));
+        }
+        let objects = parse_objects_read_iter(reader)
+            .with_decoding(Decoding::Utf8OrLatin1)
+            .with_recovery(|malformed| {
+                tracing::warn!(
+                    rir = "lacnic",
+                    line = malformed.line,
+                    "skipping malformed object"
+                );
+            })
+            .map(|res| Ok(normalize(res?)));
+        Ok(DbData {
+            gzip: false,
+            reader: Box::new(ObjectsReader::new(objects)),
+        })
+    }
+
+    fn rpsl_db_checksum_url(&self, url: &str) -> Option<String> {
+        Some(format!("{url}.md5"))
+    }
+
+    fn delegated_stats_url(&self) -> Option<&'static str> {
+        Some(Self::DELEGATED_STATS_URL)
+    }
+
+    fn min_rpsl_objects(&self) -> usize {
+        1_000
+    }
+}
+
+/// Convert a LACNIC object into RPSL.
+///
+/// Objects that are not networks or AS numbers, or that can not be
+/// interpreted, are returned unchanged.
+fn normalize(obj: Object) -> Object {
+    let normalized = match obj.obj_type() {
+        ObjectType::Inetnum | ObjectType::Inet6num => normalize_network(&obj),
+        ObjectType::AutNum => normalize_aut_num(&obj),
+        _ => None,
+    };
+    normalized.unwrap_or(obj)
+}
+
+fn normalize_network(obj: &Object) -> Option<Object> {
+    let net = parse_prefix(obj.ordered_attributes().first()?.value())?;
+    let (key, value) = network_attribute(net);
+    let mut out = Object::new(ObjectType::from_key(key));
+    out.add(key.into(), value);
+    let name = obj
+        .get_first("ownerid")
+        .or_else(|| obj.get_first("owner"))
+        .map(sanitize_name)
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "LACNIC-NET".to_string());
+    out.add("netname".into(), name);
+    if let Some(status) = obj.get_first("status") {
+        out.add("status".into(), status.to_string());
     }
+    add_holder(obj, &mut out);
+    Some(out)
+}
+
+fn normalize_aut_num(obj: &Object) -> Option<Object> {
+    let value = obj.ordered_attributes().first()?.value();
+    let digits = value
+        .strip_prefix("AS")
+        .or_else(|| value.strip_prefix("as"))
+        .unwrap_or(value);
+    let asn: u32 = digits.trim().parse().ok()?;
+
+    let mut out = Object::new(ObjectType::AutNum);
+    out.add("aut-num".into(), format!("AS{}", asn));
+    if let Some(name) = obj
+        .get_first("ownerid")
+        .map(sanitize_name)
+        .filter(|v| !v.is_empty())
+    {
+        out.add("as-name".into(), name);
+    }
+    add_holder(obj, &mut out);
+    Some(out)
+}
+
+/// Copy owner, country and dates of `obj` to `out`.
+fn add_holder(obj: &Object, out: &mut Object) {
+    if let Some(owner) = obj.get_first("owner") {
+        out.add("descr".into(), owner.to_string());
+    }
+    if let Some(owner_id) = obj.get_first("ownerid") {
+        out.add("org".into(), owner_id.to_string());
+    }
+    if let Some(country) = obj.get_first("country") {
+        out.add("country".into(), country.to_uppercase());
+    }
+    if let Some(created) = obj.get_first("created").and_then(normalize_date) {
+        out.add("created".into(), created);
+    }
+    if let Some(changed) = obj.get_first("changed").and_then(normalize_date) {
+        out.add("last-modified".into(), changed);
+    }
+    out.add("source".into(), "LACNIC".into());
+}
+
+/// Parse a prefix that may use the abbreviated IPv4 notation `200.3.16/20`.
+fn parse_prefix(value: &str) -> Option<IpNet> {
+    let (addr, len) = value.trim().split_once('/')?;
+    let addr = if addr.contains(':') {
+        addr.to_string()
+    } else {
+        let octets = addr.split('.').count();
+        if octets > 4 {
+            return None;
+        }
+        let mut addr = addr.to_string();
+        for _ in octets..4 {
+            addr.push_str(".0");
+        }
+        addr
+    };
+    let net: IpNet = format!("{}/{}", addr, len.trim()).parse().ok()?;
+    Some(net.trunc())
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+    use ipgeom_rpsl::{parse_objects, InetStatus, RpslObject};
+
+    #[test]
+    fn normalize_objects() {
+        let text = "\
+inetnum:     200.3.16/20
+status:      allocated
+owner:       Example S.A.
+ownerid:     AR-EXSA-LACNIC
+country:     ar
+created:     20000101
+changed:     20170131
+
+inetnum:     2001:db8::/32
+owner:       Example S.A.
+country:     AR
+
+aut-num:     AS64500
+owner:       Example S.A.
+ownerid:     AR-EXSA-LACNIC
+country:     AR
+";
+        let objs: Vec<_> = parse_objects(text)
+            .unwrap()
+            .into_iter()
+            .map(normalize)
+            .collect();
+
+        assert_eq!(
+            objs[0].to_rpsl(),
+            "inetnum: 200.3.16.0 - 200.3.31.255\n\
+             netname: AR-EXSA-LACNIC\n\
+             status: allocated\n\
+             descr: Example S.A.\n\
+             org: AR-EXSA-LACNIC\n\
+             country: AR\n\
+             created: 2000-01-01T00:00:00Z\n\
+             last-modified: 2017-01-31T00:00:00Z\n\
+             source: LACNIC\n"
+        );
+        assert_eq!(objs[1].obj_type(), &ObjectType::Inet6num);
+        assert_eq!(objs[1].get_first("netname"), Some("EXAMPLE-S-A"));
+        assert_eq!(objs[2].get_first("as-name"), Some("AR-EXSA-LACNIC"));
+
+        let RpslObject::Inetnum(inetnum) = RpslObject::try_from(objs[0].clone()).unwrap() else {
+            panic!("expected an inetnum");
+        };
+        assert_eq!(inetnum.status, Some(InetStatus::Allocated));
+
+        for obj in objs {
+            ipgeom_rpsl::RpslObject::try_from(obj).unwrap();
+        }
+    }
+}
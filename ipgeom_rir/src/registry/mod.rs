@@ -1,5 +1,183 @@
 pub(crate) mod afrinic;
 pub(crate) mod apnic;
 pub(crate) mod arin;
+pub(crate) mod checksum;
+pub(crate) mod download;
 pub(crate) mod lacnic;
 pub(crate) mod ripe;
+
+use std::collections::VecDeque;
+use std::io::Read;
+
+use ipgeom_rpsl::Object;
+use ipnet::IpNet;
+
+type Source = Box<dyn FnOnce() -> Result<Box<dyn Read + Send>, anyhow::Error> + Send>;
+
+/// Reader over the concatenation of several sources.
+///
+/// Sources are opened lazily once the previous one is exhausted, so only one
+/// download is in progress at a time. A blank line is inserted after each
+/// source to terminate its last object.
+pub(crate) struct ConcatReader {
+    sources: VecDeque<Source>,
+    current: Option<Box<dyn Read + Send>>,
+}
+
+impl ConcatReader {
+    pub(crate) fn new() -> Self {
+        Self {
+            sources: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    /// Append a source that is opened on first read.
+    pub(crate) fn push<F>(&mut self, open: F)
+    where
+        F: FnOnce() -> Result<Box<dyn Read + Send>, anyhow::Error> + Send + 'static,
+    {
+        self.sources.push_back(Box::new(open));
+    }
+}
+
+impl Read for ConcatReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let Some(current) = &mut self.current else {
+                let Some(open) = self.sources.pop_front() else {
+                    return Ok(0);
+                };
+                let reader = open().map_err(std::io::Error::other)?;
+                self.current = Some(Box::new(reader.chain(&b"\n"[..])));
+                continue;
+            };
+            let n = current.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            self.current = None;
+        }
+    }
+}
+
+/// Reader that serializes objects to RPSL text.
+pub(crate) struct ObjectsReader<I> {
+    objects: I,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<I> ObjectsReader<I>
+where
+    I: Iterator<Item = Result<Object, anyhow::Error>>,
+{
+    pub(crate) fn new(objects: I) -> Self {
+        Self {
+            objects,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl<I> Read for ObjectsReader<I>
+where
+    I: Iterator<Item = Result<Object, anyhow::Error>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.buf.len() {
+            match self.objects.next() {
+                Some(Ok(obj)) => {
+                    self.buf = obj.to_rpsl().into_bytes();
+                    self.buf.push(b'\n');
+                    self.pos = 0;
+                }
+                Some(Err(e)) => return Err(std::io::Error::other(e)),
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.buf.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Convert a `YYYY-MM-DD` or `YYYYMMDD` date into an RFC 3339 timestamp.
+pub(crate) fn normalize_date(value: &str) -> Option<String> {
+    let digits: String = value.chars().filter(|c| *c != '-').collect();
+    if digits.len() != 8 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}T00:00:00Z",
+        &digits[..4],
+        &digits[4..6],
+        &digits[6..]
+    ))
+}
+
+/// Format a network as an RPSL `inetnum` range or `inet6num` prefix.
+pub(crate) fn network_attribute(net: IpNet) -> (&'static str, String) {
+    match net {
+        IpNet::V4(net) => (
+            "inetnum",
+            format!("{} - {}", net.network(), net.broadcast()),
+        ),
+        IpNet::V6(net) => ("inet6num", net.to_string()),
+    }
+}
+
+/// Turn free text into a value usable as `netname`.
+pub(crate) fn sanitize_name(value: &str) -> String {
+    let name: String = value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c.to_ascii_uppercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concat_and_objects_reader() {
+        let mut obj = Object::new(ipgeom_rpsl::ObjectType::AutNum);
+        obj.add("aut-num".into(), "AS1".into());
+        let objects = ObjectsReader::new(vec![Ok(obj)].into_iter());
+
+        let mut reader = ConcatReader::new();
+        reader.push(|| Ok(Box::new(&b"person: A"[..])));
+        reader.push(move || Ok(Box::new(objects)));
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "person: A\naut-num: AS1\n\n\n");
+    }
+
+    #[test]
+    fn normalization_helpers() {
+        assert_eq!(
+            normalize_date("20240131").as_deref(),
+            Some("2024-01-31T00:00:00Z")
+        );
+        assert_eq!(
+            normalize_date("2024-01-31").as_deref(),
+            Some("2024-01-31T00:00:00Z")
+        );
+        assert_eq!(normalize_date("yesterday"), None);
+        assert_eq!(
+            network_attribute("192.0.2.0/24".parse().unwrap()),
+            ("inetnum", "192.0.2.0 - 192.0.2.255".to_string())
+        );
+        assert_eq!(sanitize_name(" Example Corp. "), "EXAMPLE-CORP");
+    }
+}
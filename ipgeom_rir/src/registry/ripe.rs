@@ -1,15 +1,29 @@
-use crate::{Client, RirProvider};
+use crate::RirProvider;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ripe {}
 
 impl Ripe {
     const RPSL_DOWNLOAD_URL: &'static str = "https://ftp.ripe.net/ripe/dbase/ripe.db.gz";
+    const DELEGATED_STATS_URL: &'static str =
+        "https://ftp.ripe.net/pub/stats/ripencc/delegated-ripencc-extended-latest";
 }
 
 impl RirProvider for Ripe {
-    fn build_rpsl_db_request(&self, client: &Client) -> reqwest::blocking::RequestBuilder {
-        client.get(Self::RPSL_DOWNLOAD_URL)
+    fn rpsl_db_urls(&self) -> Vec<String> {
+        vec![Self::RPSL_DOWNLOAD_URL.to_string()]
+    }
+
+    fn rpsl_db_checksum_url(&self, url: &str) -> Option<String> {
+        Some(format!("{url}.md5"))
+    }
+
+    fn delegated_stats_url(&self) -> Option<&'static str> {
+        Some(Self::DELEGATED_STATS_URL)
+    }
+
+    fn min_rpsl_objects(&self) -> usize {
+        1_000_000
     }
 }
 
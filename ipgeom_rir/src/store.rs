@@ -1,14 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use flate2::read::GzDecoder;
-use ipgeom_rpsl::{parse_objects_read_iter, RpslObject};
+use ipgeom_rpsl::{parse_objects_parallel, Decoding, RpslObject};
+use sha2::{Digest, Sha256};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 
-use crate::db::Database;
+use crate::countries::{Continent, Country};
+use crate::db::{Database, GeoSourceKind};
+use crate::delegated::{parse_delegated, DelegatedIter, Delegation};
+use crate::geoip::{GeoipReport, GeoipResolver, TieBreak};
+use crate::progress::{ProgressEvent, ProgressStage, Reporter};
 
-use crate::{registry, types, Client, DbData, RirProvider};
+use crate::{registry, types, Client, DbData, Downloader, RirProvider};
+
+/// Name of the file pointing at the latest snapshot of a registry.
+const LATEST_SNAPSHOT: &str = "latest";
+
+/// Name of snapshots, derived from the time they were fetched.
+const SNAPSHOT_STAMP: &[time::format_description::FormatItem<'static>] =
+    time::macros::format_description!("[year][month][day]T[hour][minute][second]Z");
 
 /// Persistent store for RIR database dumps.
 #[derive(Debug)]
@@ -16,6 +32,96 @@ pub struct Store {
     data_dir: PathBuf,
     client: Client,
     rirs: HashMap<types::Rir, Box<dyn RirProvider>>,
+    retention: RetentionPolicy,
+    mirrors: HashMap<types::Rir, String>,
+    failure_policy: FailurePolicy,
+    rir_failure_policies: HashMap<types::Rir, FailurePolicy>,
+    reporter: Reporter,
+}
+
+/// What happens to the other registries when updating a registry fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FailurePolicy {
+    /// Keep updating the other registries.
+    #[default]
+    Continue,
+    /// Stop updating the other registries. Registries that were already
+    /// updated keep their new data.
+    Abort,
+}
+
+impl std::fmt::Display for FailurePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Continue => write!(f, "continue"),
+            Self::Abort => write!(f, "abort"),
+        }
+    }
+}
+
+impl std::str::FromStr for FailurePolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "continue" => Ok(Self::Continue),
+            "abort" => Ok(Self::Abort),
+            _ => Err("Invalid failure policy, expected `continue` or `abort`"),
+        }
+    }
+}
+
+/// Result of updating a single registry.
+enum UpdateOutcome {
+    Updated,
+    Failed(anyhow::Error),
+    /// Stopped because another registry failed.
+    Aborted,
+}
+
+/// Which RPSL snapshots are kept when a registry is updated.
+///
+/// The latest snapshot is never removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Number of snapshots to keep per registry.
+    pub keep_snapshots: usize,
+    /// Remove snapshots fetched longer ago than this.
+    pub max_age: Option<std::time::Duration>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_snapshots: 3,
+            max_age: None,
+        }
+    }
+}
+
+/// Metadata of a stored RPSL snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotMeta {
+    /// File name of the snapshot in the `db` directory of the registry.
+    pub file: String,
+    /// URLs of the files the snapshot was built from.
+    pub urls: Vec<String>,
+    /// Size of the snapshot in bytes.
+    pub size: u64,
+    /// Hex encoded SHA-256 digest of the snapshot.
+    pub sha256: String,
+    /// RFC 3339 timestamp of the download.
+    pub fetched_at: String,
+    /// Serial announced in the header of the dump, if any.
+    pub serial: Option<u64>,
+    /// Number of objects in the snapshot.
+    pub objects: usize,
+}
+
+impl SnapshotMeta {
+    fn fetched_at(&self) -> Option<OffsetDateTime> {
+        OffsetDateTime::parse(&self.fetched_at, &Rfc3339).ok()
+    }
 }
 
 /// Options controlling what data is persisted into a database.
@@ -24,6 +130,8 @@ pub struct PersistFilter {
     /// Persist all RPSL objects into dedicated tables.
     pub rpsl_objects: bool,
     pub rpsl_inetnum: bool,
+    /// Persist records of the delegated-extended statistics files.
+    pub delegations: bool,
 }
 
 impl Default for PersistFilter {
@@ -31,41 +139,231 @@ impl Default for PersistFilter {
         Self {
             rpsl_objects: false,
             rpsl_inetnum: true,
+            delegations: true,
+        }
+    }
+}
+
+/// Options for building a GeoIP database.
+#[derive(Debug, Clone, Default)]
+pub struct GeoipOptions {
+    /// Source whose country wins where both sources have data for the same
+    /// network.
+    ///
+    /// Otherwise the most specific network decides, whichever source it
+    /// comes from. Database lookups follow the same rule.
+    pub primary_source: GeoSourceKind,
+    /// Whether the source or the registry is compared first between
+    /// candidates for the same network.
+    pub tie_break: TieBreak,
+    /// Registries in order of preference for the same network.
+    ///
+    /// Registries not listed rank after the listed ones.
+    pub rir_priority: Vec<types::Rir>,
+    /// Schema of the records.
+    pub format: GeoipFormat,
+}
+
+/// Schema of the records in generated GeoIP databases.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GeoipFormat {
+    /// The GeoIP2-Country schema understood by standard readers, with
+    /// `country`, `registered_country` and `continent` objects.
+    #[default]
+    Country,
+    /// Only the country code, as `{"country": "DE", "source": "rpsl"}`.
+    Minimal,
+}
+
+impl std::fmt::Display for GeoipFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Country => write!(f, "country"),
+            Self::Minimal => write!(f, "minimal"),
+        }
+    }
+}
+
+impl std::str::FromStr for GeoipFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "country" => Ok(Self::Country),
+            "minimal" => Ok(Self::Minimal),
+            _ => Err("Invalid GeoIP format, expected `country` or `minimal`"),
+        }
+    }
+}
+
+type StoredDelegations = DelegatedIter<BufReader<File>>;
+
+/// Record stored in generated GeoIP databases in the minimal format.
+#[derive(serde::Serialize)]
+struct GeoipRecord<'a> {
+    country: &'a str,
+    /// Kind of data the country was taken from, `rpsl` or `delegated`.
+    source: &'a str,
+}
+
+/// Record stored in generated GeoIP databases in the GeoIP2-Country format.
+///
+/// Country codes that are not in the ISO 3166 table, such as the `ZZ`
+/// placeholder, are stored without names and continent. The `EU` and `AP`
+/// pseudo-codes of the registries get the continent they stand for.
+#[derive(serde::Serialize)]
+struct GeoipCountryRecord<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    continent: Option<GeoipContinent>,
+    country: GeoipCountry<'a>,
+    registered_country: GeoipCountry<'a>,
+    /// Kind of data the country was taken from, `rpsl` or `delegated`.
+    source: &'a str,
+}
+
+#[derive(serde::Serialize)]
+struct GeoipContinent {
+    code: &'static str,
+    geoname_id: u32,
+    names: BTreeMap<&'static str, &'static str>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct GeoipCountry<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geoname_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    is_in_european_union: Option<bool>,
+    iso_code: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    names: Option<BTreeMap<&'static str, &'static str>>,
+}
+
+impl<'a> GeoipCountryRecord<'a> {
+    fn new(iso_code: &'a str, source: &'a str) -> Self {
+        let info = Country::from_iso_code(iso_code);
+        let country = GeoipCountry {
+            geoname_id: info.map(|c| c.geoname_id),
+            is_in_european_union: info.and_then(|c| c.is_in_european_union().then_some(true)),
+            iso_code,
+            names: info.map(|c| [("en", c.name)].into()),
+        };
+        let continent = info
+            .map(|c| c.continent)
+            .or_else(|| Continent::from_region_code(iso_code));
+        Self {
+            continent: continent.map(|c| GeoipContinent {
+                code: c.code(),
+                geoname_id: c.geoname_id(),
+                names: [("en", c.name())].into(),
+            }),
+            registered_country: country.clone(),
+            country,
+            source,
+        }
+    }
+}
+
+/// Configuration of the registries used by a [`Store`].
+#[derive(Clone)]
+pub struct StoreConfig {
+    /// Registries to download and read.
+    pub registries: Vec<types::Rir>,
+    /// API key for the ARIN bulk WHOIS service.
+    ///
+    /// Without a key only the ARIN IRR data, which contains no networks, is
+    /// available.
+    pub arin_api_key: Option<String>,
+    /// Which RPSL snapshots to keep.
+    pub retention: RetentionPolicy,
+    /// Base URLs of mirrors to download registry files from instead of the
+    /// registries themselves.
+    ///
+    /// See [`Downloader::with_mirror`].
+    pub mirrors: HashMap<types::Rir, String>,
+    /// What happens when updating a registry fails.
+    pub failure_policy: FailurePolicy,
+    /// Failure policies of individual registries, overriding
+    /// `failure_policy`.
+    pub rir_failure_policies: HashMap<types::Rir, FailurePolicy>,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        Self {
+            registries: types::Rir::ALL.to_vec(),
+            arin_api_key: None,
+            retention: RetentionPolicy::default(),
+            mirrors: HashMap::new(),
+            failure_policy: FailurePolicy::default(),
+            rir_failure_policies: HashMap::new(),
         }
     }
 }
 
+impl std::fmt::Debug for StoreConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreConfig")
+            .field("registries", &self.registries)
+            .field(
+                "arin_api_key",
+                &self.arin_api_key.as_ref().map(|_| "<redacted>"),
+            )
+            .field("retention", &self.retention)
+            .field("mirrors", &self.mirrors)
+            .field("failure_policy", &self.failure_policy)
+            .field("rir_failure_policies", &self.rir_failure_policies)
+            .finish()
+    }
+}
+
+/// Counters for problems encountered while reading stored dumps.
+#[derive(Debug, Clone, Default)]
+struct ParseDiagnostics {
+    malformed_objects: Arc<AtomicUsize>,
+}
+
+impl ParseDiagnostics {
+    fn malformed_objects(&self) -> usize {
+        self.malformed_objects.load(Ordering::Relaxed)
+    }
+}
+
 impl Store {
-    /// Create a new store using default RIR implementations.
+    /// Create a new store for all registries.
     pub fn new<P: Into<PathBuf>>(data_dir: P) -> Result<Self, anyhow::Error> {
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("ipgeomancer")
-            .build()?;
-
-        let rirs: HashMap<types::Rir, Box<dyn RirProvider>> = [
-            // (
-            //     types::Rir::Arin,
-            //     Box::new(registry::arin::Arin {}) as Box<dyn RirProvider>,
-            // ),
-            // (types::Rir::Apnic, Box::new(registry::apnic::Apnic {})),
-            (
-                types::Rir::Ripe,
-                Box::new(registry::ripe::Ripe {}) as Box<dyn RirProvider>,
-            ),
-            // (types::Rir::Lacnic, Box::new(registry::lacnic::Lacnic {})),
-            (
-                types::Rir::Afrinic,
-                Box::new(registry::afrinic::Afrinic {}) as Box<dyn RirProvider>,
-            ),
-        ]
-        .into_iter()
-        .collect();
+        Self::with_config(data_dir, StoreConfig::default())
+    }
 
-        Ok(Self {
-            data_dir: data_dir.into(),
-            client,
-            rirs,
-        })
+    /// Create a new store for the registries selected in `config`.
+    pub fn with_config<P: Into<PathBuf>>(
+        data_dir: P,
+        config: StoreConfig,
+    ) -> Result<Self, anyhow::Error> {
+        let rirs = config
+            .registries
+            .iter()
+            .map(|rir| {
+                let provider: Box<dyn RirProvider> = match rir {
+                    types::Rir::Arin => {
+                        Box::new(registry::arin::Arin::new(config.arin_api_key.clone()))
+                    }
+                    types::Rir::Apnic => Box::new(registry::apnic::Apnic {}),
+                    types::Rir::Ripe => Box::new(registry::ripe::Ripe {}),
+                    types::Rir::Lacnic => Box::new(registry::lacnic::Lacnic {}),
+                    types::Rir::Afrinic => Box::new(registry::afrinic::Afrinic {}),
+                };
+                (*rir, provider)
+            })
+            .collect();
+        let mut store = Self::with_rirs(data_dir, rirs)?
+            .with_retention(config.retention)
+            .with_mirrors(config.mirrors)
+            .with_failure_policy(config.failure_policy);
+        for (rir, policy) in config.rir_failure_policies {
+            store = store.with_rir_failure_policy(rir, policy);
+        }
+        Ok(store)
     }
 
     /// Create a store with custom RIR implementations (useful for testing).
@@ -80,72 +378,562 @@ impl Store {
             data_dir: data_dir.into(),
             client,
             rirs,
+            retention: RetentionPolicy::default(),
+            mirrors: HashMap::new(),
+            failure_policy: FailurePolicy::default(),
+            rir_failure_policies: HashMap::new(),
+            reporter: Reporter::default(),
         })
     }
 
+    /// Report progress of updates and of persisting into a database to
+    /// `callback`.
+    ///
+    /// The callback is invoked from the threads doing the work.
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ProgressEvent) + Send + Sync + 'static,
+    {
+        self.reporter = Reporter::new(callback);
+        self
+    }
+
+    /// Set what happens when updating a registry fails.
+    pub fn with_failure_policy(mut self, policy: FailurePolicy) -> Self {
+        self.failure_policy = policy;
+        self
+    }
+
+    /// Set what happens when updating `rir` fails, overriding the policy set
+    /// with [`Store::with_failure_policy`].
+    pub fn with_rir_failure_policy(mut self, rir: types::Rir, policy: FailurePolicy) -> Self {
+        self.rir_failure_policies.insert(rir, policy);
+        self
+    }
+
+    fn failure_policy(&self, rir: types::Rir) -> FailurePolicy {
+        self.rir_failure_policies
+            .get(&rir)
+            .copied()
+            .unwrap_or(self.failure_policy)
+    }
+
+    /// Download registry files from mirrors, see [`StoreConfig::mirrors`].
+    pub fn with_mirrors(mut self, mirrors: HashMap<types::Rir, String>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Set which RPSL snapshots are kept on updates.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
     /// Download the databases from all configured RIRs.
+    ///
+    /// Downloads are cached, so files that did not change since the last
+    /// update are not transferred again. Each changed RPSL dump is stored as a
+    /// new dated snapshot once it has been verified, and old snapshots are
+    /// pruned according to the [`RetentionPolicy`].
+    ///
+    /// Registries are updated concurrently. If a registry fails, its previous
+    /// files are kept and, depending on its [`FailurePolicy`], the remaining
+    /// registries are still updated or stopped.
     pub fn update(&self) -> Result<(), anyhow::Error> {
         tracing::info!("Updating RIR databases in {}", self.data_dir.display());
 
-        for (rir, handler) in &self.rirs {
-            tracing::debug!("Downloading RPSL data for {}", rir.name());
-            let data = handler.download_rpsl_db(&self.client)?;
-            self.store_data(*rir, data)?;
-            tracing::info!("Updated RPSL db for {}", rir.name());
+        let reporter = self.reporter.run();
+        let outcomes: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .rirs
+                .iter()
+                .map(|(rir, handler)| {
+                    let reporter = &reporter;
+                    let handle = scope.spawn(move || {
+                        match self.update_rir(*rir, handler.as_ref(), reporter) {
+                            Ok(()) => UpdateOutcome::Updated,
+                            Err(_) if reporter.is_cancelled() => UpdateOutcome::Aborted,
+                            Err(err) => {
+                                reporter.failed(*rir, &err);
+                                if self.failure_policy(*rir) == FailurePolicy::Abort {
+                                    reporter.cancel();
+                                }
+                                UpdateOutcome::Failed(err)
+                            }
+                        }
+                    });
+                    (*rir, handle)
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|(rir, handle)| {
+                    let outcome = handle.join().unwrap_or_else(|_| {
+                        UpdateOutcome::Failed(anyhow::anyhow!("update panicked"))
+                    });
+                    (rir, outcome)
+                })
+                .collect()
+        });
+
+        let mut failed = Vec::new();
+        let mut aborted = Vec::new();
+        for (rir, outcome) in outcomes {
+            match outcome {
+                UpdateOutcome::Updated => {}
+                UpdateOutcome::Failed(err) => {
+                    tracing::error!(
+                        rir = rir.name(),
+                        "update failed, keeping previous data: {err:#}"
+                    );
+                    failed.push(rir.name());
+                }
+                UpdateOutcome::Aborted => {
+                    tracing::warn!(rir = rir.name(), "update aborted, keeping previous data");
+                    aborted.push(rir.name());
+                }
+            }
+        }
+        if !failed.is_empty() {
+            failed.sort();
+            let mut message = format!("failed to update registries: {}", failed.join(", "));
+            if !aborted.is_empty() {
+                aborted.sort();
+                message.push_str(&format!("; aborted: {}", aborted.join(", ")));
+            }
+            anyhow::bail!(message);
         }
         tracing::info!("RIR databases updated successfully");
         Ok(())
     }
 
+    fn update_rir(
+        &self,
+        rir: types::Rir,
+        handler: &dyn RirProvider,
+        reporter: &Reporter,
+    ) -> Result<(), anyhow::Error> {
+        let mut dl = Downloader::new(self.client.clone(), self.rir_dir(rir).join("downloads"))
+            .with_reporter(reporter.clone(), rir);
+        if let Some(mirror) = self.mirrors.get(&rir) {
+            dl = dl.with_mirror(mirror.clone());
+        }
+        tracing::debug!("Downloading RPSL data for {}", rir.name());
+        let data = handler.download_rpsl_db(&dl)?;
+        reporter.check()?;
+        self.add_snapshot(rir, data, handler.min_rpsl_objects(), || {
+            dl.fetched().into_iter().map(|f| f.url).collect()
+        })?;
+
+        reporter.check()?;
+        if let Some(reader) = handler.download_delegated_stats(&dl)? {
+            reporter.check()?;
+            self.store_delegated_stats(rir, reader)?;
+            tracing::info!("Updated delegated statistics for {}", rir.name());
+        }
+        Ok(())
+    }
+
+    /// Import a dump of a registry from disk as if it had been downloaded.
+    ///
+    /// See [`Store::import_files`].
+    pub fn import_file(
+        &self,
+        rir: types::Rir,
+        path: impl AsRef<Path>,
+    ) -> Result<Option<SnapshotMeta>, anyhow::Error> {
+        self.import_files(rir, &[path.as_ref().to_path_buf()])
+    }
+
+    /// Import dump files of a registry from disk as if they had been
+    /// downloaded.
+    ///
+    /// The files are combined into a single dump, so registries that publish
+    /// one file per object type can be imported from their split files. A
+    /// directory imports all files in it. Gzip-compressed files are detected
+    /// by their content. The dump is converted, verified and stored as a new
+    /// snapshot exactly like an update.
+    ///
+    /// Returns `None` if the dump is identical to the latest snapshot.
+    pub fn import_files(
+        &self,
+        rir: types::Rir,
+        paths: &[PathBuf],
+    ) -> Result<Option<SnapshotMeta>, anyhow::Error> {
+        let Some(handler) = self.rirs.get(&rir) else {
+            anyhow::bail!("registry {} is not configured", rir.name());
+        };
+        let mut files = Vec::new();
+        for path in paths {
+            if path.is_dir() {
+                let mut entries = Vec::new();
+                for entry in std::fs::read_dir(path)? {
+                    let entry = entry?;
+                    let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                    if entry.file_type()?.is_file() && !hidden {
+                        entries.push(entry.path());
+                    }
+                }
+                entries.sort();
+                files.extend(entries);
+            } else {
+                files.push(path.clone());
+            }
+        }
+        if files.is_empty() {
+            anyhow::bail!("no files to import");
+        }
+        let urls = files
+            .iter()
+            .map(|path| Ok(format!("file://{}", std::fs::canonicalize(path)?.display())))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        let readers = files
+            .iter()
+            .map(|path| open_dump_file(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        tracing::info!(rir = rir.name(), ?urls, "importing dump files");
+        let data = handler.import_rpsl_db(readers)?;
+        self.add_snapshot(rir, data, handler.min_rpsl_objects(), || urls)
+    }
+
+    /// Import a delegated-extended statistics file of a registry from disk
+    /// as if it had been downloaded.
+    ///
+    /// Gzip-compressed files are detected by their content. The file is
+    /// verified and replaces the stored statistics exactly like an update.
+    pub fn import_delegated_stats(
+        &self,
+        rir: types::Rir,
+        path: impl AsRef<Path>,
+    ) -> Result<(), anyhow::Error> {
+        if !self.rirs.contains_key(&rir) {
+            anyhow::bail!("registry {} is not configured", rir.name());
+        }
+        let reader = open_dump_file(path.as_ref())?;
+        self.store_delegated_stats(rir, reader)?;
+        tracing::info!("Imported delegated statistics for {}", rir.name());
+        Ok(())
+    }
+
+    /// Store a dump as a new snapshot and prune old ones.
+    fn add_snapshot(
+        &self,
+        rir: types::Rir,
+        data: DbData,
+        min_objects: usize,
+        urls: impl FnOnce() -> Vec<String>,
+    ) -> Result<Option<SnapshotMeta>, anyhow::Error> {
+        let snapshot = self.store_snapshot(rir, data, min_objects, urls)?;
+        match &snapshot {
+            Some(snapshot) => tracing::info!(
+                "Updated RPSL db for {} with snapshot {}",
+                rir.name(),
+                snapshot.file
+            ),
+            None => tracing::info!("RPSL db for {} is unchanged", rir.name()),
+        }
+        self.prune_snapshots(rir)?;
+        Ok(snapshot)
+    }
+
+    fn rir_dir(&self, rir: types::Rir) -> PathBuf {
+        self.data_dir.join("rir").join(rir.name())
+    }
+
+    fn snapshot_dir(&self, rir: types::Rir) -> PathBuf {
+        self.rir_dir(rir).join("db")
+    }
+
+    /// Path of the latest RPSL dump of a registry.
+    ///
+    /// Stores created before snapshots were introduced have a single
+    /// `latest.rpsl` file, which is used until the first snapshot exists.
     fn db_path(&self, rir: types::Rir) -> PathBuf {
-        self.data_dir
-            .join("rir")
-            .join(rir.name())
-            .join("db")
-            .join("latest.rpsl")
-    }
-
-    fn store_data(&self, rir: types::Rir, mut data: DbData) -> Result<(), anyhow::Error> {
-        let file_path = self.db_path(rir);
-        if let Some(parent) = file_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        let mut file = File::create(file_path)?;
-        if data.gzip {
-            let mut reader = GzDecoder::new(data.reader);
-            std::io::copy(&mut reader, &mut file)?;
+        let dir = self.snapshot_dir(rir);
+        match std::fs::read_to_string(dir.join(LATEST_SNAPSHOT)) {
+            Ok(file) => dir.join(file.trim()),
+            Err(_) => dir.join("latest.rpsl"),
+        }
+    }
+
+    fn delegated_stats_path(&self, rir: types::Rir) -> PathBuf {
+        self.rir_dir(rir).join("delegated").join("latest.txt")
+    }
+
+    /// Path of a stored RPSL snapshot.
+    pub fn snapshot_path(&self, rir: types::Rir, snapshot: &SnapshotMeta) -> PathBuf {
+        self.snapshot_dir(rir).join(&snapshot.file)
+    }
+
+    /// Metadata of all stored RPSL snapshots of a registry, oldest first.
+    pub fn snapshots(&self, rir: types::Rir) -> Result<Vec<SnapshotMeta>, anyhow::Error> {
+        let dir = self.snapshot_dir(rir);
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let is_meta = path.extension().is_some_and(|ext| ext == "json")
+                && !path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if is_meta {
+                snapshots.push(read_snapshot_meta(&path)?);
+            }
+        }
+        snapshots.sort_by_cached_key(|s| (s.fetched_at(), s.file.clone()));
+        Ok(snapshots)
+    }
+
+    /// Metadata of the latest RPSL snapshot of a registry.
+    pub fn latest_snapshot(&self, rir: types::Rir) -> Result<Option<SnapshotMeta>, anyhow::Error> {
+        let dir = self.snapshot_dir(rir);
+        let file = match std::fs::read_to_string(dir.join(LATEST_SNAPSHOT)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let meta = read_snapshot_meta(&dir.join(snapshot_meta_name(file.trim())))?;
+        Ok(Some(meta))
+    }
+
+    /// Find a stored RPSL snapshot by its stamp, the file name without the
+    /// `.rpsl` extension, e.g. `20240102T030405Z`.
+    ///
+    /// The stamp `latest` refers to the latest snapshot.
+    pub fn find_snapshot(
+        &self,
+        rir: types::Rir,
+        stamp: &str,
+    ) -> Result<SnapshotMeta, anyhow::Error> {
+        let snapshot = if stamp.eq_ignore_ascii_case(LATEST_SNAPSHOT) {
+            self.latest_snapshot(rir)?
         } else {
-            std::io::copy(&mut data.reader, &mut file)?;
+            let file = format!("{stamp}.rpsl");
+            self.snapshots(rir)?.into_iter().find(|s| s.file == file)
+        };
+        snapshot.ok_or_else(|| anyhow::anyhow!("no snapshot {} for {}", stamp, rir.name()))
+    }
+
+    fn store_delegated_stats(
+        &self,
+        rir: types::Rir,
+        mut reader: Box<dyn Read + Send>,
+    ) -> Result<(), anyhow::Error> {
+        write_atomically(&self.delegated_stats_path(rir), &mut reader, |file| {
+            let mut records = parse_delegated(BufReader::new(file));
+            if records.next().transpose()?.is_none() {
+                anyhow::bail!("delegated statistics contain no records");
+            }
+            Ok(())
+        })
+    }
+
+    /// Store a downloaded RPSL dump as the latest snapshot.
+    ///
+    /// Returns `None` if the dump is identical to the latest snapshot.
+    fn store_snapshot(
+        &self,
+        rir: types::Rir,
+        data: DbData,
+        min_objects: usize,
+        urls: impl FnOnce() -> Vec<String>,
+    ) -> Result<Option<SnapshotMeta>, anyhow::Error> {
+        let mut reader: Box<dyn Read + Send> = if data.gzip {
+            Box::new(GzDecoder::new(data.reader))
+        } else {
+            data.reader
+        };
+        let dir = self.snapshot_dir(rir);
+        let tmp = write_temp(&dir, &mut reader)?;
+        let summary = inspect_dump(BufReader::new(tmp.as_file()))?;
+        if summary.objects < min_objects {
+            anyhow::bail!(
+                "dump contains only {} objects, expected at least {min_objects}",
+                summary.objects
+            );
+        }
+        tracing::debug!(
+            rir = rir.name(),
+            objects = summary.objects,
+            "verified RPSL dump"
+        );
+        if self
+            .latest_snapshot(rir)?
+            .is_some_and(|latest| latest.sha256 == summary.sha256)
+        {
+            return Ok(None);
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let stamp = now.format(SNAPSHOT_STAMP)?;
+        let mut file = format!("{stamp}.rpsl");
+        for n in 1.. {
+            if !dir.join(&file).exists() {
+                break;
+            }
+            file = format!("{stamp}-{n}.rpsl");
+        }
+        let meta = SnapshotMeta {
+            file,
+            urls: urls(),
+            size: summary.size,
+            sha256: summary.sha256,
+            fetched_at: now.format(&Rfc3339)?,
+            serial: summary.serial,
+            objects: summary.objects,
+        };
+        persist(tmp, &dir.join(&meta.file))?;
+        let json = serde_json::to_vec_pretty(&meta)?;
+        write_atomically(
+            &dir.join(snapshot_meta_name(&meta.file)),
+            &mut json.as_slice(),
+            |_| Ok(()),
+        )?;
+        write_atomically(
+            &dir.join(LATEST_SNAPSHOT),
+            &mut meta.file.as_bytes(),
+            |_| Ok(()),
+        )?;
+        Ok(Some(meta))
+    }
+
+    /// Remove snapshots according to the retention policy.
+    fn prune_snapshots(&self, rir: types::Rir) -> Result<(), anyhow::Error> {
+        let Some(latest) = self.latest_snapshot(rir)? else {
+            return Ok(());
+        };
+        let dir = self.snapshot_dir(rir);
+        // The pre-snapshot dump is superseded by the first snapshot.
+        let legacy = dir.join("latest.rpsl");
+        if legacy.exists() {
+            std::fs::remove_file(legacy)?;
+        }
+
+        let snapshots = self.snapshots(rir)?;
+        let keep = self.retention.keep_snapshots.max(1);
+        let now = OffsetDateTime::now_utc();
+        for (i, snapshot) in snapshots.iter().enumerate() {
+            if snapshot.file == latest.file {
+                continue;
+            }
+            let surplus = i + keep < snapshots.len();
+            let expired = self.retention.max_age.is_some_and(|max_age| {
+                snapshot
+                    .fetched_at()
+                    .is_some_and(|fetched_at| now - fetched_at > max_age)
+            });
+            if surplus || expired {
+                tracing::debug!(rir = rir.name(), file = snapshot.file, "removing snapshot");
+                std::fs::remove_file(dir.join(&snapshot.file))?;
+                std::fs::remove_file(dir.join(snapshot_meta_name(&snapshot.file)))?;
+            }
         }
         Ok(())
     }
 
     /// Iterate over typed RPSL objects stored for a given registry.
+    ///
+    /// Malformed objects in the dump are logged and skipped.
     pub fn objects_iter(
         &self,
         rir: types::Rir,
+    ) -> Result<impl Iterator<Item = Result<RpslObject, anyhow::Error>>, anyhow::Error> {
+        self.objects_iter_with_diagnostics(rir, &ParseDiagnostics::default())
+    }
+
+    fn objects_iter_with_diagnostics(
+        &self,
+        rir: types::Rir,
+        diagnostics: &ParseDiagnostics,
     ) -> Result<impl Iterator<Item = Result<RpslObject, anyhow::Error>>, anyhow::Error> {
         let file = File::open(self.db_path(rir))?;
-        let reader = BufReader::new(file);
-        let iter = parse_objects_read_iter(reader).map(|res| {
-            let obj = res?;
-            RpslObject::try_from(obj)
-        });
-        Ok(iter)
+        Ok(parse_dump(rir, file, diagnostics))
     }
 
     /// Iterate over typed RPSL objects from all stored registries.
     pub fn all_objects_iter(
         &self,
     ) -> Result<impl Iterator<Item = Result<RpslObject, anyhow::Error>>, anyhow::Error> {
+        self.all_objects_iter_with_diagnostics(&ParseDiagnostics::default())
+    }
+
+    fn all_objects_iter_with_diagnostics(
+        &self,
+        diagnostics: &ParseDiagnostics,
+    ) -> Result<impl Iterator<Item = Result<RpslObject, anyhow::Error>>, anyhow::Error> {
+        // Dumps are parsed one after another, so only one worker pool is
+        // running at a time.
+        let diagnostics = diagnostics.clone();
+        Ok(self
+            .stored_dumps()?
+            .into_iter()
+            .flat_map(move |(rir, file)| parse_dump(rir, file, &diagnostics)))
+    }
+
+    /// Dumps of all configured registries that have been downloaded.
+    fn stored_dumps(&self) -> Result<Vec<(types::Rir, File)>, anyhow::Error> {
+        let mut files = Vec::new();
+        for rir in types::Rir::ALL.iter() {
+            if !self.rirs.contains_key(rir) {
+                continue;
+            }
+            let path = self.db_path(*rir);
+            if !path.exists() {
+                tracing::warn!(
+                    rir = rir.name(),
+                    "no stored dump for registry, run an update first"
+                );
+                continue;
+            }
+            files.push((*rir, File::open(path)?));
+        }
+        Ok(files)
+    }
+
+    /// Iterate over the delegated-extended statistics records stored for a
+    /// given registry.
+    pub fn delegations_iter(&self, rir: types::Rir) -> Result<StoredDelegations, anyhow::Error> {
+        let file = File::open(self.delegated_stats_path(rir))?;
+        Ok(parse_delegated(BufReader::new(file)))
+    }
+
+    /// Delegated statistics of all configured registries that have been
+    /// downloaded.
+    fn stored_delegations(&self) -> Result<Vec<(types::Rir, StoredDelegations)>, anyhow::Error> {
         let mut iters = Vec::new();
         for rir in types::Rir::ALL.iter() {
-            if self.rirs.contains_key(rir) {
-                iters.push(self.objects_iter(*rir)?);
+            if !self.rirs.contains_key(rir) {
+                continue;
             }
+            if !self.delegated_stats_path(*rir).exists() {
+                tracing::warn!(
+                    rir = rir.name(),
+                    "no stored delegated statistics for registry, run an update first"
+                );
+                continue;
+            }
+            iters.push((*rir, self.delegations_iter(*rir)?));
         }
-        Ok(iters.into_iter().flatten())
+        Ok(iters)
+    }
+
+    /// Iterate over the delegated-extended statistics records of all stored
+    /// registries.
+    pub fn all_delegations_iter(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<Delegation, anyhow::Error>>, anyhow::Error> {
+        Ok(self
+            .stored_delegations()?
+            .into_iter()
+            .flat_map(|(_, iter)| iter))
     }
+
     /// Persist stored objects into a database using the provided filter.
     pub fn persist_to_db<D: Database>(
         &self,
@@ -159,59 +947,102 @@ impl Store {
 
         let mut batch = Vec::with_capacity(BATCH_SIZE);
         let mut count = 0usize;
+        let diagnostics = ParseDiagnostics::default();
+        let reporter = self.reporter.run();
 
-        for obj_res in self.all_objects_iter()? {
-            let obj = obj_res?;
+        for (rir, file) in self.stored_dumps()? {
+            // Snapshots know their object count, older dumps do not.
+            let total = self
+                .latest_snapshot(rir)
+                .ok()
+                .flatten()
+                .map(|s| s.objects as u64);
+            let mut parsed = reporter.track(rir, ProgressStage::Parse, total);
+            let mut persisted = reporter.track(rir, ProgressStage::Persist, None);
 
-            if filter.rpsl_objects {
-                batch.push(obj);
-                count += 1;
-            } else if filter.rpsl_inetnum && (obj.is_inetnum() || obj.is_inet6num()) {
-                // Only store inetnum/inet6num objects if the filter allows it
-                batch.push(obj);
-                count += 1;
-            } else {
-                // Skip other object types
-                continue;
+            for obj_res in parse_dump(rir, file, &diagnostics) {
+                let obj = obj_res?;
+                parsed.advance(1)?;
+
+                if filter.rpsl_objects {
+                    batch.push(obj);
+                    count += 1;
+                } else if filter.rpsl_inetnum && (obj.is_inetnum() || obj.is_inet6num()) {
+                    // Only store inetnum/inet6num objects if the filter allows it
+                    batch.push(obj);
+                    count += 1;
+                } else {
+                    // Skip other object types
+                    continue;
+                }
+
+                if batch.len() >= BATCH_SIZE {
+                    tracing::debug!(count = batch.len(), "insert rpsl batch");
+                    db.upsert_rpsl_objects(&batch)?;
+                    persisted.advance(batch.len() as u64)?;
+                    batch.clear();
+                }
             }
 
-            if batch.len() >= BATCH_SIZE {
-                tracing::debug!(count = batch.len(), "insert rpsl batch");
+            if !batch.is_empty() {
+                tracing::debug!(count = batch.len(), "insert final rpsl batch");
                 db.upsert_rpsl_objects(&batch)?;
+                persisted.advance(batch.len() as u64)?;
                 batch.clear();
             }
+            parsed.finish();
+            persisted.finish();
         }
 
-        if !batch.is_empty() {
-            tracing::debug!(count = batch.len(), "insert final rpsl batch");
-            db.upsert_rpsl_objects(&batch)?;
+        let mut delegations = 0usize;
+        if filter.delegations {
+            for (rir, iter) in self.stored_delegations()? {
+                let records = iter.collect::<Result<Vec<_>, _>>()?;
+                tracing::debug!(
+                    rir = rir.name(),
+                    count = records.len(),
+                    "insert delegations"
+                );
+                delegations += db.replace_delegations(rir, &records)?;
+            }
         }
 
-        tracing::info!(rpsl_objects = count, "persisted store successfully");
+        tracing::info!(
+            rpsl_objects = count,
+            delegations,
+            malformed_objects = diagnostics.malformed_objects(),
+            "persisted store successfully"
+        );
 
         Ok(())
     }
 
-    /// Build a GeoIP2 database from all stored objects.
-    pub fn write_geoip_db<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), anyhow::Error> {
+    /// Build a GeoIP2 database from all stored objects and delegated
+    /// statistics.
+    ///
+    /// Every record names the source its country was taken from. Where
+    /// networks overlap the most specific one wins, see [`GeoipOptions`] for
+    /// how candidates for the same network are ranked.
+    pub fn write_geoip_db<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        options: GeoipOptions,
+    ) -> Result<GeoipReport, anyhow::Error> {
         use maxminddb_writer::{
             metadata::{IpVersion, Metadata},
-            paths::IpAddrWithMask,
             Database,
         };
-        use serde::Serialize;
         use std::time::{SystemTime, UNIX_EPOCH};
 
-        #[derive(Serialize)]
-        struct Record {
-            country: String,
-        }
-
         let path = path.as_ref();
 
         let mut metadata = Metadata::default();
         metadata.ip_version = IpVersion::V6;
-        metadata.database_type = "GeoIP2-Country".into();
+        metadata.database_type = match options.format {
+            GeoipFormat::Country => "GeoIP2-Country",
+            GeoipFormat::Minimal => "ipgeomancer-Country",
+        }
+        .into();
         metadata.languages = vec!["en".into()];
         metadata.binary_format_major_version = 2;
         metadata.binary_format_minor_version = 0;
@@ -231,64 +1062,303 @@ impl Store {
 
         tracing::info!("Building GeoIP database to {}", path.display());
 
-        for obj_res in self.all_objects_iter()? {
-            let obj = obj_res.map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
-            match obj {
-                RpslObject::Inetnum(inet) => {
-                    if let Some(country) = &inet.country {
-                        let mut nets = 0;
+        let mut resolver = GeoipResolver::new(
+            options.primary_source,
+            options.tie_break,
+            options.rir_priority,
+        );
+        self.collect_geoip_rpsl(&mut resolver)?;
+        self.collect_geoip_delegated(&mut resolver)?;
+        let (networks, report) = resolver.resolve();
+
+        // Networks are ordered from the least to the most specific, so a more
+        // specific network replaces the data of its covering network in its
+        // part of the tree.
+        // Networks with the same country and source share their record.
+        let mut records = HashMap::new();
+        for network in networks {
+            let path = geoip_path(network.network);
+            let key = (network.country, network.source);
+            let data = match records.get(&key) {
+                Some(data) => *data,
+                None => {
+                    let (country, source) = (key.0.as_str(), key.1.to_string());
+                    let data = match options.format {
+                        GeoipFormat::Country => {
+                            db.insert_value(GeoipCountryRecord::new(country, &source))?
+                        }
+                        GeoipFormat::Minimal => db.insert_value(GeoipRecord {
+                            country,
+                            source: &source,
+                        })?,
+                    };
+                    records.insert(key, data);
+                    data
+                }
+            };
+            db.insert_node(path, data);
+        }
+
+        let file = std::fs::File::create(path)?;
+        let writer = std::io::BufWriter::new(file);
+        db.write_to(writer)?;
+
+        tracing::info!(
+            path = %path.display(),
+            networks = report.networks,
+            conflicts = report.conflicts.len(),
+            missing_country = report.missing_country,
+            "GeoIP database written successfully"
+        );
+
+        Ok(report)
+    }
+
+    fn collect_geoip_rpsl(&self, resolver: &mut GeoipResolver) -> Result<(), anyhow::Error> {
+        let diagnostics = ParseDiagnostics::default();
+        for (rir, file) in self.stored_dumps()? {
+            for obj in parse_dump(rir, file, &diagnostics) {
+                match obj? {
+                    RpslObject::Inetnum(inet) => {
                         for net in &inet.inetnum {
-                            let path = IpAddrWithMask::new(
-                                std::net::IpAddr::V4(net.network()),
-                                net.prefix_len(),
+                            resolver.add_rpsl(
+                                ipnet::IpNet::V4(net),
+                                inet.country.clone(),
+                                inet.org.clone(),
+                                rir,
                             );
-                            let data = db.insert_value(Record {
-                                country: country.clone(),
-                            })?;
-                            tracing::debug!(?path, ?country, "adding inetnum object");
-                            db.insert_node(path, data);
-                            nets += 1;
-                        }
-                        if nets == 0 {
-                            tracing::warn!(?inet, "inetnum object with no networks");
-                        } else {
-                            tracing::debug!(?inet, nets, "added inetnum object with networks");
                         }
-                    } else {
-                        tracing::warn!(?inet, "inetnum object without country");
                     }
-                }
-                RpslObject::Inet6num(inet) => {
-                    if let Some(country) = inet.country {
+                    RpslObject::Inet6num(inet) => {
                         for net in &inet.inet6num {
-                            let path = IpAddrWithMask::new(
-                                std::net::IpAddr::V6(net.network()),
-                                net.prefix_len(),
+                            resolver.add_rpsl(
+                                ipnet::IpNet::V6(net),
+                                inet.country.clone(),
+                                inet.org.clone(),
+                                rir,
                             );
-                            let data = db.insert_value(Record {
-                                country: country.clone(),
-                            })?;
-                            tracing::debug!(?path, ?country, "adding inet6num object");
-                            db.insert_node(path, data);
                         }
-                    } else {
-                        tracing::warn!(?inet, "inet6num object without country");
                     }
+                    RpslObject::Organisation(org) => {
+                        if let Some(country) = org.extra.get("country").and_then(|c| c.first()) {
+                            resolver.add_org(org.organisation, country);
+                        }
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
         }
+        Ok(())
+    }
 
-        let file = std::fs::File::create(path)?;
-        let writer = std::io::BufWriter::new(file);
-        db.write_to(writer)?;
+    fn collect_geoip_delegated(&self, resolver: &mut GeoipResolver) -> Result<(), anyhow::Error> {
+        for delegation in self.all_delegations_iter()? {
+            let delegation = delegation?;
+            let Some(country) = delegation.delegated_country() else {
+                continue;
+            };
+            for net in delegation.resource.networks() {
+                resolver.add_delegated(net, country, delegation.registry);
+            }
+        }
+        Ok(())
+    }
+}
 
-        tracing::info!(path=%path.display(), "GeoIP database written successfully");
+/// Write `reader` to `path` without leaving a partial file behind.
+///
+/// The data is written to a temporary file next to `path` and synced to
+/// disk. `validate` is called with the temporary file, rewound to the start,
+/// and only if it succeeds the file atomically replaces `path`.
+fn write_atomically<F>(
+    path: &std::path::Path,
+    reader: &mut dyn Read,
+    validate: F,
+) -> Result<(), anyhow::Error>
+where
+    F: FnOnce(&File) -> Result<(), anyhow::Error>,
+{
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("invalid path {}", path.display()))?;
+    let tmp = write_temp(parent, reader)?;
+    validate(tmp.as_file())?;
+    persist(tmp, path)
+}
 
-        Ok(())
+/// Open a dump file, decompressing it if it is gzip-compressed.
+pub fn open_dump_file(path: &Path) -> Result<Box<dyn Read + Send>, anyhow::Error> {
+    use std::io::BufRead;
+
+    let file =
+        File::open(path).map_err(|e| anyhow::anyhow!("could not open {}: {e}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(flate2::read::MultiGzDecoder::new(reader)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Write `reader` to a temporary file in `dir`, rewound to the start.
+///
+/// The file is removed when dropped unless it is moved into place with
+/// [`persist`].
+fn write_temp(dir: &Path, reader: &mut dyn Read) -> Result<tempfile::NamedTempFile, anyhow::Error> {
+    use std::io::{Seek, SeekFrom};
+
+    std::fs::create_dir_all(dir)?;
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".download-")
+        .tempfile_in(dir)?;
+    std::io::copy(reader, tmp.as_file_mut())?;
+    tmp.as_file().sync_all()?;
+    tmp.as_file_mut().seek(SeekFrom::Start(0))?;
+    Ok(tmp)
+}
+
+/// Atomically move a file written by [`write_temp`] to `path`.
+///
+/// The parent directory is synced as well, so the rename survives a crash.
+fn persist(tmp: tempfile::NamedTempFile, path: &Path) -> Result<(), anyhow::Error> {
+    tmp.persist(path)?;
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+fn snapshot_meta_name(file: &str) -> String {
+    format!("{}.json", file.strip_suffix(".rpsl").unwrap_or(file))
+}
+
+fn read_snapshot_meta(path: &Path) -> Result<SnapshotMeta, anyhow::Error> {
+    let data = std::fs::read(path)?;
+    serde_json::from_slice(&data)
+        .map_err(|e| anyhow::anyhow!("invalid snapshot metadata {}: {e}", path.display()))
+}
+
+/// Properties of an RPSL dump gathered in a single pass.
+#[derive(Debug)]
+struct DumpSummary {
+    objects: usize,
+    size: u64,
+    sha256: String,
+    serial: Option<u64>,
+}
+
+/// Count the objects of an RPSL dump without parsing them, and compute its
+/// digest.
+///
+/// An object starts at a line that is neither blank, a comment nor a
+/// continuation, following a blank line or the start of the input. The
+/// serial is taken from a comment before the first object, such as
+/// `% Serial: 123`.
+fn inspect_dump<R: std::io::BufRead>(mut reader: R) -> Result<DumpSummary, std::io::Error> {
+    let mut line = Vec::new();
+    let mut in_object = false;
+    let mut count = 0;
+    let mut size = 0;
+    let mut serial = None;
+    let mut hasher = Sha256::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            let sha256 = hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect();
+            return Ok(DumpSummary {
+                objects: count,
+                size,
+                sha256,
+                serial,
+            });
+        }
+        hasher.update(&line);
+        size += line.len() as u64;
+        if count == 0 && serial.is_none() && matches!(line[0], b'%' | b'#') {
+            serial = header_serial(&String::from_utf8_lossy(&line));
+        }
+        let blank = line.iter().all(|b| b.is_ascii_whitespace());
+        if blank {
+            in_object = false;
+        } else if !in_object && !matches!(line[0], b'%' | b'#' | b' ' | b'\t' | b'+') {
+            in_object = true;
+            count += 1;
+        }
+    }
+}
+
+/// Extract the number following `serial` in a header comment.
+fn header_serial(comment: &str) -> Option<u64> {
+    let lower = comment.to_ascii_lowercase();
+    let rest = &lower[lower.find("serial")? + "serial".len()..];
+    let rest = rest.trim_start_matches(|c: char| c == ':' || c == '=' || c.is_whitespace());
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Path of a network in the IPv6 tree of a GeoIP database.
+///
+/// Readers look up IPv4 addresses in the IPv4-compatible `::/96` subtree.
+fn geoip_path(net: ipnet::IpNet) -> maxminddb_writer::paths::IpAddrWithMask {
+    use maxminddb_writer::paths::IpAddrWithMask;
+
+    match net {
+        ipnet::IpNet::V4(net) => IpAddrWithMask::new(
+            std::net::IpAddr::V6(net.network().to_ipv6_compatible()),
+            net.prefix_len() + 96,
+        ),
+        ipnet::IpNet::V6(net) => {
+            IpAddrWithMask::new(std::net::IpAddr::V6(net.network()), net.prefix_len())
+        }
     }
 }
 
+/// Parse and convert the objects of a stored dump on a pool of worker threads.
+///
+/// Malformed objects are logged and skipped.
+fn parse_dump(
+    rir: types::Rir,
+    file: File,
+    diagnostics: &ParseDiagnostics,
+) -> impl Iterator<Item = Result<RpslObject, anyhow::Error>> {
+    let reader = BufReader::with_capacity(64 * 1024, file);
+    let malformed_objects = diagnostics.malformed_objects.clone();
+    // Dumps from some registries contain Latin-1 encoded text.
+    parse_objects_parallel(reader)
+        .with_decoding(Decoding::Utf8OrLatin1)
+        .with_recovery(move |malformed| {
+            malformed_objects.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                rir = rir.name(),
+                start_line = malformed.start_line,
+                end_line = malformed.end_line,
+                line = malformed.line,
+                reason = ?malformed.kind,
+                "skipping malformed RPSL object"
+            );
+            tracing::debug!(rir = rir.name(), raw = %malformed.raw, "malformed RPSL object");
+        })
+        .map_objects(move |obj| {
+            let (obj, warnings) = RpslObject::from_object(obj)?;
+            for warning in warnings {
+                tracing::warn!(
+                    rir = rir.name(),
+                    attribute = %warning.attribute,
+                    value = %warning.value,
+                    "{}",
+                    warning.message
+                );
+            }
+            Ok(obj)
+        })
+        .map(|res| res?)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::RirKind;
@@ -305,27 +1375,44 @@ mod tests {
     #[derive(Debug, Clone)]
     pub struct MockRir {
         data: String,
+        delegated: Option<String>,
     }
 
     impl MockRir {
         pub fn new(data: &str) -> Self {
             Self {
                 data: data.to_string(),
+                delegated: None,
             }
         }
+
+        pub fn with_delegated(mut self, delegated: &str) -> Self {
+            self.delegated = Some(delegated.to_string());
+            self
+        }
     }
 
     impl RirProvider for MockRir {
-        fn build_rpsl_db_request(&self, _client: &Client) -> reqwest::blocking::RequestBuilder {
+        fn rpsl_db_urls(&self) -> Vec<String> {
             unimplemented!("mock")
         }
 
-        fn download_rpsl_db(&self, _client: &Client) -> Result<DbData, anyhow::Error> {
+        fn download_rpsl_db(&self, _dl: &Downloader) -> Result<DbData, anyhow::Error> {
             Ok(DbData {
                 gzip: false,
                 reader: Box::new(std::io::Cursor::new(self.data.clone())),
             })
         }
+
+        fn download_delegated_stats(
+            &self,
+            _dl: &Downloader,
+        ) -> Result<Option<Box<dyn Read + Send>>, anyhow::Error> {
+            Ok(self
+                .delegated
+                .clone()
+                .map(|d| Box::new(std::io::Cursor::new(d)) as Box<dyn Read + Send>))
+        }
     }
 
     pub fn mock_rir_data() -> String {
@@ -403,12 +1490,46 @@ inet6num: 2001:db8::/32\nnetname: V6-NET\ncountry: ZZ\nsource: TST\n\n"
         store.update().unwrap();
 
         let db_path = base.join("geoip.mmdb");
-        store.write_geoip_db(&db_path).unwrap();
+        store
+            .write_geoip_db(&db_path, GeoipOptions::default())
+            .unwrap();
 
         let meta = fs::metadata(&db_path).unwrap();
         assert!(meta.len() > 0);
     }
 
+    #[test]
+    fn objects_iter_skips_malformed_objects() {
+        let mut base = std::env::temp_dir();
+        let t = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        base.push(format!("ipgeomancer_test_malformed_{}", t));
+        fs::create_dir_all(&base).unwrap();
+        let data = format!("broken line\nnetname: X\n\n{}", mock_rir_data());
+        let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+        rirs.insert(RirKind::Ripe, Box::new(MockRir::new(&data)));
+
+        let store = Store::with_rirs(&base, rirs).unwrap();
+        store.update().unwrap();
+
+        let objs: Vec<_> = store
+            .objects_iter(RirKind::Ripe)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(objs.len(), 2);
+
+        let diagnostics = ParseDiagnostics::default();
+        let count = store
+            .all_objects_iter_with_diagnostics(&diagnostics)
+            .unwrap()
+            .count();
+        assert_eq!(count, 2);
+        assert_eq!(diagnostics.malformed_objects(), 1);
+    }
+
     #[test]
     fn persist_to_sqlite_db() {
         let mut base = std::env::temp_dir();
@@ -434,17 +1555,568 @@ inet6num: 2001:db8::/32\nnetname: V6-NET\ncountry: ZZ\nsource: TST\n\n"
             .unwrap()
             .unwrap();
         assert_eq!(c, "ZZ");
-        let json = db.get_object(&obj_type, &key).unwrap().unwrap();
-        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
-        assert_eq!(v["netname"], "TEST-NET");
+        let obj = db.get_object(&obj_type, &key).unwrap().unwrap();
+        let RpslObject::Inetnum(inet) = obj else {
+            panic!("expected inetnum, got {obj:?}");
+        };
+        assert_eq!(inet.netname.as_deref(), Some("TEST-NET"));
 
         let (c, obj_type, key) = db
             .lookup_ipv6_with_obj("2001:db8::1".parse().unwrap())
             .unwrap()
             .unwrap();
         assert_eq!(c, "ZZ");
-        let json = db.get_object(&obj_type, &key).unwrap().unwrap();
-        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
-        assert_eq!(v["netname"], "V6-NET");
+        let obj = db.get_object(&obj_type, &key).unwrap().unwrap();
+        let RpslObject::Inet6num(inet) = obj else {
+            panic!("expected inet6num, got {obj:?}");
+        };
+        assert_eq!(inet.netname.as_deref(), Some("V6-NET"));
+    }
+
+    #[test]
+    fn delegated_stats_fallback() {
+        use crate::{GeoSource, GeoSourceKind};
+
+        let mut base = std::env::temp_dir();
+        let t = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        base.push(format!("ipgeomancer_test_delegated_{}", t));
+        fs::create_dir_all(&base).unwrap();
+        let delegated = "\
+2|ripencc|1700000000|3|19830705|20231114|+0100
+ripencc|*|ipv4|*|2|summary
+ripencc|DE|ipv4|192.0.2.0|256|20100101|allocated|a
+ripencc|FR|ipv4|198.51.100.0|512|20100101|allocated|b
+ripencc|NL|asn|64496|16|20100101|assigned|c
+";
+        let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+        rirs.insert(
+            RirKind::Ripe,
+            Box::new(MockRir::new(&mock_rir_data()).with_delegated(delegated)),
+        );
+        let store = Store::with_rirs(&base, rirs).unwrap();
+        store.update().unwrap();
+        assert_eq!(store.delegations_iter(RirKind::Ripe).unwrap().count(), 3);
+
+        let db = SqliteDb::memory().unwrap();
+        store.persist_to_db(&db, PersistFilter::default()).unwrap();
+
+        let addr = "192.0.2.1".parse().unwrap();
+        let m = db
+            .lookup_ipv4_match(addr, GeoSourceKind::Rpsl)
+            .unwrap()
+            .unwrap();
+        assert_eq!(m.country, "ZZ");
+        assert_eq!(m.source.kind(), GeoSourceKind::Rpsl);
+        let m = db
+            .lookup_ipv4_match(addr, GeoSourceKind::Delegated)
+            .unwrap()
+            .unwrap();
+        assert_eq!(m.country, "DE");
+        assert_eq!(
+            m.source,
+            GeoSource::Delegated {
+                registry: RirKind::Ripe,
+                status: "allocated".into()
+            }
+        );
+
+        // Not covered by RPSL data.
+        let addr = "198.51.101.1".parse().unwrap();
+        assert_eq!(db.lookup_ipv4(addr).unwrap().as_deref(), Some("FR"));
+        assert_eq!(
+            db.lookup_ipv4("203.0.113.1".parse().unwrap()).unwrap(),
+            None
+        );
+
+        let asn = db.lookup_asn_delegation(64500).unwrap().unwrap();
+        assert_eq!(asn.country.as_deref(), Some("NL"));
+        assert_eq!(asn.opaque_id.as_deref(), Some("c"));
+        assert!(db.lookup_asn_delegation(64512).unwrap().is_none());
+
+        // Persisting again replaces the records of the registry.
+        store.persist_to_db(&db, PersistFilter::default()).unwrap();
+        assert_eq!(db.lookup_ipv4(addr).unwrap().as_deref(), Some("FR"));
+
+        #[derive(serde::Deserialize)]
+        struct Record<'a> {
+            #[serde(borrow, flatten)]
+            record: maxminddb::geoip2::Country<'a>,
+            source: &'a str,
+        }
+        for (primary, country, source) in [
+            (GeoSourceKind::Rpsl, "ZZ", "rpsl"),
+            (GeoSourceKind::Delegated, "DE", "delegated"),
+        ] {
+            let db_path = base.join(format!("geoip-{primary}.mmdb"));
+            store
+                .write_geoip_db(
+                    &db_path,
+                    GeoipOptions {
+                        primary_source: primary,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let reader = maxminddb::Reader::open_readfile(&db_path).unwrap();
+            assert_eq!(reader.metadata.database_type, "GeoIP2-Country");
+            let ip: std::net::IpAddr = "192.0.2.1".parse().unwrap();
+            let record: Record = reader.lookup(ip).unwrap().unwrap();
+            let iso_code = record.record.country.unwrap().iso_code;
+            assert_eq!((iso_code, record.source), (Some(country), source));
+            let ip: std::net::IpAddr = "198.51.101.1".parse().unwrap();
+            let record: maxminddb::geoip2::Country = reader.lookup(ip).unwrap().unwrap();
+            let country = record.country.unwrap();
+            assert_eq!(country.iso_code, Some("FR"));
+            assert_eq!(country.names.unwrap()["en"], "France");
+            assert_eq!(country.is_in_european_union, Some(true));
+            assert_eq!(record.continent.unwrap().code, Some("EU"));
+            assert_eq!(record.registered_country.unwrap().iso_code, Some("FR"));
+        }
+
+        #[derive(serde::Deserialize)]
+        struct MinimalRecord {
+            country: String,
+            source: String,
+        }
+        let db_path = base.join("geoip-minimal.mmdb");
+        store
+            .write_geoip_db(
+                &db_path,
+                GeoipOptions {
+                    format: GeoipFormat::Minimal,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let reader = maxminddb::Reader::open_readfile(&db_path).unwrap();
+        let ip: std::net::IpAddr = "198.51.101.1".parse().unwrap();
+        let record: MinimalRecord = reader.lookup(ip).unwrap().unwrap();
+        assert_eq!(
+            (record.country.as_str(), record.source.as_str()),
+            ("FR", "delegated")
+        );
+    }
+
+    #[test]
+    fn geoip_db_and_database_lookups_agree() {
+        let base = tempfile::tempdir().unwrap();
+        let rpsl = "\
+inetnum: 10.0.0.0 - 10.0.255.255\ncountry: DE\nsource: TST\n\n\
+inetnum: 10.0.1.0 - 10.0.1.255\ncountry: FR\nsource: TST\n\n";
+        let delegated = "\
+ripencc|NL|ipv4|10.0.0.0|256|20100101|allocated|a
+ripencc|BE|ipv4|10.0.1.0|256|20100101|allocated|b
+ripencc|US|ipv4|10.0.0.0|16777216|20100101|allocated|c
+";
+        let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+        rirs.insert(
+            RirKind::Ripe,
+            Box::new(MockRir::new(rpsl).with_delegated(delegated)),
+        );
+        let store = Store::with_rirs(base.path().join("store"), rirs).unwrap();
+        store.update().unwrap();
+        let db = SqliteDb::memory().unwrap();
+        store.persist_to_db(&db, PersistFilter::default()).unwrap();
+
+        for (primary, expected) in [
+            (GeoSourceKind::Rpsl, ["NL", "FR", "DE", "US"]),
+            (GeoSourceKind::Delegated, ["NL", "BE", "DE", "US"]),
+        ] {
+            let db_path = base.path().join(format!("geoip-{primary}.mmdb"));
+            store
+                .write_geoip_db(
+                    &db_path,
+                    GeoipOptions {
+                        primary_source: primary,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let reader = maxminddb::Reader::open_readfile(&db_path).unwrap();
+            // A delegation inside an RPSL network, the same network in both
+            // sources, an RPSL network inside a delegation, and only the
+            // delegation.
+            for (ip, expected) in ["10.0.0.1", "10.0.1.1", "10.0.2.1", "10.1.0.1"]
+                .into_iter()
+                .zip(expected)
+            {
+                let addr: std::net::Ipv4Addr = ip.parse().unwrap();
+                let record: maxminddb::geoip2::Country =
+                    reader.lookup(addr.into()).unwrap().unwrap();
+                let from_mmdb = record.country.unwrap().iso_code.unwrap();
+                let from_db = db.lookup_ipv4_match(addr, primary).unwrap().unwrap();
+                assert_eq!((ip, from_mmdb), (ip, expected), "mmdb, primary {primary}");
+                assert_eq!(
+                    (ip, from_db.country.as_str()),
+                    (ip, expected),
+                    "database, primary {primary}"
+                );
+            }
+        }
+    }
+
+    /// RIR whose downloads break off or are incomplete.
+    #[derive(Debug)]
+    struct BrokenRir {
+        /// Fail with a read error instead of returning a short dump.
+        read_error: bool,
+    }
+
+    struct ResetReader;
+
+    impl Read for ResetReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("connection reset"))
+        }
+    }
+
+    impl RirProvider for BrokenRir {
+        fn rpsl_db_urls(&self) -> Vec<String> {
+            unimplemented!("mock")
+        }
+
+        fn download_rpsl_db(&self, _dl: &Downloader) -> Result<DbData, anyhow::Error> {
+            let data = std::io::Cursor::new("inetnum: 10.0.0.0/8\ncountry: XX\n\n");
+            let reader: Box<dyn Read + Send> = if self.read_error {
+                Box::new(data.chain(ResetReader))
+            } else {
+                Box::new(data)
+            };
+            Ok(DbData {
+                gzip: false,
+                reader,
+            })
+        }
+
+        fn min_rpsl_objects(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn failed_update_keeps_previous_snapshot() {
+        let mut base = std::env::temp_dir();
+        let t = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        base.push(format!("ipgeomancer_test_atomic_{}", t));
+        fs::create_dir_all(&base).unwrap();
+
+        let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+        rirs.insert(RirKind::Ripe, Box::new(MockRir::new(&mock_rir_data())));
+        rirs.insert(RirKind::Apnic, Box::new(MockRir::new(&mock_rir_data())));
+        let store = Store::with_rirs(&base, rirs).unwrap();
+        store.update().unwrap();
+        let latest = store.latest_snapshot(RirKind::Ripe).unwrap().unwrap();
+        let previous = fs::read_to_string(store.db_path(RirKind::Ripe)).unwrap();
+
+        for read_error in [true, false] {
+            let updated = format!("{}aut-num: AS64496\nsource: TST\n\n", mock_rir_data());
+            let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+            rirs.insert(RirKind::Ripe, Box::new(BrokenRir { read_error }));
+            rirs.insert(RirKind::Apnic, Box::new(MockRir::new(&updated)));
+            let store = Store::with_rirs(&base, rirs).unwrap();
+
+            let err = store.update().unwrap_err();
+            assert_eq!(err.to_string(), "failed to update registries: ripe");
+            assert_eq!(
+                store.latest_snapshot(RirKind::Ripe).unwrap().as_ref(),
+                Some(&latest)
+            );
+            assert_eq!(
+                fs::read_to_string(store.db_path(RirKind::Ripe)).unwrap(),
+                previous
+            );
+            assert_eq!(store.objects_iter(RirKind::Apnic).unwrap().count(), 3);
+        }
+
+        // No temporary files are left behind.
+        let mut names: Vec<_> = fs::read_dir(base.join("rir/ripe/db"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        let stamp = latest.file.strip_suffix(".rpsl").unwrap();
+        assert_eq!(
+            names,
+            vec![
+                format!("{stamp}.json"),
+                format!("{stamp}.rpsl"),
+                "latest".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshots_and_retention() {
+        let base = tempfile::tempdir().unwrap();
+        let dir = base.path().join("rir/ripe/db");
+        // A store written before snapshots existed.
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("latest.rpsl"), mock_rir_data()).unwrap();
+
+        let store_with = |data: &str| {
+            let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+            rirs.insert(RirKind::Ripe, Box::new(MockRir::new(data)));
+            Store::with_rirs(base.path(), rirs)
+                .unwrap()
+                .with_retention(RetentionPolicy {
+                    keep_snapshots: 2,
+                    max_age: None,
+                })
+        };
+        assert_eq!(
+            store_with("").objects_iter(RirKind::Ripe).unwrap().count(),
+            2
+        );
+        assert_eq!(store_with("").latest_snapshot(RirKind::Ripe).unwrap(), None);
+
+        let mut files = Vec::new();
+        for i in 0..3 {
+            let data = format!(
+                "% Serial: {}\n\n{}aut-num: AS{}\nsource: TST\n\n",
+                100 + i,
+                mock_rir_data(),
+                64496 + i
+            );
+            let store = store_with(&data);
+            store.update().unwrap();
+            let latest = store.latest_snapshot(RirKind::Ripe).unwrap().unwrap();
+            assert_eq!(latest.serial, Some(100 + i as u64));
+            assert_eq!(latest.objects, 3);
+            assert_eq!(latest.size, data.len() as u64);
+            assert!(latest.urls.is_empty());
+            assert_eq!(
+                fs::read_to_string(store.snapshot_path(RirKind::Ripe, &latest)).unwrap(),
+                data
+            );
+            files.push(latest.file);
+
+            // Unchanged dumps do not create new snapshots.
+            store.update().unwrap();
+            assert_eq!(
+                store.snapshots(RirKind::Ripe).unwrap().len(),
+                (i + 1).min(2)
+            );
+        }
+        assert!(!dir.join("latest.rpsl").exists());
+
+        let store = store_with("");
+        let snapshots: Vec<_> = store
+            .snapshots(RirKind::Ripe)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.file)
+            .collect();
+        assert_eq!(snapshots, files[1..]);
+        assert_eq!(store.objects_iter(RirKind::Ripe).unwrap().count(), 3);
+
+        let stamp = |file: &str| file.strip_suffix(".rpsl").unwrap().to_string();
+        for (stamp, file) in [(stamp(&files[1]), &files[1]), ("latest".into(), &files[2])] {
+            let snapshot = store.find_snapshot(RirKind::Ripe, &stamp).unwrap();
+            assert_eq!(&snapshot.file, file);
+        }
+        // Pruned snapshots are gone.
+        assert!(store
+            .find_snapshot(RirKind::Ripe, &stamp(&files[0]))
+            .is_err());
+
+        // Expired snapshots are removed, except for the latest one.
+        let store = store_with(&format!("{}mntner: M\nsource: TST\n\n", mock_rir_data()))
+            .with_retention(RetentionPolicy {
+                keep_snapshots: 5,
+                max_age: Some(std::time::Duration::ZERO),
+            });
+        store.update().unwrap();
+        let snapshots = store.snapshots(RirKind::Ripe).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].serial, None);
+        assert_eq!(
+            store.latest_snapshot(RirKind::Ripe).unwrap().as_ref(),
+            Some(&snapshots[0])
+        );
+    }
+
+    #[test]
+    fn import_dump_files() {
+        use std::io::Write;
+
+        let base = tempfile::tempdir().unwrap();
+        let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+        rirs.insert(RirKind::Apnic, Box::new(MockRir::new("")));
+        let store = Store::with_rirs(base.path().join("store"), rirs).unwrap();
+
+        let gzip = |text: &str| {
+            let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            enc.write_all(text.as_bytes()).unwrap();
+            enc.finish().unwrap()
+        };
+        let split = base.path().join("split");
+        fs::create_dir_all(&split).unwrap();
+        fs::write(
+            split.join("apnic.db.inetnum.gz"),
+            gzip("inetnum: 192.0.2.0/24\ncountry: ZZ\nsource: TST"),
+        )
+        .unwrap();
+        fs::write(
+            split.join("apnic.db.inet6num"),
+            "inet6num: 2001:db8::/32\ncountry: ZZ\nsource: TST\n",
+        )
+        .unwrap();
+        fs::write(split.join(".listing"), "not a dump").unwrap();
+
+        let snapshot = store.import_file(RirKind::Apnic, &split).unwrap().unwrap();
+        assert_eq!(snapshot.objects, 2);
+        assert_eq!(snapshot.urls.len(), 2);
+        assert!(snapshot.urls[0].starts_with("file://"));
+        assert!(snapshot.urls[1].ends_with("apnic.db.inetnum.gz"));
+        assert_eq!(
+            store.latest_snapshot(RirKind::Apnic).unwrap(),
+            Some(snapshot)
+        );
+        assert_eq!(store.objects_iter(RirKind::Apnic).unwrap().count(), 2);
+        // Importing the same data again does not create a new snapshot.
+        assert_eq!(store.import_file(RirKind::Apnic, &split).unwrap(), None);
+
+        let single = base.path().join("apnic.db.gz");
+        fs::write(&single, gzip(&format!("{}mntner: M\n", mock_rir_data()))).unwrap();
+        let snapshot = store.import_file(RirKind::Apnic, &single).unwrap().unwrap();
+        assert_eq!(snapshot.objects, 3);
+        assert_eq!(store.snapshots(RirKind::Apnic).unwrap().len(), 2);
+
+        assert!(store.import_file(RirKind::Ripe, &single).is_err());
+
+        let delegated = base.path().join("delegated-apnic-extended-latest");
+        fs::write(&delegated, "apnic|*|ipv4|*|0|summary\n").unwrap();
+        assert!(store
+            .import_delegated_stats(RirKind::Apnic, &delegated)
+            .is_err());
+        fs::write(
+            &delegated,
+            "apnic|JP|ipv4|192.0.2.0|256|20100101|allocated|a\n\
+             apnic|AU|ipv6|2001:db8::|32|20100101|allocated|b\n",
+        )
+        .unwrap();
+        store
+            .import_delegated_stats(RirKind::Apnic, &delegated)
+            .unwrap();
+        assert_eq!(store.delegations_iter(RirKind::Apnic).unwrap().count(), 2);
+    }
+
+    /// RIR whose download takes a while.
+    #[derive(Debug)]
+    struct SlowRir;
+
+    impl RirProvider for SlowRir {
+        fn rpsl_db_urls(&self) -> Vec<String> {
+            unimplemented!("mock")
+        }
+
+        fn download_rpsl_db(&self, _dl: &Downloader) -> Result<DbData, anyhow::Error> {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            Ok(DbData {
+                gzip: false,
+                reader: Box::new(std::io::Cursor::new(mock_rir_data())),
+            })
+        }
+    }
+
+    #[test]
+    fn failure_policies() {
+        for policy in [FailurePolicy::Continue, FailurePolicy::Abort] {
+            let base = tempfile::tempdir().unwrap();
+            let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+            rirs.insert(RirKind::Ripe, Box::new(BrokenRir { read_error: true }));
+            rirs.insert(RirKind::Apnic, Box::new(SlowRir));
+            let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let store = {
+                let events = events.clone();
+                Store::with_rirs(base.path(), rirs)
+                    .unwrap()
+                    .with_rir_failure_policy(RirKind::Ripe, policy)
+                    .with_progress(move |e| events.lock().unwrap().push(e.clone()))
+            };
+
+            let err = store.update().unwrap_err().to_string();
+            let failed = events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter_map(|e| match e {
+                    ProgressEvent::Failed { rir, .. } => Some(*rir),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(failed, vec![RirKind::Ripe]);
+            let apnic = store.latest_snapshot(RirKind::Apnic).unwrap();
+            match policy {
+                FailurePolicy::Continue => {
+                    assert_eq!(err, "failed to update registries: ripe");
+                    assert!(apnic.is_some());
+                }
+                FailurePolicy::Abort => {
+                    assert_eq!(err, "failed to update registries: ripe; aborted: apnic");
+                    assert!(apnic.is_none());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn persist_progress() {
+        let base = tempfile::tempdir().unwrap();
+        let mut rirs: HashMap<RirKind, Box<dyn crate::RirProvider>> = HashMap::new();
+        rirs.insert(RirKind::Ripe, Box::new(MockRir::new(&mock_rir_data())));
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let store = {
+            let events = events.clone();
+            Store::with_rirs(base.path(), rirs)
+                .unwrap()
+                .with_progress(move |e| events.lock().unwrap().push(e.clone()))
+        };
+        store.update().unwrap();
+        let db = SqliteDb::memory().unwrap();
+        store.persist_to_db(&db, PersistFilter::default()).unwrap();
+
+        let finished = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|e| match e {
+                ProgressEvent::Finished(p) => Some((p.rir, p.stage, p.done, p.total)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            finished,
+            vec![
+                (RirKind::Ripe, ProgressStage::Parse, 2, Some(2)),
+                (RirKind::Ripe, ProgressStage::Persist, 2, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn count_objects() {
+        let dump = "% comment\n\ninetnum: 192.0.2.0/24\n+ continued\n# note\n\n\n\
+                    route: 192.0.2.0/24\n  continued\norigin: AS1\n\nmntner: M\n";
+        let summary = inspect_dump(dump.as_bytes()).unwrap();
+        assert_eq!(summary.objects, 3);
+        assert_eq!(summary.size, dump.len() as u64);
+        assert_eq!(summary.serial, None);
+        assert_eq!(
+            inspect_dump("% RPSL dump, serial 42\n\nmntner: M\n".as_bytes())
+                .unwrap()
+                .serial,
+            Some(42)
+        );
+        assert_eq!(
+            inspect_dump(&b""[..]).unwrap().sha256,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
     }
 }
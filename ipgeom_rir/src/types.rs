@@ -43,7 +43,8 @@ impl std::str::FromStr for Rir {
         match s.to_uppercase().as_str() {
             "ARIN" => Ok(Rir::Arin),
             "APNIC" => Ok(Rir::Apnic),
-            "RIPE" => Ok(Rir::Ripe),
+            // Delegated statistics files use `ripencc`.
+            "RIPE" | "RIPENCC" => Ok(Rir::Ripe),
             "LACNIC" => Ok(Rir::Lacnic),
             "AFRINIC" => Ok(Rir::Afrinic),
             _ => Err("Invalid RIR"),
@@ -0,0 +1,105 @@
+//! Throughput benchmark for the streaming RPSL parser.
+//!
+//! Parses a synthetic dump by default. Set `IPGEOM_RPSL_BENCH_FILE` to the
+//! path of a real (uncompressed) dump, e.g. `ripe.db`, to benchmark that
+//! instead:
+//!
+//! ```sh
+//! IPGEOM_RPSL_BENCH_FILE=ripe.db cargo bench -p ipgeom_rpsl --bench read_iter
+//! ```
+
+use std::io::Read;
+use std::time::Instant;
+
+use ipgeom_rpsl::{parse_objects_parallel, parse_objects_read_iter, Decoding, RpslObject};
+
+/// Build a synthetic dump of roughly `target_len` bytes.
+fn synthetic_dump(target_len: usize) -> Vec<u8> {
+    let mut out = String::with_capacity(target_len + 1024);
+    let mut i = 0u32;
+    while out.len() < target_len {
+        let [a, b, c, _] = i.to_be_bytes();
+        out.push_str(&format!(
+            "inetnum:        {a}.{b}.{c}.0 - {a}.{b}.{c}.255\n\
+             netname:        NET-{i}\n\
+             descr:          Zürich Straße {i}\n\
+             \x20               continued description line\n\
+             country:        CH\n\
+             admin-c:        DUMY-RIPE\n\
+             tech-c:         DUMY-RIPE\n\
+             status:         ASSIGNED PA\n\
+             mnt-by:         EXAMPLE-MNT\n\
+             created:        2002-02-13T09:26:40Z\n\
+             last-modified:  2023-07-10T14:29:40Z\n\
+             source:         RIPE\n\n"
+        ));
+        i += 1;
+    }
+    out.into_bytes()
+}
+
+fn bench(name: &str, data: &[u8], decoding: Decoding) {
+    let start = Instant::now();
+    let mut count = 0usize;
+    for res in parse_objects_read_iter(data).with_decoding(decoding) {
+        res.expect("parse error");
+        count += 1;
+    }
+    report(name, data, count, start);
+}
+
+/// Parse and convert to typed objects on the current thread.
+fn bench_typed(name: &str, data: &[u8], decoding: Decoding) {
+    let start = Instant::now();
+    let mut count = 0usize;
+    for res in parse_objects_read_iter(data).with_decoding(decoding) {
+        RpslObject::try_from(res.expect("parse error")).expect("conversion error");
+        count += 1;
+    }
+    report(name, data, count, start);
+}
+
+/// Parse and convert to typed objects on all available cores.
+fn bench_parallel(name: &str, data: &[u8], decoding: Decoding) {
+    let start = Instant::now();
+    let mut count = 0usize;
+    let reader = std::io::Cursor::new(data.to_vec());
+    for res in parse_objects_parallel(reader)
+        .with_decoding(decoding)
+        .map_objects(RpslObject::try_from)
+    {
+        res.expect("parse error").expect("conversion error");
+        count += 1;
+    }
+    report(name, data, count, start);
+}
+
+fn report(name: &str, data: &[u8], count: usize, start: Instant) {
+    let elapsed = start.elapsed();
+    let mib = data.len() as f64 / (1024.0 * 1024.0);
+    println!(
+        "{name:<16} {count:>10} objects  {mib:>8.1} MiB  {:>8.3}s  {:>8.1} MiB/s  {:>10.0} objects/s",
+        elapsed.as_secs_f64(),
+        mib / elapsed.as_secs_f64(),
+        count as f64 / elapsed.as_secs_f64(),
+    );
+}
+
+fn main() {
+    let data = match std::env::var_os("IPGEOM_RPSL_BENCH_FILE") {
+        Some(path) => {
+            let mut data = Vec::new();
+            std::fs::File::open(&path)
+                .and_then(|mut f| f.read_to_end(&mut data))
+                .expect("failed to read benchmark file");
+            data
+        }
+        None => synthetic_dump(64 * 1024 * 1024),
+    };
+
+    bench("utf8-lossy", &data, Decoding::Utf8Lossy);
+    bench("latin1", &data, Decoding::Latin1);
+    bench("utf8-or-latin1", &data, Decoding::Utf8OrLatin1);
+    bench_typed("typed", &data, Decoding::Utf8OrLatin1);
+    bench_parallel("parallel-typed", &data, Decoding::Utf8OrLatin1);
+}
@@ -0,0 +1,220 @@
+use std::fmt::Write;
+
+use super::{PrefixList, PrefixListEntry};
+
+/// Output format of a rendered prefix list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixListFormat {
+    /// Cisco IOS `ip prefix-list` / `ipv6 prefix-list` statements.
+    Cisco,
+    /// Juniper `policy-options` with a `prefix-list`, or a
+    /// `route-filter-list` if the list contains ranges.
+    Juniper,
+    /// A BIRD prefix set.
+    Bird,
+    /// JSON in the format of `bgpq4 -j`.
+    Json,
+}
+
+impl PrefixListFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrefixListFormat::Cisco => "cisco",
+            PrefixListFormat::Juniper => "juniper",
+            PrefixListFormat::Bird => "bird",
+            PrefixListFormat::Json => "json",
+        }
+    }
+
+    /// Render `list` as a prefix list called `name`.
+    pub fn render(&self, name: &str, list: &PrefixList) -> String {
+        match self {
+            PrefixListFormat::Cisco => render_cisco(name, list),
+            PrefixListFormat::Juniper => render_juniper(name, list),
+            PrefixListFormat::Bird => render_bird(name, list),
+            PrefixListFormat::Json => render_json(name, list),
+        }
+    }
+}
+
+impl std::fmt::Display for PrefixListFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PrefixListFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cisco" => Ok(PrefixListFormat::Cisco),
+            "juniper" => Ok(PrefixListFormat::Juniper),
+            "bird" => Ok(PrefixListFormat::Bird),
+            "json" => Ok(PrefixListFormat::Json),
+            _ => Err(format!("unknown prefix list format '{}'", s)),
+        }
+    }
+}
+
+fn render_cisco(name: &str, list: &PrefixList) -> String {
+    let mut out = String::new();
+    for (family, entries) in [("ip", list.ipv4()), ("ipv6", list.ipv6())] {
+        for e in entries.entries() {
+            let _ = write!(out, "{} prefix-list {} permit {}", family, name, e.prefix);
+            if !e.is_exact() {
+                if e.min_length != e.prefix.prefix_len() {
+                    let _ = write!(out, " ge {}", e.min_length);
+                }
+                let _ = write!(out, " le {}", e.max_length);
+            }
+            out.push('\n');
+        }
+    }
+    if list.is_empty() {
+        // An empty prefix-list would permit everything.
+        let _ = writeln!(out, "ip prefix-list {} deny 0.0.0.0/0 le 32", name);
+    }
+    out
+}
+
+fn render_juniper(name: &str, list: &PrefixList) -> String {
+    let mut out = String::from("policy-options {\n");
+    if list.entries().iter().all(PrefixListEntry::is_exact) {
+        let _ = writeln!(out, "replace:\n  prefix-list {} {{", name);
+        for e in list.entries() {
+            let _ = writeln!(out, "    {};", e.prefix);
+        }
+    } else {
+        let _ = writeln!(out, "replace:\n  route-filter-list {} {{", name);
+        for e in list.entries() {
+            let len = e.prefix.prefix_len();
+            let _ = if e.is_exact() {
+                writeln!(out, "    {} exact;", e.prefix)
+            } else if e.min_length == len {
+                writeln!(out, "    {} upto /{};", e.prefix, e.max_length)
+            } else {
+                writeln!(
+                    out,
+                    "    {} prefix-length-range /{}-/{};",
+                    e.prefix, e.min_length, e.max_length
+                )
+            };
+        }
+    }
+    out.push_str("  }\n}\n");
+    out
+}
+
+fn render_bird(name: &str, list: &PrefixList) -> String {
+    let items: Vec<_> = list
+        .entries()
+        .iter()
+        .map(|e| {
+            if e.is_exact() {
+                e.prefix.to_string()
+            } else {
+                format!("{}{{{},{}}}", e.prefix, e.min_length, e.max_length)
+            }
+        })
+        .collect();
+    if items.is_empty() {
+        return format!("{} = [ ];\n", name);
+    }
+    format!("{} = [\n    {}\n];\n", name, items.join(",\n    "))
+}
+
+fn render_json(name: &str, list: &PrefixList) -> String {
+    let entries: Vec<_> = list
+        .entries()
+        .iter()
+        .map(|e| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("prefix".into(), e.prefix.to_string().into());
+            obj.insert("exact".into(), e.is_exact().into());
+            if !e.is_exact() {
+                if e.min_length != e.prefix.prefix_len() {
+                    obj.insert("greater-equal".into(), e.min_length.into());
+                }
+                obj.insert("less-equal".into(), e.max_length.into());
+            }
+            serde_json::Value::Object(obj)
+        })
+        .collect();
+    let mut root = serde_json::Map::new();
+    root.insert(name.to_string(), entries.into());
+    let mut out = serde_json::to_string_pretty(&serde_json::Value::Object(root))
+        .expect("JSON values always serialize");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::RangeOperator;
+    use ipnet::IpNet;
+
+    fn list() -> PrefixList {
+        let net = |s: &str| s.parse::<IpNet>().unwrap();
+        PrefixList::new([
+            PrefixListEntry::exact(net("192.0.2.0/24")),
+            PrefixListEntry::with_operator(net("10.0.0.0/8"), RangeOperator::Range(16, 24))
+                .unwrap(),
+            PrefixListEntry::with_operator(net("2001:db8::/32"), RangeOperator::Plus).unwrap(),
+        ])
+    }
+
+    #[test]
+    fn render_formats() {
+        let list = list();
+        assert_eq!(
+            PrefixListFormat::Cisco.render("PL", &list),
+            "ip prefix-list PL permit 10.0.0.0/8 ge 16 le 24\n\
+             ip prefix-list PL permit 192.0.2.0/24\n\
+             ipv6 prefix-list PL permit 2001:db8::/32 le 128\n"
+        );
+        assert_eq!(
+            PrefixListFormat::Juniper.render("PL", &list),
+            "policy-options {\n\
+             replace:\n  route-filter-list PL {\n    \
+             10.0.0.0/8 prefix-length-range /16-/24;\n    \
+             192.0.2.0/24 exact;\n    \
+             2001:db8::/32 upto /128;\n  }\n}\n"
+        );
+        assert_eq!(
+            PrefixListFormat::Bird.render("PL", &list),
+            "PL = [\n    10.0.0.0/8{16,24},\n    192.0.2.0/24,\n    2001:db8::/32{32,128}\n];\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&PrefixListFormat::Json.render("PL", &list)).unwrap();
+        assert_eq!(
+            json["PL"][0],
+            serde_json::json!({
+                "prefix": "10.0.0.0/8",
+                "exact": false,
+                "greater-equal": 16,
+                "less-equal": 24,
+            })
+        );
+        assert_eq!(
+            json["PL"][1],
+            serde_json::json!({"prefix": "192.0.2.0/24", "exact": true})
+        );
+    }
+
+    #[test]
+    fn render_empty() {
+        let empty = PrefixList::default();
+        assert_eq!(
+            PrefixListFormat::Cisco.render("PL", &empty),
+            "ip prefix-list PL deny 0.0.0.0/0 le 32\n"
+        );
+        assert_eq!(PrefixListFormat::Bird.render("PL", &empty), "PL = [ ];\n");
+        assert_eq!(
+            PrefixListFormat::Juniper.render("PL", &empty),
+            "policy-options {\nreplace:\n  prefix-list PL {\n  }\n}\n"
+        );
+    }
+}
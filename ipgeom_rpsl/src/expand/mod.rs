@@ -0,0 +1,686 @@
+//! Recursive expansion of `as-set` and `route-set` objects.
+//!
+//! An [`IrrIndex`] is built from the relevant objects of one or more
+//! databases and expands set names into AS numbers and prefix lists, much
+//! like `bgpq4` does with a live IRR server.
+
+mod format;
+
+use std::collections::{hash_map::Entry, BTreeSet, HashMap};
+
+use ipnet::IpNet;
+use iprange::IpRange;
+
+pub use self::format::PrefixListFormat;
+
+use crate::{
+    policy::{parse_asn, RangeOperator},
+    AsSet, RouteSet, RpslObject,
+};
+
+/// Options for [`IrrIndex::expand`].
+#[derive(Debug, Clone, Copy)]
+pub struct ExpandOptions {
+    /// Maximum nesting depth of sets. Members of sets nested deeper are
+    /// ignored and reported as [`ExpandWarning::DepthLimit`].
+    pub max_depth: usize,
+}
+
+impl Default for ExpandOptions {
+    fn default() -> Self {
+        Self { max_depth: 32 }
+    }
+}
+
+/// Problems found while expanding a set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpandWarning {
+    /// A referenced set does not exist in the index.
+    MissingSet(String),
+    /// A set references itself. Contains the chain of sets that leads back
+    /// to the first one.
+    Cycle(Vec<String>),
+    /// A set was not expanded because it is nested too deep.
+    DepthLimit(String),
+    /// A member that is neither an AS number, a prefix nor a set name.
+    InvalidMember { set: String, member: String },
+}
+
+impl std::fmt::Display for ExpandWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpandWarning::MissingSet(name) => write!(f, "set {} not found", name),
+            ExpandWarning::Cycle(chain) => write!(f, "cycle: {}", chain.join(" -> ")),
+            ExpandWarning::DepthLimit(name) => {
+                write!(f, "set {} not expanded, depth limit reached", name)
+            }
+            ExpandWarning::InvalidMember { set, member } => {
+                write!(f, "invalid member '{}' in {}", member, set)
+            }
+        }
+    }
+}
+
+/// An entry of a prefix list, matching `prefix` and its more specifics
+/// with a length between `min_length` and `max_length`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PrefixListEntry {
+    pub prefix: IpNet,
+    pub min_length: u8,
+    pub max_length: u8,
+}
+
+impl PrefixListEntry {
+    /// An entry matching only `prefix` itself.
+    pub fn exact(prefix: IpNet) -> Self {
+        let len = prefix.prefix_len();
+        Self {
+            prefix: prefix.trunc(),
+            min_length: len,
+            max_length: len,
+        }
+    }
+
+    /// Apply an RPSL range operator to `prefix`.
+    ///
+    /// Returns `None` if the operator selects no prefixes, e.g. `^16` on a
+    /// `/24`.
+    pub fn with_operator(prefix: IpNet, operator: RangeOperator) -> Option<Self> {
+        let len = prefix.prefix_len();
+        let max = prefix.max_prefix_len();
+        let (min_length, max_length) = match operator {
+            RangeOperator::None => (len, len),
+            RangeOperator::Minus => (len + 1, max),
+            RangeOperator::Plus => (len, max),
+            RangeOperator::Length(n) => (n.max(len), n),
+            RangeOperator::Range(n, m) => (n.max(len), m),
+        };
+        if min_length > max_length || max_length > max {
+            return None;
+        }
+        Some(Self {
+            prefix: prefix.trunc(),
+            min_length,
+            max_length,
+        })
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.min_length == self.prefix.prefix_len() && self.max_length == self.min_length
+    }
+}
+
+/// A sorted list of unique prefix list entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefixList {
+    entries: Vec<PrefixListEntry>,
+}
+
+impl PrefixList {
+    pub fn new<I: IntoIterator<Item = PrefixListEntry>>(entries: I) -> Self {
+        let entries: BTreeSet<_> = entries.into_iter().collect();
+        Self {
+            entries: entries.into_iter().collect(),
+        }
+    }
+
+    pub fn entries(&self) -> &[PrefixListEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Only the IPv4 entries.
+    pub fn ipv4(&self) -> Self {
+        Self::new(
+            self.entries
+                .iter()
+                .filter(|e| matches!(e.prefix, IpNet::V4(_)))
+                .copied(),
+        )
+    }
+
+    /// Only the IPv6 entries.
+    pub fn ipv6(&self) -> Self {
+        Self::new(
+            self.entries
+                .iter()
+                .filter(|e| matches!(e.prefix, IpNet::V6(_)))
+                .copied(),
+        )
+    }
+
+    /// Merge exact entries into covering prefixes.
+    ///
+    /// Each aggregate matches the lengths of the prefixes it was built from,
+    /// so `192.0.2.0/25` and `192.0.2.128/25` become `192.0.2.0/24` with
+    /// lengths 25 to 25. Like `bgpq4 -A`, the result may match a few more
+    /// prefixes than the original list. Entries with ranges are kept as
+    /// they are.
+    pub fn aggregate(&self) -> Self {
+        let (exact, ranges): (Vec<&PrefixListEntry>, Vec<_>) =
+            self.entries.iter().partition(|e| e.is_exact());
+
+        let mut v4 = IpRange::new();
+        let mut v6 = IpRange::new();
+        for e in &exact {
+            match e.prefix {
+                IpNet::V4(net) => {
+                    v4.add(net);
+                }
+                IpNet::V6(net) => {
+                    v6.add(net);
+                }
+            }
+        }
+        v4.simplify();
+        v6.simplify();
+
+        let aggregates = v4
+            .iter()
+            .map(IpNet::V4)
+            .chain(v6.iter().map(IpNet::V6))
+            .map(|net| {
+                let lengths = exact
+                    .iter()
+                    .filter(|e| net.contains(&e.prefix))
+                    .map(|e| e.prefix.prefix_len());
+                let (min, max) =
+                    lengths.fold((u8::MAX, 0), |(min, max), l| (min.min(l), max.max(l)));
+                PrefixListEntry {
+                    prefix: net,
+                    min_length: min,
+                    max_length: max,
+                }
+            });
+        Self::new(aggregates.chain(ranges.into_iter().copied()))
+    }
+}
+
+/// Result of expanding a set.
+#[derive(Debug, Clone, Default)]
+pub struct Expansion {
+    /// AS numbers the set expands to. Empty for route sets that only
+    /// contain prefixes.
+    pub asns: BTreeSet<u32>,
+    /// Prefixes of the set, or of the routes originated by its AS numbers.
+    pub prefixes: PrefixList,
+    pub warnings: Vec<ExpandWarning>,
+}
+
+/// Index of the objects needed to expand sets.
+#[derive(Debug, Clone, Default)]
+pub struct IrrIndex {
+    as_sets: HashMap<String, AsSet>,
+    route_sets: HashMap<String, RouteSet>,
+    /// `aut-num` objects referencing a set with `member-of`, together with
+    /// their maintainers.
+    as_member_of: HashMap<String, Vec<(u32, Vec<String>)>>,
+    /// `route` and `route6` objects referencing a set with `member-of`.
+    route_member_of: HashMap<String, Vec<(IpNet, Vec<String>)>>,
+    /// Routes by origin AS.
+    routes: HashMap<u32, Vec<IpNet>>,
+}
+
+impl IrrIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an object to the index. Objects that are not needed for set
+    /// expansion are ignored.
+    pub fn insert(&mut self, obj: RpslObject) {
+        match obj {
+            RpslObject::AsSet(set) => {
+                self.as_sets.insert(set.as_set.to_ascii_uppercase(), set);
+            }
+            RpslObject::RouteSet(set) => {
+                self.route_sets
+                    .insert(set.route_set.to_ascii_uppercase(), set);
+            }
+            RpslObject::AutNum(aut) => {
+                let Some(asn) = parse_asn(&aut.aut_num) else {
+                    return;
+                };
+                for set in &aut.member_of {
+                    self.as_member_of
+                        .entry(set.to_ascii_uppercase())
+                        .or_default()
+                        .push((asn, aut.common.mnt_by.clone()));
+                }
+            }
+            RpslObject::Route(route) => {
+                let nets = route.route.iter().map(IpNet::V4);
+                self.insert_route(
+                    nets,
+                    route.origin.as_deref(),
+                    &route.member_of,
+                    &route.common.mnt_by,
+                );
+            }
+            RpslObject::Route6(route) => {
+                let nets = route.route6.iter().map(IpNet::V6);
+                self.insert_route(
+                    nets,
+                    route.origin.as_deref(),
+                    &route.member_of,
+                    &route.common.mnt_by,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn insert_route(
+        &mut self,
+        nets: impl Iterator<Item = IpNet>,
+        origin: Option<&str>,
+        member_of: &[String],
+        mnt_by: &[String],
+    ) {
+        let origin = origin.and_then(parse_asn);
+        for net in nets {
+            if let Some(origin) = origin {
+                self.routes.entry(origin).or_default().push(net);
+            }
+            for set in member_of {
+                self.route_member_of
+                    .entry(set.to_ascii_uppercase())
+                    .or_default()
+                    .push((net, mnt_by.to_vec()));
+            }
+        }
+    }
+
+    /// Prefixes of the routes originated by `asn`.
+    pub fn routes(&self, asn: u32) -> &[IpNet] {
+        self.routes.get(&asn).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Expand an AS number, `as-set` or `route-set`.
+    ///
+    /// For AS numbers and `as-set` objects the prefixes are those of the
+    /// `route` and `route6` objects originated by the member AS numbers.
+    pub fn expand(&self, name: &str, options: &ExpandOptions) -> Expansion {
+        let mut walker = Walker {
+            index: self,
+            options,
+            path: Vec::new(),
+            visited: HashMap::new(),
+            asns: BTreeSet::new(),
+            prefixes: Vec::new(),
+            warnings: Vec::new(),
+        };
+        walker.member(name.trim(), RangeOperator::None, 0, None);
+        Expansion {
+            asns: walker.asns,
+            prefixes: PrefixList::new(walker.prefixes),
+            warnings: walker.warnings,
+        }
+    }
+}
+
+impl Extend<RpslObject> for IrrIndex {
+    fn extend<T: IntoIterator<Item = RpslObject>>(&mut self, iter: T) {
+        for obj in iter {
+            self.insert(obj);
+        }
+    }
+}
+
+impl FromIterator<RpslObject> for IrrIndex {
+    fn from_iter<T: IntoIterator<Item = RpslObject>>(iter: T) -> Self {
+        let mut index = Self::new();
+        index.extend(iter);
+        index
+    }
+}
+
+/// Whether any component of a hierarchical set name starts with `prefix`,
+/// e.g. `AS-` for `AS65000:AS-CUSTOMERS`.
+fn is_set_name(name: &str, prefix: &str) -> bool {
+    name.split(':').any(|c| {
+        c.len() > prefix.len()
+            && c.get(..prefix.len())
+                .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+    })
+}
+
+/// Whether a set with `mbrs-by-ref` accepts a member maintained by
+/// `mnt_by`.
+fn accepts_by_ref(mbrs_by_ref: &[String], mnt_by: &[String]) -> bool {
+    mbrs_by_ref
+        .iter()
+        .any(|m| m.eq_ignore_ascii_case("ANY") || mnt_by.iter().any(|n| n.eq_ignore_ascii_case(m)))
+}
+
+/// Split a trailing range operator off a route set member.
+fn split_operator(member: &str) -> Option<(&str, RangeOperator)> {
+    let Some((name, op)) = member.split_once('^') else {
+        return Some((member, RangeOperator::None));
+    };
+    let op = match op {
+        "+" => RangeOperator::Plus,
+        "-" => RangeOperator::Minus,
+        _ => match op.split_once('-') {
+            Some((n, m)) => RangeOperator::Range(n.parse().ok()?, m.parse().ok()?),
+            None => RangeOperator::Length(op.parse().ok()?),
+        },
+    };
+    Some((name, op))
+}
+
+struct Walker<'a> {
+    index: &'a IrrIndex,
+    options: &'a ExpandOptions,
+    /// Sets currently being expanded, used to detect cycles.
+    path: Vec<String>,
+    /// Sets that were already expanded with the given operator, with the
+    /// shallowest depth they were expanded at.
+    visited: HashMap<(String, RangeOperator), usize>,
+    asns: BTreeSet<u32>,
+    prefixes: Vec<PrefixListEntry>,
+    warnings: Vec<ExpandWarning>,
+}
+
+impl Walker<'_> {
+    /// Expand a single member. `set` is the name of the containing set, if
+    /// any.
+    fn member(&mut self, member: &str, operator: RangeOperator, depth: usize, set: Option<&str>) {
+        if let Some(asn) = parse_asn(member) {
+            self.asn(asn, operator);
+        } else if let Ok(prefix) = member.parse::<IpNet>() {
+            self.prefixes
+                .extend(PrefixListEntry::with_operator(prefix, operator));
+        } else if is_set_name(member, "AS-") {
+            self.set(member, operator, depth, |w, key| {
+                w.as_set(key, operator, depth)
+            });
+        } else if is_set_name(member, "RS-") {
+            self.set(member, operator, depth, |w, key| {
+                w.route_set(key, operator, depth)
+            });
+        } else {
+            match set {
+                Some(set) => self.warnings.push(ExpandWarning::InvalidMember {
+                    set: set.to_string(),
+                    member: member.to_string(),
+                }),
+                None => self
+                    .warnings
+                    .push(ExpandWarning::MissingSet(member.to_string())),
+            }
+        }
+    }
+
+    fn asn(&mut self, asn: u32, operator: RangeOperator) {
+        self.asns.insert(asn);
+        for prefix in self.index.routes(asn) {
+            self.prefixes
+                .extend(PrefixListEntry::with_operator(*prefix, operator));
+        }
+    }
+
+    /// Expand a set with cycle and depth checks.
+    fn set<F>(&mut self, name: &str, operator: RangeOperator, depth: usize, expand: F)
+    where
+        F: FnOnce(&mut Self, &str) -> bool,
+    {
+        let key = name.to_ascii_uppercase();
+        if let Some(start) = self.path.iter().position(|p| *p == key) {
+            let mut chain = self.path[start..].to_vec();
+            chain.push(key);
+            self.warnings.push(ExpandWarning::Cycle(chain));
+            return;
+        }
+        if depth > self.options.max_depth {
+            self.warnings
+                .push(ExpandWarning::DepthLimit(name.to_string()));
+            return;
+        }
+        // A set reached again at a shallower depth is expanded again, since
+        // members that hit the depth limit before may now be in reach.
+        match self.visited.entry((key.clone(), operator)) {
+            Entry::Occupied(e) if *e.get() <= depth => return,
+            Entry::Occupied(mut e) => {
+                e.insert(depth);
+            }
+            Entry::Vacant(e) => {
+                e.insert(depth);
+            }
+        }
+        self.path.push(key.clone());
+        if !expand(self, &key) {
+            self.warnings
+                .push(ExpandWarning::MissingSet(name.to_string()));
+        }
+        self.path.pop();
+    }
+
+    fn as_set(&mut self, key: &str, operator: RangeOperator, depth: usize) -> bool {
+        let index = self.index;
+        let Some(set) = index.as_sets.get(key) else {
+            return false;
+        };
+        for member in &set.members {
+            self.member(member, operator, depth + 1, Some(&set.as_set));
+        }
+        if !set.mbrs_by_ref.is_empty() {
+            for (asn, mnt_by) in index.as_member_of.get(key).into_iter().flatten() {
+                if accepts_by_ref(&set.mbrs_by_ref, mnt_by) {
+                    self.asn(*asn, operator);
+                }
+            }
+        }
+        true
+    }
+
+    fn route_set(&mut self, key: &str, operator: RangeOperator, depth: usize) -> bool {
+        let index = self.index;
+        let Some(set) = index.route_sets.get(key) else {
+            return false;
+        };
+        for member in set.members.iter().chain(&set.mp_members) {
+            let Some((name, member_op)) = split_operator(member) else {
+                self.warnings.push(ExpandWarning::InvalidMember {
+                    set: set.route_set.clone(),
+                    member: member.clone(),
+                });
+                continue;
+            };
+            // An operator on the set reference overrides those of the
+            // members.
+            let op = match operator {
+                RangeOperator::None => member_op,
+                op => op,
+            };
+            self.member(name, op, depth + 1, Some(&set.route_set));
+        }
+        if !set.mbrs_by_ref.is_empty() {
+            for (prefix, mnt_by) in index.route_member_of.get(key).into_iter().flatten() {
+                if accepts_by_ref(&set.mbrs_by_ref, mnt_by) {
+                    self.prefixes
+                        .extend(PrefixListEntry::with_operator(*prefix, operator));
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_objects;
+
+    const DATA: &str = "\
+as-set: AS-TOP
+members: AS65001, AS-CUSTOMERS, AS65000:AS-PEERS
+
+as-set: AS-CUSTOMERS
+members: AS65002, AS-TOP
+mbrs-by-ref: CUST-MNT
+
+as-set: AS65000:AS-PEERS
+members: AS65003, AS-MISSING
+
+aut-num: AS65004
+member-of: AS-CUSTOMERS
+mnt-by: CUST-MNT
+
+aut-num: AS65005
+member-of: AS-CUSTOMERS
+mnt-by: OTHER-MNT
+
+route: 192.0.2.0/25
+origin: AS65001
+
+route: 192.0.2.128/25
+origin: AS65002
+
+route: 198.51.100.0/24
+origin: AS65004
+
+route6: 2001:db8::/48
+origin: AS65003
+
+route-set: RS-TOP
+members: 203.0.113.0/24^+, RS-NESTED, AS65001
+mp-members: 2001:db8:1::/48
+
+route-set: RS-NESTED
+members: 10.0.0.0/8^16-24
+";
+
+    fn index() -> IrrIndex {
+        parse_objects(DATA)
+            .unwrap()
+            .into_iter()
+            .map(|o| RpslObject::try_from(o).unwrap())
+            .collect()
+    }
+
+    fn prefixes(list: &PrefixList) -> Vec<String> {
+        list.entries()
+            .iter()
+            .map(|e| format!("{} {}-{}", e.prefix, e.min_length, e.max_length))
+            .collect()
+    }
+
+    #[test]
+    fn expand_as_set() {
+        let exp = index().expand("as-top", &ExpandOptions::default());
+        assert_eq!(
+            exp.asns.into_iter().collect::<Vec<_>>(),
+            [65001, 65002, 65003, 65004]
+        );
+        assert_eq!(
+            prefixes(&exp.prefixes),
+            [
+                "192.0.2.0/25 25-25",
+                "192.0.2.128/25 25-25",
+                "198.51.100.0/24 24-24",
+                "2001:db8::/48 48-48",
+            ]
+        );
+        assert_eq!(
+            exp.warnings,
+            [
+                ExpandWarning::Cycle(vec![
+                    "AS-TOP".into(),
+                    "AS-CUSTOMERS".into(),
+                    "AS-TOP".into()
+                ]),
+                ExpandWarning::MissingSet("AS-MISSING".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn depth_limit() {
+        let exp = index().expand("AS-TOP", &ExpandOptions { max_depth: 0 });
+        assert_eq!(exp.asns.into_iter().collect::<Vec<_>>(), [65001]);
+        assert!(exp
+            .warnings
+            .contains(&ExpandWarning::DepthLimit("AS-CUSTOMERS".into())));
+    }
+
+    #[test]
+    fn depth_limit_uses_shallowest_path() {
+        let data = "\
+as-set: AS-ROOT
+members: AS-A, AS-B
+
+as-set: AS-A
+members: AS-B
+
+as-set: AS-B
+members: AS-C
+
+as-set: AS-C
+members: AS65010
+";
+        let index: IrrIndex = parse_objects(data)
+            .unwrap()
+            .into_iter()
+            .map(|o| RpslObject::try_from(o).unwrap())
+            .collect();
+
+        // AS-B is first reached through AS-A, where AS-C is past the limit,
+        // and then directly from AS-ROOT.
+        let exp = index.expand("AS-ROOT", &ExpandOptions { max_depth: 2 });
+        assert_eq!(exp.asns.into_iter().collect::<Vec<_>>(), [65010]);
+
+        // AS-B is first reached past the limit.
+        let exp = index.expand("AS-ROOT", &ExpandOptions { max_depth: 1 });
+        assert!(exp
+            .warnings
+            .contains(&ExpandWarning::DepthLimit("AS-B".into())));
+        assert!(exp
+            .warnings
+            .contains(&ExpandWarning::DepthLimit("AS-C".into())));
+    }
+
+    #[test]
+    fn expand_route_set() {
+        let exp = index().expand("RS-TOP", &ExpandOptions::default());
+        assert_eq!(
+            prefixes(&exp.prefixes),
+            [
+                "10.0.0.0/8 16-24",
+                "192.0.2.0/25 25-25",
+                "203.0.113.0/24 24-32",
+                "2001:db8:1::/48 48-48",
+            ]
+        );
+        assert!(exp.warnings.is_empty());
+    }
+
+    #[test]
+    fn aggregate_prefixes() {
+        let list = PrefixList::new(
+            [
+                "192.0.2.0/25",
+                "192.0.2.128/25",
+                "198.51.100.0/24",
+                "198.51.100.0/25",
+            ]
+            .into_iter()
+            .map(|p| PrefixListEntry::exact(p.parse().unwrap()))
+            .chain(PrefixListEntry::with_operator(
+                "10.0.0.0/8".parse().unwrap(),
+                RangeOperator::Plus,
+            )),
+        );
+        assert_eq!(
+            prefixes(&list.aggregate()),
+            [
+                "10.0.0.0/8 8-32",
+                "192.0.2.0/24 25-25",
+                "198.51.100.0/24 24-25",
+            ]
+        );
+    }
+}
@@ -0,0 +1,607 @@
+//! Filter expressions for selecting RPSL objects.
+//!
+//! A filter is a list of conditions that must all match, for example
+//! `type=route origin=AS3333`. Supported conditions:
+//!
+//! * `key=value` and `key!=value`: case-insensitive comparison. Values of
+//!   list attributes such as `members` are compared item by item.
+//! * `key~regex` and `key!~regex`: case-insensitive regular expression
+//!   search in the value.
+//! * `key in (a,b,...)`: the value equals one of the given values.
+//! * `key within PREFIX` and `key contains ADDR`: the value, an address
+//!   range, prefix or address, lies within or contains the given range,
+//!   prefix or address.
+//! * `key`: the attribute is present.
+//!
+//! Conditions can be combined with `and` (the default), `or`, `not` and
+//! parentheses. The pseudo key `type` refers to the object type. Keys may
+//! be given as attribute names (`mnt-by`) or typed field names (`mnt_by`).
+
+use std::borrow::Cow;
+use std::net::IpAddr;
+use std::ops::Range;
+
+use ipnet::IpNet;
+use regex::{Regex, RegexBuilder};
+
+use crate::{Object, RpslObject};
+
+/// A parsed filter expression.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parse a filter expression. An empty expression matches all objects.
+    pub fn parse(input: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            len: input.len(),
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(FilterError {
+                span: token.span.clone(),
+                message: "unexpected token".to_string(),
+            });
+        }
+        Ok(Self { expr })
+    }
+
+    /// Whether the filter matches a raw object.
+    pub fn matches(&self, obj: &Object) -> bool {
+        self.expr.matches(obj, None)
+    }
+
+    /// Whether the filter matches a raw object together with its typed
+    /// form.
+    ///
+    /// Attributes are compared as written in `obj`, except the range of an
+    /// `inetnum` or `inet6num`, which is compared in its normalized form,
+    /// so it matches regardless of how it was written in the source.
+    pub fn matches_typed(&self, obj: &Object, typed: &RpslObject) -> bool {
+        self.expr.matches(obj, Some(typed))
+    }
+
+    /// Whether the filter refers to attributes that
+    /// [`Filter::matches_typed`] compares in their typed form.
+    pub fn needs_typed(&self) -> bool {
+        self.expr.needs_typed()
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Error produced when a filter expression can not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterError {
+    /// Byte range of the offending input.
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Condition { key: String, op: Op },
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Exists,
+    Eq(String),
+    Regex(Regex),
+    In(Vec<String>),
+    Within(Interval),
+    Contains(Interval),
+}
+
+impl Expr {
+    fn matches(&self, obj: &Object, typed: Option<&RpslObject>) -> bool {
+        match self {
+            Expr::And(exprs) => exprs.iter().all(|e| e.matches(obj, typed)),
+            Expr::Or(exprs) => exprs.iter().any(|e| e.matches(obj, typed)),
+            Expr::Not(expr) => !expr.matches(obj, typed),
+            Expr::Condition { key, op } => {
+                let values = values(obj, typed, key);
+                match op {
+                    Op::Exists => !values.is_empty(),
+                    Op::Eq(expected) => values
+                        .iter()
+                        .flat_map(|v| items(v))
+                        .any(|v| v.eq_ignore_ascii_case(expected)),
+                    Op::Regex(re) => values.iter().any(|v| re.is_match(v)),
+                    Op::In(expected) => values
+                        .iter()
+                        .flat_map(|v| items(v))
+                        .any(|v| expected.iter().any(|e| v.eq_ignore_ascii_case(e))),
+                    Op::Within(outer) => values
+                        .iter()
+                        .filter_map(|v| Interval::parse(v))
+                        .any(|v| outer.contains(&v)),
+                    Op::Contains(inner) => values
+                        .iter()
+                        .filter_map(|v| Interval::parse(v))
+                        .any(|v| v.contains(inner)),
+                }
+            }
+        }
+    }
+
+    fn needs_typed(&self) -> bool {
+        match self {
+            Expr::And(exprs) | Expr::Or(exprs) => exprs.iter().any(Expr::needs_typed),
+            Expr::Not(expr) => expr.needs_typed(),
+            Expr::Condition { key, .. } => TYPED_KEYS.contains(&key.as_str()),
+        }
+    }
+}
+
+/// Keys compared in their typed form if the typed object is available.
+const TYPED_KEYS: &[&str] = &["inetnum", "inet6num"];
+
+/// Values of `key` in `obj`. `type` yields the object type.
+fn values<'a>(obj: &'a Object, typed: Option<&RpslObject>, key: &str) -> Vec<Cow<'a, str>> {
+    if key == "type" {
+        return vec![Cow::Borrowed(obj.obj_type().as_key())];
+    }
+    if TYPED_KEYS.contains(&key) && key == obj.obj_type().as_key() {
+        if let Some(range) = typed.and_then(RpslObject::normalized_range) {
+            return vec![Cow::Owned(range)];
+        }
+    }
+    obj.ordered_attributes()
+        .iter()
+        .filter(|a| a.key() == key)
+        .map(|a| Cow::Borrowed(a.value()))
+        .collect()
+}
+
+/// Items of a comma separated list value.
+fn items(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// An inclusive range of addresses of a single family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Interval {
+    V4(u32, u32),
+    V6(u128, u128),
+}
+
+impl Interval {
+    /// Parse an address, a prefix or an `inetnum` style `first - last`
+    /// range.
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Ok(net) = s.parse::<IpNet>() {
+            return Some(match net {
+                IpNet::V4(net) => Self::V4(net.network().into(), net.broadcast().into()),
+                IpNet::V6(net) => Self::V6(net.network().into(), net.broadcast().into()),
+            });
+        }
+        if let Ok(addr) = s.parse::<IpAddr>() {
+            return Self::from_addrs(addr, addr);
+        }
+        let (first, last) = s.split_once('-')?;
+        Self::from_addrs(first.trim().parse().ok()?, last.trim().parse().ok()?)
+    }
+
+    fn from_addrs(first: IpAddr, last: IpAddr) -> Option<Self> {
+        match (first, last) {
+            (IpAddr::V4(a), IpAddr::V4(b)) if a <= b => Some(Self::V4(a.into(), b.into())),
+            (IpAddr::V6(a), IpAddr::V6(b)) if a <= b => Some(Self::V6(a.into(), b.into())),
+            _ => None,
+        }
+    }
+
+    fn contains(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::V4(a, b), Self::V4(c, d)) => a <= c && d <= b,
+            (Self::V6(a, b), Self::V6(c, d)) => a <= c && d <= b,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Open,
+    Close,
+    Word(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            let kind = if c == '(' {
+                TokenKind::Open
+            } else {
+                TokenKind::Close
+            };
+            tokens.push(Token {
+                kind,
+                span: start..start + 1,
+            });
+            continue;
+        }
+
+        // Parentheses end a word, except in the value of a comparison
+        // where they may be part of a regular expression.
+        let mut word = String::new();
+        let mut in_value = false;
+        let mut depth = 0usize;
+        let mut end = start;
+        while let Some(&(i, c)) = chars.peek() {
+            match c {
+                c if c.is_whitespace() => break,
+                '(' if !in_value => break,
+                ')' if depth == 0 => break,
+                '"' => {
+                    chars.next();
+                    let mut closed = false;
+                    for (i, c) in chars.by_ref() {
+                        if c == '"' {
+                            closed = true;
+                            end = i + 1;
+                            break;
+                        }
+                        word.push(c);
+                    }
+                    if !closed {
+                        return Err(FilterError {
+                            span: i..input.len(),
+                            message: "unterminated string".to_string(),
+                        });
+                    }
+                    continue;
+                }
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                '=' | '~' => in_value = true,
+                _ => {}
+            }
+            word.push(c);
+            end = i + c.len_utf8();
+            chars.next();
+        }
+        tokens.push(Token {
+            kind: TokenKind::Word(word),
+            span: start..end,
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Word(w), .. }) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn next(&mut self) -> Result<Token, FilterError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or(FilterError {
+            span: self.len..self.len,
+            message: "unexpected end of filter".to_string(),
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn next_word(&mut self) -> Result<(String, Range<usize>), FilterError> {
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Word(w) => Ok((w, token.span)),
+            _ => Err(FilterError {
+                span: token.span,
+                message: "expected a value".to_string(),
+            }),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            exprs.push(self.parse_and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FilterError> {
+        let mut exprs = Vec::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some(Token {
+                    kind: TokenKind::Close,
+                    ..
+                }) => break,
+                _ if self.peek_keyword("or") => break,
+                _ if self.peek_keyword("and") => self.pos += 1,
+                _ => exprs.push(self.parse_unary()?),
+            }
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            Expr::And(exprs)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FilterError> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        let token = self.next()?;
+        match token.kind {
+            TokenKind::Open => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Ok(Token {
+                        kind: TokenKind::Close,
+                        ..
+                    }) => Ok(expr),
+                    _ => Err(FilterError {
+                        span: token.span,
+                        message: "unclosed parenthesis".to_string(),
+                    }),
+                }
+            }
+            TokenKind::Close => Err(FilterError {
+                span: token.span,
+                message: "unexpected ')'".to_string(),
+            }),
+            TokenKind::Word(word) => self.parse_condition(word, token.span),
+        }
+    }
+
+    fn parse_condition(&mut self, word: String, span: Range<usize>) -> Result<Expr, FilterError> {
+        let key_len = word
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(word.len());
+        if key_len == 0 {
+            return Err(FilterError {
+                span,
+                message: "expected an attribute name".to_string(),
+            });
+        }
+        let key = word[..key_len].to_ascii_lowercase().replace('_', "-");
+        let rest = &word[key_len..];
+
+        let (negate, op) = if rest.is_empty() {
+            self.parse_keyword_op()?
+        } else if let Some(value) = rest.strip_prefix("!=") {
+            (true, Op::Eq(value.to_string()))
+        } else if let Some(value) = rest.strip_prefix("!~") {
+            (true, Op::Regex(regex(value, &span)?))
+        } else if let Some(value) = rest.strip_prefix('=') {
+            (false, Op::Eq(value.to_string()))
+        } else if let Some(value) = rest.strip_prefix('~') {
+            (false, Op::Regex(regex(value, &span)?))
+        } else {
+            return Err(FilterError {
+                span: span.start + key_len..span.end,
+                message: "expected an operator".to_string(),
+            });
+        };
+
+        let cond = Expr::Condition { key, op };
+        Ok(if negate {
+            Expr::Not(Box::new(cond))
+        } else {
+            cond
+        })
+    }
+
+    /// Parse the operator of a condition that is separated from the key by
+    /// whitespace.
+    fn parse_keyword_op(&mut self) -> Result<(bool, Op), FilterError> {
+        if self.peek_keyword("in") {
+            self.pos += 1;
+            let open = self.next()?;
+            if open.kind != TokenKind::Open {
+                return Err(FilterError {
+                    span: open.span,
+                    message: "expected '(' after 'in'".to_string(),
+                });
+            }
+            let mut values = Vec::new();
+            loop {
+                let token = self.next()?;
+                match token.kind {
+                    TokenKind::Close => break,
+                    TokenKind::Word(w) => values.extend(items(&w).map(str::to_string)),
+                    TokenKind::Open => {
+                        return Err(FilterError {
+                            span: token.span,
+                            message: "unexpected '('".to_string(),
+                        })
+                    }
+                }
+            }
+            return Ok((false, Op::In(values)));
+        }
+        for keyword in ["within", "contains"] {
+            if self.peek_keyword(keyword) {
+                self.pos += 1;
+                let (value, value_span) = self.next_word()?;
+                let interval = Interval::parse(&value).ok_or(FilterError {
+                    span: value_span,
+                    message: format!("invalid address, prefix or range '{}'", value),
+                })?;
+                let op = if keyword == "within" {
+                    Op::Within(interval)
+                } else {
+                    Op::Contains(interval)
+                };
+                return Ok((false, op));
+            }
+        }
+        Ok((false, Op::Exists))
+    }
+}
+
+fn regex(pattern: &str, span: &Range<usize>) -> Result<Regex, FilterError> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| FilterError {
+            span: span.clone(),
+            message: format!("invalid regular expression: {}", e),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_objects;
+
+    const DATA: &str = "\
+inetnum: 10.1.0.0 - 10.1.255.255
+netname: NET-A
+country: DE
+mnt-by: RIPE-NCC-HM-MNT
+mnt-by: EXAMPLE-MNT
+
+inetnum: 192.0.2.0 - 192.0.2.255
+netname: NET-B
+country: FR
+mnt-by: EXAMPLE-MNT
+
+route: 10.1.0.0/16
+origin: AS3333
+mnt-by: RIPE-NCC-MNT
+
+route6: 2001:db8::/32
+origin: AS3333
+
+as-set: AS-EXAMPLE
+members: AS1, AS3333
+";
+
+    fn matching(filter: &str) -> Vec<String> {
+        let filter = Filter::parse(filter).unwrap();
+        parse_objects(DATA)
+            .unwrap()
+            .into_iter()
+            .filter(|o| filter.matches(o))
+            .map(|o| o.ordered_attributes()[0].value().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!(matching("type=route origin=AS3333"), ["10.1.0.0/16"]);
+        assert_eq!(matching("origin=as3333"), ["10.1.0.0/16", "2001:db8::/32"]);
+        assert_eq!(
+            matching("mnt-by~^RIPE-"),
+            ["10.1.0.0 - 10.1.255.255", "10.1.0.0/16"]
+        );
+        assert_eq!(
+            matching("mnt_by!~^ripe- type=inetnum"),
+            ["192.0.2.0 - 192.0.2.255"]
+        );
+        assert_eq!(matching("country in (DE,AT)"), ["10.1.0.0 - 10.1.255.255"]);
+        assert_eq!(
+            matching("inetnum within 10.0.0.0/8"),
+            ["10.1.0.0 - 10.1.255.255"]
+        );
+        assert_eq!(matching("route6 contains 2001:db8::1"), ["2001:db8::/32"]);
+        assert_eq!(matching("members=AS3333"), ["AS-EXAMPLE"]);
+        assert_eq!(
+            matching("country"),
+            ["10.1.0.0 - 10.1.255.255", "192.0.2.0 - 192.0.2.255"]
+        );
+        assert_eq!(matching("").len(), 5);
+    }
+
+    #[test]
+    fn combinators() {
+        assert_eq!(
+            matching("type=route6 or (type=inetnum and not country=DE)"),
+            ["192.0.2.0 - 192.0.2.255", "2001:db8::/32"]
+        );
+        assert_eq!(matching("netname~^NET-(A|C)$"), ["10.1.0.0 - 10.1.255.255"]);
+        assert_eq!(matching(r#"netname="net-b""#), ["192.0.2.0 - 192.0.2.255"]);
+    }
+
+    #[test]
+    fn typed_fields() {
+        let filter = Filter::parse(r#"inetnum="10.1.0.0 - 10.1.255.255""#).unwrap();
+        let obj = parse_objects("inetnum: 10.1.0.0-10.1.255.255\nnetname: NET-A\n")
+            .unwrap()
+            .remove(0);
+        assert!(!filter.matches(&obj));
+        assert!(filter.needs_typed());
+        let typed = RpslObject::try_from(obj.clone()).unwrap();
+        assert!(filter.matches_typed(&obj, &typed));
+        // Other attributes are compared as written.
+        let filter = Filter::parse("netname=NET-A").unwrap();
+        assert!(!filter.needs_typed());
+        assert!(filter.matches_typed(&obj, &typed));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Filter::parse("(type=route").unwrap_err().span, 0..1);
+        assert_eq!(Filter::parse("type=route )").unwrap_err().span, 11..12);
+        assert_eq!(Filter::parse("=route").unwrap_err().span, 0..6);
+        assert_eq!(
+            Filter::parse("inetnum within foo").unwrap_err().span,
+            15..18
+        );
+        assert!(Filter::parse("netname~(").is_err());
+        assert!(Filter::parse("country in DE").is_err());
+    }
+}
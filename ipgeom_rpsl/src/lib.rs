@@ -1,14 +1,26 @@
+pub mod expand;
+pub mod filter;
+pub mod nrtm;
 mod object;
 mod parser;
+pub mod policy;
+mod status;
 mod typed;
+pub mod validate;
 
+#[cfg(feature = "tokio")]
+pub use self::parser::{parse_objects_async_read, parse_objects_stream, ObjectsStream};
 pub use self::{
-    object::{Object, ObjectType},
+    object::{Attribute, Object, ObjectType},
     parser::{
-        parse_objects, parse_objects_iter, parse_objects_read_iter, MalformedLineError,
-        ObjectsIter, ObjectsReadIter, ParseError,
+        parse_objects, parse_objects_bufread_iter, parse_objects_iter, parse_objects_parallel,
+        parse_objects_read_iter, Decoding, MalformedLineError, MalformedObject, ObjectsIter,
+        ObjectsReadIter, ParallelIter, ParallelParser, ParseError,
     },
+    status::InetStatus,
     typed::{
-        AutNum, Inet6num, Inetnum, Mntner, Organisation, Person, Role, Route, Route6, RpslObject,
+        AsBlock, AsSet, AutNum, CommonAttributes, ConversionWarning, Domain, FilterSet, Inet6num,
+        InetRtr, Inetnum, Irt, KeyCert, Mntner, Organisation, PeeringSet, Person, Role, Route,
+        Route6, RouteSet, RpslObject, RtrSet,
     },
 };
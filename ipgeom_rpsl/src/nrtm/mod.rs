@@ -0,0 +1,101 @@
+//! Near Real Time Mirroring (NRTM) of RPSL databases.
+//!
+//! NRTMv3 streams describe the changes made to a database since a given
+//! serial as a sequence of `ADD` and `DEL` operations. NRTMv4 publishes a
+//! signed notification file referencing a snapshot and delta files. This
+//! module only deals with the formats; transport and signature verification
+//! are left to the caller.
+
+mod v3;
+mod v4;
+
+pub use self::{
+    v3::{v3_query, NrtmV3Header, NrtmV3Reader},
+    v4::{
+        NrtmV4Change, NrtmV4FileHeader, NrtmV4FileReader, NrtmV4FileRef, NrtmV4FileType,
+        NrtmV4Notification,
+    },
+};
+
+use crate::{Object, ParseError};
+
+/// Kind of change described by an [`NrtmOperation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NrtmOperationKind {
+    /// The object was created or updated.
+    Add,
+    /// The object was deleted.
+    Del,
+}
+
+impl NrtmOperationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NrtmOperationKind::Add => "ADD",
+            NrtmOperationKind::Del => "DEL",
+        }
+    }
+}
+
+impl std::fmt::Display for NrtmOperationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single change from an NRTM stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NrtmOperation {
+    pub serial: u64,
+    pub kind: NrtmOperationKind,
+    pub object: Object,
+}
+
+#[derive(Debug)]
+pub enum NrtmError {
+    /// The server answered with an `%ERROR` line.
+    Server(String),
+    /// The stream did not follow the NRTM format.
+    Protocol {
+        line: usize,
+        message: String,
+    },
+    /// An NRTMv4 file did not follow the expected format. `record` is the
+    /// 1-based index of the offending JSON record.
+    Format {
+        record: usize,
+        message: String,
+    },
+    /// An object in the stream could not be parsed.
+    Parse(ParseError),
+    Json(serde_json::Error),
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for NrtmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NrtmError::Server(msg) => write!(f, "NRTM server error: {}", msg),
+            NrtmError::Protocol { line, message } => {
+                write!(f, "Invalid NRTM stream at line {}: {}", line, message)
+            }
+            NrtmError::Format { record, message } => {
+                write!(f, "Invalid NRTM file at record {}: {}", record, message)
+            }
+            NrtmError::Parse(e) => write!(f, "Invalid object in NRTM stream: {}", e),
+            NrtmError::Json(e) => write!(f, "Invalid JSON in NRTM file: {}", e),
+            NrtmError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NrtmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NrtmError::Parse(e) => Some(e),
+            NrtmError::Json(e) => Some(e),
+            NrtmError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
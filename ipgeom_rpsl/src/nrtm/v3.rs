@@ -0,0 +1,339 @@
+use std::io::BufRead;
+
+use super::{NrtmError, NrtmOperation, NrtmOperationKind};
+use crate::parser::ObjectBuilder;
+use crate::Decoding;
+
+/// Build the query requesting the changes of `source` from serial `first` up
+/// to `last`, or up to the most recent serial if `last` is `None`.
+pub fn v3_query(source: &str, first: u64, last: Option<u64>) -> String {
+    match last {
+        Some(last) => format!("-g {}:3:{}-{}\n", source, first, last),
+        None => format!("-g {}:3:{}-LAST\n", source, first),
+    }
+}
+
+/// The `%START` line of an NRTMv3 stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NrtmV3Header {
+    pub version: u32,
+    pub source: String,
+    pub first_serial: u64,
+    pub last_serial: u64,
+}
+
+impl NrtmV3Header {
+    /// Parse a line like `%START Version: 3 RIPE 100-200`.
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix("%START")?.trim_start();
+        let rest = rest.strip_prefix("Version:")?;
+        let mut parts = rest.split_whitespace();
+        let version = parts.next()?.parse().ok()?;
+        let source = parts.next()?.to_string();
+        let (first, last) = parts.next()?.split_once('-')?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            version,
+            source,
+            first_serial: first.parse().ok()?,
+            last_serial: last.parse().ok()?,
+        })
+    }
+}
+
+/// Reader for the response to an NRTMv3 query.
+///
+/// Yields the operations of the stream in order. Comment lines before
+/// `%START` are skipped; an `%ERROR` line is reported as
+/// [`NrtmError::Server`]. A response without `%START` that only contains
+/// comments, which servers send when there are no new changes, yields no
+/// operations.
+pub struct NrtmV3Reader<R: BufRead> {
+    reader: R,
+    line_buf: Vec<u8>,
+    line_number: usize,
+    decoding: Decoding,
+    builder: ObjectBuilder,
+    header: Option<NrtmV3Header>,
+    /// Operation whose object is currently being read.
+    pending: Option<(NrtmOperationKind, u64)>,
+    ended: bool,
+    done: bool,
+}
+
+impl<R: BufRead> NrtmV3Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line_buf: Vec::new(),
+            line_number: 0,
+            decoding: Decoding::default(),
+            builder: ObjectBuilder::new(),
+            header: None,
+            pending: None,
+            ended: false,
+            done: false,
+        }
+    }
+
+    /// Set the policy used to decode the input bytes.
+    pub fn with_decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    /// The `%START` header, once it was read.
+    pub fn header(&self) -> Option<&NrtmV3Header> {
+        self.header.as_ref()
+    }
+
+    /// Whether the `%END` line was read.
+    pub fn is_complete(&self) -> bool {
+        self.ended
+    }
+
+    fn protocol_error(&self, message: impl Into<String>) -> NrtmError {
+        NrtmError::Protocol {
+            line: self.line_number,
+            message: message.into(),
+        }
+    }
+
+    /// Handle a line outside of an object.
+    fn control_line(&mut self, line: &str) -> Result<(), NrtmError> {
+        if line.is_empty() {
+            return Ok(());
+        }
+        if let Some(msg) = line.strip_prefix("%ERROR") {
+            let msg = msg.trim_start_matches(':').trim();
+            return Err(NrtmError::Server(msg.to_string()));
+        }
+        if line.starts_with("%START") {
+            if self.header.is_some() {
+                return Err(self.protocol_error("duplicate %START"));
+            }
+            let header = NrtmV3Header::parse(line)
+                .ok_or_else(|| self.protocol_error(format!("invalid header '{}'", line)))?;
+            self.header = Some(header);
+            return Ok(());
+        }
+        if line.starts_with("%END") {
+            if self.header.is_none() {
+                return Err(self.protocol_error("%END without %START"));
+            }
+            self.ended = true;
+            return Ok(());
+        }
+        if line.starts_with('%') || line.starts_with('#') {
+            return Ok(());
+        }
+
+        let Some(header) = &self.header else {
+            return Err(self.protocol_error(format!("unexpected line before %START: '{}'", line)));
+        };
+        let (kind, serial) = line.split_once(' ').unwrap_or((line, ""));
+        let kind = match kind {
+            "ADD" => NrtmOperationKind::Add,
+            "DEL" => NrtmOperationKind::Del,
+            _ => return Err(self.protocol_error(format!("expected ADD or DEL, got '{}'", line))),
+        };
+        let serial: u64 = serial
+            .trim()
+            .parse()
+            .map_err(|_| self.protocol_error(format!("invalid serial in '{}'", line)))?;
+        if serial < header.first_serial || serial > header.last_serial {
+            return Err(self.protocol_error(format!(
+                "serial {} outside of announced range {}-{}",
+                serial, header.first_serial, header.last_serial
+            )));
+        }
+        self.pending = Some((kind, serial));
+        Ok(())
+    }
+
+    fn finish_pending(&mut self) -> Option<NrtmOperation> {
+        let object = self.builder.finish()?;
+        let (kind, serial) = self.pending.take()?;
+        Some(NrtmOperation {
+            serial,
+            kind,
+            object,
+        })
+    }
+
+    fn read_line(&mut self) -> Result<bool, std::io::Error> {
+        self.line_buf.clear();
+        loop {
+            match self.reader.read_until(b'\n', &mut self.line_buf) {
+                Ok(n) => return Ok(n > 0),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn next_operation(&mut self) -> Result<Option<NrtmOperation>, NrtmError> {
+        loop {
+            if !self.read_line().map_err(NrtmError::Io)? {
+                self.done = true;
+                if self.pending.is_some() {
+                    return Err(self.protocol_error("stream ended inside an operation"));
+                }
+                if self.header.is_some() && !self.ended {
+                    return Err(self.protocol_error("stream ended without %END"));
+                }
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let line = self.decoding.decode(&self.line_buf).into_owned();
+
+            if self.pending.is_none() {
+                if self.ended && !line.trim().is_empty() {
+                    return Err(self.protocol_error("data after %END"));
+                }
+                self.control_line(line.trim())?;
+                continue;
+            }
+
+            let trimmed = line.trim();
+            if !self.builder.is_started() {
+                if trimmed.is_empty() {
+                    // Blank line between the operation and its object.
+                    continue;
+                }
+                if trimmed.starts_with('%') {
+                    return Err(self.protocol_error("operation without object"));
+                }
+            }
+            // Objects end at a blank line, but some servers put `%END` right
+            // after the last object.
+            if trimmed.is_empty() || trimmed.starts_with("%END") {
+                let op = self.finish_pending();
+                self.control_line(trimmed)?;
+                return Ok(op);
+            }
+            self.builder
+                .push_line(&line, self.line_number)
+                .map_err(NrtmError::Parse)?;
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for NrtmV3Reader<R> {
+    type Item = Result<NrtmOperation, NrtmError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.next_operation();
+        if res.is_err() {
+            self.done = true;
+        }
+        res.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM: &str = "\
+% The TEST database is subject to terms and conditions.
+
+%START Version: 3 TEST 101-103
+
+ADD 101
+
+person: John Doe
+nic-hdl: JD1-TEST
+source: TEST
+
+DEL 102
+
+inetnum: 192.0.2.0 - 192.0.2.255
+netname: TEST-NET
+source: TEST
+
+ADD 103
+
+aut-num: AS64500
+as-name: EXAMPLE
+source: TEST
+%END TEST
+";
+
+    #[test]
+    fn reads_operations() {
+        let mut reader = NrtmV3Reader::new(STREAM.as_bytes());
+        let ops: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            reader.header(),
+            Some(&NrtmV3Header {
+                version: 3,
+                source: "TEST".into(),
+                first_serial: 101,
+                last_serial: 103,
+            })
+        );
+        assert!(reader.is_complete());
+
+        let summary: Vec<_> = ops
+            .iter()
+            .map(|op| {
+                (
+                    op.serial,
+                    op.kind,
+                    op.object.obj_type().as_key().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (101, NrtmOperationKind::Add, "person".to_string()),
+                (102, NrtmOperationKind::Del, "inetnum".to_string()),
+                (103, NrtmOperationKind::Add, "aut-num".to_string()),
+            ]
+        );
+        assert_eq!(
+            ops[1].object.get("netname").collect::<Vec<_>>(),
+            ["TEST-NET"]
+        );
+    }
+
+    #[test]
+    fn no_new_changes() {
+        let text = "% Warning: there are no newer updates available\n";
+        let mut reader = NrtmV3Reader::new(text.as_bytes());
+        assert!(reader.next().is_none());
+        assert!(reader.header().is_none());
+    }
+
+    #[test]
+    fn server_error() {
+        let text = "%ERROR:401: invalid range: Not within 1-100\n";
+        let err = NrtmV3Reader::new(text.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(
+            matches!(err, NrtmError::Server(msg) if msg == "401: invalid range: Not within 1-100")
+        );
+    }
+
+    #[test]
+    fn truncated_stream() {
+        let text = "%START Version: 3 TEST 1-2\n\nADD 1\n\nperson: A\n\nADD 2\n\nperson: B\n";
+        let res: Result<Vec<_>, _> = NrtmV3Reader::new(text.as_bytes()).collect();
+        assert!(matches!(res, Err(NrtmError::Protocol { line: 9, .. })));
+    }
+
+    #[test]
+    fn query_format() {
+        assert_eq!(v3_query("RIPE", 5, None), "-g RIPE:3:5-LAST\n");
+        assert_eq!(v3_query("RIPE", 5, Some(9)), "-g RIPE:3:5-9\n");
+    }
+}
@@ -0,0 +1,330 @@
+use std::io::BufRead;
+
+use serde::Deserialize;
+
+use super::NrtmError;
+use crate::{parse_objects, Object};
+
+/// Record separator that starts every record of a JSON text sequence
+/// (RFC 7464).
+const RS: u8 = 0x1e;
+
+/// Payload of an NRTMv4 update notification file.
+///
+/// The notification file itself is a JWS; verifying its signature and
+/// extracting the payload is up to the caller.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NrtmV4Notification {
+    pub nrtm_version: u32,
+    pub timestamp: String,
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub source: String,
+    pub session_id: String,
+    pub version: u64,
+    pub snapshot: NrtmV4FileRef,
+    #[serde(default)]
+    pub deltas: Vec<NrtmV4FileRef>,
+    /// Key that will be used to sign future notification files.
+    #[serde(default)]
+    pub next_signing_key: Option<String>,
+}
+
+impl NrtmV4Notification {
+    /// Parse and validate a notification payload.
+    pub fn from_json(json: &[u8]) -> Result<Self, NrtmError> {
+        let notification: Self = serde_json::from_slice(json).map_err(NrtmError::Json)?;
+        check_version(notification.nrtm_version)?;
+        if notification.file_type != "notification" {
+            return Err(NrtmError::Format {
+                record: 1,
+                message: format!(
+                    "expected notification file, got '{}'",
+                    notification.file_type
+                ),
+            });
+        }
+        Ok(notification)
+    }
+}
+
+/// Reference to a snapshot or delta file in a notification file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NrtmV4FileRef {
+    pub version: u64,
+    pub url: String,
+    /// Hex encoded SHA-256 digest of the file as served.
+    pub hash: String,
+}
+
+/// Kind of an NRTMv4 snapshot or delta file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NrtmV4FileType {
+    Snapshot,
+    Delta,
+}
+
+/// The first record of a snapshot or delta file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NrtmV4FileHeader {
+    pub nrtm_version: u32,
+    #[serde(rename = "type")]
+    pub file_type: NrtmV4FileType,
+    pub source: String,
+    pub session_id: String,
+    pub version: u64,
+}
+
+/// A change from a snapshot or delta file.
+///
+/// Snapshot files only contain [`NrtmV4Change::AddModify`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum NrtmV4Change {
+    AddModify(Object),
+    Delete {
+        object_class: String,
+        primary_key: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum DeltaRecord {
+    AddModify {
+        object: String,
+    },
+    Delete {
+        object_class: String,
+        primary_key: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct SnapshotRecord {
+    object: String,
+}
+
+/// Reader for NRTMv4 snapshot and delta files.
+///
+/// The files are JSON text sequences. The header is read when the reader
+/// is created; iterating yields the changes.
+pub struct NrtmV4FileReader<R: BufRead> {
+    reader: R,
+    header: NrtmV4FileHeader,
+    buf: Vec<u8>,
+    record: usize,
+    done: bool,
+}
+
+impl<R: BufRead> NrtmV4FileReader<R> {
+    /// Read the header of a (decompressed) snapshot or delta file.
+    pub fn new(mut reader: R) -> Result<Self, NrtmError> {
+        let mut buf = Vec::new();
+        let header = match next_record(&mut reader, &mut buf)? {
+            Some(json) => {
+                serde_json::from_slice::<NrtmV4FileHeader>(json).map_err(|e| NrtmError::Format {
+                    record: 1,
+                    message: format!("invalid file header: {}", e),
+                })?
+            }
+            None => {
+                return Err(NrtmError::Format {
+                    record: 1,
+                    message: "empty file".to_string(),
+                })
+            }
+        };
+        check_version(header.nrtm_version)?;
+        Ok(Self {
+            reader,
+            header,
+            buf,
+            record: 1,
+            done: false,
+        })
+    }
+
+    pub fn header(&self) -> &NrtmV4FileHeader {
+        &self.header
+    }
+
+    fn format_error(&self, message: impl Into<String>) -> NrtmError {
+        NrtmError::Format {
+            record: self.record,
+            message: message.into(),
+        }
+    }
+
+    fn parse_object(&self, text: &str) -> Result<Object, NrtmError> {
+        let mut objects = parse_objects(text).map_err(NrtmError::Parse)?;
+        if objects.len() != 1 {
+            return Err(
+                self.format_error(format!("expected a single object, found {}", objects.len()))
+            );
+        }
+        Ok(objects.remove(0))
+    }
+
+    fn next_change(&mut self) -> Result<Option<NrtmV4Change>, NrtmError> {
+        let Some(json) = next_record(&mut self.reader, &mut self.buf)? else {
+            return Ok(None);
+        };
+        self.record += 1;
+        let change = match self.header.file_type {
+            NrtmV4FileType::Snapshot => {
+                let record: SnapshotRecord =
+                    serde_json::from_slice(json).map_err(|e| self.format_error(e.to_string()))?;
+                NrtmV4Change::AddModify(self.parse_object(&record.object)?)
+            }
+            NrtmV4FileType::Delta => {
+                let record: DeltaRecord =
+                    serde_json::from_slice(json).map_err(|e| self.format_error(e.to_string()))?;
+                match record {
+                    DeltaRecord::AddModify { object } => {
+                        NrtmV4Change::AddModify(self.parse_object(&object)?)
+                    }
+                    DeltaRecord::Delete {
+                        object_class,
+                        primary_key,
+                    } => NrtmV4Change::Delete {
+                        object_class,
+                        primary_key,
+                    },
+                }
+            }
+        };
+        Ok(Some(change))
+    }
+}
+
+impl<R: BufRead> Iterator for NrtmV4FileReader<R> {
+    type Item = Result<NrtmV4Change, NrtmError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.next_change();
+        if !matches!(res, Ok(Some(_))) {
+            self.done = true;
+        }
+        res.transpose()
+    }
+}
+
+fn check_version(version: u32) -> Result<(), NrtmError> {
+    if version != 4 {
+        return Err(NrtmError::Format {
+            record: 1,
+            message: format!("unsupported NRTM version {}", version),
+        });
+    }
+    Ok(())
+}
+
+/// Read the next non-empty record of a JSON text sequence into `buf`.
+fn next_record<'a, R: BufRead>(
+    reader: &mut R,
+    buf: &'a mut Vec<u8>,
+) -> Result<Option<&'a [u8]>, NrtmError> {
+    loop {
+        buf.clear();
+        let n = loop {
+            match reader.read_until(RS, buf) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(NrtmError::Io(e)),
+            }
+        };
+        if n == 0 {
+            return Ok(None);
+        }
+        if buf.last() == Some(&RS) {
+            buf.pop();
+        }
+        if buf.iter().any(|b| !b.is_ascii_whitespace()) {
+            return Ok(Some(buf.trim_ascii()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seq(records: &[&str]) -> String {
+        records.iter().map(|r| format!("\x1e{}\n", r)).collect()
+    }
+
+    #[test]
+    fn parse_notification() {
+        let json = r#"{
+            "nrtm_version": 4,
+            "timestamp": "2022-01-05T15:00:00Z",
+            "type": "notification",
+            "source": "EXAMPLE",
+            "session_id": "ca128382-78d9-41d1-8927-1ecef15275be",
+            "version": 4,
+            "snapshot": {"version": 3, "url": "snapshot.3.json.gz", "hash": "ab"},
+            "deltas": [{"version": 4, "url": "delta.4.json", "hash": "cd"}]
+        }"#;
+        let n = NrtmV4Notification::from_json(json.as_bytes()).unwrap();
+        assert_eq!(n.source, "EXAMPLE");
+        assert_eq!(n.version, 4);
+        assert_eq!(n.snapshot.version, 3);
+        assert_eq!(n.deltas[0].url, "delta.4.json");
+        assert_eq!(n.next_signing_key, None);
+
+        let err = NrtmV4Notification::from_json(&json.replace("4,", "3,").into_bytes());
+        assert!(matches!(err, Err(NrtmError::Format { .. })));
+    }
+
+    #[test]
+    fn read_snapshot() {
+        let text = seq(&[
+            r#"{"nrtm_version": 4, "type": "snapshot", "source": "EXAMPLE", "session_id": "s", "version": 3}"#,
+            r#"{"object": "route: 192.0.2.0/24\norigin: AS65530\nsource: EXAMPLE\n"}"#,
+            r#"{"object": "mntner: EXAMPLE-MNT\nsource: EXAMPLE\n"}"#,
+        ]);
+        let mut reader = NrtmV4FileReader::new(text.as_bytes()).unwrap();
+        assert_eq!(reader.header().file_type, NrtmV4FileType::Snapshot);
+        assert_eq!(reader.header().version, 3);
+        let changes: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(changes.len(), 2);
+        let NrtmV4Change::AddModify(obj) = &changes[0] else {
+            panic!("expected object");
+        };
+        assert_eq!(obj.get("origin").collect::<Vec<_>>(), ["AS65530"]);
+    }
+
+    #[test]
+    fn read_delta() {
+        let text = seq(&[
+            r#"{"nrtm_version": 4, "type": "delta", "source": "EXAMPLE", "session_id": "s", "version": 4}"#,
+            r#"{"action": "delete", "object_class": "person", "primary_key": "PRSN1-EXAMPLE"}"#,
+            r#"{"action": "add_modify", "object": "aut-num: AS65530\nsource: EXAMPLE\n"}"#,
+        ]);
+        let changes: Vec<_> = NrtmV4FileReader::new(text.as_bytes())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            changes[0],
+            NrtmV4Change::Delete {
+                object_class: "person".into(),
+                primary_key: "PRSN1-EXAMPLE".into(),
+            }
+        );
+        assert!(
+            matches!(&changes[1], NrtmV4Change::AddModify(obj) if obj.get_first("aut-num") == Some("AS65530"))
+        );
+
+        let bad = seq(&[
+            r#"{"nrtm_version": 4, "type": "delta", "source": "EXAMPLE", "session_id": "s", "version": 4}"#,
+            r#"{"action": "rename"}"#,
+        ]);
+        let res: Result<Vec<_>, _> = NrtmV4FileReader::new(bad.as_bytes()).unwrap().collect();
+        assert!(matches!(res, Err(NrtmError::Format { record: 2, .. })));
+    }
+}
@@ -1,8 +1,8 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Known RPSL object types.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ObjectType {
     Inetnum,
     Inet6num,
@@ -13,6 +13,16 @@ pub enum ObjectType {
     Mntner,
     Route,
     Route6,
+    AsSet,
+    RouteSet,
+    RtrSet,
+    Domain,
+    InetRtr,
+    Irt,
+    KeyCert,
+    AsBlock,
+    PeeringSet,
+    FilterSet,
     Other(String),
 }
 
@@ -28,16 +38,158 @@ impl ObjectType {
             "mntner" => ObjectType::Mntner,
             "route" => ObjectType::Route,
             "route6" => ObjectType::Route6,
+            "as-set" => ObjectType::AsSet,
+            "route-set" => ObjectType::RouteSet,
+            "rtr-set" => ObjectType::RtrSet,
+            "domain" => ObjectType::Domain,
+            "inet-rtr" => ObjectType::InetRtr,
+            "irt" => ObjectType::Irt,
+            "key-cert" => ObjectType::KeyCert,
+            "as-block" => ObjectType::AsBlock,
+            "peering-set" => ObjectType::PeeringSet,
+            "filter-set" => ObjectType::FilterSet,
             other => ObjectType::Other(other.to_string()),
         }
     }
+
+    /// The attribute key that introduces objects of this type.
+    pub fn as_key(&self) -> &str {
+        match self {
+            ObjectType::Inetnum => "inetnum",
+            ObjectType::Inet6num => "inet6num",
+            ObjectType::AutNum => "aut-num",
+            ObjectType::Person => "person",
+            ObjectType::Role => "role",
+            ObjectType::Organisation => "organisation",
+            ObjectType::Mntner => "mntner",
+            ObjectType::Route => "route",
+            ObjectType::Route6 => "route6",
+            ObjectType::AsSet => "as-set",
+            ObjectType::RouteSet => "route-set",
+            ObjectType::RtrSet => "rtr-set",
+            ObjectType::Domain => "domain",
+            ObjectType::InetRtr => "inet-rtr",
+            ObjectType::Irt => "irt",
+            ObjectType::KeyCert => "key-cert",
+            ObjectType::AsBlock => "as-block",
+            ObjectType::PeeringSet => "peering-set",
+            ObjectType::FilterSet => "filter-set",
+            ObjectType::Other(name) => name,
+        }
+    }
+}
+
+/// A single attribute of an RPSL object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribute {
+    key: String,
+    value: String,
+    /// Line number of the attribute in the parsed source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    /// Original text of the attribute, including continuation lines and line
+    /// endings, exactly as it appeared in the source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw: Option<String>,
+}
+
+impl Attribute {
+    /// Create a new attribute that was not parsed from a source.
+    pub fn new(key: String, value: String) -> Self {
+        Attribute {
+            key,
+            value,
+            line: None,
+            raw: None,
+        }
+    }
+
+    /// Create an attribute that remembers its source location and layout.
+    pub fn with_source(key: String, value: String, line: usize, raw: String) -> Self {
+        Attribute {
+            key,
+            value,
+            line: Some(line),
+            raw: Some(raw),
+        }
+    }
+
+    /// The lowercased attribute key.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The attribute value with continuation lines joined.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Line number in the source this attribute was parsed from.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Original source text of the attribute, if known.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    /// Replace the value.
+    ///
+    /// This discards the original source layout, since it no longer matches.
+    pub fn set_value(&mut self, value: String) {
+        self.value = value;
+        self.raw = None;
+    }
+
+    /// Append a continuation line to the attribute.
+    ///
+    /// The joined value separates lines with a single space, while the raw
+    /// source text keeps the original layout.
+    pub(crate) fn append_continuation(&mut self, trimmed: &str, raw_line: &str) {
+        if !self.value.is_empty() && !self.value.ends_with(' ') && !trimmed.is_empty() {
+            self.value.push(' ');
+        }
+        self.value.push_str(trimmed);
+        if let Some(raw) = &mut self.raw {
+            raw.push_str(raw_line);
+        }
+    }
+
+    /// Append the RPSL representation of this attribute to `out`.
+    fn write_rpsl(&self, out: &mut String) {
+        if let Some(raw) = &self.raw {
+            out.push_str(raw);
+            if !raw.ends_with('\n') {
+                out.push('\n');
+            }
+            return;
+        }
+
+        if self.value.contains('\n') {
+            for line in self.value.lines() {
+                out.push_str(&self.key);
+                out.push_str(": ");
+                out.push_str(line);
+                out.push('\n');
+            }
+        } else {
+            out.push_str(&self.key);
+            out.push_str(": ");
+            out.push_str(&self.value);
+            out.push('\n');
+        }
+    }
 }
 
 /// RPSL object
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+///
+/// Attributes are kept in their original order, including repeated keys.
+/// The first attribute is the type line of the object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Object {
     obj_type: ObjectType,
-    attributes: HashMap<String, Vec<String>>,
+    attributes: Vec<Attribute>,
 }
 
 impl Object {
@@ -45,13 +197,18 @@ impl Object {
     pub fn new(obj_type: ObjectType) -> Self {
         Object {
             obj_type,
-            attributes: HashMap::new(),
+            attributes: Vec::new(),
         }
     }
 
     /// Add an attribute to the object
     pub fn add(&mut self, key: String, value: String) {
-        self.attributes.entry(key).or_default().push(value);
+        self.attributes.push(Attribute::new(key, value));
+    }
+
+    /// Append an attribute to the end of the object.
+    pub fn push(&mut self, attr: Attribute) {
+        self.attributes.push(attr);
     }
 
     /// Get the type of this RPSL object
@@ -59,47 +216,84 @@ impl Object {
         &self.obj_type
     }
 
-    pub fn get(&self, key: &str) -> Option<&[String]> {
-        self.attributes.get(key).map(|v| v.as_slice())
+    /// Get all values of the given attribute, in source order.
+    pub fn get<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.attributes
+            .iter()
+            .filter(move |a| a.key == key)
+            .map(|a| a.value.as_str())
+    }
+
+    /// Get the first value of the given attribute.
+    pub fn get_first(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.key == key)
+            .map(|a| a.value.as_str())
     }
 
-    /// Get a reference to the underlying attribute map
-    pub fn attributes(&self) -> &HashMap<String, Vec<String>> {
-        &self.attributes
+    /// Iterate over the attributes as key/value pairs, in source order.
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .iter()
+            .map(|a| (a.key.as_str(), a.value.as_str()))
     }
 
-    /// Consume the object and return the underlying attribute map
+    /// Consume the object and return the attributes grouped by key.
     pub fn into_attributes(self) -> HashMap<String, Vec<String>> {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        for attr in self.attributes {
+            map.entry(attr.key).or_default().push(attr.value);
+        }
+        map
+    }
+
+    /// Create an object from an attribute map and a type.
+    ///
+    /// The type attribute is placed first, the order of the remaining keys is
+    /// unspecified.
+    pub fn from_attributes(obj_type: ObjectType, mut map: HashMap<String, Vec<String>>) -> Self {
+        let mut obj = Object::new(obj_type);
+        if let Some(values) = map.remove(obj.obj_type.as_key()) {
+            let key = obj.obj_type.as_key().to_string();
+            for value in values {
+                obj.add(key.clone(), value);
+            }
+        }
+        for (key, values) in map {
+            for value in values {
+                obj.add(key.clone(), value);
+            }
+        }
+        obj
+    }
+
+    /// Get the attributes in their original order.
+    pub fn ordered_attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// Consume the object and return the attributes in their original order.
+    pub fn into_ordered_attributes(self) -> Vec<Attribute> {
         self.attributes
     }
 
-    /// Create an object from an attribute map and a type
-    pub fn from_attributes(obj_type: ObjectType, map: HashMap<String, Vec<String>>) -> Self {
+    /// Create an object from an ordered list of attributes.
+    pub fn from_ordered_attributes(obj_type: ObjectType, attributes: Vec<Attribute>) -> Self {
         Object {
             obj_type,
-            attributes: map,
+            attributes,
         }
     }
 
-    /// Convert the object back into an RPSL formatted string
+    /// Convert the object back into an RPSL formatted string.
+    ///
+    /// Attributes that were parsed from a source are reproduced exactly as
+    /// they appeared, others are written as `key: value` lines.
     pub fn to_rpsl(&self) -> String {
         let mut out = String::new();
-        for (key, vals) in &self.attributes {
-            for val in vals {
-                if val.contains('\n') {
-                    for line in val.lines() {
-                        out.push_str(key);
-                        out.push_str(": ");
-                        out.push_str(line);
-                        out.push('\n');
-                    }
-                } else {
-                    out.push_str(key);
-                    out.push_str(": ");
-                    out.push_str(val);
-                    out.push('\n');
-                }
-            }
+        for attr in &self.attributes {
+            attr.write_rpsl(&mut out);
         }
         out
     }
@@ -116,7 +310,7 @@ mod tests {
         obj.add("descr".into(), "Line1\nLine2".into());
 
         assert_eq!(obj.obj_type(), &ObjectType::Person);
-        assert_eq!(obj.get("person").unwrap(), ["John"]);
+        assert_eq!(obj.get("person").collect::<Vec<_>>(), ["John"]);
 
         let text = obj.to_rpsl();
         let mut lines: Vec<_> = text.lines().collect();
@@ -125,6 +319,45 @@ mod tests {
 
         let map = obj.clone().into_attributes();
         let obj2 = Object::from_attributes(ObjectType::Person, map);
-        assert_eq!(obj2.get("descr").unwrap(), ["Line1\nLine2"]);
+        assert_eq!(obj2.get("descr").collect::<Vec<_>>(), ["Line1\nLine2"]);
+        assert_eq!(obj2.ordered_attributes()[0].key(), "person");
+    }
+
+    #[test]
+    fn keeps_attribute_order() {
+        let mut obj = Object::new(ObjectType::Person);
+        obj.add("person".into(), "John".into());
+        obj.add("descr".into(), "a".into());
+        obj.add("remarks".into(), "r".into());
+        obj.add("descr".into(), "b".into());
+
+        assert_eq!(
+            obj.to_rpsl(),
+            "person: John\ndescr: a\nremarks: r\ndescr: b\n"
+        );
+        assert_eq!(obj.get("descr").collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(obj.get_first("remarks"), Some("r"));
+        assert!(obj.get("missing").next().is_none());
+        assert_eq!(
+            obj.attributes().collect::<Vec<_>>(),
+            [
+                ("person", "John"),
+                ("descr", "a"),
+                ("remarks", "r"),
+                ("descr", "b")
+            ]
+        );
+    }
+
+    #[test]
+    fn set_value_drops_layout() {
+        let mut attr =
+            Attribute::with_source("descr".into(), "a b".into(), 3, "descr:   a\n   b\n".into());
+        assert_eq!(attr.line(), Some(3));
+        attr.set_value("c".into());
+        assert!(attr.raw().is_none());
+
+        let obj = Object::from_ordered_attributes(ObjectType::Other("x".into()), vec![attr]);
+        assert_eq!(obj.to_rpsl(), "descr: c\n");
     }
 }
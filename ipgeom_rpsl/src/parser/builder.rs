@@ -0,0 +1,112 @@
+use super::{MalformedLineError, ParseError};
+use crate::{Attribute, Object, ObjectType};
+
+/// Line driven state machine that assembles a single object.
+///
+/// Lines are fed one at a time, including their line ending. A finished
+/// object is returned once the terminating blank line is seen, or when
+/// [`ObjectBuilder::finish`] is called at the end of the input.
+#[derive(Debug, Default)]
+pub(crate) struct ObjectBuilder {
+    attributes: Vec<Attribute>,
+    obj_type: Option<ObjectType>,
+}
+
+impl ObjectBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether at least one attribute line of the current object was seen.
+    pub fn is_started(&self) -> bool {
+        !self.attributes.is_empty()
+    }
+
+    /// Feed the next line of input.
+    ///
+    /// `raw_line` is the full source line including the line ending, `line_no`
+    /// its 1-based line number.
+    pub fn push_line(
+        &mut self,
+        raw_line: &str,
+        line_no: usize,
+    ) -> Result<Option<Object>, ParseError> {
+        let line = raw_line.trim_end_matches(['\r', '\n']);
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            // Blank lines terminate the current object, or are skipped
+            // between objects.
+            return Ok(self.finish());
+        }
+
+        if trimmed.starts_with('#') || trimmed.starts_with('%') {
+            // comment, ignore
+            return Ok(None);
+        }
+
+        let is_continuation = line.starts_with(|c: char| c.is_whitespace());
+        if is_continuation {
+            if let Some(last) = self.attributes.last_mut() {
+                last.append_continuation(trimmed, raw_line);
+                return Ok(None);
+            }
+        }
+
+        if let Some(pos) = trimmed.find(':') {
+            let key = trimmed[..pos].trim().to_lowercase();
+            let value = trimmed[pos + 1..].trim().to_string();
+            if self.obj_type.is_none() {
+                self.obj_type = Some(ObjectType::from_key(&key));
+            }
+            self.attributes.push(Attribute::with_source(
+                key,
+                value,
+                line_no,
+                raw_line.to_string(),
+            ));
+            Ok(None)
+        } else if let Some(last) = self.attributes.last_mut() {
+            last.append_continuation(trimmed, raw_line);
+            Ok(None)
+        } else {
+            let kind = if is_continuation {
+                MalformedLineError::UnexpectedContinuation
+            } else {
+                MalformedLineError::MissingColon
+            };
+            Err(ParseError::MalformedLine {
+                line: line_no,
+                content: trimmed.to_string(),
+                kind,
+            })
+        }
+    }
+
+    /// Discard the current object and reset the builder.
+    ///
+    /// Returns the line number of the first attribute, if any, and the source
+    /// text of the attributes seen so far.
+    pub fn abort(&mut self) -> (Option<usize>, String) {
+        self.obj_type = None;
+        let start = self.attributes.first().and_then(|a| a.line());
+        let raw = std::mem::take(&mut self.attributes)
+            .iter()
+            .filter_map(|a| a.raw())
+            .collect();
+        (start, raw)
+    }
+
+    /// Finish the current object, if any, and reset the builder.
+    pub fn finish(&mut self) -> Option<Object> {
+        if self.attributes.is_empty() {
+            return None;
+        }
+        let obj_type = self
+            .obj_type
+            .take()
+            .unwrap_or_else(|| ObjectType::Other(String::new()));
+        let attributes = std::mem::take(&mut self.attributes);
+        Some(Object::from_ordered_attributes(obj_type, attributes))
+    }
+}
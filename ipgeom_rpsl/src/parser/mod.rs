@@ -1,6 +1,17 @@
+mod builder;
+mod parallel;
+mod read;
+#[cfg(feature = "tokio")]
+mod stream;
+
+pub(crate) use self::builder::ObjectBuilder;
 use crate::Object;
-use std::collections::HashMap;
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read};
+
+pub use self::parallel::{ParallelIter, ParallelParser};
+pub use self::read::{Decoding, MalformedObject, ObjectsReadIter};
+#[cfg(feature = "tokio")]
+pub use self::stream::ObjectsStream;
 
 /// Result type returned by parser helper functions.
 pub type ParseResult<'a, T> = Result<(Option<T>, &'a str, usize), ParseError>;
@@ -19,90 +30,44 @@ pub fn parse_objects_iter(input: &str) -> ObjectsIter<'_> {
 }
 
 /// Incrementally parse objects from a `Read` implementation.
-pub fn parse_objects_read_iter<R: Read>(reader: R) -> ObjectsReadIter<R> {
-    ObjectsReadIter {
-        reader,
-        buf: String::new(),
-        done: false,
-        line_number: 1,
-    }
+///
+/// The reader is wrapped in a [`BufReader`]. Use [`parse_objects_bufread_iter`]
+/// if the input is already buffered.
+pub fn parse_objects_read_iter<R: Read>(reader: R) -> ObjectsReadIter<BufReader<R>> {
+    ObjectsReadIter::new(BufReader::with_capacity(64 * 1024, reader))
 }
 
-pub struct ObjectsReadIter<R: Read> {
-    reader: R,
-    buf: String,
-    done: bool,
-    line_number: usize,
+/// Incrementally parse objects from a `BufRead` implementation.
+pub fn parse_objects_bufread_iter<R: BufRead>(reader: R) -> ObjectsReadIter<R> {
+    ObjectsReadIter::new(reader)
 }
 
-impl<R: Read> ObjectsReadIter<R> {
-    fn read_more(&mut self) -> Result<(), std::io::Error> {
-        let mut tmp = [0u8; 8192];
-        match self.reader.read(&mut tmp) {
-            Ok(0) => {
-                self.done = true;
-                Ok(())
-            }
-            Ok(n) => {
-                self.buf.push_str(&String::from_utf8_lossy(&tmp[..n]));
-                Ok(())
-            }
-            Err(e) => {
-                self.done = true;
-                Err(e)
-            }
-        }
-    }
+/// Incrementally parse objects from a tokio `AsyncRead` implementation.
+///
+/// The reader is wrapped in a [`tokio::io::BufReader`]. Use
+/// [`parse_objects_stream`] if the input is already buffered.
+///
+/// Requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+pub fn parse_objects_async_read<R: tokio::io::AsyncRead + Unpin>(
+    reader: R,
+) -> ObjectsStream<tokio::io::BufReader<R>> {
+    ObjectsStream::new(tokio::io::BufReader::with_capacity(64 * 1024, reader))
 }
 
-impl<R: Read> Iterator for ObjectsReadIter<R> {
-    type Item = Result<Object, ParseError>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if self.done && self.buf.is_empty() {
-                return None;
-            }
+/// Incrementally parse objects from a tokio `AsyncBufRead` implementation.
+///
+/// Requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+pub fn parse_objects_stream<R: tokio::io::AsyncBufRead + Unpin>(reader: R) -> ObjectsStream<R> {
+    ObjectsStream::new(reader)
+}
 
-            match parse_object(&self.buf, self.done, self.line_number) {
-                Ok((None, rest, lines)) => {
-                    let consumed = self.buf.len() - rest.len();
-                    self.buf.drain(..consumed);
-                    self.line_number += lines;
-                    if self.buf.is_empty() {
-                        if self.done {
-                            return None;
-                        }
-                        if let Err(e) = self.read_more() {
-                            return Some(Err(ParseError::Io(e)));
-                        }
-                        continue;
-                    }
-                    continue;
-                }
-                Ok((Some(obj), rest, lines)) => {
-                    let consumed = self.buf.len() - rest.len();
-                    self.buf.drain(..consumed);
-                    self.line_number += lines;
-                    return Some(Ok(obj));
-                }
-                Err(ParseError::Incomplete { line }) => {
-                    if self.done {
-                        return Some(Err(ParseError::Incomplete { line }));
-                    }
-                    if let Err(e) = self.read_more() {
-                        return Some(Err(ParseError::Io(e)));
-                    }
-                    continue;
-                }
-                Err(e) => {
-                    self.done = true;
-                    self.buf.clear();
-                    return Some(Err(e));
-                }
-            }
-        }
-    }
+/// Parse objects from a `BufRead` implementation on a pool of worker threads.
+///
+/// See [`ParallelParser`] for the available options.
+pub fn parse_objects_parallel<R: BufRead + Send + 'static>(reader: R) -> ParallelParser<R> {
+    ParallelParser::new(reader)
 }
 
 pub struct ObjectsIter<'a> {
@@ -143,133 +108,31 @@ impl Iterator for ObjectsIter<'_> {
 
 /// Parse a single object from the input string.
 /// The `eof` flag indicates whether no more data will follow the input.
-fn parse_object(input: &str, eof: bool, start_line: usize) -> ParseResult<Object> {
+fn parse_object(input: &str, eof: bool, start_line: usize) -> ParseResult<'_, Object> {
+    let mut builder = ObjectBuilder::new();
     let mut rest = input;
-    let mut map: HashMap<String, Vec<String>> = HashMap::new();
-    let mut current_key: Option<String> = None;
-    let mut obj_type: Option<crate::ObjectType> = None;
-    let mut started = false;
-    let mut terminated = false;
     let mut lines_consumed = 0usize;
     let mut line_no = start_line;
 
     while !rest.is_empty() {
-        let (line, next, had_newline) = split_first_line(rest);
+        let (_, next, had_newline) = split_first_line(rest);
         if !had_newline && !eof {
             return Err(ParseError::Incomplete { line: line_no });
         }
+        // The full source text of the line, including the line ending.
+        let raw_line = &rest[..rest.len() - next.len()];
         rest = next;
-        let trimmed_end = line.trim_end_matches(['\r', '\n']);
-        let trimmed = trimmed_end.trim();
-
-        // Skip initial empty/comment lines before the object starts
-        if !started && (trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('%'))
-        {
-            lines_consumed += 1;
-            line_no += 1;
-            continue;
-        }
-
-        if trimmed.is_empty() {
-            if started {
-                terminated = true;
-                lines_consumed += 1;
-                line_no += 1;
-                break;
-            } else {
-                lines_consumed += 1;
-                line_no += 1;
-                continue;
-            }
-        }
-
-        if trimmed.starts_with('#') || trimmed.starts_with('%') {
-            // comment inside object, ignore
-            lines_consumed += 1;
-            line_no += 1;
-            continue;
-        }
-
-        started = true;
-
-        if line.starts_with(|c: char| c.is_whitespace()) && current_key.is_some() {
-            if let Some(key) = &current_key {
-                if let Some(values) = map.get_mut(key) {
-                    if let Some(last) = values.last_mut() {
-                        if !last.is_empty() && !last.ends_with(' ') && !trimmed.is_empty() {
-                            last.push(' ');
-                        }
-                        last.push_str(trimmed);
-                    } else {
-                        values.push(trimmed.to_string());
-                    }
-                }
-            }
-            lines_consumed += 1;
-            line_no += 1;
-            continue;
-        }
-
-        if let Some(pos) = trimmed.find(':') {
-            let key = trimmed[..pos].trim().to_lowercase();
-            let value = trimmed[pos + 1..].trim().to_string();
-            if obj_type.is_none() {
-                obj_type = Some(crate::ObjectType::from_key(&key));
-            }
-            map.entry(key.clone()).or_default().push(value);
-            current_key = Some(key);
-        } else if let Some(key) = &current_key {
-            if let Some(values) = map.get_mut(key) {
-                if let Some(last) = values.last_mut() {
-                    if !last.is_empty() && !last.ends_with(' ') && !trimmed.is_empty() {
-                        last.push(' ');
-                    }
-                    last.push_str(trimmed);
-                } else {
-                    values.push(trimmed.to_string());
-                }
-            }
-        } else {
-            if rest.is_empty() && !eof {
-                return Err(ParseError::Incomplete { line: line_no });
-            }
-            let kind = if line.starts_with(|c: char| c.is_whitespace()) {
-                MalformedLineError::UnexpectedContinuation
-            } else {
-                MalformedLineError::MissingColon
-            };
-            return Err(ParseError::MalformedLine {
-                line: line_no,
-                content: trimmed.to_string(),
-                kind,
-            });
-        }
-
-        if rest.is_empty() && !terminated && !eof {
-            return Err(ParseError::Incomplete { line: line_no + 1 });
-        }
         lines_consumed += 1;
+        if let Some(obj) = builder.push_line(raw_line, line_no)? {
+            return Ok((Some(obj), rest, lines_consumed));
+        }
         line_no += 1;
     }
 
-    if started {
-        if !terminated && !eof && rest.is_empty() {
-            return Err(ParseError::Incomplete { line: line_no });
-        }
-        let obj_type = obj_type.unwrap_or_else(|| crate::ObjectType::Other(String::new()));
-        let mut obj = Object::new(obj_type);
-        for (k, vals) in map {
-            for v in vals {
-                obj.add(k.clone(), v);
-            }
-        }
-        Ok((Some(obj), rest, lines_consumed))
-    } else {
-        if !eof && !rest.is_empty() {
-            return Err(ParseError::Incomplete { line: line_no });
-        }
-        Ok((None, rest, lines_consumed))
+    if !eof && builder.is_started() {
+        return Err(ParseError::Incomplete { line: line_no });
     }
+    Ok((builder.finish(), rest, lines_consumed))
 }
 
 /// Split the input at the first newline, returning the line and remaining input.
@@ -338,8 +201,11 @@ mod tests {
         let objs = parse_objects(text).unwrap();
         assert_eq!(objs.len(), 1);
         let obj = &objs[0];
-        assert_eq!(obj.get("inetnum").unwrap(), ["192.0.2.0 - 192.0.2.255"]);
-        assert_eq!(obj.get("netname").unwrap(), ["TEST-NET"]);
+        assert_eq!(
+            obj.get("inetnum").collect::<Vec<_>>(),
+            ["192.0.2.0 - 192.0.2.255"]
+        );
+        assert_eq!(obj.get("netname").collect::<Vec<_>>(), ["TEST-NET"]);
     }
 
     #[test]
@@ -347,8 +213,8 @@ mod tests {
         let text = "person: John Doe\nsource: TEST\n\naut-num: AS1\nsource: TEST\n";
         let objs: Vec<_> = parse_objects(text).unwrap();
         assert_eq!(objs.len(), 2);
-        assert_eq!(objs[0].get("person").unwrap(), ["John Doe"]);
-        assert_eq!(objs[1].get("aut-num").unwrap(), ["AS1"]);
+        assert_eq!(objs[0].get("person").collect::<Vec<_>>(), ["John Doe"]);
+        assert_eq!(objs[1].get("aut-num").collect::<Vec<_>>(), ["AS1"]);
     }
 
     #[test]
@@ -356,8 +222,11 @@ mod tests {
         let text = "descr: First line\n  second line\nremarks: a\nremarks: b\n\n";
         let objs = parse_objects(text).unwrap();
         let obj = &objs[0];
-        assert_eq!(obj.get("descr").unwrap(), ["First line second line"]);
-        assert_eq!(obj.get("remarks").unwrap(), ["a", "b"]);
+        assert_eq!(
+            obj.get("descr").collect::<Vec<_>>(),
+            ["First line second line"]
+        );
+        assert_eq!(obj.get("remarks").collect::<Vec<_>>(), ["a", "b"]);
     }
 
     #[test]
@@ -367,7 +236,7 @@ mod tests {
         let objs = parse_objects(text).unwrap();
         assert_eq!(objs.len(), 1);
         let obj = &objs[0];
-        assert_eq!(obj.get("netname").unwrap(), ["NET"]);
+        assert_eq!(obj.get("netname").collect::<Vec<_>>(), ["NET"]);
     }
 
     #[test]
@@ -376,7 +245,7 @@ mod tests {
         let objs = parse_objects(text).unwrap();
         assert_eq!(objs.len(), 1);
         let obj = &objs[0];
-        assert_eq!(obj.get("netname").unwrap(), ["TEST-NET"]);
+        assert_eq!(obj.get("netname").collect::<Vec<_>>(), ["TEST-NET"]);
     }
 
     #[test]
@@ -384,7 +253,25 @@ mod tests {
         let text = "inetnum: 192.0.2.0 - 192.0.2.255\r\nnetname: TEST-NET\r\n\r\n";
         let objs = parse_objects(text).unwrap();
         assert_eq!(objs.len(), 1);
-        assert_eq!(objs[0].get("inetnum").unwrap(), ["192.0.2.0 - 192.0.2.255"]);
+        assert_eq!(
+            objs[0].get("inetnum").collect::<Vec<_>>(),
+            ["192.0.2.0 - 192.0.2.255"]
+        );
+    }
+
+    #[test]
+    fn preserves_order_and_layout() {
+        let text = "aut-num:   AS1\r\ndescr:     First\r\nremarks:   between\r\ndescr:     Second\r\n            continued\r\nsource:    TEST\r\n\r\n";
+        let objs = parse_objects(text).unwrap();
+        let obj = &objs[0];
+        let keys: Vec<_> = obj.ordered_attributes().iter().map(|a| a.key()).collect();
+        assert_eq!(keys, ["aut-num", "descr", "remarks", "descr", "source"]);
+        assert_eq!(
+            obj.get("descr").collect::<Vec<_>>(),
+            ["First", "Second continued"]
+        );
+        assert_eq!(obj.ordered_attributes()[3].line(), Some(4));
+        assert_eq!(obj.to_rpsl(), text.strip_suffix("\r\n").unwrap());
     }
 
     #[test]
@@ -394,8 +281,8 @@ mod tests {
         let obj1 = iter.next().unwrap().unwrap();
         let obj2 = iter.next().unwrap().unwrap();
         assert!(iter.next().is_none());
-        assert_eq!(obj1.get("netname").unwrap(), ["NET1"]);
-        assert_eq!(obj2.get("netname").unwrap(), ["NET2"]);
+        assert_eq!(obj1.get("netname").collect::<Vec<_>>(), ["NET1"]);
+        assert_eq!(obj2.get("netname").collect::<Vec<_>>(), ["NET2"]);
     }
 
     #[test]
@@ -463,7 +350,7 @@ mod tests {
             .map(Result::unwrap)
             .collect();
         assert_eq!(objs.len(), 1);
-        assert_eq!(objs[0].get("netname").unwrap(), ["TEST"]);
+        assert_eq!(objs[0].get("netname").collect::<Vec<_>>(), ["TEST"]);
     }
 
     struct ChunkReader<R: std::io::Read> {
@@ -492,6 +379,200 @@ mod tests {
         assert_eq!(objs.len(), 2);
     }
 
+    #[test]
+    fn read_iter_keeps_code_points_split_across_reads() {
+        let text = "person: Jürgen Müller\ndescr: 東京\n\n";
+        let reader = std::io::BufReader::with_capacity(
+            1,
+            ChunkReader {
+                inner: std::io::Cursor::new(text),
+                chunk: 1,
+            },
+        );
+        let objs: Vec<_> = parse_objects_bufread_iter(reader)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(objs[0].get("person").collect::<Vec<_>>(), ["Jürgen Müller"]);
+        assert_eq!(objs[0].get("descr").collect::<Vec<_>>(), ["東京"]);
+        assert_eq!(objs[0].to_rpsl(), text.strip_suffix('\n').unwrap());
+    }
+
+    #[test]
+    fn read_iter_decoding_policies() {
+        // "São Paulo" in Latin-1, followed by a UTF-8 line.
+        let mut data = b"inetnum: 192.0.2.0/24\ndescr: S\xe3o Paulo\n".to_vec();
+        data.extend_from_slice("remarks: Ñandú\n\n".as_bytes());
+
+        let parse = |decoding| {
+            parse_objects_read_iter(data.as_slice())
+                .with_decoding(decoding)
+                .next()
+                .unwrap()
+                .unwrap()
+        };
+
+        let obj = parse(Decoding::Utf8Lossy);
+        assert_eq!(obj.get("descr").collect::<Vec<_>>(), ["S\u{fffd}o Paulo"]);
+        assert_eq!(obj.get("remarks").collect::<Vec<_>>(), ["Ñandú"]);
+
+        let obj = parse(Decoding::Latin1);
+        assert_eq!(obj.get("descr").collect::<Vec<_>>(), ["São Paulo"]);
+        assert_eq!(obj.get("remarks").collect::<Vec<_>>(), ["Ã\u{91}andÃº"]);
+
+        let obj = parse(Decoding::Utf8OrLatin1);
+        assert_eq!(obj.get("descr").collect::<Vec<_>>(), ["São Paulo"]);
+        assert_eq!(obj.get("remarks").collect::<Vec<_>>(), ["Ñandú"]);
+    }
+
+    #[test]
+    fn read_iter_line_numbers() {
+        let text = "% header\n\nperson: A\nsource: T\n\n\naut-num: AS1\n";
+        let objs: Vec<_> = parse_objects_read_iter(text.as_bytes())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(objs[0].ordered_attributes()[0].line(), Some(3));
+        assert_eq!(objs[1].ordered_attributes()[0].line(), Some(7));
+    }
+
+    #[test]
+    fn read_iter_recovers_from_malformed_objects() {
+        let text = "person: A\nsource: T\n\n% before\ngarbage line\n# after\n  more garbage\nkey: value\n\naut-num: AS1\nsource: T\n\n  stray\n";
+        let malformed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = malformed.clone();
+        let mut iter = parse_objects_read_iter(text.as_bytes())
+            .with_recovery(move |m| sink.lock().unwrap().push(m));
+        let objs: Vec<_> = iter.by_ref().map(Result::unwrap).collect();
+
+        assert_eq!(objs.len(), 2);
+        assert_eq!(objs[0].get("person").collect::<Vec<_>>(), ["A"]);
+        assert_eq!(objs[1].get("aut-num").collect::<Vec<_>>(), ["AS1"]);
+        assert_eq!(iter.malformed_count(), 2);
+
+        let malformed = malformed.lock().unwrap();
+        assert_eq!(malformed[0].start_line, 5);
+        assert_eq!(malformed[0].end_line, 8);
+        assert_eq!(malformed[0].kind, MalformedLineError::MissingColon);
+        assert_eq!(
+            malformed[0].raw,
+            "garbage line\n  more garbage\nkey: value\n"
+        );
+        assert_eq!(malformed[1].line, 13);
+        assert_eq!(
+            malformed[1].kind,
+            MalformedLineError::UnexpectedContinuation
+        );
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let text: String = (0..500)
+            .map(|i| format!("% comment\naut-num: AS{i}\ndescr: first\n  second\n\n\n"))
+            .collect();
+        let expected: Vec<_> = parse_objects_read_iter(text.as_bytes())
+            .map(Result::unwrap)
+            .collect();
+
+        let parser = || {
+            parse_objects_parallel(std::io::Cursor::new(text.clone().into_bytes()))
+                .with_threads(4)
+                .with_chunk_size(100)
+        };
+        let ordered: Vec<_> = parser().into_iter().map(Result::unwrap).collect();
+        assert_eq!(ordered, expected);
+
+        let mut unordered: Vec<_> = parser()
+            .unordered()
+            .map_objects(|obj| obj.get_first("aut-num").unwrap().to_string())
+            .map(Result::unwrap)
+            .collect();
+        unordered.sort_by_key(|asn| asn[2..].parse::<u32>().unwrap());
+        let asns: Vec<_> = (0..500).map(|i| format!("AS{i}")).collect();
+        assert_eq!(unordered, asns);
+    }
+
+    #[test]
+    fn parallel_recovery_and_errors() {
+        let text = "person: A\nsource: T\n\ngarbage line\n  more garbage\nkey: value\n\naut-num: AS1\nsource: T\n\n  stray\n";
+        let malformed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = malformed.clone();
+        let objs: Vec<_> = parse_objects_parallel(text.as_bytes())
+            .with_chunk_size(1)
+            .with_recovery(move |m| sink.lock().unwrap().push(m))
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(objs.len(), 2);
+        assert_eq!(objs[1].ordered_attributes()[0].line(), Some(8));
+        let mut lines: Vec<_> = malformed.lock().unwrap().iter().map(|m| m.line).collect();
+        lines.sort();
+        assert_eq!(lines, [4, 11]);
+
+        let res: Vec<_> = parse_objects_parallel(text.as_bytes())
+            .with_chunk_size(1)
+            .into_iter()
+            .collect();
+        assert_eq!(res.len(), 2);
+        assert!(matches!(
+            res[1],
+            Err(ParseError::MalformedLine { line: 4, .. })
+        ));
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn collect_stream<R: tokio::io::AsyncBufRead + Unpin>(
+        mut stream: ObjectsStream<R>,
+    ) -> Vec<Result<Object, ParseError>> {
+        use futures_core::Stream;
+        use std::pin::Pin;
+
+        let mut items = Vec::new();
+        while let Some(item) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        {
+            items.push(item);
+        }
+        items
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn stream_matches_read_iter() {
+        let text = "% header\n\nperson: A\nsource: T\n\ngarbage\n\naut-num: AS1\ndescr: Zürich\n  continued\nsource: T";
+        let expected: Vec<_> = parse_objects_read_iter(text.as_bytes())
+            .with_recovery(|_| {})
+            .map(Result::unwrap)
+            .collect();
+
+        // A tiny buffer splits lines and multi-byte characters across reads.
+        let reader = tokio::io::BufReader::with_capacity(3, text.as_bytes());
+        let stream = parse_objects_stream(reader).with_recovery(|_| {});
+        let objs: Vec<_> = collect_stream(stream)
+            .await
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(objs, expected);
+        assert_eq!(objs.len(), 2);
+        assert_eq!(objs[1].ordered_attributes()[0].line(), Some(8));
+
+        let res = collect_stream(parse_objects_async_read(text.as_bytes())).await;
+        assert_eq!(res.len(), 2);
+        assert!(matches!(
+            res[1],
+            Err(ParseError::MalformedLine { line: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn read_iter_stops_at_malformed_object_by_default() {
+        let text = "garbage\n\nperson: A\n\n";
+        let res: Vec<_> = parse_objects_read_iter(text.as_bytes()).collect();
+        assert_eq!(res.len(), 1);
+        assert!(matches!(
+            res[0],
+            Err(ParseError::MalformedLine { line: 1, .. })
+        ));
+    }
+
     struct FailReader;
 
     impl std::io::Read for FailReader {
@@ -1269,6 +1350,6 @@ remarks:        ****************************
         assert_eq!(objs.len(), 1);
         let obj = &objs[0];
 
-        assert_eq!(obj.get("aut-num").unwrap(), ["AS1126"]);
+        assert_eq!(obj.get("aut-num").collect::<Vec<_>>(), ["AS1126"]);
     }
 }
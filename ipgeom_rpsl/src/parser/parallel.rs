@@ -0,0 +1,332 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::read::{Decoding, MalformedObject, ObjectsReadIter};
+use super::ParseError;
+use crate::Object;
+
+/// Default size of the blocks of input handed to a worker.
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+type SharedMalformedHandler = Arc<dyn Fn(MalformedObject) + Send + Sync>;
+
+/// Parser that splits its input into blocks of objects and parses them on a
+/// pool of worker threads.
+///
+/// The input is read on a dedicated thread and split at blank lines, so every
+/// block contains whole objects. Workers parse the blocks and apply a mapping
+/// function to every object, e.g. the conversion into [`crate::RpslObject`].
+///
+/// At most two blocks per worker are in flight at any time, which bounds
+/// memory usage to roughly `2 * threads * chunk_size` bytes of input, plus
+/// the mapped objects of these blocks.
+///
+/// Created with [`crate::parse_objects_parallel`].
+pub struct ParallelParser<R> {
+    reader: R,
+    decoding: Decoding,
+    on_malformed: Option<SharedMalformedHandler>,
+    threads: usize,
+    chunk_size: usize,
+    ordered: bool,
+}
+
+impl<R: BufRead + Send + 'static> ParallelParser<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            decoding: Decoding::default(),
+            on_malformed: None,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            ordered: true,
+        }
+    }
+
+    /// Set the policy used to decode the input bytes.
+    pub fn with_decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    /// Enable recovery mode.
+    ///
+    /// See [`ObjectsReadIter::with_recovery`]. The handler is called from the
+    /// worker threads.
+    pub fn with_recovery<F>(mut self, on_malformed: F) -> Self
+    where
+        F: Fn(MalformedObject) + Send + Sync + 'static,
+    {
+        self.on_malformed = Some(Arc::new(on_malformed));
+        self
+    }
+
+    /// Set the number of worker threads.
+    ///
+    /// Defaults to the available parallelism of the system.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Set the approximate size in bytes of the blocks handed to workers.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Yield objects in the order in which blocks finish parsing, instead of
+    /// the input order.
+    ///
+    /// Objects within a block keep their relative order.
+    pub fn unordered(mut self) -> Self {
+        self.ordered = false;
+        self
+    }
+
+    /// Start parsing, applying `f` to every object on the worker threads.
+    pub fn map_objects<T, F>(self, f: F) -> ParallelIter<T>
+    where
+        T: Send + 'static,
+        F: Fn(Object) -> T + Send + Sync + 'static,
+    {
+        let in_flight = self.threads * 2;
+        let (permit_tx, permit_rx) = mpsc::sync_channel(in_flight);
+        for _ in 0..in_flight {
+            // Can not fail, the channel has room for all permits.
+            let _ = permit_tx.send(());
+        }
+        let (chunk_tx, chunk_rx) = mpsc::sync_channel(self.threads);
+        let (result_tx, result_rx) = mpsc::sync_channel(in_flight);
+
+        let chunk_size = self.chunk_size;
+        let reader = self.reader;
+        thread::spawn(move || read_chunks(reader, chunk_size, chunk_tx, permit_rx));
+
+        let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+        let f = Arc::new(f);
+        for _ in 0..self.threads {
+            let worker = Worker {
+                chunks: chunk_rx.clone(),
+                results: result_tx.clone(),
+                decoding: self.decoding,
+                on_malformed: self.on_malformed.clone(),
+                f: f.clone(),
+            };
+            thread::spawn(move || worker.run());
+        }
+
+        ParallelIter {
+            results: result_rx,
+            permits: permit_tx,
+            ordered: self.ordered,
+            next_seq: 0,
+            pending: BTreeMap::new(),
+            current: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead + Send + 'static> IntoIterator for ParallelParser<R> {
+    type Item = Result<Object, ParseError>;
+    type IntoIter = ParallelIter<Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map_objects(|obj| obj)
+    }
+}
+
+/// A block of input that ends at an object boundary.
+struct Chunk {
+    seq: usize,
+    first_line: usize,
+    data: Vec<u8>,
+    /// Read error that ended the input after this block.
+    error: Option<std::io::Error>,
+}
+
+/// Mapped objects of a block, or the panic payload of a failed worker.
+type ChunkResult<T> = Result<(usize, Vec<Result<T, ParseError>>), Box<dyn Any + Send>>;
+
+/// Split the input into chunks.
+///
+/// A permit is taken for every chunk and returned by the consumer once the
+/// chunk was yielded, which bounds the number of chunks in flight.
+fn read_chunks<R: BufRead>(
+    mut reader: R,
+    chunk_size: usize,
+    chunks: SyncSender<Chunk>,
+    permits: Receiver<()>,
+) {
+    let mut line_number = 1;
+    for seq in 0.. {
+        if permits.recv().is_err() {
+            // The iterator was dropped.
+            return;
+        }
+        let first_line = line_number;
+        let mut data = Vec::with_capacity(chunk_size);
+        let mut error = None;
+        let mut eof = false;
+        loop {
+            let start = data.len();
+            match reader.read_until(b'\n', &mut data) {
+                Ok(0) => {
+                    eof = true;
+                    break;
+                }
+                Ok(_) => {
+                    line_number += 1;
+                    let blank = data[start..].iter().all(u8::is_ascii_whitespace);
+                    if blank && data.len() >= chunk_size {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    error = Some(e);
+                    eof = true;
+                    break;
+                }
+            }
+        }
+        let chunk = Chunk {
+            seq,
+            first_line,
+            data,
+            error,
+        };
+        if chunks.send(chunk).is_err() || eof {
+            return;
+        }
+    }
+}
+
+struct Worker<T, F> {
+    chunks: Arc<Mutex<Receiver<Chunk>>>,
+    results: SyncSender<ChunkResult<T>>,
+    decoding: Decoding,
+    on_malformed: Option<SharedMalformedHandler>,
+    f: Arc<F>,
+}
+
+impl<T, F> Worker<T, F>
+where
+    F: Fn(Object) -> T,
+{
+    fn run(self) {
+        loop {
+            let chunk = {
+                let chunks = self.chunks.lock().unwrap_or_else(|e| e.into_inner());
+                match chunks.recv() {
+                    Ok(chunk) => chunk,
+                    Err(_) => return,
+                }
+            };
+            let seq = chunk.seq;
+            let res = panic::catch_unwind(AssertUnwindSafe(|| self.parse(chunk)));
+            if self.results.send(res.map(|items| (seq, items))).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn parse(&self, chunk: Chunk) -> Vec<Result<T, ParseError>> {
+        let mut iter = ObjectsReadIter::new(chunk.data.as_slice())
+            .with_decoding(self.decoding)
+            .starting_at_line(chunk.first_line);
+        if let Some(handler) = &self.on_malformed {
+            let handler = handler.clone();
+            iter = iter.with_recovery(move |malformed| handler(malformed));
+        }
+
+        let mut items = Vec::new();
+        for res in iter {
+            match res {
+                Ok(obj) => items.push(Ok((self.f)(obj))),
+                Err(e) => {
+                    items.push(Err(e));
+                    return items;
+                }
+            }
+        }
+        if let Some(e) = chunk.error {
+            items.push(Err(ParseError::Io(e)));
+        }
+        items
+    }
+}
+
+/// Iterator over the objects parsed by a [`ParallelParser`].
+///
+/// Like [`ObjectsReadIter`], iteration ends after the first error. Panics in
+/// the mapping function are propagated to the consuming thread.
+pub struct ParallelIter<T> {
+    results: Receiver<ChunkResult<T>>,
+    permits: SyncSender<()>,
+    ordered: bool,
+    next_seq: usize,
+    /// Chunks that finished before their predecessors.
+    pending: BTreeMap<usize, Vec<Result<T, ParseError>>>,
+    current: std::vec::IntoIter<Result<T, ParseError>>,
+    done: bool,
+}
+
+impl<T> ParallelIter<T> {
+    fn start_chunk(&mut self, items: Vec<Result<T, ParseError>>) {
+        self.current = items.into_iter();
+        // Never blocks, there are never more permits than the channel holds.
+        let _ = self.permits.try_send(());
+    }
+}
+
+impl<T> Iterator for ParallelIter<T> {
+    type Item = Result<T, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.current.next() {
+                if item.is_err() {
+                    self.done = true;
+                    self.current = Vec::new().into_iter();
+                }
+                return Some(item);
+            }
+            if self.done {
+                return None;
+            }
+
+            if self.ordered {
+                if let Some(items) = self.pending.remove(&self.next_seq) {
+                    self.next_seq += 1;
+                    self.start_chunk(items);
+                    continue;
+                }
+            }
+
+            match self.results.recv() {
+                Ok(Ok((seq, items))) => {
+                    if self.ordered {
+                        self.pending.insert(seq, items);
+                    } else {
+                        self.start_chunk(items);
+                    }
+                }
+                Ok(Err(payload)) => {
+                    self.done = true;
+                    panic::resume_unwind(payload);
+                }
+                Err(_) => {
+                    // All workers finished.
+                    self.done = true;
+                }
+            }
+        }
+    }
+}
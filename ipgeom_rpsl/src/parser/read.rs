@@ -0,0 +1,261 @@
+use std::borrow::Cow;
+use std::io::BufRead;
+
+use super::{builder::ObjectBuilder, MalformedLineError, ParseError};
+use crate::Object;
+
+/// Policy for decoding input bytes into text.
+///
+/// RIR dumps are not always valid UTF-8. LACNIC and AFRINIC data in
+/// particular often contains ISO-8859-1 (Latin-1) encoded text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Decoding {
+    /// Decode as UTF-8, replacing invalid sequences with U+FFFD.
+    #[default]
+    Utf8Lossy,
+    /// Decode every byte as ISO-8859-1 (Latin-1).
+    Latin1,
+    /// Decode each line as UTF-8, falling back to Latin-1 for lines that are
+    /// not valid UTF-8.
+    Utf8OrLatin1,
+}
+
+impl Decoding {
+    /// Decode a single line of input.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        match self {
+            Decoding::Utf8Lossy => String::from_utf8_lossy(bytes),
+            Decoding::Latin1 => latin1(bytes),
+            Decoding::Utf8OrLatin1 => match std::str::from_utf8(bytes) {
+                Ok(s) => Cow::Borrowed(s),
+                Err(_) => latin1(bytes),
+            },
+        }
+    }
+}
+
+fn latin1(bytes: &[u8]) -> Cow<'_, str> {
+    if bytes.is_ascii() {
+        // ASCII is valid UTF-8, so this can not fail.
+        return String::from_utf8_lossy(bytes);
+    }
+    Cow::Owned(bytes.iter().map(|&b| b as char).collect())
+}
+
+/// A malformed object that was skipped by a parser in recovery mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedObject {
+    /// First line of the skipped object.
+    pub start_line: usize,
+    /// Last non-blank line of the skipped object.
+    pub end_line: usize,
+    /// Source text of the skipped object, excluding comments.
+    pub raw: String,
+    /// Line that could not be parsed.
+    pub line: usize,
+    /// Content of the offending line.
+    pub content: String,
+    /// Why the line could not be parsed.
+    pub kind: MalformedLineError,
+}
+
+impl std::fmt::Display for MalformedObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Malformed object at lines {}-{}: line {}: '{}': {:?}",
+            self.start_line, self.end_line, self.line, self.content, self.kind
+        )
+    }
+}
+
+type MalformedHandler = Box<dyn FnMut(MalformedObject) + Send>;
+
+/// Line driven parser shared by the blocking and async readers.
+///
+/// Raw input lines are fed one at a time. Handles decoding, line numbering and
+/// skipping of malformed objects in recovery mode.
+pub(super) struct LineParser {
+    builder: ObjectBuilder,
+    pub(super) decoding: Decoding,
+    line_number: usize,
+    pub(super) on_malformed: Option<MalformedHandler>,
+    /// Malformed object that is currently being skipped.
+    skipping: Option<MalformedObject>,
+    pub(super) malformed_count: usize,
+}
+
+impl LineParser {
+    pub(super) fn new() -> Self {
+        Self {
+            builder: ObjectBuilder::new(),
+            decoding: Decoding::default(),
+            line_number: 1,
+            on_malformed: None,
+            skipping: None,
+            malformed_count: 0,
+        }
+    }
+
+    /// Report the object that is currently being skipped, if any.
+    fn finish_skipping(&mut self) {
+        if let Some(malformed) = self.skipping.take() {
+            self.malformed_count += 1;
+            if let Some(handler) = &mut self.on_malformed {
+                handler(malformed);
+            }
+        }
+    }
+
+    /// Feed the next raw line, including its line ending.
+    ///
+    /// Returns a finished object, or the error that ends parsing.
+    pub(super) fn push_line(&mut self, raw: &[u8]) -> Option<Result<Object, ParseError>> {
+        let line_no = self.line_number;
+        self.line_number += 1;
+        let line = self.decoding.decode(raw);
+
+        if let Some(skipping) = &mut self.skipping {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                self.finish_skipping();
+            } else {
+                // Comments are left out of `raw`, as in the builder.
+                if !trimmed.starts_with('#') && !trimmed.starts_with('%') {
+                    skipping.raw.push_str(&line);
+                }
+                skipping.end_line = line_no;
+            }
+            return None;
+        }
+
+        match self.builder.push_line(&line, line_no) {
+            Ok(obj) => obj.map(Ok),
+            Err(ParseError::MalformedLine {
+                line: bad_line,
+                content,
+                kind,
+            }) if self.on_malformed.is_some() => {
+                let (start_line, mut raw) = self.builder.abort();
+                raw.push_str(&line);
+                self.skipping = Some(MalformedObject {
+                    start_line: start_line.unwrap_or(line_no),
+                    end_line: line_no,
+                    raw,
+                    line: bad_line,
+                    content,
+                    kind,
+                });
+                None
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Signal the end of the input and return the last object, if any.
+    pub(super) fn finish(&mut self) -> Option<Object> {
+        self.finish_skipping();
+        self.builder.finish()
+    }
+}
+
+/// Iterator over objects read from a [`BufRead`] implementation.
+///
+/// Input is consumed one line at a time. Lines always end at a `\n` byte,
+/// which never occurs inside a multi-byte UTF-8 sequence, so code points are
+/// never split regardless of how the underlying reader chunks its data.
+///
+/// By default iteration stops at the first malformed line. See
+/// [`ObjectsReadIter::with_recovery`] for skipping malformed objects instead.
+pub struct ObjectsReadIter<R: BufRead> {
+    reader: R,
+    line_buf: Vec<u8>,
+    parser: LineParser,
+    done: bool,
+}
+
+impl<R: BufRead> ObjectsReadIter<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line_buf: Vec::new(),
+            parser: LineParser::new(),
+            done: false,
+        }
+    }
+
+    /// Set the policy used to decode the input bytes.
+    pub fn with_decoding(mut self, decoding: Decoding) -> Self {
+        self.parser.decoding = decoding;
+        self
+    }
+
+    /// Enable recovery mode.
+    ///
+    /// Instead of ending iteration, a malformed line causes the parser to skip
+    /// ahead to the next blank line. The skipped object is passed to
+    /// `on_malformed`, and parsing continues with the following object.
+    pub fn with_recovery<F>(mut self, on_malformed: F) -> Self
+    where
+        F: FnMut(MalformedObject) + Send + 'static,
+    {
+        self.parser.on_malformed = Some(Box::new(on_malformed));
+        self
+    }
+
+    /// Number the lines starting at `line_number` instead of 1.
+    pub(super) fn starting_at_line(mut self, line_number: usize) -> Self {
+        self.parser.line_number = line_number;
+        self
+    }
+
+    /// Number of malformed objects skipped so far in recovery mode.
+    pub fn malformed_count(&self) -> usize {
+        self.parser.malformed_count
+    }
+
+    /// Read the next line into `line_buf`.
+    ///
+    /// Returns `false` at the end of the input.
+    fn read_line(&mut self) -> Result<bool, std::io::Error> {
+        self.line_buf.clear();
+        loop {
+            match self.reader.read_until(b'\n', &mut self.line_buf) {
+                Ok(n) => return Ok(n > 0),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ObjectsReadIter<R> {
+    type Item = Result<Object, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            match self.read_line() {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.done = true;
+                    return self.parser.finish().map(Ok);
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(ParseError::Io(e)));
+                }
+            }
+
+            if let Some(res) = self.parser.push_line(&self.line_buf) {
+                if res.is_err() {
+                    self.done = true;
+                }
+                return Some(res);
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::AsyncBufRead;
+
+use super::read::{Decoding, LineParser, MalformedObject};
+use super::ParseError;
+use crate::Object;
+
+/// Stream of objects read from a tokio [`AsyncBufRead`] implementation.
+///
+/// The async counterpart of [`super::ObjectsReadIter`], with the same
+/// decoding and recovery options. Objects are yielded as soon as their
+/// terminating blank line was received, so whois responses, HTTP bodies or
+/// NRTM sockets can be parsed incrementally.
+pub struct ObjectsStream<R> {
+    reader: R,
+    line_buf: Vec<u8>,
+    parser: LineParser,
+    done: bool,
+}
+
+impl<R: AsyncBufRead + Unpin> ObjectsStream<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line_buf: Vec::new(),
+            parser: LineParser::new(),
+            done: false,
+        }
+    }
+
+    /// Set the policy used to decode the input bytes.
+    pub fn with_decoding(mut self, decoding: Decoding) -> Self {
+        self.parser.decoding = decoding;
+        self
+    }
+
+    /// Enable recovery mode.
+    ///
+    /// See [`super::ObjectsReadIter::with_recovery`].
+    pub fn with_recovery<F>(mut self, on_malformed: F) -> Self
+    where
+        F: FnMut(MalformedObject) + Send + 'static,
+    {
+        self.parser.on_malformed = Some(Box::new(on_malformed));
+        self
+    }
+
+    /// Number of malformed objects skipped so far in recovery mode.
+    pub fn malformed_count(&self) -> usize {
+        self.parser.malformed_count
+    }
+
+    /// Read the rest of the current line into `line_buf`.
+    ///
+    /// Returns `false` at the end of the input. Partially read lines are kept
+    /// in `line_buf` if the reader is not ready.
+    fn poll_read_line(&mut self, cx: &mut Context<'_>) -> Poll<Result<bool, std::io::Error>> {
+        loop {
+            let buf = match ready!(Pin::new(&mut self.reader).poll_fill_buf(cx)) {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Poll::Ready(Err(e)),
+            };
+            if buf.is_empty() {
+                return Poll::Ready(Ok(!self.line_buf.is_empty()));
+            }
+            let (used, complete) = match buf.iter().position(|&b| b == b'\n') {
+                Some(pos) => (pos + 1, true),
+                None => (buf.len(), false),
+            };
+            self.line_buf.extend_from_slice(&buf[..used]);
+            Pin::new(&mut self.reader).consume(used);
+            if complete {
+                return Poll::Ready(Ok(true));
+            }
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for ObjectsStream<R> {
+    type Item = Result<Object, ParseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match ready!(this.poll_read_line(cx)) {
+                Ok(true) => {}
+                Ok(false) => {
+                    this.done = true;
+                    return Poll::Ready(this.parser.finish().map(Ok));
+                }
+                Err(e) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(ParseError::Io(e))));
+                }
+            }
+
+            let res = this.parser.push_line(&this.line_buf);
+            this.line_buf.clear();
+            if let Some(res) = res {
+                if res.is_err() {
+                    this.done = true;
+                }
+                return Poll::Ready(Some(res));
+            }
+        }
+    }
+}
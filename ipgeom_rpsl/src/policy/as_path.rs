@@ -0,0 +1,278 @@
+use serde::Serialize;
+
+use super::{parser::parse_asn, PolicyError};
+
+/// An AS path regular expression, the contents of `<...>` in a filter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum AsPathRegex {
+    Asn(u32),
+    /// Matches any AS in the referenced `as-set`.
+    AsSet(String),
+    PeerAs,
+    /// `.`, matches any AS.
+    Any,
+    /// `[...]` or `[^...]`.
+    Set {
+        negated: bool,
+        items: Vec<AsPathSetItem>,
+    },
+    /// `^`, the start of the path.
+    Start,
+    /// `$`, the end of the path.
+    End,
+    Sequence(Vec<AsPathRegex>),
+    /// Alternatives separated by `|`.
+    Alternation(Vec<AsPathRegex>),
+    Repeat {
+        expr: Box<AsPathRegex>,
+        min: u32,
+        max: Option<u32>,
+        /// `~*`, `~+` and `~{..}`: every repetition must match the same AS.
+        same: bool,
+    },
+}
+
+/// A member of an AS path regex set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum AsPathSetItem {
+    Asn(u32),
+    /// An inclusive range of AS numbers, `AS1-AS5`.
+    Range(u32, u32),
+    AsSet(String),
+    PeerAs,
+    Any,
+}
+
+/// Parse the regex between `<` and `>`. `offset` is the position of `input`
+/// in the full policy text, used for error spans.
+pub(super) fn parse(input: &str, offset: usize) -> Result<AsPathRegex, PolicyError> {
+    let mut p = RegexParser {
+        input,
+        pos: 0,
+        offset,
+    };
+    let regex = p.parse_alternation()?;
+    p.skip_ws();
+    if p.pos < input.len() {
+        return Err(p.error(p.pos..p.pos + 1, "unexpected character in AS path"));
+    }
+    Ok(regex)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':')
+}
+
+struct RegexParser<'a> {
+    input: &'a str,
+    pos: usize,
+    offset: usize,
+}
+
+impl<'a> RegexParser<'a> {
+    fn error(&self, span: std::ops::Range<usize>, message: impl Into<String>) -> PolicyError {
+        PolicyError {
+            span: span.start + self.offset..span.end + self.offset,
+            message: message.into(),
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn word(&mut self) -> Option<(&'a str, std::ops::Range<usize>)> {
+        self.skip_ws();
+        let rest = &self.input[self.pos..];
+        if !rest.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        let len = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+        let span = self.pos..self.pos + len;
+        self.pos += len;
+        Some((&rest[..len], span))
+    }
+
+    fn parse_alternation(&mut self) -> Result<AsPathRegex, PolicyError> {
+        let mut alternatives = vec![self.parse_sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.parse_sequence()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            AsPathRegex::Alternation(alternatives)
+        })
+    }
+
+    fn parse_sequence(&mut self) -> Result<AsPathRegex, PolicyError> {
+        let mut items = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            let atom = self.parse_atom()?;
+            items.push(self.parse_repeat(atom)?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            AsPathRegex::Sequence(items)
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<AsPathRegex, PolicyError> {
+        let start = self.pos;
+        if self.eat('^') {
+            return Ok(AsPathRegex::Start);
+        }
+        if self.eat('$') {
+            return Ok(AsPathRegex::End);
+        }
+        if self.eat('.') {
+            return Ok(AsPathRegex::Any);
+        }
+        if self.eat('(') {
+            let inner = self.parse_alternation()?;
+            if !self.eat(')') {
+                return Err(self.error(start..self.pos, "unclosed group in AS path"));
+            }
+            return Ok(inner);
+        }
+        if self.eat('[') {
+            return self.parse_set(start);
+        }
+        match self.word() {
+            Some((word, span)) => self.classify(word, span).map(|item| match item {
+                AsPathSetItem::Asn(asn) => AsPathRegex::Asn(asn),
+                AsPathSetItem::AsSet(name) => AsPathRegex::AsSet(name),
+                AsPathSetItem::PeerAs => AsPathRegex::PeerAs,
+                AsPathSetItem::Range(..) | AsPathSetItem::Any => unreachable!(),
+            }),
+            None => Err(self.error(self.pos..self.pos + 1, "unexpected character in AS path")),
+        }
+    }
+
+    /// Classify a single AS path word as an AS number, `PeerAS` or as-set.
+    fn classify(
+        &self,
+        word: &str,
+        span: std::ops::Range<usize>,
+    ) -> Result<AsPathSetItem, PolicyError> {
+        if word.eq_ignore_ascii_case("peeras") {
+            Ok(AsPathSetItem::PeerAs)
+        } else if let Some(asn) = parse_asn(word) {
+            Ok(AsPathSetItem::Asn(asn))
+        } else if word
+            .split(':')
+            .any(|c| c.len() > 3 && c.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("as-")))
+        {
+            Ok(AsPathSetItem::AsSet(word.to_string()))
+        } else {
+            Err(self.error(span, format!("expected AS number or as-set, got '{word}'")))
+        }
+    }
+
+    /// Items of a `[...]` set, after the opening bracket.
+    fn parse_set(&mut self, start: usize) -> Result<AsPathRegex, PolicyError> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        loop {
+            if self.eat(']') {
+                return Ok(AsPathRegex::Set { negated, items });
+            }
+            if self.eat('.') {
+                items.push(AsPathSetItem::Any);
+                continue;
+            }
+            let Some((word, span)) = self.word() else {
+                return Err(self.error(start..self.pos, "unclosed set in AS path"));
+            };
+            // `AS1-AS5` is lexed as a single word, `AS1 - AS5` is not.
+            if let Some((lo, hi)) = word
+                .split_once('-')
+                .and_then(|(lo, hi)| Some((parse_asn(lo)?, parse_asn(hi)?)))
+            {
+                items.push(AsPathSetItem::Range(lo, hi));
+                continue;
+            }
+            let item = self.classify(word, span)?;
+            if let AsPathSetItem::Asn(lo) = item {
+                if self.eat('-') {
+                    let Some((word, span)) = self.word() else {
+                        return Err(self.error(start..self.pos, "unclosed AS range"));
+                    };
+                    let hi =
+                        parse_asn(word).ok_or_else(|| self.error(span, "expected AS number"))?;
+                    items.push(AsPathSetItem::Range(lo, hi));
+                    continue;
+                }
+            }
+            items.push(item);
+        }
+    }
+
+    /// Postfix repetition operators following an atom.
+    fn parse_repeat(&mut self, mut atom: AsPathRegex) -> Result<AsPathRegex, PolicyError> {
+        loop {
+            let start = self.pos;
+            let same = self.eat('~');
+            let (min, max) = if self.eat('*') {
+                (0, None)
+            } else if self.eat('+') {
+                (1, None)
+            } else if !same && self.eat('?') {
+                (0, Some(1))
+            } else if self.eat('{') {
+                self.parse_bounds(start)?
+            } else if same {
+                return Err(self.error(start..self.pos, "expected '*', '+' or '{' after '~'"));
+            } else {
+                return Ok(atom);
+            };
+            atom = AsPathRegex::Repeat {
+                expr: Box::new(atom),
+                min,
+                max,
+                same,
+            };
+        }
+    }
+
+    /// `m}`, `m,}` or `m,n}` after an opening brace.
+    fn parse_bounds(&mut self, start: usize) -> Result<(u32, Option<u32>), PolicyError> {
+        let Some(len) = self.input[self.pos..].find('}') else {
+            return Err(self.error(start..self.input.len(), "unclosed repetition"));
+        };
+        let spec = &self.input[self.pos..self.pos + len];
+        self.pos += len + 1;
+        let invalid = || self.error(start..self.pos, "invalid repetition bounds");
+        let num = |s: &str| s.trim().parse::<u32>().map_err(|_| invalid());
+        match spec.split_once(',') {
+            None => {
+                let n = num(spec)?;
+                Ok((n, Some(n)))
+            }
+            Some((min, max)) if max.trim().is_empty() => Ok((num(min)?, None)),
+            Some((min, max)) => {
+                let (min, max) = (num(min)?, num(max)?);
+                if min > max {
+                    return Err(invalid());
+                }
+                Ok((min, Some(max)))
+            }
+        }
+    }
+}
@@ -0,0 +1,557 @@
+//! Parser for RPSL routing policy expressions.
+//!
+//! Covers the `import`, `export`, `mp-import` and `mp-export` attributes of
+//! `aut-num` objects as specified in RFC 2622 and RFC 4012: peerings with
+//! router expressions, actions, filters including prefix sets and AS path
+//! regular expressions, address family lists and structured policies using
+//! `refine` and `except`.
+
+mod as_path;
+mod parser;
+
+use std::{net::IpAddr, ops::Range};
+
+use ipnet::IpNet;
+use serde::Serialize;
+
+pub use self::as_path::{AsPathRegex, AsPathSetItem};
+pub(crate) use self::parser::parse_asn;
+
+/// Parse the value of an `import` attribute.
+pub fn parse_import(input: &str) -> Result<Policy, PolicyError> {
+    parser::parse_policy(input, Direction::Import, false)
+}
+
+/// Parse the value of an `export` attribute.
+pub fn parse_export(input: &str) -> Result<Policy, PolicyError> {
+    parser::parse_policy(input, Direction::Export, false)
+}
+
+/// Parse the value of an `mp-import` attribute.
+pub fn parse_mp_import(input: &str) -> Result<Policy, PolicyError> {
+    parser::parse_policy(input, Direction::Import, true)
+}
+
+/// Parse the value of an `mp-export` attribute.
+pub fn parse_mp_export(input: &str) -> Result<Policy, PolicyError> {
+    parser::parse_policy(input, Direction::Export, true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Import,
+    Export,
+}
+
+/// A parsed import or export policy.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Policy {
+    /// Protocol the routes are received from or announced with (`protocol`).
+    pub protocol: Option<String>,
+    /// Protocol the routes are imported into or exported from (`into`).
+    pub into: Option<String>,
+    /// Address families the policy applies to. Empty for `import` and
+    /// `export`, which are IPv4 unicast only.
+    pub afi: Vec<Afi>,
+    pub expression: PolicyExpression,
+}
+
+impl Policy {
+    /// All peerings of the policy, including those of nested expressions.
+    pub fn peerings(&self) -> Vec<&PeeringAction> {
+        let mut out = Vec::new();
+        let mut expr = &self.expression;
+        loop {
+            let (term, rest) = match expr {
+                PolicyExpression::Term(term) => (term, None),
+                PolicyExpression::Except { term, rest, .. }
+                | PolicyExpression::Refine { term, rest, .. } => (term, Some(rest)),
+            };
+            out.extend(term.iter().flat_map(|f| f.peerings.iter()));
+            match rest {
+                Some(rest) => expr = rest,
+                None => return out,
+            }
+        }
+    }
+}
+
+/// A policy expression, possibly structured with `except` or `refine`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PolicyExpression {
+    /// A single factor, or a `{ ... }` block of factors.
+    Term(Vec<PolicyFactor>),
+    Except {
+        term: Vec<PolicyFactor>,
+        /// Address families given after `except`, if any.
+        afi: Vec<Afi>,
+        rest: Box<PolicyExpression>,
+    },
+    Refine {
+        term: Vec<PolicyFactor>,
+        /// Address families given after `refine`, if any.
+        afi: Vec<Afi>,
+        rest: Box<PolicyExpression>,
+    },
+}
+
+/// One or more peerings with their actions, followed by a filter.
+///
+/// `from <peering> [action ...] ... accept <filter>` for imports and
+/// `to <peering> [action ...] ... announce <filter>` for exports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PolicyFactor {
+    pub peerings: Vec<PeeringAction>,
+    pub filter: Filter,
+}
+
+/// A peering and the actions applied to routes exchanged over it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeeringAction {
+    pub peering: Peering,
+    pub actions: Vec<Action>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Peering {
+    /// Reference to a `peering-set` object.
+    Set(String),
+    Expression {
+        as_expr: AsExpr,
+        /// Router expression for the routers of the peer.
+        remote_router: Option<RouterExpr>,
+        /// Router expression for the local routers, given after `at`.
+        local_router: Option<RouterExpr>,
+    },
+}
+
+/// An expression over AS numbers and as-sets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum AsExpr {
+    Asn(u32),
+    /// Reference to an `as-set` object, including `AS-ANY`.
+    Set(String),
+    And(Box<AsExpr>, Box<AsExpr>),
+    Or(Box<AsExpr>, Box<AsExpr>),
+    Except(Box<AsExpr>, Box<AsExpr>),
+}
+
+impl AsExpr {
+    /// AS numbers referenced directly by the expression.
+    pub fn asns(&self) -> Vec<u32> {
+        match self {
+            AsExpr::Asn(asn) => vec![*asn],
+            AsExpr::Set(_) => Vec::new(),
+            AsExpr::And(a, b) | AsExpr::Or(a, b) | AsExpr::Except(a, b) => {
+                let mut out = a.asns();
+                out.extend(b.asns());
+                out
+            }
+        }
+    }
+}
+
+/// An expression over router addresses, `inet-rtr` and `rtr-set` names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum RouterExpr {
+    Addr(IpAddr),
+    /// Name of an `inet-rtr` or `rtr-set` object.
+    Name(String),
+    And(Box<RouterExpr>, Box<RouterExpr>),
+    Or(Box<RouterExpr>, Box<RouterExpr>),
+    Except(Box<RouterExpr>, Box<RouterExpr>),
+}
+
+/// A single policy action, e.g. `pref = 10` or `community.append(1:2)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Action {
+    Assign {
+        attribute: String,
+        operator: String,
+        value: String,
+    },
+    Method {
+        attribute: String,
+        method: String,
+        args: Vec<String>,
+    },
+}
+
+/// A route filter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Filter {
+    Any,
+    PeerAs,
+    Asn(u32, RangeOperator),
+    /// Reference to an `as-set`, `route-set` or `filter-set` object.
+    Set(String, RangeOperator),
+    /// An address prefix set, e.g. `{ 192.0.2.0/24^+ }`.
+    Prefixes(Vec<PrefixRange>, RangeOperator),
+    AsPath(AsPathRegex),
+    /// A method call on a route attribute, e.g. `community(1:2)` or
+    /// `community.contains(1:2)`.
+    Method {
+        attribute: String,
+        method: Option<String>,
+        args: Vec<String>,
+    },
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+/// An address prefix with an optional range operator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrefixRange {
+    pub prefix: IpNet,
+    pub operator: RangeOperator,
+}
+
+/// Address prefix range operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub enum RangeOperator {
+    #[default]
+    None,
+    /// `^-`: exclusive more specifics.
+    Minus,
+    /// `^+`: inclusive more specifics.
+    Plus,
+    /// `^n`: more specifics of length `n`.
+    Length(u8),
+    /// `^n-m`: more specifics of length `n` to `m`.
+    Range(u8, u8),
+}
+
+/// Address family identifier used by `mp-import` and `mp-export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Afi {
+    Ipv4,
+    Ipv4Unicast,
+    Ipv4Multicast,
+    Ipv6,
+    Ipv6Unicast,
+    Ipv6Multicast,
+    Any,
+    AnyUnicast,
+    AnyMulticast,
+}
+
+impl Afi {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let afi = match name.to_ascii_lowercase().as_str() {
+            "ipv4" => Afi::Ipv4,
+            "ipv4.unicast" => Afi::Ipv4Unicast,
+            "ipv4.multicast" => Afi::Ipv4Multicast,
+            "ipv6" => Afi::Ipv6,
+            "ipv6.unicast" => Afi::Ipv6Unicast,
+            "ipv6.multicast" => Afi::Ipv6Multicast,
+            "any" => Afi::Any,
+            "any.unicast" => Afi::AnyUnicast,
+            "any.multicast" => Afi::AnyMulticast,
+            _ => return None,
+        };
+        Some(afi)
+    }
+}
+
+/// Error produced when a policy expression can not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyError {
+    /// Byte range of the offending input.
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl PolicyError {
+    /// The offending part of `input`, which must be the parsed string.
+    pub fn fragment<'a>(&self, input: &'a str) -> &'a str {
+        input.get(self.span.clone()).unwrap_or("")
+    }
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_objects, RpslObject};
+
+    fn single_factor(policy: &Policy) -> &PolicyFactor {
+        match &policy.expression {
+            PolicyExpression::Term(term) if term.len() == 1 => &term[0],
+            other => panic!("expected single factor, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_aut_num_policies() {
+        // Trimmed down from the RIPE NCC aut-num AS3333.
+        let text = "aut-num:        AS3333
+as-name:        RIPE-NCC-AS
+import:         from AS1103 accept ANY
+import:         from AS2914 action pref=100; community.append(3333:2914); accept ANY
+export:         to AS1103 announce AS3333
+mp-import:      afi ipv6.unicast from AS1103 accept ANY
+mp-export:      afi ipv6.unicast to AS1103 announce AS3333:AS-RIPENCC
+source:         RIPE
+";
+        let obj = RpslObject::try_from(parse_objects(text).unwrap().remove(0)).unwrap();
+        let RpslObject::AutNum(aut_num) = obj else {
+            panic!();
+        };
+
+        let imports: Vec<_> = aut_num.imports().into_iter().map(Result::unwrap).collect();
+        assert_eq!(imports.len(), 2);
+        let factor = single_factor(&imports[0]);
+        assert_eq!(factor.filter, Filter::Any);
+        match &factor.peerings[0].peering {
+            Peering::Expression { as_expr, .. } => assert_eq!(as_expr.asns(), vec![1103]),
+            other => panic!("{other:?}"),
+        }
+
+        let factor = single_factor(&imports[1]);
+        assert_eq!(
+            factor.peerings[0].actions,
+            vec![
+                Action::Assign {
+                    attribute: "pref".into(),
+                    operator: "=".into(),
+                    value: "100".into(),
+                },
+                Action::Method {
+                    attribute: "community".into(),
+                    method: "append".into(),
+                    args: vec!["3333:2914".into()],
+                },
+            ]
+        );
+
+        let export = aut_num.exports().remove(0).unwrap();
+        assert_eq!(
+            single_factor(&export).filter,
+            Filter::Asn(3333, RangeOperator::None)
+        );
+
+        let mp_import = aut_num.mp_imports().remove(0).unwrap();
+        assert_eq!(mp_import.afi, vec![Afi::Ipv6Unicast]);
+        let mp_export = aut_num.mp_exports().remove(0).unwrap();
+        assert_eq!(
+            single_factor(&mp_export).filter,
+            Filter::Set("AS3333:AS-RIPENCC".into(), RangeOperator::None)
+        );
+    }
+
+    #[test]
+    fn parse_peering_with_routers() {
+        let policy = parse_mp_import(
+            "afi ipv6.unicast from AS6939 2001:7f8::1b1b:0:1 at 2001:7f8::d1f:0:1 action pref=200; accept ANY",
+        )
+        .unwrap();
+        let factor = single_factor(&policy);
+        assert_eq!(
+            factor.peerings[0].peering,
+            Peering::Expression {
+                as_expr: AsExpr::Asn(6939),
+                remote_router: Some(RouterExpr::Addr("2001:7f8::1b1b:0:1".parse().unwrap())),
+                local_router: Some(RouterExpr::Addr("2001:7f8::d1f:0:1".parse().unwrap())),
+            }
+        );
+
+        let policy = parse_export("to AS-ANY EXCEPT (AS1 OR AS2) announce AS-FOO").unwrap();
+        assert_eq!(
+            single_factor(&policy).peerings[0].peering,
+            Peering::Expression {
+                as_expr: AsExpr::Except(
+                    Box::new(AsExpr::Set("AS-ANY".into())),
+                    Box::new(AsExpr::Or(
+                        Box::new(AsExpr::Asn(1)),
+                        Box::new(AsExpr::Asn(2))
+                    )),
+                ),
+                remote_router: None,
+                local_router: None,
+            }
+        );
+
+        let policy = parse_import("from PRNG-EXAMPLE accept ANY").unwrap();
+        assert_eq!(
+            single_factor(&policy).peerings[0].peering,
+            Peering::Set("PRNG-EXAMPLE".into())
+        );
+    }
+
+    #[test]
+    fn parse_filters() {
+        let policy = parse_import(
+            "from AS1 accept NOT fltr-bogons AND { 192.0.2.0/24^+, 10.0.0.0/8^16-24 }",
+        )
+        .unwrap();
+        assert_eq!(
+            single_factor(&policy).filter,
+            Filter::And(
+                Box::new(Filter::Not(Box::new(Filter::Set(
+                    "fltr-bogons".into(),
+                    RangeOperator::None
+                )))),
+                Box::new(Filter::Prefixes(
+                    vec![
+                        PrefixRange {
+                            prefix: "192.0.2.0/24".parse().unwrap(),
+                            operator: RangeOperator::Plus,
+                        },
+                        PrefixRange {
+                            prefix: "10.0.0.0/8".parse().unwrap(),
+                            operator: RangeOperator::Range(16, 24),
+                        },
+                    ],
+                    RangeOperator::None
+                )),
+            )
+        );
+
+        // Filters without an operator between them are OR'ed.
+        let policy = parse_import("from AS1 accept AS1 AS-FOO^24 community(65535:666)").unwrap();
+        assert_eq!(
+            single_factor(&policy).filter,
+            Filter::Or(
+                Box::new(Filter::Or(
+                    Box::new(Filter::Asn(1, RangeOperator::None)),
+                    Box::new(Filter::Set("AS-FOO".into(), RangeOperator::Length(24))),
+                )),
+                Box::new(Filter::Method {
+                    attribute: "community".into(),
+                    method: None,
+                    args: vec!["65535:666".into()],
+                }),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_as_path_regex() {
+        let policy =
+            parse_import("from AS1 accept <^AS1+ [AS2-AS5 AS-FOO]* .? AS6~{1,3}$>").unwrap();
+        let Filter::AsPath(regex) = &single_factor(&policy).filter else {
+            panic!();
+        };
+        assert_eq!(
+            regex,
+            &AsPathRegex::Sequence(vec![
+                AsPathRegex::Start,
+                AsPathRegex::Repeat {
+                    expr: Box::new(AsPathRegex::Asn(1)),
+                    min: 1,
+                    max: None,
+                    same: false,
+                },
+                AsPathRegex::Repeat {
+                    expr: Box::new(AsPathRegex::Set {
+                        negated: false,
+                        items: vec![
+                            AsPathSetItem::Range(2, 5),
+                            AsPathSetItem::AsSet("AS-FOO".into()),
+                        ],
+                    }),
+                    min: 0,
+                    max: None,
+                    same: false,
+                },
+                AsPathRegex::Repeat {
+                    expr: Box::new(AsPathRegex::Any),
+                    min: 0,
+                    max: Some(1),
+                    same: false,
+                },
+                AsPathRegex::Repeat {
+                    expr: Box::new(AsPathRegex::Asn(6)),
+                    min: 1,
+                    max: Some(3),
+                    same: true,
+                },
+                AsPathRegex::End,
+            ])
+        );
+
+        let policy = parse_import("from AS1 accept <^PeerAS (AS2|AS3)$>").unwrap();
+        let Filter::AsPath(AsPathRegex::Sequence(items)) = &single_factor(&policy).filter else {
+            panic!();
+        };
+        assert_eq!(
+            items[2],
+            AsPathRegex::Alternation(vec![AsPathRegex::Asn(2), AsPathRegex::Asn(3)])
+        );
+    }
+
+    #[test]
+    fn parse_structured_policy() {
+        // Example from RFC 2622, section 6.6.
+        let policy = parse_import(
+            "from AS1 action pref = 1; accept as-foo; \
+             except { from AS2 action pref = 2; accept AS226; \
+             except { from AS3 action pref = 3; accept {128.9.0.0/16}; } }",
+        )
+        .unwrap();
+        let PolicyExpression::Except { term, rest, .. } = &policy.expression else {
+            panic!("{:?}", policy.expression);
+        };
+        assert_eq!(
+            term[0].filter,
+            Filter::Set("as-foo".into(), RangeOperator::None)
+        );
+        let PolicyExpression::Except { rest, .. } = rest.as_ref() else {
+            panic!("{rest:?}");
+        };
+        assert!(matches!(rest.as_ref(), PolicyExpression::Term(t) if t.len() == 1));
+        assert_eq!(policy.peerings().len(), 3);
+
+        let policy = parse_mp_import(
+            "{ from AS-ANY action pref = 1; accept community(3560:10); \
+               from AS-ANY action pref = 2; accept community(3560:20); \
+             } refine afi ipv4.unicast, ipv6.unicast { from AS1 accept AS1; from AS2 accept AS2; }",
+        )
+        .unwrap();
+        let PolicyExpression::Refine { term, afi, rest } = &policy.expression else {
+            panic!("{:?}", policy.expression);
+        };
+        assert_eq!(term.len(), 2);
+        assert_eq!(afi, &vec![Afi::Ipv4Unicast, Afi::Ipv6Unicast]);
+        assert!(matches!(rest.as_ref(), PolicyExpression::Term(t) if t.len() == 2));
+
+        let policy = parse_import("protocol BGP4 into OSPF from AS1 accept ANY").unwrap();
+        assert_eq!(policy.protocol.as_deref(), Some("BGP4"));
+        assert_eq!(policy.into.as_deref(), Some("OSPF"));
+    }
+
+    #[test]
+    fn errors_keep_span() {
+        let input = "from AS1 accept {10.0.0.0/33}";
+        let err = parse_import(input).unwrap_err();
+        assert_eq!(err.fragment(input), "10.0.0.0/33");
+
+        let input = "from AS1 accept <^AS1 foo$>";
+        let err = parse_import(input).unwrap_err();
+        assert_eq!(err.fragment(input), "foo");
+
+        let input = "from AS1 announce ANY";
+        let err = parse_import(input).unwrap_err();
+        assert_eq!(err.fragment(input), "announce");
+        assert!(err.to_string().starts_with("expected 'accept'"));
+
+        let input = "afi ipv5 from AS1 accept ANY";
+        let err = parse_mp_import(input).unwrap_err();
+        assert_eq!(err.fragment(input), "ipv5");
+
+        // `afi` is only valid in multiprotocol attributes.
+        assert!(parse_import(input).is_err());
+    }
+}
@@ -0,0 +1,655 @@
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+
+use super::{
+    as_path, Action, Afi, AsExpr, Direction, Filter, Peering, PeeringAction, Policy, PolicyError,
+    PolicyExpression, PolicyFactor, PrefixRange, RangeOperator, RouterExpr,
+};
+
+/// Keywords that can not start an expression operand.
+const KEYWORDS: &[&str] = &[
+    "from", "to", "accept", "announce", "action", "at", "except", "refine", "afi", "and", "or",
+    "not", "protocol", "into",
+];
+
+pub(super) fn parse_policy(
+    input: &str,
+    direction: Direction,
+    multiprotocol: bool,
+) -> Result<Policy, PolicyError> {
+    // Trailing comments are not part of the policy.
+    let input = &input[..input.find('#').unwrap_or(input.len())];
+    let mut p = Parser {
+        input,
+        pos: 0,
+        direction,
+        multiprotocol,
+    };
+
+    let protocol = if p.eat_keyword("protocol") {
+        Some(p.expect_word("protocol name")?.0.to_string())
+    } else {
+        None
+    };
+    let into = if p.eat_keyword("into") {
+        Some(p.expect_word("protocol name")?.0.to_string())
+    } else {
+        None
+    };
+    let afi = p.parse_optional_afi()?;
+    let expression = p.parse_expression()?;
+
+    p.skip_ws();
+    if !p.at_end() {
+        return Err(p.error_here("unexpected input"));
+    }
+
+    Ok(Policy {
+        protocol,
+        into,
+        afi,
+        expression,
+    })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.' | '/')
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+/// Parse an AS number in `AS<n>` or `AS<high>.<low>` notation.
+pub(crate) fn parse_asn(word: &str) -> Option<u32> {
+    let num = word
+        .get(..2)
+        .filter(|p| p.eq_ignore_ascii_case("as"))
+        .map(|_| &word[2..])?;
+    if num.is_empty() || !num.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    match num.split_once('.') {
+        Some((high, low)) => {
+            let high: u16 = high.parse().ok()?;
+            let low: u16 = low.parse().ok()?;
+            Some(((high as u32) << 16) | low as u32)
+        }
+        None => num.parse().ok(),
+    }
+}
+
+/// Whether any component of a hierarchical set name starts with `prefix`.
+fn is_set_name(word: &str, prefix: &str) -> bool {
+    word.split(':').any(|c| {
+        c.len() > prefix.len()
+            && c.get(..prefix.len())
+                .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+    })
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    direction: Direction,
+    multiprotocol: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, span: std::ops::Range<usize>, message: impl Into<String>) -> PolicyError {
+        PolicyError {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Error spanning the next token, or the end of the input.
+    fn error_here(&self, message: impl Into<String>) -> PolicyError {
+        let rest = &self.input[self.pos..];
+        let len = match rest.chars().next() {
+            None => 0,
+            Some(c) if is_word_char(c) => rest.find(|c| !is_word_char(c)).unwrap_or(rest.len()),
+            Some(c) => c.len_utf8(),
+        };
+        self.error(self.pos..self.pos + len, message)
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat_char(&mut self, c: char) -> bool {
+        if self.peek_char() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_char(&mut self, c: char) -> Result<(), PolicyError> {
+        if self.eat_char(c) {
+            Ok(())
+        } else {
+            Err(self.error_here(format!("expected '{c}'")))
+        }
+    }
+
+    /// The next word and its span, without consuming it.
+    fn peek_word(&mut self) -> Option<(&'a str, std::ops::Range<usize>)> {
+        self.skip_ws();
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            None
+        } else {
+            Some((&rest[..len], self.pos..self.pos + len))
+        }
+    }
+
+    fn next_word(&mut self) -> Option<(&'a str, std::ops::Range<usize>)> {
+        let word = self.peek_word()?;
+        self.pos = word.1.end;
+        Some(word)
+    }
+
+    fn expect_word(
+        &mut self,
+        what: &str,
+    ) -> Result<(&'a str, std::ops::Range<usize>), PolicyError> {
+        self.next_word()
+            .ok_or_else(|| self.error_here(format!("expected {what}")))
+    }
+
+    fn peek_keyword(&mut self, keyword: &str) -> bool {
+        self.peek_word()
+            .is_some_and(|(w, _)| w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.next_word();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the next token is a word that is not a keyword.
+    fn peek_operand_word(&mut self) -> bool {
+        self.peek_word().is_some_and(|(w, _)| !is_keyword(w))
+    }
+
+    fn peering_keywords(&self) -> (&'static str, &'static str) {
+        match self.direction {
+            Direction::Import => ("from", "accept"),
+            Direction::Export => ("to", "announce"),
+        }
+    }
+
+    fn parse_optional_afi(&mut self) -> Result<Vec<Afi>, PolicyError> {
+        if !self.multiprotocol || !self.eat_keyword("afi") {
+            return Ok(Vec::new());
+        }
+        let mut afis = Vec::new();
+        loop {
+            let (word, span) = self.expect_word("address family")?;
+            let afi = Afi::from_name(word)
+                .ok_or_else(|| self.error(span, format!("unknown address family '{word}'")))?;
+            afis.push(afi);
+            if !self.eat_char(',') {
+                return Ok(afis);
+            }
+        }
+    }
+
+    fn parse_expression(&mut self) -> Result<PolicyExpression, PolicyError> {
+        if self.peek_char() == Some('{') {
+            let open = self.pos;
+            self.pos += 1;
+            let expr = self.parse_block()?;
+            self.expect_char('}')?;
+            self.eat_char(';');
+            return match expr {
+                PolicyExpression::Term(term) => self.parse_structure(term),
+                _ if self.peek_keyword("except") || self.peek_keyword("refine") => Err(self.error(
+                    open..self.pos,
+                    "structured block can not be followed by except or refine",
+                )),
+                expr => Ok(expr),
+            };
+        }
+
+        let factor = self.parse_factor()?;
+        self.eat_char(';');
+        self.parse_structure(vec![factor])
+    }
+
+    /// Factors inside `{ ... }`, up to the closing brace.
+    fn parse_block(&mut self) -> Result<PolicyExpression, PolicyError> {
+        let mut factors = Vec::new();
+        loop {
+            if self.peek_char() == Some('}') {
+                return Ok(PolicyExpression::Term(factors));
+            }
+            if !factors.is_empty() && (self.peek_keyword("except") || self.peek_keyword("refine")) {
+                return self.parse_structure(factors);
+            }
+            factors.push(self.parse_factor()?);
+            if self.peek_char() != Some('}') {
+                self.expect_char(';')?;
+            }
+        }
+    }
+
+    /// An optional `except` or `refine` following a term.
+    fn parse_structure(
+        &mut self,
+        term: Vec<PolicyFactor>,
+    ) -> Result<PolicyExpression, PolicyError> {
+        let refine = if self.eat_keyword("except") {
+            false
+        } else if self.eat_keyword("refine") {
+            true
+        } else {
+            return Ok(PolicyExpression::Term(term));
+        };
+        let afi = self.parse_optional_afi()?;
+        let rest = Box::new(self.parse_expression()?);
+        Ok(if refine {
+            PolicyExpression::Refine { term, afi, rest }
+        } else {
+            PolicyExpression::Except { term, afi, rest }
+        })
+    }
+
+    fn parse_factor(&mut self) -> Result<PolicyFactor, PolicyError> {
+        let (peer_kw, filter_kw) = self.peering_keywords();
+        let mut peerings = Vec::new();
+        while self.eat_keyword(peer_kw) {
+            let peering = self.parse_peering()?;
+            let actions = if self.eat_keyword("action") {
+                self.parse_actions()?
+            } else {
+                Vec::new()
+            };
+            peerings.push(PeeringAction { peering, actions });
+        }
+        if peerings.is_empty() {
+            return Err(self.error_here(format!("expected '{peer_kw}'")));
+        }
+        if !self.eat_keyword(filter_kw) {
+            return Err(self.error_here(format!("expected '{filter_kw}'")));
+        }
+        let filter = self.parse_filter()?;
+        Ok(PolicyFactor { peerings, filter })
+    }
+
+    fn parse_peering(&mut self) -> Result<Peering, PolicyError> {
+        if let Some((word, _)) = self.peek_word() {
+            if is_set_name(word, "prng-") {
+                self.next_word();
+                return Ok(Peering::Set(word.to_string()));
+            }
+        }
+
+        let as_expr = self.parse_as_or()?;
+        let remote_router = if self.peek_operand_word() || self.peek_char() == Some('(') {
+            Some(self.parse_router_or()?)
+        } else {
+            None
+        };
+        let local_router = if self.eat_keyword("at") {
+            Some(self.parse_router_or()?)
+        } else {
+            None
+        };
+        Ok(Peering::Expression {
+            as_expr,
+            remote_router,
+            local_router,
+        })
+    }
+
+    fn parse_as_or(&mut self) -> Result<AsExpr, PolicyError> {
+        let mut lhs = self.parse_as_and()?;
+        while self.eat_keyword("or") {
+            let rhs = self.parse_as_and()?;
+            lhs = AsExpr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_as_and(&mut self) -> Result<AsExpr, PolicyError> {
+        let mut lhs = self.parse_as_primary()?;
+        loop {
+            if self.eat_keyword("and") {
+                let rhs = self.parse_as_primary()?;
+                lhs = AsExpr::And(Box::new(lhs), Box::new(rhs));
+            } else if self.eat_keyword("except") {
+                let rhs = self.parse_as_primary()?;
+                lhs = AsExpr::Except(Box::new(lhs), Box::new(rhs));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn parse_as_primary(&mut self) -> Result<AsExpr, PolicyError> {
+        if self.eat_char('(') {
+            let expr = self.parse_as_or()?;
+            self.expect_char(')')?;
+            return Ok(expr);
+        }
+        let (word, span) = self.expect_word("AS number or as-set")?;
+        if let Some(asn) = parse_asn(word) {
+            Ok(AsExpr::Asn(asn))
+        } else if is_set_name(word, "as-") {
+            Ok(AsExpr::Set(word.to_string()))
+        } else {
+            Err(self.error(span, format!("expected AS number or as-set, got '{word}'")))
+        }
+    }
+
+    fn parse_router_or(&mut self) -> Result<RouterExpr, PolicyError> {
+        let mut lhs = self.parse_router_and()?;
+        while self.eat_keyword("or") {
+            let rhs = self.parse_router_and()?;
+            lhs = RouterExpr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_router_and(&mut self) -> Result<RouterExpr, PolicyError> {
+        let mut lhs = self.parse_router_primary()?;
+        loop {
+            if self.eat_keyword("and") {
+                let rhs = self.parse_router_primary()?;
+                lhs = RouterExpr::And(Box::new(lhs), Box::new(rhs));
+            } else if self.eat_keyword("except") {
+                let rhs = self.parse_router_primary()?;
+                lhs = RouterExpr::Except(Box::new(lhs), Box::new(rhs));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn parse_router_primary(&mut self) -> Result<RouterExpr, PolicyError> {
+        if self.eat_char('(') {
+            let expr = self.parse_router_or()?;
+            self.expect_char(')')?;
+            return Ok(expr);
+        }
+        let (word, span) = self.expect_word("router address or name")?;
+        if is_keyword(word) {
+            return Err(self.error(span, format!("expected router expression, got '{word}'")));
+        }
+        Ok(match word.parse::<IpAddr>() {
+            Ok(addr) => RouterExpr::Addr(addr),
+            Err(_) => RouterExpr::Name(word.to_string()),
+        })
+    }
+
+    /// Actions following `action`, up to the next peering or filter keyword.
+    fn parse_actions(&mut self) -> Result<Vec<Action>, PolicyError> {
+        let (peer_kw, filter_kw) = self.peering_keywords();
+        let mut actions = Vec::new();
+        loop {
+            if self.peek_keyword(peer_kw) || self.peek_keyword(filter_kw) {
+                return Ok(actions);
+            }
+            let (word, span) = self.expect_word("action")?;
+
+            if self.input[self.pos..].starts_with('(') {
+                self.pos += 1;
+                let Some((attribute, method)) = word.split_once('.') else {
+                    return Err(self.error(span, "expected attribute method call"));
+                };
+                let args = self.parse_args()?;
+                actions.push(Action::Method {
+                    attribute: attribute.to_string(),
+                    method: method.to_string(),
+                    args,
+                });
+            } else {
+                // `community.= {..}` is lexed as the word `community.`.
+                let (attribute, op_prefix) = match word.strip_suffix('.') {
+                    Some(attr) => (attr, "."),
+                    None => (word, ""),
+                };
+                self.skip_ws();
+                let rest = &self.input[self.pos..];
+                let op_len = rest
+                    .find(|c| !matches!(c, '=' | '<' | '>' | '!' | '.' | '+' | '-' | '*' | '/'))
+                    .unwrap_or(rest.len());
+                if op_len == 0 && op_prefix.is_empty() {
+                    return Err(self.error_here("expected action operator"));
+                }
+                let operator = format!("{op_prefix}{}", &rest[..op_len]);
+                self.pos += op_len;
+                let value = self.scan_raw(&[peer_kw, filter_kw]);
+                if value.is_empty() {
+                    return Err(self.error_here("expected action value"));
+                }
+                actions.push(Action::Assign {
+                    attribute: attribute.to_string(),
+                    operator,
+                    value: value.to_string(),
+                });
+            }
+            self.eat_char(';');
+        }
+    }
+
+    /// Raw text up to a top level `;`, closing bracket or one of `stop`.
+    fn scan_raw(&mut self, stop: &[&str]) -> &'a str {
+        let start = self.pos;
+        let mut depth = 0usize;
+        while let Some(c) = self.input[self.pos..].chars().next() {
+            match c {
+                ';' if depth == 0 => break,
+                '(' | '{' => depth += 1,
+                ')' | '}' if depth == 0 => break,
+                ')' | '}' => depth -= 1,
+                c if is_word_char(c) => {
+                    let rest = &self.input[self.pos..];
+                    let len = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+                    if depth == 0 && stop.iter().any(|k| k.eq_ignore_ascii_case(&rest[..len])) {
+                        break;
+                    }
+                    self.pos += len;
+                    continue;
+                }
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+        self.input[start..self.pos].trim()
+    }
+
+    /// Comma separated arguments after an opening parenthesis, including the
+    /// closing parenthesis.
+    fn parse_args(&mut self) -> Result<Vec<String>, PolicyError> {
+        let open = self.pos - 1;
+        let mut args = Vec::new();
+        let mut arg_start = self.pos;
+        let mut depth = 0usize;
+        while let Some(c) = self.input[self.pos..].chars().next() {
+            match c {
+                '(' | '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                ')' if depth > 0 => depth -= 1,
+                ',' | ')' if depth == 0 => {
+                    let arg = self.input[arg_start..self.pos].trim();
+                    if !arg.is_empty() {
+                        args.push(arg.to_string());
+                    }
+                    self.pos += 1;
+                    if c == ')' {
+                        return Ok(args);
+                    }
+                    arg_start = self.pos;
+                    continue;
+                }
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+        Err(self.error(open..self.pos, "unterminated argument list"))
+    }
+
+    fn parse_filter(&mut self) -> Result<Filter, PolicyError> {
+        let mut lhs = self.parse_filter_and()?;
+        loop {
+            // Filters next to each other without an operator are OR'ed.
+            let implicit = matches!(self.peek_char(), Some('(' | '{' | '<'))
+                || self.peek_operand_word()
+                || self.peek_keyword("not");
+            if !self.eat_keyword("or") && !implicit {
+                return Ok(lhs);
+            }
+            let rhs = self.parse_filter_and()?;
+            lhs = Filter::Or(Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_filter_and(&mut self) -> Result<Filter, PolicyError> {
+        let mut lhs = self.parse_filter_not()?;
+        while self.eat_keyword("and") {
+            let rhs = self.parse_filter_not()?;
+            lhs = Filter::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_filter_not(&mut self) -> Result<Filter, PolicyError> {
+        if self.eat_keyword("not") {
+            Ok(Filter::Not(Box::new(self.parse_filter_not()?)))
+        } else {
+            self.parse_filter_primary()
+        }
+    }
+
+    fn parse_filter_primary(&mut self) -> Result<Filter, PolicyError> {
+        match self.peek_char() {
+            Some('(') => {
+                self.pos += 1;
+                let filter = self.parse_filter()?;
+                self.expect_char(')')?;
+                return Ok(filter);
+            }
+            Some('{') => {
+                self.pos += 1;
+                let prefixes = self.parse_prefix_set()?;
+                let op = self.parse_range_operator()?;
+                return Ok(Filter::Prefixes(prefixes, op));
+            }
+            Some('<') => {
+                let start = self.pos + 1;
+                let Some(len) = self.input[start..].find('>') else {
+                    return Err(self.error(self.pos..self.input.len(), "unterminated AS path"));
+                };
+                self.pos = start + len + 1;
+                let regex = as_path::parse(&self.input[start..start + len], start)?;
+                return Ok(Filter::AsPath(regex));
+            }
+            _ => {}
+        }
+
+        let (word, span) = self.expect_word("filter")?;
+        if is_keyword(word) {
+            return Err(self.error(span, format!("expected filter, got '{word}'")));
+        }
+        if word.eq_ignore_ascii_case("any") {
+            return Ok(Filter::Any);
+        }
+        if word.eq_ignore_ascii_case("peeras") {
+            return Ok(Filter::PeerAs);
+        }
+        if self.input[self.pos..].starts_with('(') {
+            self.pos += 1;
+            let (attribute, method) = match word.split_once('.') {
+                Some((attr, method)) => (attr, Some(method.to_string())),
+                None => (word, None),
+            };
+            let args = self.parse_args()?;
+            return Ok(Filter::Method {
+                attribute: attribute.to_string(),
+                method,
+                args,
+            });
+        }
+        let op = self.parse_range_operator()?;
+        match parse_asn(word) {
+            Some(asn) => Ok(Filter::Asn(asn, op)),
+            None => Ok(Filter::Set(word.to_string(), op)),
+        }
+    }
+
+    /// Prefixes of an address prefix set, after the opening brace.
+    fn parse_prefix_set(&mut self) -> Result<Vec<PrefixRange>, PolicyError> {
+        let mut prefixes = Vec::new();
+        if self.eat_char('}') {
+            return Ok(prefixes);
+        }
+        loop {
+            let (word, span) = self.expect_word("address prefix")?;
+            let prefix: IpNet = word
+                .parse()
+                .map_err(|_| self.error(span, format!("invalid address prefix '{word}'")))?;
+            let operator = self.parse_range_operator()?;
+            prefixes.push(PrefixRange { prefix, operator });
+            if self.eat_char('}') {
+                return Ok(prefixes);
+            }
+            self.expect_char(',')?;
+        }
+    }
+
+    /// An optional range operator directly following a prefix or set.
+    fn parse_range_operator(&mut self) -> Result<RangeOperator, PolicyError> {
+        let start = self.pos;
+        let rest = &self.input[self.pos..];
+        let Some(rest) = rest.strip_prefix('^') else {
+            return Ok(RangeOperator::None);
+        };
+        if rest.starts_with('+') {
+            self.pos += 2;
+            return Ok(RangeOperator::Plus);
+        }
+        if rest.starts_with('-') {
+            self.pos += 2;
+            return Ok(RangeOperator::Minus);
+        }
+
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '-')
+            .unwrap_or(rest.len());
+        self.pos += 1 + len;
+        let invalid = || self.error(start..self.pos, "invalid range operator");
+        let spec = &rest[..len];
+        match spec.split_once('-') {
+            Some((n, m)) => {
+                let n = n.parse().map_err(|_| invalid())?;
+                let m = m.parse().map_err(|_| invalid())?;
+                if n > m {
+                    return Err(invalid());
+                }
+                Ok(RangeOperator::Range(n, m))
+            }
+            None => Ok(RangeOperator::Length(spec.parse().map_err(|_| invalid())?)),
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Value of the `status` attribute of `inetnum` and `inet6num` objects.
+///
+/// Covers the vocabularies of RIPE, APNIC, AFRINIC, LACNIC and ARIN. Known
+/// values are matched case-insensitively and displayed in their canonical
+/// RIPE style spelling; anything else is kept verbatim as
+/// [`InetStatus::Unknown`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InetStatus {
+    /// `ALLOCATED PA`
+    AllocatedPa,
+    /// `ALLOCATED PI`
+    AllocatedPi,
+    /// `ALLOCATED UNSPECIFIED`
+    AllocatedUnspecified,
+    /// `ALLOCATED-ASSIGNED PA`
+    AllocatedAssignedPa,
+    /// `ALLOCATED-BY-RIR`
+    AllocatedByRir,
+    /// `ALLOCATED-BY-LIR`
+    AllocatedByLir,
+    /// `ALLOCATED PORTABLE` (APNIC)
+    AllocatedPortable,
+    /// `ALLOCATED NON-PORTABLE` (APNIC)
+    AllocatedNonPortable,
+    /// `ALLOCATED` (LACNIC, ARIN `Allocation`)
+    Allocated,
+    /// `REALLOCATED` (LACNIC, ARIN `Reallocation`)
+    Reallocated,
+    /// `LIR-PARTITIONED PA`
+    LirPartitionedPa,
+    /// `LIR-PARTITIONED PI`
+    LirPartitionedPi,
+    /// `SUB-ALLOCATED PA`
+    SubAllocatedPa,
+    /// `AGGREGATED-BY-LIR`
+    AggregatedByLir,
+    /// `ASSIGNED PA`
+    AssignedPa,
+    /// `ASSIGNED PI`
+    AssignedPi,
+    /// `ASSIGNED ANYCAST`
+    AssignedAnycast,
+    /// `ASSIGNED PORTABLE` (APNIC)
+    AssignedPortable,
+    /// `ASSIGNED NON-PORTABLE` (APNIC)
+    AssignedNonPortable,
+    /// `ASSIGNED` (inet6num, LACNIC, ARIN `Assignment`)
+    Assigned,
+    /// `REASSIGNED` (LACNIC, ARIN `Reassignment`)
+    Reassigned,
+    /// `EARLY-REGISTRATION`
+    EarlyRegistration,
+    /// `LEGACY`
+    Legacy,
+    /// `NOT-SET`
+    NotSet,
+    Unknown(String),
+}
+
+impl InetStatus {
+    /// Known statuses and their canonical spelling.
+    const NAMES: &'static [(InetStatus, &'static str)] = &[
+        (InetStatus::AllocatedPa, "ALLOCATED PA"),
+        (InetStatus::AllocatedPi, "ALLOCATED PI"),
+        (InetStatus::AllocatedUnspecified, "ALLOCATED UNSPECIFIED"),
+        (InetStatus::AllocatedAssignedPa, "ALLOCATED-ASSIGNED PA"),
+        (InetStatus::AllocatedByRir, "ALLOCATED-BY-RIR"),
+        (InetStatus::AllocatedByLir, "ALLOCATED-BY-LIR"),
+        (InetStatus::AllocatedPortable, "ALLOCATED PORTABLE"),
+        (InetStatus::AllocatedNonPortable, "ALLOCATED NON-PORTABLE"),
+        (InetStatus::Allocated, "ALLOCATED"),
+        (InetStatus::Reallocated, "REALLOCATED"),
+        (InetStatus::LirPartitionedPa, "LIR-PARTITIONED PA"),
+        (InetStatus::LirPartitionedPi, "LIR-PARTITIONED PI"),
+        (InetStatus::SubAllocatedPa, "SUB-ALLOCATED PA"),
+        (InetStatus::AggregatedByLir, "AGGREGATED-BY-LIR"),
+        (InetStatus::AssignedPa, "ASSIGNED PA"),
+        (InetStatus::AssignedPi, "ASSIGNED PI"),
+        (InetStatus::AssignedAnycast, "ASSIGNED ANYCAST"),
+        (InetStatus::AssignedPortable, "ASSIGNED PORTABLE"),
+        (InetStatus::AssignedNonPortable, "ASSIGNED NON-PORTABLE"),
+        (InetStatus::Assigned, "ASSIGNED"),
+        (InetStatus::Reassigned, "REASSIGNED"),
+        (InetStatus::EarlyRegistration, "EARLY-REGISTRATION"),
+        (InetStatus::Legacy, "LEGACY"),
+        (InetStatus::NotSet, "NOT-SET"),
+    ];
+
+    /// Alternative spellings used by some registries.
+    const ALIASES: &'static [(&'static str, InetStatus)] = &[
+        ("ALLOCATION", InetStatus::Allocated),
+        ("DIRECT ALLOCATION", InetStatus::Allocated),
+        ("REALLOCATION", InetStatus::Reallocated),
+        ("ASSIGNMENT", InetStatus::Assigned),
+        ("DIRECT ASSIGNMENT", InetStatus::Assigned),
+        ("REASSIGNMENT", InetStatus::Reassigned),
+    ];
+
+    /// Parse a status value. Never fails, unknown values are kept verbatim.
+    pub fn parse(value: &str) -> Self {
+        let normalized = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        Self::NAMES
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(&normalized))
+            .map(|(status, _)| status.clone())
+            .or_else(|| {
+                Self::ALIASES
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(&normalized))
+                    .map(|(_, status)| status.clone())
+            })
+            .unwrap_or_else(|| InetStatus::Unknown(value.to_string()))
+    }
+
+    /// The canonical spelling of the status.
+    pub fn as_str(&self) -> &str {
+        match self {
+            InetStatus::Unknown(value) => value,
+            known => Self::NAMES
+                .iter()
+                .find(|(status, _)| status == known)
+                .map(|(_, name)| *name)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Whether the range was allocated to an organisation that assigns or
+    /// sub-allocates parts of it, usually an LIR.
+    pub fn is_allocation(&self) -> bool {
+        matches!(
+            self,
+            InetStatus::AllocatedPa
+                | InetStatus::AllocatedPi
+                | InetStatus::AllocatedUnspecified
+                | InetStatus::AllocatedAssignedPa
+                | InetStatus::AllocatedByRir
+                | InetStatus::AllocatedByLir
+                | InetStatus::AllocatedPortable
+                | InetStatus::AllocatedNonPortable
+                | InetStatus::Allocated
+                | InetStatus::Reallocated
+                | InetStatus::SubAllocatedPa
+                | InetStatus::LirPartitionedPa
+                | InetStatus::LirPartitionedPi
+        )
+    }
+
+    /// Whether the range was assigned to an end user.
+    pub fn is_assignment(&self) -> bool {
+        matches!(
+            self,
+            InetStatus::AllocatedAssignedPa
+                | InetStatus::AssignedPa
+                | InetStatus::AssignedPi
+                | InetStatus::AssignedAnycast
+                | InetStatus::AssignedPortable
+                | InetStatus::AssignedNonPortable
+                | InetStatus::Assigned
+                | InetStatus::Reassigned
+        )
+    }
+}
+
+impl std::fmt::Display for InetStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for InetStatus {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(InetStatus::parse(s))
+    }
+}
+
+impl Serialize for InetStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for InetStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(InetStatus::parse(&value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_registry_vocabularies() {
+        assert_eq!(InetStatus::parse("ALLOCATED PA"), InetStatus::AllocatedPa);
+        assert_eq!(InetStatus::parse("assigned   pa"), InetStatus::AssignedPa);
+        assert_eq!(
+            InetStatus::parse("ALLOCATED NON-PORTABLE"),
+            InetStatus::AllocatedNonPortable
+        );
+        assert_eq!(InetStatus::parse("reassigned"), InetStatus::Reassigned);
+        assert_eq!(
+            InetStatus::parse("Direct Allocation"),
+            InetStatus::Allocated
+        );
+        assert_eq!(
+            InetStatus::parse("AGGREGATED-BY-LIR"),
+            InetStatus::AggregatedByLir
+        );
+        assert_eq!(
+            InetStatus::parse("SOMETHING ELSE"),
+            InetStatus::Unknown("SOMETHING ELSE".into())
+        );
+
+        assert_eq!(
+            InetStatus::parse("sub-allocated pa").to_string(),
+            "SUB-ALLOCATED PA"
+        );
+        assert_eq!(InetStatus::Unknown("odd".into()).to_string(), "odd");
+        assert!(InetStatus::SubAllocatedPa.is_allocation());
+        assert!(!InetStatus::SubAllocatedPa.is_assignment());
+        assert!(InetStatus::AssignedPi.is_assignment());
+        assert!(!InetStatus::Legacy.is_allocation());
+    }
+
+    #[test]
+    fn serde_uses_status_text() {
+        let json = serde_json::to_string(&InetStatus::AllocatedPa).unwrap();
+        assert_eq!(json, "\"ALLOCATED PA\"");
+        let status: InetStatus = serde_json::from_str("\"assigned pi\"").unwrap();
+        assert_eq!(status, InetStatus::AssignedPi);
+    }
+}
@@ -1,65 +1,137 @@
-use crate::Object;
+use crate::{
+    policy::{self, Policy, PolicyError},
+    InetStatus, Object, ObjectType,
+};
 use anyhow::{anyhow, bail, Context, Error};
 use ipnet::{Ipv4Net, Ipv6Net};
-use iprange::IpRange;
-use serde::Serialize;
-use std::collections::HashMap;
+use iprange::{IpNet, IpRange};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use time::{macros::format_description, Date, OffsetDateTime, PrimitiveDateTime};
 
+/// Attributes shared by all typed objects.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommonAttributes {
+    pub mnt_by: Vec<String>,
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub created: Option<OffsetDateTime>,
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub last_modified: Option<OffsetDateTime>,
+    #[serde(deserialize_with = "deserialize_datetime")]
+    pub changed: Option<OffsetDateTime>,
+    pub source: Option<String>,
+    pub remarks: Vec<String>,
+    pub notify: Vec<String>,
+    pub abuse_c: Option<String>,
+    pub mnt_lower: Vec<String>,
+    pub mnt_routes: Vec<String>,
+    pub mnt_irt: Vec<String>,
+    pub geoloc: Option<String>,
+    pub geofeed: Option<String>,
+    pub language: Vec<String>,
+}
+
 /// Data for an `inetnum` object
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Inetnum {
+    #[serde(deserialize_with = "deserialize_range")]
     pub inetnum: IpRange<Ipv4Net>,
     pub netname: Option<String>,
     pub descr: Option<String>,
     pub country: Option<String>,
+    #[serde(default)]
     pub admin_c: Vec<String>,
+    #[serde(default)]
     pub tech_c: Vec<String>,
-    pub status: Option<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub changed: Option<OffsetDateTime>,
-    pub source: Option<String>,
+    pub status: Option<InetStatus>,
     pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Inet6num {
+    #[serde(deserialize_with = "deserialize_range")]
     pub inet6num: IpRange<Ipv6Net>,
     pub netname: Option<String>,
     pub descr: Option<String>,
     pub country: Option<String>,
+    #[serde(default)]
     pub admin_c: Vec<String>,
+    #[serde(default)]
     pub tech_c: Vec<String>,
-    pub status: Option<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub changed: Option<OffsetDateTime>,
-    pub source: Option<String>,
+    pub status: Option<InetStatus>,
     pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AutNum {
     pub aut_num: String,
     pub as_name: Option<String>,
     pub descr: Option<String>,
+    #[serde(default)]
     pub member_of: Vec<String>,
+    #[serde(default)]
     pub import: Vec<String>,
+    #[serde(default)]
     pub export: Vec<String>,
+    #[serde(default)]
+    pub mp_import: Vec<String>,
+    #[serde(default)]
+    pub mp_export: Vec<String>,
+    #[serde(default)]
     pub admin_c: Vec<String>,
+    #[serde(default)]
     pub tech_c: Vec<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub changed: Option<OffsetDateTime>,
-    pub source: Option<String>,
     pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+impl AutNum {
+    /// Parsed `import` policies, in attribute order.
+    pub fn imports(&self) -> Vec<Result<Policy, PolicyError>> {
+        self.import
+            .iter()
+            .map(|v| policy::parse_import(v))
+            .collect()
+    }
+
+    /// Parsed `export` policies, in attribute order.
+    pub fn exports(&self) -> Vec<Result<Policy, PolicyError>> {
+        self.export
+            .iter()
+            .map(|v| policy::parse_export(v))
+            .collect()
+    }
+
+    /// Parsed `mp-import` policies, in attribute order.
+    pub fn mp_imports(&self) -> Vec<Result<Policy, PolicyError>> {
+        self.mp_import
+            .iter()
+            .map(|v| policy::parse_mp_import(v))
+            .collect()
+    }
+
+    /// Parsed `mp-export` policies, in attribute order.
+    pub fn mp_exports(&self) -> Vec<Result<Policy, PolicyError>> {
+        self.mp_export
+            .iter()
+            .map(|v| policy::parse_mp_export(v))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Person {
     pub person: String,
     pub address: Option<String>,
@@ -67,32 +139,32 @@ pub struct Person {
     pub fax_no: Option<String>,
     pub email: Option<String>,
     pub nic_hdl: Option<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub changed: Option<OffsetDateTime>,
-    pub source: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Role {
     pub role: String,
     pub address: Option<String>,
     pub phone: Option<String>,
     pub fax_no: Option<String>,
     pub email: Option<String>,
+    #[serde(default)]
     pub admin_c: Vec<String>,
+    #[serde(default)]
     pub tech_c: Vec<String>,
     pub nic_hdl: Option<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub changed: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub source: Option<String>,
     pub abuse_mailbox: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Organisation {
     pub organisation: String,
     pub org_name: Option<String>,
@@ -100,59 +172,304 @@ pub struct Organisation {
     pub address: Option<String>,
     pub email: Option<String>,
     pub abuse_mailbox: Option<String>,
+    #[serde(default)]
     pub mnt_ref: Vec<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub source: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Mntner {
     pub mntner: String,
     pub descr: Option<String>,
+    #[serde(default)]
     pub admin_c: Vec<String>,
+    #[serde(default)]
     pub tech_c: Vec<String>,
+    #[serde(default)]
     pub upd_to: Vec<String>,
+    #[serde(default)]
     pub mnt_nfy: Vec<String>,
+    #[serde(default)]
     pub auth: Vec<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub source: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Route {
+    #[serde(deserialize_with = "deserialize_range")]
     pub route: IpRange<Ipv4Net>,
     pub descr: Option<String>,
     pub origin: Option<String>,
+    #[serde(default)]
     pub member_of: Vec<String>,
+    #[serde(default)]
     pub inject: Vec<String>,
     pub aggr_mtd: Option<String>,
     pub aggr_bndry: Option<String>,
     pub export_comps: Option<String>,
     pub components: Option<String>,
+    #[serde(default)]
     pub holes: Vec<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub source: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Route6 {
+    #[serde(deserialize_with = "deserialize_range")]
     pub route6: IpRange<Ipv6Net>,
     pub descr: Option<String>,
     pub origin: Option<String>,
+    #[serde(default)]
     pub member_of: Vec<String>,
-    pub mnt_by: Vec<String>,
-    pub created: Option<OffsetDateTime>,
-    pub last_modified: Option<OffsetDateTime>,
-    pub source: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for an `as-set` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsSet {
+    pub as_set: String,
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub mbrs_by_ref: Vec<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for a `route-set` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteSet {
+    pub route_set: String,
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub mp_members: Vec<String>,
+    #[serde(default)]
+    pub mbrs_by_ref: Vec<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for an `rtr-set` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RtrSet {
+    pub rtr_set: String,
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub mp_members: Vec<String>,
+    #[serde(default)]
+    pub mbrs_by_ref: Vec<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for a reverse DNS `domain` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Domain {
+    pub domain: String,
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    #[serde(default)]
+    pub zone_c: Vec<String>,
+    #[serde(default)]
+    pub nserver: Vec<String>,
+    #[serde(default)]
+    pub ds_rdata: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for an `inet-rtr` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InetRtr {
+    pub inet_rtr: String,
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub alias: Vec<String>,
+    pub local_as: Option<String>,
+    #[serde(default)]
+    pub ifaddr: Vec<String>,
+    #[serde(default)]
+    pub interface: Vec<String>,
+    #[serde(default)]
+    pub peer: Vec<String>,
+    #[serde(default)]
+    pub mp_peer: Vec<String>,
+    #[serde(default)]
+    pub member_of: Vec<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for an `irt` (incident response team) object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Irt {
+    pub irt: String,
+    pub address: Option<String>,
+    pub phone: Option<String>,
+    pub fax_no: Option<String>,
+    pub email: Option<String>,
+    pub abuse_mailbox: Option<String>,
+    #[serde(default)]
+    pub signature: Vec<String>,
+    #[serde(default)]
+    pub encryption: Vec<String>,
+    #[serde(default)]
+    pub auth: Vec<String>,
+    #[serde(default)]
+    pub irt_nfy: Vec<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for a `key-cert` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyCert {
+    pub key_cert: String,
+    pub method: Option<String>,
+    #[serde(default)]
+    pub owner: Vec<String>,
+    pub fingerpr: Option<String>,
+    /// The certificate, one line per `certif` attribute.
+    pub certif: Option<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for an `as-block` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsBlock {
+    pub as_block: String,
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for a `peering-set` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeeringSet {
+    pub peering_set: String,
+    pub descr: Option<String>,
+    #[serde(default)]
+    pub peering: Vec<String>,
+    #[serde(default)]
+    pub mp_peering: Vec<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// Data for a `filter-set` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterSet {
+    pub filter_set: String,
+    pub descr: Option<String>,
+    pub filter: Option<String>,
+    pub mp_filter: Option<String>,
+    #[serde(default)]
+    pub admin_c: Vec<String>,
+    #[serde(default)]
+    pub tech_c: Vec<String>,
+    pub org: Option<String>,
+    #[serde(flatten)]
+    pub common: CommonAttributes,
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<String>>,
+}
+
+/// A problem found while converting an [`Object`] into an [`RpslObject`]
+/// that did not prevent the conversion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConversionWarning {
+    pub attribute: String,
+    pub value: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConversionWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: '{}': {}", self.attribute, self.value, self.message)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RpslObject {
     Inetnum(Inetnum),
     Inet6num(Inet6num),
@@ -163,6 +480,16 @@ pub enum RpslObject {
     Mntner(Mntner),
     Route(Route),
     Route6(Route6),
+    AsSet(AsSet),
+    RouteSet(RouteSet),
+    RtrSet(RtrSet),
+    Domain(Domain),
+    InetRtr(InetRtr),
+    Irt(Irt),
+    KeyCert(KeyCert),
+    AsBlock(AsBlock),
+    PeeringSet(PeeringSet),
+    FilterSet(FilterSet),
     Other(Object),
 }
 
@@ -223,34 +550,90 @@ fn parse_datetime_flexible(s: &str) -> Result<OffsetDateTime, anyhow::Error> {
     bail!("invalid datetime format: {s}")
 }
 
+/// Pop the first value of an attribute.
+///
+/// Any further values are left in the map, so they end up in `extra`.
 fn pop_single(map: &mut HashMap<String, Vec<String>>, key: &str) -> Option<String> {
-    map.remove(key).and_then(|mut v| {
-        if v.is_empty() {
-            None
-        } else {
-            Some(v.remove(0))
-        }
-    })
+    let values = map.get_mut(key)?;
+    let value = (!values.is_empty()).then(|| values.remove(0));
+    if values.is_empty() {
+        map.remove(key);
+    }
+    value
 }
 
 fn pop_multi(map: &mut HashMap<String, Vec<String>>, key: &str) -> Vec<String> {
     map.remove(key).unwrap_or_default()
 }
 
+/// Pop a multi-valued attribute whose values are comma separated lists, such
+/// as `members`, and return the individual list items.
+fn pop_list(map: &mut HashMap<String, Vec<String>>, key: &str) -> Vec<String> {
+    map.remove(key)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 fn pop_text(map: &mut HashMap<String, Vec<String>>, key: &str) -> Option<String> {
     map.remove(key).map(|v| v.join("\n"))
 }
 
+/// Pop and parse a date attribute.
+///
+/// Unparsable values are reported as a warning and kept in the map.
 fn pop_datetime(
     map: &mut HashMap<String, Vec<String>>,
     key: &str,
-) -> Result<Option<OffsetDateTime>, Error> {
-    match pop_single(map, key) {
-        Some(s) => Ok(parse_datetime_flexible(&s).ok()),
-        None => Ok(None),
+    warnings: &mut Vec<ConversionWarning>,
+) -> Option<OffsetDateTime> {
+    let value = pop_single(map, key)?;
+    match parse_datetime_flexible(&value) {
+        Ok(dt) => Some(dt),
+        Err(err) => {
+            warnings.push(ConversionWarning {
+                attribute: key.to_string(),
+                value: value.clone(),
+                message: err.to_string(),
+            });
+            map.entry(key.to_string()).or_default().insert(0, value);
+            None
+        }
     }
 }
 
+/// Pop the attributes shared by all typed objects.
+fn pop_common(
+    map: &mut HashMap<String, Vec<String>>,
+    warnings: &mut Vec<ConversionWarning>,
+) -> CommonAttributes {
+    CommonAttributes {
+        mnt_by: pop_multi(map, "mnt-by"),
+        created: pop_datetime(map, "created", warnings),
+        last_modified: pop_datetime(map, "last-modified", warnings),
+        changed: pop_datetime(map, "changed", warnings),
+        source: pop_single(map, "source"),
+        remarks: pop_multi(map, "remarks"),
+        notify: pop_multi(map, "notify"),
+        abuse_c: pop_single(map, "abuse-c"),
+        mnt_lower: pop_multi(map, "mnt-lower"),
+        mnt_routes: pop_multi(map, "mnt-routes"),
+        mnt_irt: pop_multi(map, "mnt-irt"),
+        geoloc: pop_single(map, "geoloc"),
+        geofeed: pop_single(map, "geofeed"),
+        language: pop_multi(map, "language"),
+    }
+}
+
+/// Collect the attributes that were not consumed by a typed field.
+fn into_extra(map: HashMap<String, Vec<String>>) -> BTreeMap<String, Vec<String>> {
+    map.into_iter().filter(|(_, v)| !v.is_empty()).collect()
+}
+
 fn parse_ipv4_range(s: &str) -> Result<IpRange<Ipv4Net>, Error> {
     let trimmed = s.trim();
     if let Ok(net) = trimmed.parse::<Ipv4Net>() {
@@ -383,7 +766,572 @@ impl TryFrom<Object> for RpslObject {
     type Error = anyhow::Error;
 
     fn try_from(obj: Object) -> Result<Self, Self::Error> {
+        RpslObject::from_object(obj).map(|(obj, _warnings)| obj)
+    }
+}
+
+impl From<RpslObject> for Object {
+    fn from(obj: RpslObject) -> Self {
+        match obj {
+            RpslObject::Inetnum(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Inetnum);
+                w.single("inetnum", Some(format_ipv4_range(&o.inetnum, false)));
+                w.single("netname", o.netname);
+                w.text("descr", o.descr);
+                w.single("country", o.country);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("status", o.status.map(|s| s.to_string()));
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Inet6num(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Inet6num);
+                w.single("inet6num", Some(format_ipv6_range(&o.inet6num, true)));
+                w.single("netname", o.netname);
+                w.text("descr", o.descr);
+                w.single("country", o.country);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("status", o.status.map(|s| s.to_string()));
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::AutNum(o) => {
+                let mut w = ObjectWriter::new(ObjectType::AutNum);
+                w.single("aut-num", Some(o.aut_num));
+                w.single("as-name", o.as_name);
+                w.text("descr", o.descr);
+                w.multi("member-of", o.member_of);
+                w.multi("import", o.import);
+                w.multi("export", o.export);
+                w.multi("mp-import", o.mp_import);
+                w.multi("mp-export", o.mp_export);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Person(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Person);
+                w.single("person", Some(o.person));
+                w.text("address", o.address);
+                w.single("phone", o.phone);
+                w.single("fax-no", o.fax_no);
+                w.single("e-mail", o.email);
+                w.single("nic-hdl", o.nic_hdl);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Role(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Role);
+                w.single("role", Some(o.role));
+                w.text("address", o.address);
+                w.single("phone", o.phone);
+                w.single("fax-no", o.fax_no);
+                w.single("e-mail", o.email);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("nic-hdl", o.nic_hdl);
+                w.single("abuse-mailbox", o.abuse_mailbox);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Organisation(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Organisation);
+                w.single("organisation", Some(o.organisation));
+                w.single("org-name", o.org_name);
+                w.single("org-type", o.org_type);
+                w.text("address", o.address);
+                w.single("e-mail", o.email);
+                w.single("abuse-mailbox", o.abuse_mailbox);
+                w.multi("mnt-ref", o.mnt_ref);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Mntner(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Mntner);
+                w.single("mntner", Some(o.mntner));
+                w.text("descr", o.descr);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.multi("upd-to", o.upd_to);
+                w.multi("mnt-nfy", o.mnt_nfy);
+                w.multi("auth", o.auth);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Route(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Route);
+                w.single("route", Some(format_ipv4_range(&o.route, true)));
+                w.text("descr", o.descr);
+                w.single("origin", o.origin);
+                w.multi("member-of", o.member_of);
+                w.multi("inject", o.inject);
+                w.single("aggr-mtd", o.aggr_mtd);
+                w.single("aggr-bndry", o.aggr_bndry);
+                w.single("export-comps", o.export_comps);
+                w.single("components", o.components);
+                w.multi("holes", o.holes);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Route6(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Route6);
+                w.single("route6", Some(format_ipv6_range(&o.route6, true)));
+                w.text("descr", o.descr);
+                w.single("origin", o.origin);
+                w.multi("member-of", o.member_of);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::AsSet(o) => {
+                let mut w = ObjectWriter::new(ObjectType::AsSet);
+                w.single("as-set", Some(o.as_set));
+                w.text("descr", o.descr);
+                w.list("members", o.members);
+                w.list("mbrs-by-ref", o.mbrs_by_ref);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::RouteSet(o) => {
+                let mut w = ObjectWriter::new(ObjectType::RouteSet);
+                w.single("route-set", Some(o.route_set));
+                w.text("descr", o.descr);
+                w.list("members", o.members);
+                w.list("mp-members", o.mp_members);
+                w.list("mbrs-by-ref", o.mbrs_by_ref);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::RtrSet(o) => {
+                let mut w = ObjectWriter::new(ObjectType::RtrSet);
+                w.single("rtr-set", Some(o.rtr_set));
+                w.text("descr", o.descr);
+                w.list("members", o.members);
+                w.list("mp-members", o.mp_members);
+                w.list("mbrs-by-ref", o.mbrs_by_ref);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Domain(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Domain);
+                w.single("domain", Some(o.domain));
+                w.text("descr", o.descr);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.multi("zone-c", o.zone_c);
+                w.multi("nserver", o.nserver);
+                w.multi("ds-rdata", o.ds_rdata);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::InetRtr(o) => {
+                let mut w = ObjectWriter::new(ObjectType::InetRtr);
+                w.single("inet-rtr", Some(o.inet_rtr));
+                w.text("descr", o.descr);
+                w.multi("alias", o.alias);
+                w.single("local-as", o.local_as);
+                w.multi("ifaddr", o.ifaddr);
+                w.multi("interface", o.interface);
+                w.multi("peer", o.peer);
+                w.multi("mp-peer", o.mp_peer);
+                w.multi("member-of", o.member_of);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Irt(o) => {
+                let mut w = ObjectWriter::new(ObjectType::Irt);
+                w.single("irt", Some(o.irt));
+                w.text("address", o.address);
+                w.single("phone", o.phone);
+                w.single("fax-no", o.fax_no);
+                w.single("e-mail", o.email);
+                w.single("abuse-mailbox", o.abuse_mailbox);
+                w.multi("signature", o.signature);
+                w.multi("encryption", o.encryption);
+                w.multi("auth", o.auth);
+                w.multi("irt-nfy", o.irt_nfy);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::KeyCert(o) => {
+                let mut w = ObjectWriter::new(ObjectType::KeyCert);
+                w.single("key-cert", Some(o.key_cert));
+                w.single("method", o.method);
+                w.multi("owner", o.owner);
+                w.single("fingerpr", o.fingerpr);
+                w.text("certif", o.certif);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::AsBlock(o) => {
+                let mut w = ObjectWriter::new(ObjectType::AsBlock);
+                w.single("as-block", Some(o.as_block));
+                w.text("descr", o.descr);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::PeeringSet(o) => {
+                let mut w = ObjectWriter::new(ObjectType::PeeringSet);
+                w.single("peering-set", Some(o.peering_set));
+                w.text("descr", o.descr);
+                w.multi("peering", o.peering);
+                w.multi("mp-peering", o.mp_peering);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::FilterSet(o) => {
+                let mut w = ObjectWriter::new(ObjectType::FilterSet);
+                w.single("filter-set", Some(o.filter_set));
+                w.text("descr", o.descr);
+                w.single("filter", o.filter);
+                w.single("mp-filter", o.mp_filter);
+                w.multi("admin-c", o.admin_c);
+                w.multi("tech-c", o.tech_c);
+                w.single("org", o.org);
+                w.common(o.common, o.extra);
+                w.object
+            }
+            RpslObject::Other(obj) => obj,
+        }
+    }
+}
+
+/// Builds an [`Object`] from typed fields, skipping empty values.
+struct ObjectWriter {
+    object: Object,
+}
+
+impl ObjectWriter {
+    fn new(obj_type: ObjectType) -> Self {
+        ObjectWriter {
+            object: Object::new(obj_type),
+        }
+    }
+
+    fn single(&mut self, key: &str, value: Option<String>) {
+        if let Some(value) = value {
+            self.object.add(key.to_string(), value);
+        }
+    }
+
+    fn multi(&mut self, key: &str, values: Vec<String>) {
+        for value in values {
+            self.object.add(key.to_string(), value);
+        }
+    }
+
+    /// Write a comma separated list attribute on a single line.
+    fn list(&mut self, key: &str, values: Vec<String>) {
+        if !values.is_empty() {
+            self.object.add(key.to_string(), values.join(", "));
+        }
+    }
+
+    /// Write multi-line text, one attribute per line.
+    fn text(&mut self, key: &str, value: Option<String>) {
+        if let Some(value) = value {
+            self.multi(key, value.split('\n').map(str::to_string).collect());
+        }
+    }
+
+    fn datetime(&mut self, key: &str, value: Option<OffsetDateTime>) {
+        let Some(value) = value else {
+            return;
+        };
+        // `changed` carries a plain date, the other attributes use RFC 3339.
+        let formatted = if key == "changed" {
+            value.format(format_description!("[year][month][day]"))
+        } else {
+            value.format(&time::format_description::well_known::Rfc3339)
+        };
+        if let Ok(formatted) = formatted {
+            self.object.add(key.to_string(), formatted);
+        }
+    }
+
+    /// Write the common attributes and the unknown attributes in `extra`.
+    ///
+    /// Dates and `source` go last, as in registry dumps.
+    fn common(&mut self, common: CommonAttributes, extra: BTreeMap<String, Vec<String>>) {
+        self.multi("mnt-by", common.mnt_by);
+        self.multi("remarks", common.remarks);
+        self.multi("notify", common.notify);
+        self.single("abuse-c", common.abuse_c);
+        self.multi("mnt-lower", common.mnt_lower);
+        self.multi("mnt-routes", common.mnt_routes);
+        self.multi("mnt-irt", common.mnt_irt);
+        self.single("geoloc", common.geoloc);
+        self.single("geofeed", common.geofeed);
+        self.multi("language", common.language);
+        for (key, values) in extra {
+            self.multi(&key, values);
+        }
+        self.datetime("created", common.created);
+        self.datetime("last-modified", common.last_modified);
+        self.datetime("changed", common.changed);
+        self.single("source", common.source);
+    }
+}
+
+/// Format an IPv4 range as a single prefix if possible, or as an
+/// `first - last` address range.
+fn format_ipv4_range(range: &IpRange<Ipv4Net>, prefer_prefix: bool) -> String {
+    let nets: Vec<Ipv4Net> = range.iter().collect();
+    if prefer_prefix && nets.len() == 1 {
+        return nets[0].to_string();
+    }
+    let first = nets.iter().map(|n| n.network()).min();
+    let last = nets.iter().map(|n| n.broadcast()).max();
+    match (first, last) {
+        (Some(first), Some(last)) => format!("{first} - {last}"),
+        _ => String::new(),
+    }
+}
+
+/// Format an IPv6 range, see [`format_ipv4_range`].
+fn format_ipv6_range(range: &IpRange<Ipv6Net>, prefer_prefix: bool) -> String {
+    let nets: Vec<Ipv6Net> = range.iter().collect();
+    if prefer_prefix && nets.len() == 1 {
+        return nets[0].to_string();
+    }
+    let first = nets.iter().map(|n| n.network()).min();
+    let last = nets.iter().map(|n| n.broadcast()).max();
+    match (first, last) {
+        (Some(first), Some(last)) => format!("{first} - {last}"),
+        _ => String::new(),
+    }
+}
+
+/// Deserialize a date either in the native serde representation of
+/// [`OffsetDateTime`] or from a string in any supported RPSL date format.
+fn deserialize_datetime<'de, D>(deserializer: D) -> Result<Option<OffsetDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Text(String),
+        Native(OffsetDateTime),
+    }
+
+    match Option::<Repr>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Repr::Native(dt)) => Ok(Some(dt)),
+        Some(Repr::Text(s)) => parse_datetime_flexible(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+fn deserialize_range<'de, D, N>(deserializer: D) -> Result<IpRange<N>, D::Error>
+where
+    D: Deserializer<'de>,
+    N: IpNet,
+{
+    let range = IpRange::<N>::deserialize(deserializer)?;
+    if range.is_empty() {
+        return Err(serde::de::Error::custom("empty address range"));
+    }
+    Ok(range)
+}
+
+/// Serializes the data of an [`RpslObject`] without the enum wrapper.
+struct ObjectData<'a>(&'a RpslObject);
+
+impl Serialize for ObjectData<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            RpslObject::Inetnum(o) => o.serialize(serializer),
+            RpslObject::Inet6num(o) => o.serialize(serializer),
+            RpslObject::AutNum(o) => o.serialize(serializer),
+            RpslObject::Person(o) => o.serialize(serializer),
+            RpslObject::Role(o) => o.serialize(serializer),
+            RpslObject::Organisation(o) => o.serialize(serializer),
+            RpslObject::Mntner(o) => o.serialize(serializer),
+            RpslObject::Route(o) => o.serialize(serializer),
+            RpslObject::Route6(o) => o.serialize(serializer),
+            RpslObject::AsSet(o) => o.serialize(serializer),
+            RpslObject::RouteSet(o) => o.serialize(serializer),
+            RpslObject::RtrSet(o) => o.serialize(serializer),
+            RpslObject::Domain(o) => o.serialize(serializer),
+            RpslObject::InetRtr(o) => o.serialize(serializer),
+            RpslObject::Irt(o) => o.serialize(serializer),
+            RpslObject::KeyCert(o) => o.serialize(serializer),
+            RpslObject::AsBlock(o) => o.serialize(serializer),
+            RpslObject::PeeringSet(o) => o.serialize(serializer),
+            RpslObject::FilterSet(o) => o.serialize(serializer),
+            RpslObject::Other(o) => o.serialize(serializer),
+        }
+    }
+}
+
+impl RpslObject {
+    /// The RPSL class name of the object, e.g. `inetnum`.
+    pub fn type_name(&self) -> &str {
+        match self {
+            RpslObject::Inetnum(_) => "inetnum",
+            RpslObject::Inet6num(_) => "inet6num",
+            RpslObject::AutNum(_) => "aut-num",
+            RpslObject::Person(_) => "person",
+            RpslObject::Role(_) => "role",
+            RpslObject::Organisation(_) => "organisation",
+            RpslObject::Mntner(_) => "mntner",
+            RpslObject::Route(_) => "route",
+            RpslObject::Route6(_) => "route6",
+            RpslObject::AsSet(_) => "as-set",
+            RpslObject::RouteSet(_) => "route-set",
+            RpslObject::RtrSet(_) => "rtr-set",
+            RpslObject::Domain(_) => "domain",
+            RpslObject::InetRtr(_) => "inet-rtr",
+            RpslObject::Irt(_) => "irt",
+            RpslObject::KeyCert(_) => "key-cert",
+            RpslObject::AsBlock(_) => "as-block",
+            RpslObject::PeeringSet(_) => "peering-set",
+            RpslObject::FilterSet(_) => "filter-set",
+            RpslObject::Other(o) => o.obj_type().as_key(),
+        }
+    }
+
+    /// The attributes shared by all typed objects, `None` for
+    /// [`RpslObject::Other`].
+    pub fn common(&self) -> Option<&CommonAttributes> {
+        let common = match self {
+            RpslObject::Inetnum(o) => &o.common,
+            RpslObject::Inet6num(o) => &o.common,
+            RpslObject::AutNum(o) => &o.common,
+            RpslObject::Person(o) => &o.common,
+            RpslObject::Role(o) => &o.common,
+            RpslObject::Organisation(o) => &o.common,
+            RpslObject::Mntner(o) => &o.common,
+            RpslObject::Route(o) => &o.common,
+            RpslObject::Route6(o) => &o.common,
+            RpslObject::AsSet(o) => &o.common,
+            RpslObject::RouteSet(o) => &o.common,
+            RpslObject::RtrSet(o) => &o.common,
+            RpslObject::Domain(o) => &o.common,
+            RpslObject::InetRtr(o) => &o.common,
+            RpslObject::Irt(o) => &o.common,
+            RpslObject::KeyCert(o) => &o.common,
+            RpslObject::AsBlock(o) => &o.common,
+            RpslObject::PeeringSet(o) => &o.common,
+            RpslObject::FilterSet(o) => &o.common,
+            RpslObject::Other(_) => return None,
+        };
+        Some(common)
+    }
+
+    /// The address range of an `inetnum` or `inet6num`, formatted like in
+    /// the conversion into an [`Object`].
+    pub(crate) fn normalized_range(&self) -> Option<String> {
+        match self {
+            RpslObject::Inetnum(o) => Some(format_ipv4_range(&o.inetnum, false)),
+            RpslObject::Inet6num(o) => Some(format_ipv6_range(&o.inet6num, true)),
+            _ => None,
+        }
+    }
+
+    /// The `source` attribute of the object.
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            RpslObject::Other(o) => o.get_first("source"),
+            _ => self.common()?.source.as_deref(),
+        }
+    }
+
+    /// Convert the object into RPSL text.
+    pub fn to_rpsl(&self) -> String {
+        Object::from(self.clone()).to_rpsl()
+    }
+
+    /// Serialize the object data to JSON, without the enum wrapper.
+    ///
+    /// Use [`RpslObject::from_json`] with the [`type_name`](Self::type_name)
+    /// to load it back.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&ObjectData(self))
+    }
+
+    /// Serialize the object data to a JSON value, without the enum wrapper.
+    pub fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::to_value(ObjectData(self))
+    }
+
+    /// Load an object from JSON produced by [`RpslObject::to_json`].
+    pub fn from_json(type_name: &str, json: &str) -> Result<Self, serde_json::Error> {
+        let obj = match ObjectType::from_key(type_name) {
+            ObjectType::Inetnum => RpslObject::Inetnum(serde_json::from_str(json)?),
+            ObjectType::Inet6num => RpslObject::Inet6num(serde_json::from_str(json)?),
+            ObjectType::AutNum => RpslObject::AutNum(serde_json::from_str(json)?),
+            ObjectType::Person => RpslObject::Person(serde_json::from_str(json)?),
+            ObjectType::Role => RpslObject::Role(serde_json::from_str(json)?),
+            ObjectType::Organisation => RpslObject::Organisation(serde_json::from_str(json)?),
+            ObjectType::Mntner => RpslObject::Mntner(serde_json::from_str(json)?),
+            ObjectType::Route => RpslObject::Route(serde_json::from_str(json)?),
+            ObjectType::Route6 => RpslObject::Route6(serde_json::from_str(json)?),
+            ObjectType::AsSet => RpslObject::AsSet(serde_json::from_str(json)?),
+            ObjectType::RouteSet => RpslObject::RouteSet(serde_json::from_str(json)?),
+            ObjectType::RtrSet => RpslObject::RtrSet(serde_json::from_str(json)?),
+            ObjectType::Domain => RpslObject::Domain(serde_json::from_str(json)?),
+            ObjectType::InetRtr => RpslObject::InetRtr(serde_json::from_str(json)?),
+            ObjectType::Irt => RpslObject::Irt(serde_json::from_str(json)?),
+            ObjectType::KeyCert => RpslObject::KeyCert(serde_json::from_str(json)?),
+            ObjectType::AsBlock => RpslObject::AsBlock(serde_json::from_str(json)?),
+            ObjectType::PeeringSet => RpslObject::PeeringSet(serde_json::from_str(json)?),
+            ObjectType::FilterSet => RpslObject::FilterSet(serde_json::from_str(json)?),
+            ObjectType::Other(_) => RpslObject::Other(serde_json::from_str(json)?),
+        };
+        Ok(obj)
+    }
+}
+
+impl RpslObject {
+    /// Convert a generic object into its typed representation.
+    ///
+    /// Unlike the [`TryFrom`] implementation this also returns the problems
+    /// that did not prevent the conversion, such as unparsable dates. The
+    /// values of such attributes are kept in the `extra` map.
+    pub fn from_object(obj: Object) -> Result<(Self, Vec<ConversionWarning>), Error> {
+        let mut warnings = Vec::new();
+        let obj = Self::convert(obj, &mut warnings)?;
+        Ok((obj, warnings))
+    }
+
+    fn convert(obj: Object, warnings: &mut Vec<ConversionWarning>) -> Result<Self, Error> {
         let obj_type = obj.obj_type().clone();
+        if let crate::ObjectType::Other(_) = obj_type {
+            return Ok(RpslObject::Other(obj));
+        }
         let mut map = obj.into_attributes();
         match obj_type {
             crate::ObjectType::Inetnum => {
@@ -395,13 +1343,10 @@ impl TryFrom<Object> for RpslObject {
                     country: pop_single(&mut map, "country"),
                     admin_c: pop_multi(&mut map, "admin-c"),
                     tech_c: pop_multi(&mut map, "tech-c"),
-                    status: pop_single(&mut map, "status"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    changed: pop_datetime(&mut map, "changed")?,
-                    source: pop_single(&mut map, "source"),
+                    status: pop_single(&mut map, "status").map(|s| InetStatus::parse(&s)),
                     org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -415,13 +1360,10 @@ impl TryFrom<Object> for RpslObject {
                     country: pop_single(&mut map, "country"),
                     admin_c: pop_multi(&mut map, "admin-c"),
                     tech_c: pop_multi(&mut map, "tech-c"),
-                    status: pop_single(&mut map, "status"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    changed: pop_datetime(&mut map, "changed")?,
-                    source: pop_single(&mut map, "source"),
+                    status: pop_single(&mut map, "status").map(|s| InetStatus::parse(&s)),
                     org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -435,14 +1377,13 @@ impl TryFrom<Object> for RpslObject {
                     member_of: pop_multi(&mut map, "member-of"),
                     import: pop_multi(&mut map, "import"),
                     export: pop_multi(&mut map, "export"),
+                    mp_import: pop_multi(&mut map, "mp-import"),
+                    mp_export: pop_multi(&mut map, "mp-export"),
                     admin_c: pop_multi(&mut map, "admin-c"),
                     tech_c: pop_multi(&mut map, "tech-c"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    changed: pop_datetime(&mut map, "changed")?,
-                    source: pop_single(&mut map, "source"),
                     org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -455,11 +1396,8 @@ impl TryFrom<Object> for RpslObject {
                     fax_no: pop_single(&mut map, "fax-no"),
                     email: pop_single(&mut map, "email").or_else(|| pop_single(&mut map, "e-mail")),
                     nic_hdl: pop_single(&mut map, "nic-hdl"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    changed: pop_datetime(&mut map, "changed")?,
-                    source: pop_single(&mut map, "source"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -474,12 +1412,9 @@ impl TryFrom<Object> for RpslObject {
                     admin_c: pop_multi(&mut map, "admin-c"),
                     tech_c: pop_multi(&mut map, "tech-c"),
                     nic_hdl: pop_single(&mut map, "nic-hdl"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    changed: pop_datetime(&mut map, "changed")?,
-                    source: pop_single(&mut map, "source"),
                     abuse_mailbox: pop_single(&mut map, "abuse-mailbox"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -496,10 +1431,8 @@ impl TryFrom<Object> for RpslObject {
                     email: pop_single(&mut map, "email").or_else(|| pop_single(&mut map, "e-mail")),
                     abuse_mailbox: pop_single(&mut map, "abuse-mailbox"),
                     mnt_ref: pop_multi(&mut map, "mnt-ref"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    source: pop_single(&mut map, "source"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -513,10 +1446,8 @@ impl TryFrom<Object> for RpslObject {
                     upd_to: pop_multi(&mut map, "upd-to"),
                     mnt_nfy: pop_multi(&mut map, "mnt-nfy"),
                     auth: pop_multi(&mut map, "auth"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    source: pop_single(&mut map, "source"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -533,10 +1464,8 @@ impl TryFrom<Object> for RpslObject {
                     export_comps: pop_single(&mut map, "export-comps"),
                     components: pop_single(&mut map, "components"),
                     holes: pop_multi(&mut map, "holes"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    source: pop_single(&mut map, "source"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
@@ -547,17 +1476,177 @@ impl TryFrom<Object> for RpslObject {
                     descr: pop_text(&mut map, "descr"),
                     origin: pop_single(&mut map, "origin"),
                     member_of: pop_multi(&mut map, "member-of"),
-                    mnt_by: pop_multi(&mut map, "mnt-by"),
-                    created: pop_datetime(&mut map, "created")?,
-                    last_modified: pop_datetime(&mut map, "last-modified")?,
-                    source: pop_single(&mut map, "source"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::AsSet => {
+                let as_set = pop_single(&mut map, "as-set").context("missing as-set")?;
+                let res = RpslObject::AsSet(AsSet {
+                    as_set,
+                    descr: pop_text(&mut map, "descr"),
+                    members: pop_list(&mut map, "members"),
+                    mbrs_by_ref: pop_list(&mut map, "mbrs-by-ref"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::RouteSet => {
+                let route_set = pop_single(&mut map, "route-set").context("missing route-set")?;
+                let res = RpslObject::RouteSet(RouteSet {
+                    route_set,
+                    descr: pop_text(&mut map, "descr"),
+                    members: pop_list(&mut map, "members"),
+                    mp_members: pop_list(&mut map, "mp-members"),
+                    mbrs_by_ref: pop_list(&mut map, "mbrs-by-ref"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::RtrSet => {
+                let rtr_set = pop_single(&mut map, "rtr-set").context("missing rtr-set")?;
+                let res = RpslObject::RtrSet(RtrSet {
+                    rtr_set,
+                    descr: pop_text(&mut map, "descr"),
+                    members: pop_list(&mut map, "members"),
+                    mp_members: pop_list(&mut map, "mp-members"),
+                    mbrs_by_ref: pop_list(&mut map, "mbrs-by-ref"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::Domain => {
+                let domain = pop_single(&mut map, "domain").context("missing domain")?;
+                let res = RpslObject::Domain(Domain {
+                    domain,
+                    descr: pop_text(&mut map, "descr"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    zone_c: pop_multi(&mut map, "zone-c"),
+                    nserver: pop_multi(&mut map, "nserver"),
+                    ds_rdata: pop_multi(&mut map, "ds-rdata"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::InetRtr => {
+                let inet_rtr = pop_single(&mut map, "inet-rtr").context("missing inet-rtr")?;
+                let res = RpslObject::InetRtr(InetRtr {
+                    inet_rtr,
+                    descr: pop_text(&mut map, "descr"),
+                    alias: pop_multi(&mut map, "alias"),
+                    local_as: pop_single(&mut map, "local-as"),
+                    ifaddr: pop_multi(&mut map, "ifaddr"),
+                    interface: pop_multi(&mut map, "interface"),
+                    peer: pop_multi(&mut map, "peer"),
+                    mp_peer: pop_multi(&mut map, "mp-peer"),
+                    member_of: pop_multi(&mut map, "member-of"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::Irt => {
+                let irt = pop_single(&mut map, "irt").context("missing irt")?;
+                let res = RpslObject::Irt(Irt {
+                    irt,
+                    address: pop_text(&mut map, "address"),
+                    phone: pop_single(&mut map, "phone"),
+                    fax_no: pop_single(&mut map, "fax-no"),
+                    email: pop_single(&mut map, "e-mail").or_else(|| pop_single(&mut map, "email")),
+                    abuse_mailbox: pop_single(&mut map, "abuse-mailbox"),
+                    signature: pop_multi(&mut map, "signature"),
+                    encryption: pop_multi(&mut map, "encryption"),
+                    auth: pop_multi(&mut map, "auth"),
+                    irt_nfy: pop_multi(&mut map, "irt-nfy"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::KeyCert => {
+                let key_cert = pop_single(&mut map, "key-cert").context("missing key-cert")?;
+                let res = RpslObject::KeyCert(KeyCert {
+                    key_cert,
+                    method: pop_single(&mut map, "method"),
+                    owner: pop_multi(&mut map, "owner"),
+                    fingerpr: pop_single(&mut map, "fingerpr"),
+                    certif: pop_text(&mut map, "certif"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::AsBlock => {
+                let as_block = pop_single(&mut map, "as-block").context("missing as-block")?;
+                let res = RpslObject::AsBlock(AsBlock {
+                    as_block,
+                    descr: pop_text(&mut map, "descr"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::PeeringSet => {
+                let peering_set =
+                    pop_single(&mut map, "peering-set").context("missing peering-set")?;
+                let res = RpslObject::PeeringSet(PeeringSet {
+                    peering_set,
+                    descr: pop_text(&mut map, "descr"),
+                    peering: pop_multi(&mut map, "peering"),
+                    mp_peering: pop_multi(&mut map, "mp-peering"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
+                });
+                Ok(res)
+            }
+            crate::ObjectType::FilterSet => {
+                let filter_set =
+                    pop_single(&mut map, "filter-set").context("missing filter-set")?;
+                let res = RpslObject::FilterSet(FilterSet {
+                    filter_set,
+                    descr: pop_text(&mut map, "descr"),
+                    filter: pop_single(&mut map, "filter"),
+                    mp_filter: pop_single(&mut map, "mp-filter"),
+                    admin_c: pop_multi(&mut map, "admin-c"),
+                    tech_c: pop_multi(&mut map, "tech-c"),
+                    org: pop_single(&mut map, "org"),
+                    common: pop_common(&mut map, warnings),
+                    extra: into_extra(map),
                 });
                 Ok(res)
             }
-            crate::ObjectType::Other(name) => Ok(RpslObject::Other(Object::from_attributes(
-                crate::ObjectType::Other(name),
-                map,
-            ))),
+            crate::ObjectType::Other(_) => unreachable!("handled above"),
         }
     }
 }
@@ -580,10 +1669,16 @@ mod tests {
             assert_eq!(inet.netname.as_deref(), Some("TEST-NET"));
             assert_eq!(inet.descr.as_deref(), Some("Example"));
             assert_eq!(inet.admin_c, vec!["AC1"]);
-            assert_eq!(inet.mnt_by, vec!["MAINT"]);
+            assert_eq!(inet.common.mnt_by, vec!["MAINT"]);
             assert_eq!(inet.inetnum, parse_ipv4_range("192.0.2.0/24").unwrap());
-            assert_eq!(inet.created, Some(datetime!(2001-01-01 00:00:00 UTC)));
-            assert_eq!(inet.last_modified, Some(datetime!(2002-02-02 00:00:00 UTC)));
+            assert_eq!(
+                inet.common.created,
+                Some(datetime!(2001-01-01 00:00:00 UTC))
+            );
+            assert_eq!(
+                inet.common.last_modified,
+                Some(datetime!(2002-02-02 00:00:00 UTC))
+            );
         } else {
             panic!();
         }
@@ -652,7 +1747,7 @@ mod tests {
         if let RpslObject::Person(p) = RpslObject::try_from(obj).unwrap() {
             assert_eq!(p.person, "John Doe");
             assert_eq!(p.address.as_deref(), Some("1 Main St\nTown"));
-            assert_eq!(p.mnt_by, vec!["MAINT"]);
+            assert_eq!(p.common.mnt_by, vec!["MAINT"]);
         } else {
             panic!();
         }
@@ -676,7 +1771,7 @@ mod tests {
         let obj = first(data);
         if let RpslObject::Organisation(o) = RpslObject::try_from(obj).unwrap() {
             assert_eq!(o.organisation, "ORG1");
-            assert_eq!(o.mnt_by, vec!["MAINT"]);
+            assert_eq!(o.common.mnt_by, vec!["MAINT"]);
         } else {
             panic!();
         }
@@ -724,7 +1819,7 @@ mod tests {
         let obj = first(data);
         match RpslObject::try_from(obj).unwrap() {
             RpslObject::Other(o) => {
-                assert!(o.get("poem").is_some());
+                assert!(o.get_first("poem").is_some());
             }
             _ => panic!(),
         }
@@ -735,8 +1830,8 @@ mod tests {
         let data = "person: John\nchanged: ripe-dbm@ripe.net 20040521\nsource: TEST\n";
         let obj = first(data);
         if let RpslObject::Person(p) = RpslObject::try_from(obj).unwrap() {
-            assert_eq!(p.changed, Some(datetime!(2004-05-21 00:00:00 UTC)));
-            assert_eq!(p.last_modified, None);
+            assert_eq!(p.common.changed, Some(datetime!(2004-05-21 00:00:00 UTC)));
+            assert_eq!(p.common.last_modified, None);
         } else {
             panic!();
         }
@@ -769,4 +1864,253 @@ mod tests {
         assert!(r.iter().next().is_none());
         assert!(parse_ipv6_range("invalid").is_err());
     }
+
+    #[test]
+    fn convert_as_set() {
+        let data = "as-set: AS-EXAMPLE\ndescr: Example set\nmembers: AS1, AS2\nmembers: AS-OTHER\nmbrs-by-ref: MAINT-A\nmnt-by: MAINT-A\nsource: RIPE\n";
+        if let RpslObject::AsSet(set) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(set.as_set, "AS-EXAMPLE");
+            assert_eq!(set.members, vec!["AS1", "AS2", "AS-OTHER"]);
+            assert_eq!(set.mbrs_by_ref, vec!["MAINT-A"]);
+            assert_eq!(set.common.source.as_deref(), Some("RIPE"));
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn convert_route_set() {
+        let data = "route-set: AS1:RS-TEST\nmembers: 192.0.2.0/24^+, AS2\nmp-members: 2001:db8::/32\nsource: RIPE\n";
+        if let RpslObject::RouteSet(set) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(set.route_set, "AS1:RS-TEST");
+            assert_eq!(set.members, vec!["192.0.2.0/24^+", "AS2"]);
+            assert_eq!(set.mp_members, vec!["2001:db8::/32"]);
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn convert_domain() {
+        let data = "domain: 2.0.192.in-addr.arpa\nnserver: ns1.example.net\nnserver: ns2.example.net\nzone-c: ZC1\nds-rdata: 12345 8 2 ABCDEF\nsource: RIPE\n";
+        if let RpslObject::Domain(d) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(d.domain, "2.0.192.in-addr.arpa");
+            assert_eq!(d.nserver, vec!["ns1.example.net", "ns2.example.net"]);
+            assert_eq!(d.zone_c, vec!["ZC1"]);
+            assert_eq!(d.ds_rdata, vec!["12345 8 2 ABCDEF"]);
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn convert_inet_rtr_and_irt() {
+        let data = "inet-rtr: rtr1.example.net\nlocal-as: AS1\nifaddr: 192.0.2.1 masklen 24\npeer: BGP4 192.0.2.2 asno(AS2)\nmember-of: RTRS-EXAMPLE\nsource: RIPE\n";
+        if let RpslObject::InetRtr(r) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(r.inet_rtr, "rtr1.example.net");
+            assert_eq!(r.local_as.as_deref(), Some("AS1"));
+            assert_eq!(r.ifaddr, vec!["192.0.2.1 masklen 24"]);
+            assert_eq!(r.peer, vec!["BGP4 192.0.2.2 asno(AS2)"]);
+            assert_eq!(r.member_of, vec!["RTRS-EXAMPLE"]);
+        } else {
+            panic!();
+        }
+
+        let data = "irt: IRT-EXAMPLE\naddress: Street 1\naddress: City\ne-mail: irt@example.net\nabuse-mailbox: abuse@example.net\nauth: PGPKEY-1234\nirt-nfy: nfy@example.net\nsource: RIPE\n";
+        if let RpslObject::Irt(irt) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(irt.irt, "IRT-EXAMPLE");
+            assert_eq!(irt.address.as_deref(), Some("Street 1\nCity"));
+            assert_eq!(irt.email.as_deref(), Some("irt@example.net"));
+            assert_eq!(irt.abuse_mailbox.as_deref(), Some("abuse@example.net"));
+            assert_eq!(irt.auth, vec!["PGPKEY-1234"]);
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn convert_key_cert() {
+        let data = "key-cert: PGPKEY-1234\nmethod: PGP\nowner: Example <a@example.net>\nfingerpr: AAAA BBBB\ncertif: -----BEGIN PGP PUBLIC KEY BLOCK-----\ncertif:\ncertif: mQENBF\ncertif: -----END PGP PUBLIC KEY BLOCK-----\nsource: RIPE\n";
+        if let RpslObject::KeyCert(k) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(k.key_cert, "PGPKEY-1234");
+            assert_eq!(k.method.as_deref(), Some("PGP"));
+            assert_eq!(k.owner, vec!["Example <a@example.net>"]);
+            assert_eq!(
+                k.certif.as_deref(),
+                Some("-----BEGIN PGP PUBLIC KEY BLOCK-----\n\nmQENBF\n-----END PGP PUBLIC KEY BLOCK-----")
+            );
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn convert_as_block_and_policy_sets() {
+        let data = "as-block: AS1 - AS100\ndescr: Block\nsource: RIPE\n";
+        if let RpslObject::AsBlock(b) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(b.as_block, "AS1 - AS100");
+        } else {
+            panic!();
+        }
+
+        let data =
+            "peering-set: PRNG-EXAMPLE\npeering: AS2 at 192.0.2.1\nmp-peering: AS3\nsource: RIPE\n";
+        if let RpslObject::PeeringSet(p) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(p.peering_set, "PRNG-EXAMPLE");
+            assert_eq!(p.peering, vec!["AS2 at 192.0.2.1"]);
+            assert_eq!(p.mp_peering, vec!["AS3"]);
+        } else {
+            panic!();
+        }
+
+        let data = "filter-set: FLTR-EXAMPLE\nfilter: { 192.0.2.0/24 }\nsource: RIPE\n";
+        if let RpslObject::FilterSet(f) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(f.filter_set, "FLTR-EXAMPLE");
+            assert_eq!(f.filter.as_deref(), Some("{ 192.0.2.0/24 }"));
+        } else {
+            panic!();
+        }
+
+        let data = "rtr-set: RTRS-EXAMPLE\nmembers: rtr1.example.net\nmp-members: 2001:db8::1\nsource: RIPE\n";
+        if let RpslObject::RtrSet(r) = RpslObject::try_from(first(data)).unwrap() {
+            assert_eq!(r.rtr_set, "RTRS-EXAMPLE");
+            assert_eq!(r.members, vec!["rtr1.example.net"]);
+            assert_eq!(r.mp_members, vec!["2001:db8::1"]);
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn keeps_common_and_unknown_attributes() {
+        let data = "inetnum: 192.0.2.0/24\nnetname: TEST-NET\ncountry: NL\ncountry: DE\nremarks: first\nremarks: second\nabuse-c: AB1\ngeofeed: https://example.net/geofeed.csv\ngeoloc: 52.37 4.89\nlanguage: NL\nnotify: noc@example.net\nmnt-lower: MAINT-LOW\nmnt-routes: MAINT-RT\nmnt-irt: IRT-EXAMPLE\nsponsoring-org: ORG-SP1\nsource: TEST\n";
+        let RpslObject::Inetnum(inet) = RpslObject::try_from(first(data)).unwrap() else {
+            panic!();
+        };
+        assert_eq!(inet.country.as_deref(), Some("NL"));
+        assert_eq!(inet.common.remarks, vec!["first", "second"]);
+        assert_eq!(inet.common.abuse_c.as_deref(), Some("AB1"));
+        assert_eq!(
+            inet.common.geofeed.as_deref(),
+            Some("https://example.net/geofeed.csv")
+        );
+        assert_eq!(inet.common.geoloc.as_deref(), Some("52.37 4.89"));
+        assert_eq!(inet.common.language, vec!["NL"]);
+        assert_eq!(inet.common.notify, vec!["noc@example.net"]);
+        assert_eq!(inet.common.mnt_lower, vec!["MAINT-LOW"]);
+        assert_eq!(inet.common.mnt_routes, vec!["MAINT-RT"]);
+        assert_eq!(inet.common.mnt_irt, vec!["IRT-EXAMPLE"]);
+        assert_eq!(
+            inet.extra,
+            BTreeMap::from([
+                ("country".to_string(), vec!["DE".to_string()]),
+                ("sponsoring-org".to_string(), vec!["ORG-SP1".to_string()]),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_invalid_dates() {
+        let data = "person: John Doe\nnic-hdl: JD1\ncreated: yesterday\nlast-modified: 2020-01-02T03:04:05Z\nsource: TEST\n";
+        let (obj, warnings) = RpslObject::from_object(first(data)).unwrap();
+        let RpslObject::Person(person) = obj else {
+            panic!();
+        };
+        assert_eq!(person.common.created, None);
+        assert_eq!(
+            person.common.last_modified,
+            Some(datetime!(2020-01-02 03:04:05 UTC))
+        );
+        assert_eq!(person.extra["created"], vec!["yesterday"]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].attribute, "created");
+        assert_eq!(warnings[0].value, "yesterday");
+    }
+
+    #[test]
+    fn typed_to_rpsl_roundtrip() {
+        let samples = [
+            "inetnum: 192.0.2.0 - 192.0.2.255\nnetname: TEST-NET\ndescr: Line 1\ndescr: Line 2\ncountry: NL\nadmin-c: AC1\nstatus: ASSIGNED PA\nmnt-by: MAINT\nsponsoring-org: ORG-SP1\ncreated: 2001-01-01T00:00:00Z\nlast-modified: 2002-02-02T10:00:00Z\nsource: TEST\n",
+            "inet6num: 2001:db8::/32\nnetname: V6-NET\ncountry: NL\nsource: TEST\n",
+            "route: 192.0.2.0/24\norigin: AS1\nmnt-by: MAINT\nsource: TEST\n",
+            "aut-num: AS1\nas-name: EXAMPLE\nimport: from AS2 accept ANY\nmp-export: afi ipv6 to AS2 announce AS1\nsource: TEST\n",
+            "person: John Doe\naddress: Street 1\naddress: City\ne-mail: john@example.net\nnic-hdl: JD1\nsource: TEST\n",
+            "as-set: AS-EXAMPLE\nmembers: AS1, AS2\nsource: TEST\n",
+        ];
+        for text in samples {
+            let typed = RpslObject::try_from(first(text)).unwrap();
+            let rendered = typed.to_rpsl();
+            assert_eq!(rendered, text, "unexpected rendering of {typed:?}");
+            assert_eq!(RpslObject::try_from(first(&rendered)).unwrap(), typed);
+        }
+
+        let other = first("poem: Roses\nauthor: Someone\n");
+        assert_eq!(
+            Object::from(RpslObject::try_from(other.clone()).unwrap()),
+            other
+        );
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let text = "inetnum: 192.0.2.0/24\nnetname: TEST-NET\nlanguage: EN\nfoo: bar\ncreated: 20010101\nsource: TEST\n";
+        let typed = RpslObject::try_from(first(text)).unwrap();
+
+        let json = typed.to_json().unwrap();
+        assert_eq!(
+            RpslObject::from_json(typed.type_name(), &json).unwrap(),
+            typed
+        );
+        assert_eq!(typed.source(), Some("TEST"));
+
+        // Common attributes are flattened into the top level object.
+        let value = typed.to_json_value().unwrap();
+        assert_eq!(value["source"], "TEST");
+        assert_eq!(value["language"], serde_json::json!(["EN"]));
+        assert!(value.get("common").is_none());
+
+        let tagged = serde_json::to_string(&typed).unwrap();
+        let back: RpslObject = serde_json::from_str(&tagged).unwrap();
+        assert_eq!(back, typed);
+    }
+
+    #[test]
+    fn deserialize_rpsl_dates_and_missing_fields() {
+        let json = r#"{"person": "John Doe", "created": "20200102", "last_modified": "2020-01-02T03:04:05Z"}"#;
+        let person: Person = serde_json::from_str(json).unwrap();
+        assert_eq!(person.person, "John Doe");
+        assert_eq!(
+            person.common.created,
+            Some(datetime!(2020-01-02 00:00:00 UTC))
+        );
+        assert_eq!(
+            person.common.last_modified,
+            Some(datetime!(2020-01-02 03:04:05 UTC))
+        );
+        assert!(person.common.mnt_by.is_empty());
+
+        assert!(
+            serde_json::from_str::<Person>(r#"{"person": "John Doe", "created": "never"}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn deserialize_rejects_missing_primary_key() {
+        let err = serde_json::from_str::<Person>(r#"{"source": "TEST"}"#).unwrap_err();
+        assert!(err.to_string().contains("missing field `person`"), "{err}");
+        let err = RpslObject::from_json("aut-num", r#"{"as_name": "EXAMPLE"}"#).unwrap_err();
+        assert!(err.to_string().contains("missing field `aut_num`"), "{err}");
+        assert!(RpslObject::from_json("mntner", "{}").is_err());
+
+        let typed = RpslObject::try_from(first("inetnum: 192.0.2.0/24\n")).unwrap();
+        let mut value = typed.to_json_value().unwrap();
+        value.as_object_mut().unwrap().remove("inetnum");
+        assert!(RpslObject::from_json("inetnum", &value.to_string()).is_err());
+        assert!(RpslObject::from_json("inetnum", r#"{"inetnum": null}"#).is_err());
+
+        value["inetnum"] = serde_json::to_value(IpRange::<Ipv4Net>::new()).unwrap();
+        let err = RpslObject::from_json("inetnum", &value.to_string()).unwrap_err();
+        assert!(err.to_string().contains("empty address range"), "{err}");
+    }
 }
@@ -0,0 +1,249 @@
+//! Validation of RPSL objects against per-type templates.
+//!
+//! [`validate`] checks that mandatory attributes are present, single
+//! attributes are not repeated, unknown attributes are flagged and values
+//! match the expected syntax.
+
+mod syntax;
+mod template;
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+pub use self::syntax::Syntax;
+pub use self::template::{
+    template, AttributeTemplate, Cardinality, KeyKind, Requirement, Template,
+};
+
+use crate::Object;
+
+/// Severity of a [`Finding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// Kind of problem described by a [`Finding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    /// There is no template for the object type.
+    UnknownType,
+    /// A mandatory attribute is missing.
+    MissingAttribute,
+    /// An attribute that may only appear once is repeated.
+    RepeatedAttribute,
+    /// The attribute is not part of the template.
+    UnknownAttribute,
+    /// The value does not match the syntax of the attribute.
+    InvalidSyntax,
+    /// The object could not be parsed and was skipped.
+    MalformedObject,
+}
+
+/// A problem found in an object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub kind: FindingKind,
+    /// The affected attribute.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<String>,
+    /// Source line of the affected attribute, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{}: ", self.severity)?;
+        if let Some(attribute) = &self.attribute {
+            write!(f, "{}: ", attribute)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+/// Validate an object against the template of its type.
+///
+/// Findings are ordered by attribute position, followed by missing
+/// attributes.
+pub fn validate(obj: &Object) -> Vec<Finding> {
+    let obj_type = obj.obj_type().as_key();
+    let Some(template) = template(obj_type) else {
+        return vec![Finding {
+            severity: Severity::Warning,
+            kind: FindingKind::UnknownType,
+            attribute: None,
+            line: obj.ordered_attributes().first().and_then(|a| a.line()),
+            message: format!("unknown object type '{}'", obj_type),
+        }];
+    };
+
+    let mut findings = Vec::new();
+    let mut counts = HashMap::<&str, usize>::new();
+    for attr in obj.ordered_attributes() {
+        let finding = |severity, kind, message| Finding {
+            severity,
+            kind,
+            attribute: Some(attr.key().to_string()),
+            line: attr.line(),
+            message,
+        };
+        let Some(spec) = template.attribute(attr.key()) else {
+            findings.push(finding(
+                Severity::Warning,
+                FindingKind::UnknownAttribute,
+                format!("attribute is not part of the {} template", obj_type),
+            ));
+            continue;
+        };
+
+        let count = counts.entry(spec.name).or_default();
+        *count += 1;
+        if *count == 2 && spec.cardinality == Cardinality::Single {
+            findings.push(finding(
+                Severity::Error,
+                FindingKind::RepeatedAttribute,
+                "attribute may only appear once".to_string(),
+            ));
+        }
+
+        let value = strip_comment(attr.value());
+        let items: Vec<&str> = match spec.cardinality {
+            // Multi-valued attributes may also contain comma separated lists.
+            Cardinality::Multiple if spec.syntax.is_list_item() => {
+                value.split(',').map(str::trim).collect()
+            }
+            _ => vec![value],
+        };
+        for item in items {
+            if !spec.syntax.check(item) {
+                findings.push(finding(
+                    Severity::Error,
+                    FindingKind::InvalidSyntax,
+                    format!("'{}' is not a valid {}", item, spec.syntax),
+                ));
+            }
+        }
+    }
+
+    for spec in template.all_attributes() {
+        if spec.requirement == Requirement::Mandatory && !counts.contains_key(spec.name) {
+            findings.push(Finding {
+                severity: Severity::Error,
+                kind: FindingKind::MissingAttribute,
+                attribute: Some(spec.name.to_string()),
+                line: None,
+                message: "mandatory attribute is missing".to_string(),
+            });
+        }
+    }
+    findings
+}
+
+/// Remove an end-of-line comment from a value.
+fn strip_comment(value: &str) -> &str {
+    value.split('#').next().unwrap_or_default().trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_objects;
+
+    fn findings(text: &str) -> Vec<(FindingKind, Option<String>)> {
+        let obj = parse_objects(text).unwrap().remove(0);
+        validate(&obj)
+            .into_iter()
+            .map(|f| (f.kind, f.attribute))
+            .collect()
+    }
+
+    #[test]
+    fn valid_object() {
+        let text = "\
+inetnum: 192.0.2.0 - 192.0.2.255
+netname: EXAMPLE-NET
+country: DE
+admin-c: JD1-RIPE
+tech-c: AUTO-1
+status: ASSIGNED PA
+mnt-by: EXAMPLE-MNT, OTHER-MNT
+notify: noc@example.net # operations
+created: 2024-01-01T00:00:00Z
+source: RIPE
+";
+        assert_eq!(findings(text), []);
+    }
+
+    #[test]
+    fn invalid_object() {
+        let text = "\
+route: 192.0.2.1/24
+origin: 65000
+origin: AS65001
+descr: test
+mnt-by: EXAMPLE-MNT
+notify: not-an-email
+foo: bar
+last-modified: yesterday
+";
+        let attr = |kind, name: &str| (kind, Some(name.to_string()));
+        assert_eq!(
+            findings(text),
+            [
+                attr(FindingKind::InvalidSyntax, "route"),
+                attr(FindingKind::InvalidSyntax, "origin"),
+                attr(FindingKind::RepeatedAttribute, "origin"),
+                attr(FindingKind::InvalidSyntax, "notify"),
+                attr(FindingKind::UnknownAttribute, "foo"),
+                attr(FindingKind::InvalidSyntax, "last-modified"),
+                attr(FindingKind::MissingAttribute, "source"),
+            ]
+        );
+
+        let obj = parse_objects(text).unwrap().remove(0);
+        let finding = &validate(&obj)[1];
+        assert_eq!(finding.line, Some(2));
+        assert_eq!(
+            finding.to_string(),
+            "line 2: error: origin: '65000' is not a valid AS number"
+        );
+    }
+
+    #[test]
+    fn unknown_type() {
+        assert_eq!(
+            findings("poem: test\nsource: RIPE\n"),
+            [(FindingKind::UnknownType, None)]
+        );
+    }
+
+    #[test]
+    fn templates() {
+        let route = template("route").unwrap();
+        assert_eq!(route.primary_key().collect::<Vec<_>>(), ["route", "origin"]);
+        assert!(route.lookup_keys().any(|k| k == "mnt-by"));
+        assert_eq!(
+            route.attribute("source").unwrap().requirement,
+            Requirement::Mandatory
+        );
+    }
+}
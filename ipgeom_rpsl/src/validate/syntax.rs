@@ -0,0 +1,217 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::policy::parse_asn;
+use crate::InetStatus;
+
+/// Syntax of an attribute value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Syntax {
+    /// Any text.
+    Free,
+    /// Letters, digits, `-` and `_`, starting with a letter, e.g. a
+    /// maintainer or network name.
+    ObjectName,
+    /// A NIC handle such as `JD1-RIPE`, or `AUTO-<n>`.
+    NicHdl,
+    /// An organisation ID such as `ORG-EXA1-RIPE`, or `AUTO-<n>`.
+    OrgId,
+    /// `AS<n>`.
+    Asn,
+    /// `AS<n> - AS<m>`.
+    AsBlock,
+    AsSetName,
+    RouteSetName,
+    RtrSetName,
+    PeeringSetName,
+    FilterSetName,
+    /// A member of an as-set: an AS number or as-set name.
+    AsSetMember,
+    /// An IPv4 range `first - last` or prefix.
+    Ipv4Range,
+    Ipv4Prefix,
+    Ipv6Prefix,
+    Email,
+    /// Two letter ISO 3166 country code.
+    CountryCode,
+    /// RFC 3339 timestamp.
+    Timestamp,
+    /// `inetnum`/`inet6num` status.
+    Status,
+    /// Name of a registry, e.g. `RIPE` or `RIPE-NONAUTH`.
+    Source,
+}
+
+impl Syntax {
+    /// Whether `value` matches the syntax.
+    pub fn check(&self, value: &str) -> bool {
+        match self {
+            Syntax::Free => true,
+            Syntax::ObjectName => is_object_name(value),
+            Syntax::NicHdl => is_nic_hdl(value),
+            Syntax::OrgId => value
+                .get(..4)
+                .filter(|p| p.eq_ignore_ascii_case("ORG-"))
+                .map(|_| is_nic_hdl(&value[4..]))
+                .unwrap_or_else(|| is_auto(value)),
+            Syntax::Asn => parse_asn(value).is_some(),
+            Syntax::AsBlock => value
+                .split_once('-')
+                .and_then(|(a, b)| Some((parse_asn(a.trim())?, parse_asn(b.trim())?)))
+                .is_some_and(|(a, b)| a <= b),
+            Syntax::AsSetName => is_set_name(value, "AS-"),
+            Syntax::RouteSetName => is_set_name(value, "RS-"),
+            Syntax::RtrSetName => is_set_name(value, "RTRS-"),
+            Syntax::PeeringSetName => is_set_name(value, "PRNG-"),
+            Syntax::FilterSetName => is_set_name(value, "FLTR-"),
+            Syntax::AsSetMember => parse_asn(value).is_some() || is_set_name(value, "AS-"),
+            Syntax::Ipv4Range => is_ipv4_range(value),
+            Syntax::Ipv4Prefix => value.parse::<Ipv4Net>().is_ok_and(|net| net.trunc() == net),
+            Syntax::Ipv6Prefix => value.parse::<Ipv6Net>().is_ok_and(|net| net.trunc() == net),
+            Syntax::Email => is_email(value),
+            Syntax::CountryCode => {
+                value.len() == 2 && value.bytes().all(|b| b.is_ascii_alphabetic())
+            }
+            Syntax::Timestamp => OffsetDateTime::parse(value, &Rfc3339).is_ok(),
+            Syntax::Status => !matches!(InetStatus::parse(value), InetStatus::Unknown(_)),
+            Syntax::Source => {
+                !value.is_empty()
+                    && value
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+            }
+        }
+    }
+
+    /// Whether values of multi-valued attributes with this syntax may be
+    /// comma separated lists.
+    pub(super) fn is_list_item(&self) -> bool {
+        !matches!(self, Syntax::Free)
+    }
+}
+
+impl std::fmt::Display for Syntax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Syntax::Free => "value",
+            Syntax::ObjectName => "object name",
+            Syntax::NicHdl => "NIC handle",
+            Syntax::OrgId => "organisation ID",
+            Syntax::Asn => "AS number",
+            Syntax::AsBlock => "AS number range",
+            Syntax::AsSetName => "as-set name",
+            Syntax::RouteSetName => "route-set name",
+            Syntax::RtrSetName => "rtr-set name",
+            Syntax::PeeringSetName => "peering-set name",
+            Syntax::FilterSetName => "filter-set name",
+            Syntax::AsSetMember => "AS number or as-set name",
+            Syntax::Ipv4Range => "IPv4 range",
+            Syntax::Ipv4Prefix => "IPv4 prefix",
+            Syntax::Ipv6Prefix => "IPv6 prefix",
+            Syntax::Email => "e-mail address",
+            Syntax::CountryCode => "country code",
+            Syntax::Timestamp => "timestamp",
+            Syntax::Status => "status",
+            Syntax::Source => "source",
+        })
+    }
+}
+
+fn is_object_name(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// `AUTO-<n>`, a placeholder for a handle assigned by the registry.
+fn is_auto(value: &str) -> bool {
+    value
+        .get(..5)
+        .filter(|p| p.eq_ignore_ascii_case("AUTO-"))
+        .is_some_and(|_| {
+            let rest = &value[5..];
+            let digits = rest.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+        })
+}
+
+/// NIC handles consist of 2 to 4 letters, an optional number and an
+/// optional suffix, e.g. `JD1-RIPE`. Some registries also use longer
+/// alphanumeric handles, which are accepted as well.
+fn is_nic_hdl(value: &str) -> bool {
+    if is_auto(value) {
+        return true;
+    }
+    let (handle, suffix) = match value.split_once('-') {
+        Some((handle, suffix)) => (handle, Some(suffix)),
+        None => (value, None),
+    };
+    let letters = handle
+        .bytes()
+        .take_while(|b| b.is_ascii_alphabetic())
+        .count();
+    let digits = &handle[letters..];
+    (1..=10).contains(&letters)
+        && digits.len() <= 6
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && !digits.starts_with('0')
+        && suffix.is_none_or(|s| {
+            !s.is_empty()
+                && s.len() <= 10
+                && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// A hierarchical set name like `AS65000:AS-CUSTOMERS`. At least one
+/// component has to start with `prefix`, the others may be AS numbers.
+fn is_set_name(value: &str, prefix: &str) -> bool {
+    let mut has_set = false;
+    let valid = value.split(':').all(|component| {
+        if parse_asn(component).is_some() {
+            return true;
+        }
+        let is_set = component.len() > prefix.len()
+            && component
+                .get(..prefix.len())
+                .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+            && component
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        has_set |= is_set;
+        is_set
+    });
+    valid && has_set
+}
+
+fn is_ipv4_range(value: &str) -> bool {
+    if let Ok(net) = value.parse::<IpNet>() {
+        return matches!(net, IpNet::V4(net) if net.trunc() == net);
+    }
+    let Some((first, last)) = value.split_once('-') else {
+        return false;
+    };
+    match (
+        first.trim().parse::<Ipv4Addr>(),
+        last.trim().parse::<Ipv4Addr>(),
+    ) {
+        (Ok(first), Ok(last)) => first <= last,
+        _ => false,
+    }
+}
+
+fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.rsplit_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '<' || c == '>')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain.parse::<IpAddr>().is_err()
+}
@@ -0,0 +1,456 @@
+use self::Cardinality::{Multiple as M, Single as S};
+use self::Requirement::{Generated as G, Mandatory as R, Optional as O};
+use super::Syntax::{self, *};
+
+/// Whether an attribute has to be present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    Mandatory,
+    Optional,
+    /// Set by the registry, e.g. `created`. May be omitted when submitting
+    /// objects.
+    Generated,
+}
+
+/// How often an attribute may appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    Single,
+    Multiple,
+}
+
+/// Role of an attribute in looking up objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyKind {
+    None,
+    /// Part of the primary key of the object.
+    Primary,
+    /// Indexed for lookups.
+    Lookup,
+}
+
+/// Template of a single attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeTemplate {
+    pub name: &'static str,
+    pub requirement: Requirement,
+    pub cardinality: Cardinality,
+    pub key: KeyKind,
+    pub syntax: Syntax,
+}
+
+impl AttributeTemplate {
+    const fn primary(mut self) -> Self {
+        self.key = KeyKind::Primary;
+        self
+    }
+
+    const fn lookup(mut self) -> Self {
+        self.key = KeyKind::Lookup;
+        self
+    }
+}
+
+/// Template of an object type, listing the allowed attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Template {
+    pub obj_type: &'static str,
+    /// Attributes specific to the type. The attributes in
+    /// [`Template::COMMON`] are allowed for every type.
+    pub attributes: &'static [AttributeTemplate],
+}
+
+impl Template {
+    /// Attributes shared by all object types.
+    pub const COMMON: &'static [AttributeTemplate] = &[
+        a("remarks", O, M, Free),
+        a("notify", O, M, Email),
+        a("changed", O, M, Free),
+        a("created", G, S, Timestamp),
+        a("last-modified", G, S, Timestamp),
+        a("source", R, S, Source),
+    ];
+
+    /// Look up an attribute, including the common ones.
+    pub fn attribute(&self, name: &str) -> Option<&'static AttributeTemplate> {
+        self.attributes
+            .iter()
+            .chain(Self::COMMON)
+            .find(|a| a.name == name)
+    }
+
+    /// All attributes, including the common ones.
+    pub fn all_attributes(&self) -> impl Iterator<Item = &'static AttributeTemplate> {
+        self.attributes.iter().chain(Self::COMMON)
+    }
+
+    /// Names of the attributes forming the primary key.
+    pub fn primary_key(&self) -> impl Iterator<Item = &'static str> {
+        self.attributes
+            .iter()
+            .filter(|a| a.key == KeyKind::Primary)
+            .map(|a| a.name)
+    }
+
+    /// Names of the lookup key attributes.
+    pub fn lookup_keys(&self) -> impl Iterator<Item = &'static str> {
+        self.attributes
+            .iter()
+            .filter(|a| a.key == KeyKind::Lookup)
+            .map(|a| a.name)
+    }
+}
+
+/// The template for objects of type `obj_type`, based on the RIPE
+/// database templates.
+pub fn template(obj_type: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|t| t.obj_type == obj_type)
+}
+
+const fn a(
+    name: &'static str,
+    requirement: Requirement,
+    cardinality: Cardinality,
+    syntax: Syntax,
+) -> AttributeTemplate {
+    AttributeTemplate {
+        name,
+        requirement,
+        cardinality,
+        key: KeyKind::None,
+        syntax,
+    }
+}
+
+static TEMPLATES: &[Template] = &[
+    Template {
+        obj_type: "inetnum",
+        attributes: &[
+            a("inetnum", R, S, Ipv4Range).primary(),
+            a("netname", R, S, ObjectName).lookup(),
+            a("descr", O, M, Free),
+            a("country", R, M, CountryCode),
+            a("geofeed", O, S, Free),
+            a("geoloc", O, S, Free),
+            a("language", O, M, Free),
+            a("org", O, S, OrgId).lookup(),
+            a("sponsoring-org", O, S, OrgId),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("abuse-c", O, S, NicHdl),
+            a("status", R, S, Status),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+            a("mnt-domains", O, M, ObjectName),
+            a("mnt-routes", O, M, Free),
+            a("mnt-irt", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "inet6num",
+        attributes: &[
+            a("inet6num", R, S, Ipv6Prefix).primary(),
+            a("netname", R, S, ObjectName).lookup(),
+            a("descr", O, M, Free),
+            a("country", R, M, CountryCode),
+            a("geofeed", O, S, Free),
+            a("geoloc", O, S, Free),
+            a("language", O, M, Free),
+            a("org", O, S, OrgId).lookup(),
+            a("sponsoring-org", O, S, OrgId),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("abuse-c", O, S, NicHdl),
+            a("status", R, S, Status),
+            a("assignment-size", O, S, Free),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+            a("mnt-domains", O, M, ObjectName),
+            a("mnt-routes", O, M, Free),
+            a("mnt-irt", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "aut-num",
+        attributes: &[
+            a("aut-num", R, S, Asn).primary(),
+            a("as-name", R, S, ObjectName),
+            a("descr", O, M, Free),
+            a("member-of", O, M, AsSetName).lookup(),
+            a("import-via", O, M, Free),
+            a("import", O, M, Free),
+            a("mp-import", O, M, Free),
+            a("export-via", O, M, Free),
+            a("export", O, M, Free),
+            a("mp-export", O, M, Free),
+            a("default", O, M, Free),
+            a("mp-default", O, M, Free),
+            a("org", O, S, OrgId).lookup(),
+            a("sponsoring-org", O, S, OrgId),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("abuse-c", O, S, NicHdl),
+            a("status", G, S, Free),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+            a("mnt-routes", O, M, Free),
+            a("mnt-irt", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "person",
+        attributes: &[
+            a("person", R, S, Free).lookup(),
+            a("address", R, M, Free),
+            a("phone", R, M, Free),
+            a("fax-no", O, M, Free),
+            a("e-mail", O, M, Email).lookup(),
+            a("org", O, M, OrgId).lookup(),
+            a("nic-hdl", R, S, NicHdl).primary(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "role",
+        attributes: &[
+            a("role", R, S, Free).lookup(),
+            a("address", R, M, Free),
+            a("phone", O, M, Free),
+            a("fax-no", O, M, Free),
+            a("e-mail", R, M, Email).lookup(),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", O, M, NicHdl).lookup(),
+            a("tech-c", O, M, NicHdl).lookup(),
+            a("nic-hdl", R, S, NicHdl).primary(),
+            a("abuse-mailbox", O, S, Email).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "organisation",
+        attributes: &[
+            a("organisation", R, S, OrgId).primary(),
+            a("org-name", R, S, Free).lookup(),
+            a("org-type", R, S, Free),
+            a("descr", O, M, Free),
+            a("address", R, M, Free),
+            a("country", O, S, CountryCode),
+            a("phone", O, M, Free),
+            a("fax-no", O, M, Free),
+            a("e-mail", R, M, Email).lookup(),
+            a("geoloc", O, S, Free),
+            a("language", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", O, M, NicHdl).lookup(),
+            a("tech-c", O, M, NicHdl).lookup(),
+            a("abuse-c", O, S, NicHdl),
+            a("ref-nfy", O, M, Email),
+            a("mnt-ref", R, M, ObjectName),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "mntner",
+        attributes: &[
+            a("mntner", R, S, ObjectName).primary(),
+            a("descr", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", O, M, NicHdl).lookup(),
+            a("upd-to", R, M, Email),
+            a("mnt-nfy", O, M, Email),
+            a("auth", R, M, Free),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "route",
+        attributes: &[
+            a("route", R, S, Ipv4Prefix).primary(),
+            a("descr", O, M, Free),
+            a("origin", R, S, Asn).primary(),
+            a("pingable", O, M, Free),
+            a("ping-hdl", O, M, NicHdl),
+            a("holes", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("member-of", O, M, RouteSetName).lookup(),
+            a("inject", O, M, Free),
+            a("aggr-mtd", O, S, Free),
+            a("aggr-bndry", O, S, Free),
+            a("export-comps", O, S, Free),
+            a("components", O, S, Free),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+            a("mnt-routes", O, M, Free),
+        ],
+    },
+    Template {
+        obj_type: "route6",
+        attributes: &[
+            a("route6", R, S, Ipv6Prefix).primary(),
+            a("descr", O, M, Free),
+            a("origin", R, S, Asn).primary(),
+            a("pingable", O, M, Free),
+            a("ping-hdl", O, M, NicHdl),
+            a("holes", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("member-of", O, M, RouteSetName).lookup(),
+            a("inject", O, M, Free),
+            a("aggr-mtd", O, S, Free),
+            a("aggr-bndry", O, S, Free),
+            a("export-comps", O, S, Free),
+            a("components", O, S, Free),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+            a("mnt-routes", O, M, Free),
+        ],
+    },
+    Template {
+        obj_type: "as-set",
+        attributes: &[
+            a("as-set", R, S, AsSetName).primary(),
+            a("descr", O, M, Free),
+            a("members", O, M, AsSetMember),
+            a("mp-members", O, M, AsSetMember),
+            a("mbrs-by-ref", O, M, ObjectName),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "route-set",
+        attributes: &[
+            a("route-set", R, S, RouteSetName).primary(),
+            a("descr", O, M, Free),
+            a("members", O, M, Free),
+            a("mp-members", O, M, Free),
+            a("mbrs-by-ref", O, M, ObjectName),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "rtr-set",
+        attributes: &[
+            a("rtr-set", R, S, RtrSetName).primary(),
+            a("descr", O, M, Free),
+            a("members", O, M, Free),
+            a("mp-members", O, M, Free),
+            a("mbrs-by-ref", O, M, ObjectName),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "peering-set",
+        attributes: &[
+            a("peering-set", R, S, PeeringSetName).primary(),
+            a("descr", O, M, Free),
+            a("peering", O, M, Free),
+            a("mp-peering", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "filter-set",
+        attributes: &[
+            a("filter-set", R, S, FilterSetName).primary(),
+            a("descr", O, M, Free),
+            a("filter", O, S, Free),
+            a("mp-filter", O, S, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+        ],
+    },
+    Template {
+        obj_type: "domain",
+        attributes: &[
+            a("domain", R, S, Free).primary(),
+            a("descr", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("zone-c", R, M, NicHdl).lookup(),
+            a("nserver", O, M, Free).lookup(),
+            a("ds-rdata", O, M, Free),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "inet-rtr",
+        attributes: &[
+            a("inet-rtr", R, S, Free).primary(),
+            a("descr", O, M, Free),
+            a("alias", O, M, Free),
+            a("local-as", R, S, Asn).lookup(),
+            a("ifaddr", R, M, Free).lookup(),
+            a("interface", O, M, Free),
+            a("peer", O, M, Free),
+            a("mp-peer", O, M, Free),
+            a("member-of", O, M, RtrSetName).lookup(),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "irt",
+        attributes: &[
+            a("irt", R, S, ObjectName).primary(),
+            a("address", R, M, Free),
+            a("phone", O, M, Free),
+            a("fax-no", O, M, Free),
+            a("e-mail", R, M, Email).lookup(),
+            a("abuse-mailbox", O, M, Email).lookup(),
+            a("signature", O, M, Free),
+            a("encryption", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("auth", R, M, Free),
+            a("irt-nfy", O, M, Email),
+            a("admin-c", R, M, NicHdl).lookup(),
+            a("tech-c", R, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "key-cert",
+        attributes: &[
+            a("key-cert", R, S, Free).primary(),
+            a("method", G, S, Free),
+            a("owner", G, M, Free),
+            a("fingerpr", G, S, Free),
+            a("certif", R, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("admin-c", O, M, NicHdl).lookup(),
+            a("tech-c", O, M, NicHdl).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+        ],
+    },
+    Template {
+        obj_type: "as-block",
+        attributes: &[
+            a("as-block", R, S, AsBlock).primary(),
+            a("descr", O, M, Free),
+            a("org", O, M, OrgId).lookup(),
+            a("mnt-by", R, M, ObjectName).lookup(),
+            a("mnt-lower", O, M, ObjectName),
+        ],
+    },
+];
@@ -25,5 +25,5 @@ fn read_long_object_in_small_chunks() {
         .collect();
     assert_eq!(objs.len(), 1);
     let obj = &objs[0];
-    assert_eq!(obj.get("aut-num").unwrap(), ["AS1126"]);
+    assert_eq!(obj.get("aut-num").collect::<Vec<_>>(), ["AS1126"]);
 }
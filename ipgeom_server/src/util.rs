@@ -23,9 +23,8 @@ pub fn lookup_inet_object(
     };
 
     if let Some((_country, obj_type, obj_key)) = res {
-        if let Some(json) = db.get_object(&obj_type, &obj_key)? {
-            let val: Value = serde_json::from_str(&json)?;
-            return Ok(Some((obj_type, val)));
+        if let Some(obj) = db.get_object(&obj_type, &obj_key)? {
+            return Ok(Some((obj_type, obj.to_json_value()?)));
         }
     }
 